@@ -139,6 +139,7 @@ test_cases!(
     pp_alias_operator,
     pp_config_directive,
     // INSERT
+    custom_aggregate,
     script_ports,
     initial_state,
     unused_node,
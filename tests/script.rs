@@ -162,6 +162,7 @@ test_cases!(
     empty_array_pattern,
     const_in_const_lookup,
     // INSERT
+    try_catch,
     fold_bool_or,
     fold_bool_or_imut,
     fold_bool_imut,
@@ -16,6 +16,12 @@ use clap::{Parser, ValueEnum};
 /// Tremor cli - Command Line Interface
 #[derive(Parser, Debug)]
 #[clap(name = "tremor", author, version)]
+#[cfg_attr(
+    not(feature = "lsp"),
+    clap(
+        after_help = "The `lsp` language server subcommand is only available when tremor is built with the `lsp` feature:\n    cargo build --release -p tremor-cli --features lsp"
+    )
+)]
 pub(crate) struct Cli {
     /// Instance identifier
     #[clap(short, long, default_value = "tremor", value_parser = clap::value_parser!(String))]
@@ -53,6 +59,14 @@ pub(crate) enum Command {
         #[clap(subcommand)]
         command: ArchiveCommand,
     },
+    /// Formats tremor, trickle and troy files in place
+    ///
+    /// Line breaks are kept as written, only the indentation and the spacing
+    /// between tokens are normalized.
+    Fmt(Fmt),
+    /// Runs the tremor language server over stdin and stdout
+    #[cfg(feature = "lsp")]
+    Lsp,
     /// Creates a template tremor project
     New {
         #[clap( value_parser = clap::value_parser!(String))]
@@ -177,6 +191,16 @@ pub(crate) struct Doc {
     pub(crate) outdir: String,
 }
 
+#[derive(Parser, Debug)]
+pub(crate) struct Fmt {
+    /// Only checks that the files are formatted, without changing them
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) check: bool,
+    #[clap(default_value = ".", value_parser = clap::value_parser!(String))]
+    /// Files or directories to format
+    pub(crate) paths: Vec<String>,
+}
+
 #[derive(Parser, Debug)]
 pub(crate) struct Run {
     #[clap(value_parser = clap::value_parser!(String))]
@@ -296,6 +320,9 @@ pub(crate) struct ServerRun {
     /// function tail-recursion stack depth limit
     #[clap(short, long, default_value = "1024", value_parser = clap::value_parser!(u32))]
     pub(crate) recursion_limit: u32,
+    /// compile scripts and select statements to bytecode
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) compile: bool,
 }
 
 // TODO: since the API will change this isn't translated yet
@@ -149,6 +149,17 @@ fn gen_module_docs(module: &Module, module_name: &str) -> String {
             }
         }
     }
+
+    if !module.content.aggregates.is_empty() {
+        push_line("## Aggregates", &mut gen);
+        for (aggr_id, defn) in &module.content.aggregates {
+            if let Some(fn_doc) = module.docs.fns.iter().find(|doc| doc.name == defn.name) {
+                push_line(&fn_doc.to_string(), &mut gen);
+            } else {
+                push_line(&format!("### {aggr_id}"), &mut gen);
+            }
+        }
+    }
     gen
 }
 
@@ -0,0 +1,83 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cli::Fmt;
+use crate::errors::{ErrorKind, Result};
+use crate::util::{get_source_kind, visit_path_str, SourceKind};
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use tremor_script::formatter::{self, Kind};
+use tremor_script::highlighter::{Highlighter, Term as TermHighlighter};
+
+impl Fmt {
+    pub(crate) fn run(&self) -> Result<()> {
+        // the path visitor has to be `'static` so the count is shared rather than borrowed
+        let failed = Rc::new(Cell::new(0_usize));
+        let check = self.check;
+        for path in &self.paths {
+            let failed = failed.clone();
+            visit_path_str(path, &move |rel_path, src_path| {
+                if !format(check, rel_path.is_none(), src_path)? {
+                    failed.set(failed.get() + 1);
+                }
+                Ok(())
+            })?;
+        }
+        match failed.get() {
+            0 => Ok(()),
+            n if check => Err(format!("{n} file(s) are not formatted").into()),
+            n => Err(format!("{n} file(s) could not be formatted").into()),
+        }
+    }
+}
+
+/// Formats a single file, returns `false` if it failed to parse or, when
+/// checking, is not formatted. Only files that were named explicitly
+/// are rejected for their type, unknown files in directories are skipped.
+fn format(check: bool, explicit: bool, path: &Path) -> Result<bool> {
+    let file = path.to_string_lossy();
+    let kind = match get_source_kind(&file) {
+        SourceKind::Tremor => Kind::Tremor,
+        SourceKind::Trickle => Kind::Trickle,
+        SourceKind::Troy => Kind::Troy,
+        other if explicit => {
+            return Err(ErrorKind::UnsupportedFileType(
+                file.to_string(),
+                other,
+                "tremor, trickle or troy file",
+            )
+            .into())
+        }
+        _ => return Ok(true),
+    };
+    let src = std::fs::read_to_string(path)?;
+    let formatted = match formatter::format(&src, kind) {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            eprintln!("{file}:");
+            TermHighlighter::stderr().format_error(&e)?;
+            return Ok(false);
+        }
+    };
+    if formatted == src {
+        Ok(true)
+    } else if check {
+        println!("{file}");
+        Ok(false)
+    } else {
+        std::fs::write(path, formatted)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Language server for tremor, trickle and troy files
+
+mod document;
+mod modules;
+
+use crate::errors::Result;
+use crate::util::get_source_kind;
+use document::Document;
+use modules::Modules;
+use std::collections::HashMap;
+use tokio::sync::{Mutex, RwLock};
+use tower_lsp::jsonrpc;
+use tower_lsp::lsp_types::{
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, Location,
+    MarkupContent, MarkupKind, MessageType, OneOf, SemanticTokens, SemanticTokensFullOptions,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    SemanticTokensServerCapabilities, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+use tremor_script::{module::Manager, FN_REGISTRY};
+
+struct Backend {
+    client: Client,
+    documents: RwLock<HashMap<Url, Document>>,
+    modules: Mutex<Modules>,
+}
+
+impl Backend {
+    fn new(client: Client) -> Self {
+        Self {
+            client,
+            documents: RwLock::new(HashMap::new()),
+            modules: Mutex::new(Modules::default()),
+        }
+    }
+
+    /// Adds a workspace folder to the module path
+    async fn add_path(&self, uri: &Url) {
+        let Some(path) = uri.to_file_path().ok() else {
+            return;
+        };
+        let path = path.to_string_lossy();
+        if let Err(e) = Manager::add_path(&path) {
+            warn!("Failed to add module path {path}: {e}");
+        }
+        self.modules.lock().await.add_path(&path);
+    }
+
+    async fn update(&self, uri: Url, text: String, version: Option<i32>) {
+        let kind = get_source_kind(uri.path());
+        if let Ok(path) = uri.to_file_path() {
+            // the document could be a module other documents `use`
+            self.modules.lock().await.invalidate(&path);
+        }
+        let document = Document::new(kind, text);
+        let diagnostics = document.diagnostics();
+        self.documents.write().await.insert(uri.clone(), document);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, params: InitializeParams) -> jsonrpc::Result<InitializeResult> {
+        for folder in params.workspace_folders.unwrap_or_default() {
+            self.add_path(&folder.uri).await;
+        }
+        #[allow(deprecated)] // we still honor clients that only send `root_uri`
+        if let Some(root) = params.root_uri {
+            self.add_path(&root).await;
+        }
+        Ok(InitializeResult {
+            server_info: Some(ServerInfo {
+                name: "tremor".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![":".to_string()]),
+                    ..CompletionOptions::default()
+                }),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: document::legend(),
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..SemanticTokensOptions::default()
+                        },
+                    ),
+                ),
+                ..ServerCapabilities::default()
+            },
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        self.client
+            .log_message(MessageType::INFO, "tremor language server initialized")
+            .await;
+    }
+
+    async fn shutdown(&self) -> jsonrpc::Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let doc = params.text_document;
+        self.update(doc.uri, doc.text, Some(doc.version)).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // we only advertise full sync so the last change is the whole document
+        if let Some(change) = params.content_changes.into_iter().last() {
+            let doc = params.text_document;
+            self.update(doc.uri, change.text, Some(doc.version)).await;
+        }
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let text = self
+            .documents
+            .read()
+            .await
+            .get(&uri)
+            .map(|d| d.text.clone());
+        if let Some(text) = params.text.or(text) {
+            self.update(uri, text, None).await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.write().await.remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn hover(&self, params: HoverParams) -> jsonrpc::Result<Option<Hover>> {
+        let pos = params.text_document_position_params;
+        let documents = self.documents.read().await;
+        let Some(doc) = documents.get(&pos.text_document.uri) else {
+            return Ok(None);
+        };
+        let hover = self.modules.lock().await.hover(doc, pos.position);
+        Ok(hover.map(|(value, range)| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        }))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> jsonrpc::Result<Option<GotoDefinitionResponse>> {
+        let pos = params.text_document_position_params;
+        let documents = self.documents.read().await;
+        let Some(doc) = documents.get(&pos.text_document.uri) else {
+            return Ok(None);
+        };
+        let definition = self.modules.lock().await.definition(doc, pos.position);
+        Ok(definition.and_then(|(file, range)| {
+            let uri = match file {
+                Some(file) => Url::from_file_path(file).ok()?,
+                None => pos.text_document.uri.clone(),
+            };
+            Some(GotoDefinitionResponse::Scalar(Location::new(uri, range)))
+        }))
+    }
+
+    async fn completion(
+        &self,
+        params: CompletionParams,
+    ) -> jsonrpc::Result<Option<CompletionResponse>> {
+        let pos = params.text_document_position;
+        let documents = self.documents.read().await;
+        let Some(doc) = documents.get(&pos.text_document.uri) else {
+            return Ok(None);
+        };
+        let mut modules = self.modules.lock().await;
+        // the registry guard must not be held across an await point
+        let Ok(reg) = FN_REGISTRY.read() else {
+            return Ok(None);
+        };
+        let items = modules.completion(&reg, doc, pos.position);
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> jsonrpc::Result<Option<SemanticTokensResult>> {
+        let documents = self.documents.read().await;
+        Ok(documents.get(&params.text_document.uri).map(|doc| {
+            SemanticTokensResult::Tokens(SemanticTokens {
+                result_id: None,
+                data: doc.semantic_tokens(),
+            })
+        }))
+    }
+}
+
+/// Runs the language server over stdin and stdout until the client disconnects
+pub(crate) async fn run() -> Result<()> {
+    let (service, socket) = LspService::new(Backend::new);
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+    Ok(())
+}
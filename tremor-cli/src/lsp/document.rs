@@ -0,0 +1,402 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::util::SourceKind;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, SemanticToken,
+    SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend,
+};
+use tremor_script::{
+    arena::{self, Arena},
+    ast::{warning::Warning, NodeId},
+    deploy::Deploy,
+    errors::{Error, ErrorWithIndex},
+    highlighter::{self, Class},
+    lexer::{Lexer, Token, TokenSpan},
+    module::{Id, Module, PreCachedNodes},
+    pos::{Location, Span},
+    query::Query,
+    registry::Registry,
+    NodeMeta, Script, FN_REGISTRY,
+};
+
+const TOKEN_TYPES: [SemanticTokenType; 8] = [
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::COMMENT,
+];
+const NAMESPACE: u32 = 0;
+const FUNCTION: u32 = 1;
+const VARIABLE: u32 = 2;
+const KEYWORD: u32 = 3;
+const OPERATOR: u32 = 4;
+const NUMBER: u32 = 5;
+const STRING: u32 = 6;
+const COMMENT: u32 = 7;
+
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 1] = [SemanticTokenModifier::DOCUMENTATION];
+const DOCUMENTATION: u32 = 1;
+
+/// The legend for the semantic tokens we emit
+pub(crate) fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// An open tremor, trickle or troy document
+pub(crate) struct Document {
+    pub(crate) kind: SourceKind,
+    pub(crate) text: String,
+}
+
+type Checked = std::result::Result<Vec<Diagnostic>, (Diagnostic, usize)>;
+
+impl Document {
+    pub(crate) fn new(kind: SourceKind, text: String) -> Self {
+        Self { kind, text }
+    }
+
+    /// Tokenizes the document, stopping at the first lexer error
+    pub(crate) fn tokens(&self) -> Vec<TokenSpan> {
+        Lexer::new(&self.text, arena::Index::INVALID)
+            .tokenize_until_err()
+            .collect()
+    }
+
+    /// Parses the document and reports the errors and warnings in it
+    pub(crate) fn diagnostics(&self) -> Vec<Diagnostic> {
+        let Ok(reg) = FN_REGISTRY.read() else {
+            return Vec::new();
+        };
+        let checked =
+            match self.kind {
+                // `.tremor` files are either scripts or modules, if both fail we
+                // report the error of the one that made it further into the file
+                SourceKind::Tremor => check_script(&self.text, &reg).or_else(|script| {
+                    check_module(&self.text).map_err(|module| {
+                        if module.1 > script.1 {
+                            module
+                        } else {
+                            script
+                        }
+                    })
+                }),
+                SourceKind::Trickle => check_query(&self.text, &reg),
+                SourceKind::Troy => check_deploy(&self.text, &reg),
+                _ => Ok(Vec::new()),
+            };
+        match checked {
+            Ok(warnings) => warnings,
+            Err((error, _)) => vec![error],
+        }
+    }
+
+    /// Semantic tokens for the document, using the same classes as the terminal highlighter
+    pub(crate) fn semantic_tokens(&self) -> Vec<SemanticToken> {
+        let tokens = self.tokens();
+        let mut res = Vec::with_capacity(tokens.len());
+        let (mut prev_line, mut prev_start) = (0, 0);
+        for (i, t) in tokens.iter().enumerate() {
+            let (start, end) = (t.span.start(), t.span.end());
+            // multi line tokens are not supported by all clients
+            if start.line() != end.line() || end.column() <= start.column() {
+                continue;
+            }
+            let Some((token_type, token_modifiers_bitset)) = semantic_type(&tokens, i) else {
+                continue;
+            };
+            let Position { line, character } = position(start);
+            let delta_line = line - prev_line;
+            let delta_start = if delta_line == 0 {
+                character - prev_start
+            } else {
+                character
+            };
+            res.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length: to_u32(end.column() - start.column()),
+                token_type,
+                token_modifiers_bitset,
+            });
+            (prev_line, prev_start) = (line, character);
+        }
+        res
+    }
+}
+
+fn semantic_type(tokens: &[TokenSpan], i: usize) -> Option<(u32, u32)> {
+    let token = &tokens.get(i)?.value;
+    let t = match Class::of(token) {
+        Class::Keyword => KEYWORD,
+        Class::Operator => OPERATOR,
+        Class::Literal => NUMBER,
+        Class::String => STRING,
+        Class::Comment => COMMENT,
+        Class::Doc => return Some((COMMENT, DOCUMENTATION)),
+        Class::Ident => match tokens.get(i + 1..)?.iter().find(|t| !is_trivia(&t.value)) {
+            Some(TokenSpan {
+                value: Token::ColonColon,
+                ..
+            }) => NAMESPACE,
+            Some(TokenSpan {
+                value: Token::LParen,
+                ..
+            }) => FUNCTION,
+            _ => VARIABLE,
+        },
+        Class::Other if matches!(token, Token::DQuote) => STRING,
+        Class::Bad | Class::Other => return None,
+    };
+    Some((t, 0))
+}
+
+/// Whitespace and comments
+pub(crate) fn is_trivia(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Whitespace(_) | Token::NewLine | Token::SingleLineComment(_)
+    )
+}
+
+pub(crate) fn to_u32(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+/// Converts a location to a LSP position, lines and columns in tremor start at 1
+pub(crate) fn position(loc: Location) -> Position {
+    Position::new(
+        to_u32(loc.line().saturating_sub(1)),
+        to_u32(loc.column().saturating_sub(1)),
+    )
+}
+
+/// The position after the last character of `text`
+fn end_of(text: &str) -> Position {
+    let line = text.split('\n').count().saturating_sub(1);
+    let column = text.rsplit('\n').next().map_or(0, |l| l.chars().count());
+    Position::new(to_u32(line), to_u32(column))
+}
+
+pub(crate) fn range(span: Span) -> Range {
+    Range::new(position(span.start()), position(span.end()))
+}
+
+/// Frees a source we only parsed to check it
+pub(crate) fn free(aid: arena::Index) {
+    if aid == arena::Index::INVALID {
+        return;
+    }
+    // ALLOW: nothing references the source anymore once the parse result was converted
+    if let Err(e) = unsafe { Arena::delte_index_this_is_really_unsafe_dont_use_it(aid) } {
+        warn!("Failed to free source {aid}: {e}");
+    }
+}
+
+fn warning(w: &Warning) -> Diagnostic {
+    Diagnostic {
+        range: range(w.inner),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(w.class.to_string())),
+        source: Some("tremor".to_string()),
+        message: w.msg.clone(),
+        ..Diagnostic::default()
+    }
+}
+
+fn error(e: &Error, aid: arena::Index, text: &str) -> (Diagnostic, usize) {
+    let h = highlighter::Error::from(e);
+    let (range, offset) = match e.context() {
+        (_, Some(inner)) if inner.aid() == aid => (range(inner), inner.start().absolute()),
+        // the parser can't point at the end of the input so we do it for it
+        (Some(outer), None) if outer.aid() == arena::Index::INVALID => {
+            let end = end_of(text);
+            (Range::new(end, end), text.len())
+        }
+        // errors in `use`d modules point into their source, not this one
+        _ => (Range::default(), 0),
+    };
+    let message = if let Some(hint) = h.hint() {
+        format!("{}\nNOTE: {hint}", h.callout())
+    } else {
+        h.callout().to_string()
+    };
+    let diagnostic = Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("tremor".to_string()),
+        message,
+        ..Diagnostic::default()
+    };
+    (diagnostic, offset)
+}
+
+fn check_script(text: &str, reg: &Registry) -> Checked {
+    match Script::parse_with_aid(text, reg) {
+        Ok(script) => {
+            let warnings = script.warnings().map(warning).collect();
+            let aid = script.aid;
+            drop(script);
+            free(aid);
+            Ok(warnings)
+        }
+        Err(ErrorWithIndex(aid, e)) => {
+            let e = error(&e, aid, text);
+            free(aid);
+            Err(e)
+        }
+    }
+}
+
+fn check_query(text: &str, reg: &Registry) -> Checked {
+    match Query::parse_with_aid(text, reg, &tremor_script::aggr_registry()) {
+        Ok(query) => {
+            let warnings = query.warnings.iter().map(warning).collect();
+            let aid = query.aid;
+            drop(query);
+            free(aid);
+            Ok(warnings)
+        }
+        Err(ErrorWithIndex(aid, e)) => {
+            let e = error(&e, aid, text);
+            free(aid);
+            Err(e)
+        }
+    }
+}
+
+fn check_deploy(text: &str, reg: &Registry) -> Checked {
+    match Deploy::parse_with_aid(text, reg, &tremor_script::aggr_registry()) {
+        Ok(deploy) => {
+            let warnings = deploy.warnings.iter().map(warning).collect();
+            let aid = deploy.aid;
+            drop(deploy);
+            free(aid);
+            Ok(warnings)
+        }
+        Err(ErrorWithIndex(aid, e)) => {
+            let e = error(&e, aid, text);
+            free(aid);
+            Err(e)
+        }
+    }
+}
+
+fn check_module(text: &str) -> Checked {
+    let (aid, src) = Arena::insert(text).map_err(|e| error(&e, arena::Index::INVALID, text))?;
+    let module = Module::load(
+        NodeId::new("lsp", Vec::new(), NodeMeta::dummy()),
+        Id::from(src.as_bytes()),
+        &mut Vec::new(),
+        aid,
+        src,
+        &PreCachedNodes::new(),
+    );
+    let checked = match module {
+        Ok(module) => {
+            drop(module);
+            Ok(Vec::new())
+        }
+        Err(e) => Err(error(&e, aid, text)),
+    };
+    free(aid);
+    checked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(kind: SourceKind, text: &str) -> Vec<Diagnostic> {
+        tremor_script::module::Manager::add_path(&"../tremor-script/lib").ok();
+        Document::new(kind, text.to_string()).diagnostics()
+    }
+
+    #[test]
+    fn script_errors() {
+        assert!(diagnostics(SourceKind::Tremor, "let a = 1; a + 1").is_empty());
+
+        let d = diagnostics(SourceKind::Tremor, "let a = 1;\nlet = 2");
+        assert_eq!(1, d.len());
+        assert_eq!(Some(DiagnosticSeverity::ERROR), d[0].severity);
+        assert_eq!(Position::new(1, 4), d[0].range.start);
+    }
+
+    #[test]
+    fn modules() {
+        let module = "## a function\nfn snot() with 1 end;\ndefine window w from tumbling with size = 1 end;";
+        assert!(diagnostics(SourceKind::Tremor, module).is_empty());
+        let d = diagnostics(
+            SourceKind::Tremor,
+            "fn snot() with 1 end;\ndefine window w from tumbling with size = 1 end;\ndefine window",
+        );
+        assert_eq!(1, d.len());
+        assert_eq!(2, d[0].range.start.line);
+    }
+
+    #[test]
+    fn queries_and_deploys() {
+        assert!(diagnostics(SourceKind::Trickle, "select event from in into out;").is_empty());
+        let d = diagnostics(SourceKind::Trickle, "select event from in into");
+        assert_eq!(1, d.len());
+        assert_eq!(Position::new(0, 25), d[0].range.start);
+        assert!(diagnostics(
+            SourceKind::Troy,
+            "define flow main\nflow\n  define connector exit from exit;\n  create connector exit;\nend;\ndeploy flow main;"
+        )
+        .is_empty());
+        assert_eq!(1, diagnostics(SourceKind::Troy, "deploy flow").len());
+    }
+
+    #[test]
+    fn semantic_tokens() {
+        let doc = Document::new(
+            SourceKind::Tremor,
+            "use std::string;\n## docs\nstring::len(\"snot\") + 1".to_string(),
+        );
+        let tokens: Vec<_> = doc
+            .semantic_tokens()
+            .into_iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length, t.token_type))
+            .collect();
+        assert_eq!(
+            vec![
+                (0, 0, 3, KEYWORD),
+                (0, 4, 3, NAMESPACE),
+                (0, 3, 2, OPERATOR),
+                (0, 2, 6, VARIABLE),
+                (0, 6, 1, OPERATOR),
+                (1, 0, 7, COMMENT),
+                (1, 0, 6, NAMESPACE),
+                (0, 6, 2, OPERATOR),
+                (0, 2, 3, FUNCTION),
+                (0, 3, 1, OPERATOR),
+                (0, 1, 1, STRING),
+                (0, 1, 4, STRING),
+                (0, 4, 1, STRING),
+                (0, 1, 1, OPERATOR),
+                (0, 2, 1, OPERATOR),
+                (0, 2, 1, NUMBER),
+            ],
+            tokens
+        );
+    }
+}
@@ -0,0 +1,621 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::document::{free, is_trivia, range, Document};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Documentation, MarkupContent, MarkupKind, Position, Range,
+};
+use tremor_script::{
+    arena::Arena,
+    ast::{docs::Docs, NodeId},
+    lexer::{Token, TokenSpan},
+    module::{Id, Module, PreCachedNodes},
+    path::ModulePath,
+    prelude::{BaseExpr, Ranged},
+    registry::{Registry, TremorFnWrapper},
+    NodeMeta,
+};
+
+/// A module brought into scope with `use`
+pub(crate) struct Use {
+    /// name the module is referred to by
+    alias: String,
+    /// path of the module, including its own name
+    module: Vec<String>,
+    /// where each segment of the module path is written
+    segments: Vec<Range>,
+}
+
+fn value<'t>(tokens: &[&'t TokenSpan], i: usize) -> Option<&'t Token<'t>> {
+    tokens.get(i).map(|t| &t.value)
+}
+
+fn ident(tokens: &[&TokenSpan], i: usize) -> Option<String> {
+    match value(tokens, i)? {
+        Token::Ident(id, _) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Parses a `a::b::c` module path starting at `i`
+fn mod_path(tokens: &[&TokenSpan], mut i: usize) -> (Vec<String>, Vec<Range>, usize) {
+    let (mut path, mut ranges) = (Vec::new(), Vec::new());
+    while let Some((id, t)) = ident(tokens, i).zip(tokens.get(i)) {
+        path.push(id);
+        ranges.push(range(t.span));
+        i += 1;
+        if matches!(value(tokens, i), Some(Token::ColonColon)) && ident(tokens, i + 1).is_some() {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    (path, ranges, i)
+}
+
+fn alias(tokens: &[&TokenSpan], i: usize) -> (Option<String>, usize) {
+    if matches!(value(tokens, i), Some(Token::As)) {
+        (ident(tokens, i + 1), i + 2)
+    } else {
+        (None, i)
+    }
+}
+
+fn push_use(uses: &mut Vec<Use>, module: Vec<String>, segments: Vec<Range>, alias: Option<String>) {
+    if let Some(alias) = alias.or_else(|| module.last().cloned()) {
+        uses.push(Use {
+            alias,
+            module,
+            segments,
+        });
+    }
+}
+
+/// Parses the target of a `use` starting at `i`, this handles
+/// `use a::b`, `use a::b as c` and `use a::{b, c as d}`
+fn use_target(tokens: &[&TokenSpan], i: usize, uses: &mut Vec<Use>) -> usize {
+    let (prefix, prefix_ranges, mut i) = mod_path(tokens, i);
+    if matches!(value(tokens, i), Some(Token::ColonColon))
+        && matches!(value(tokens, i + 1), Some(Token::LBrace))
+    {
+        i += 2;
+        loop {
+            let (path, ranges, j) = mod_path(tokens, i);
+            let (alias, j) = alias(tokens, j);
+            let module = prefix.iter().chain(&path).cloned().collect();
+            let segments = prefix_ranges.iter().chain(&ranges).copied().collect();
+            push_use(uses, module, segments, alias);
+            i = j;
+            if matches!(value(tokens, i), Some(Token::Comma)) {
+                i += 1;
+            } else {
+                break i;
+            }
+        }
+    } else {
+        let (alias, i) = alias(tokens, i);
+        push_use(uses, prefix, prefix_ranges, alias);
+        i
+    }
+}
+
+/// All modules `use`d in a document
+pub(crate) fn uses(tokens: &[TokenSpan]) -> Vec<Use> {
+    let tokens: Vec<_> = tokens.iter().filter(|t| !is_trivia(&t.value)).collect();
+    let mut uses = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        i = if matches!(value(&tokens, i), Some(Token::Use)) {
+            use_target(&tokens, i + 1, &mut uses)
+        } else {
+            i + 1
+        };
+    }
+    uses
+}
+
+fn contains(r: Range, p: Position) -> bool {
+    r.start <= p && p <= r.end
+}
+
+/// The `a::b::c` path under the cursor, up to and including the segment the cursor is on
+pub(crate) fn path_at(tokens: &[TokenSpan], pos: Position) -> Option<(Vec<String>, Range)> {
+    let i = tokens
+        .iter()
+        .position(|t| matches!(t.value, Token::Ident(..)) && contains(range(t.span), pos))?;
+    let mut start = i;
+    while start >= 2
+        && matches!(tokens[start - 1].value, Token::ColonColon)
+        && matches!(tokens[start - 2].value, Token::Ident(..))
+    {
+        start -= 2;
+    }
+    let path = tokens[start..=i]
+        .iter()
+        .filter_map(|t| match &t.value {
+            Token::Ident(id, _) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+    Some((path, range(tokens[i].span)))
+}
+
+/// What a path refers to
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// A module
+    Module(Vec<String>),
+    /// A definition inside of a module
+    Item(Vec<String>, String),
+}
+
+/// Resolves the module path of `path` through the `use`d aliases
+fn module_path(uses: &[Use], path: &[String]) -> Option<Vec<String>> {
+    let (alias, nested) = path.split_first()?;
+    let u = uses.iter().find(|u| &u.alias == alias)?;
+    Some(u.module.iter().chain(nested).cloned().collect())
+}
+
+/// Resolves the path under the cursor to a module or a definition in a module
+pub(crate) fn target(uses: &[Use], path: &[String], pos: Position) -> Option<Target> {
+    // inside of a `use` the path is the module path itself
+    for u in uses {
+        if let Some(i) = u.segments.iter().position(|r| contains(*r, pos)) {
+            return Some(Target::Module(
+                u.module.iter().take(i + 1).cloned().collect(),
+            ));
+        }
+    }
+    let (item, module) = path.split_last()?;
+    if module.is_empty() {
+        module_path(uses, path).map(Target::Module)
+    } else {
+        module_path(uses, module).map(|m| Target::Item(m, item.clone()))
+    }
+}
+
+/// Finds the definition of `name` in the document itself
+pub(crate) fn local_definition(tokens: &[TokenSpan], name: &str) -> Option<Range> {
+    let tokens: Vec<_> = tokens.iter().filter(|t| !is_trivia(&t.value)).collect();
+    let defines = |i: usize| match value(&tokens, i) {
+        Some(Token::Fun | Token::Const | Token::Let) => Some(i + 1),
+        // `define <kind> <name>`
+        Some(Token::Define) => Some(i + 2),
+        _ => None,
+    };
+    (0..tokens.len())
+        .filter_map(defines)
+        .find(|i| ident(&tokens, *i).as_deref() == Some(name))
+        .and_then(|i| tokens.get(i))
+        .map(|t| range(t.span))
+}
+
+/// Kind of a definition in a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DefKind {
+    Function,
+    Aggregate,
+    Const,
+    Connector,
+    Flow,
+    Pipeline,
+    Window,
+    Operator,
+    Script,
+}
+
+impl DefKind {
+    fn completion_kind(self) -> CompletionItemKind {
+        match self {
+            DefKind::Function | DefKind::Aggregate => CompletionItemKind::FUNCTION,
+            DefKind::Const => CompletionItemKind::CONSTANT,
+            DefKind::Connector | DefKind::Flow | DefKind::Pipeline => CompletionItemKind::CLASS,
+            DefKind::Window | DefKind::Operator | DefKind::Script => CompletionItemKind::STRUCT,
+        }
+    }
+}
+
+/// A definition in a module
+pub(crate) struct Def {
+    pub(crate) name: String,
+    pub(crate) kind: DefKind,
+    pub(crate) range: Range,
+}
+
+fn add_defs<'a, T>(
+    defs: &mut Vec<Def>,
+    kind: DefKind,
+    entities: impl IntoIterator<Item = (&'a String, &'a T)>,
+) where
+    T: BaseExpr + 'a,
+{
+    defs.extend(entities.into_iter().map(|(name, e)| Def {
+        name: name.clone(),
+        kind,
+        range: range(e.extent()),
+    }));
+}
+
+/// Documentation and definitions of a module file
+pub(crate) struct Info {
+    pub(crate) file: PathBuf,
+    name: String,
+    docs: Docs,
+    defs: Vec<Def>,
+}
+
+impl Info {
+    fn new(file: &Path, module: &Module) -> Self {
+        let c = &module.content;
+        let mut defs = Vec::new();
+        add_defs(&mut defs, DefKind::Function, &c.functions);
+        add_defs(&mut defs, DefKind::Aggregate, &c.aggregates);
+        add_defs(&mut defs, DefKind::Const, &c.consts);
+        add_defs(&mut defs, DefKind::Connector, &c.connectors);
+        add_defs(&mut defs, DefKind::Flow, &c.flows);
+        add_defs(&mut defs, DefKind::Pipeline, &c.pipelines);
+        add_defs(&mut defs, DefKind::Window, &c.windows);
+        add_defs(&mut defs, DefKind::Operator, &c.operators);
+        add_defs(&mut defs, DefKind::Script, &c.scripts);
+        let name = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self {
+            file: file.to_path_buf(),
+            name,
+            docs: module.docs.clone(),
+            defs,
+        }
+    }
+
+    fn load(file: &Path) -> Option<Self> {
+        let src = std::fs::read_to_string(file).ok()?;
+        let (aid, src) = Arena::insert(&src).ok()?;
+        let module = Module::load(
+            NodeId::new("lsp", Vec::new(), NodeMeta::dummy()),
+            Id::from(src.as_bytes()),
+            &mut Vec::new(),
+            aid,
+            src,
+            &PreCachedNodes::new(),
+        );
+        // the info owns everything it needs so we can free the source again
+        let info = module.ok().map(|m| Self::new(file, &m));
+        free(aid);
+        info
+    }
+
+    /// Markdown documentation of the module
+    pub(crate) fn module_docs(&self) -> String {
+        self.docs.module.as_ref().map_or_else(
+            || format!("# {}", self.name),
+            |m| m.print_with_name(&self.name),
+        )
+    }
+
+    /// Markdown documentation of a definition in the module
+    pub(crate) fn docs(&self, item: &str) -> Option<String> {
+        let d = &self.docs;
+        d.fns
+            .iter()
+            .find(|f| f.name == item)
+            .map(ToString::to_string)
+            .or_else(|| {
+                d.consts
+                    .iter()
+                    .find(|c| c.name == item)
+                    .map(ToString::to_string)
+            })
+            .or_else(|| {
+                d.queries
+                    .iter()
+                    .find(|q| q.name == item)
+                    .map(ToString::to_string)
+            })
+            .or_else(|| {
+                d.flows.iter().find(|f| f.name == item).map(|f| {
+                    format!(
+                        "\n### {}\n\n{}\n",
+                        f.name,
+                        f.doc.clone().unwrap_or_default()
+                    )
+                })
+            })
+    }
+
+    pub(crate) fn def(&self, item: &str) -> Option<&Def> {
+        self.defs.iter().find(|d| d.name == item)
+    }
+}
+
+/// Cache of the modules documents refer to
+pub(crate) struct Modules {
+    path: ModulePath,
+    cache: HashMap<PathBuf, Arc<Info>>,
+}
+
+impl Default for Modules {
+    fn default() -> Self {
+        Self {
+            path: ModulePath::load(),
+            cache: HashMap::new(),
+        }
+    }
+}
+
+impl Modules {
+    /// Adds a directory to look for modules in
+    pub(crate) fn add_path(&mut self, path: &str) {
+        self.path.add(&path);
+    }
+
+    /// Drops a module file from the cache after it changed
+    pub(crate) fn invalidate(&mut self, file: &Path) {
+        self.cache.remove(file);
+    }
+
+    /// Looks up a module by its path
+    pub(crate) fn get(&mut self, module: &[String]) -> Option<Arc<Info>> {
+        let (id, module) = module.split_last()?;
+        let file = self
+            .path
+            .resolve_id(&NodeId::new(id, module.to_vec(), NodeMeta::dummy()))?;
+        if let Some(info) = self.cache.get(&file) {
+            return Some(info.clone());
+        }
+        let info = Arc::new(Info::load(&file)?);
+        self.cache.insert(file, info.clone());
+        Some(info)
+    }
+
+    /// Markdown documentation for the path under the cursor
+    pub(crate) fn hover(&mut self, doc: &Document, pos: Position) -> Option<(String, Range)> {
+        let tokens = doc.tokens();
+        let (path, range) = path_at(&tokens, pos)?;
+        let docs = match target(&uses(&tokens), &path, pos)? {
+            Target::Module(m) => self.get(&m)?.module_docs(),
+            Target::Item(m, item) => self.get(&m)?.docs(&item)?,
+        };
+        Some((docs, range))
+    }
+
+    /// The file and range the path under the cursor is defined in, no file
+    /// means the definition is in the document itself
+    pub(crate) fn definition(
+        &mut self,
+        doc: &Document,
+        pos: Position,
+    ) -> Option<(Option<PathBuf>, Range)> {
+        let tokens = doc.tokens();
+        let (path, _) = path_at(&tokens, pos)?;
+        match target(&uses(&tokens), &path, pos) {
+            Some(Target::Module(m)) => Some((Some(self.get(&m)?.file.clone()), Range::default())),
+            Some(Target::Item(m, item)) => {
+                let info = self.get(&m)?;
+                let range = info.def(&item)?.range;
+                Some((Some(info.file.clone()), range))
+            }
+            None => match path.as_slice() {
+                [name] => local_definition(&tokens, name).map(|r| (None, r)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Completions for the `a::b::` prefix in front of the cursor
+    pub(crate) fn completion(
+        &mut self,
+        reg: &Registry,
+        doc: &Document,
+        pos: Position,
+    ) -> Vec<CompletionItem> {
+        let tokens = doc.tokens();
+        let uses = uses(&tokens);
+        let mut path: Vec<String> = prefix_at(&doc.text, pos)
+            .split("::")
+            .map(ToString::to_string)
+            .collect();
+        // the client filters by the partially typed last segment itself
+        path.pop();
+
+        let mut items = Vec::new();
+        if path.is_empty() {
+            items.extend(uses.iter().map(|u| {
+                let detail = u.module.join("::");
+                item(&u.alias, CompletionItemKind::MODULE, Some(detail), None)
+            }));
+            items.extend(reg.functions().map(|f| {
+                item(
+                    &format!("{}::{}", f.module(), f.name()),
+                    CompletionItemKind::FUNCTION,
+                    Some(arity(f)),
+                    None,
+                )
+            }));
+            return items;
+        }
+        if let Some(info) = module_path(&uses, &path).and_then(|m| self.get(&m)) {
+            items.extend(
+                info.defs
+                    .iter()
+                    .map(|d| item(&d.name, d.kind.completion_kind(), None, info.docs(&d.name))),
+            );
+        }
+        // intrinsics are backed by the registry module of the same name
+        if let Some(fns) = reg.find_module(&path.join("::")) {
+            for f in fns.values() {
+                if !items.iter().any(|i| i.label == f.name()) {
+                    items.push(item(
+                        f.name(),
+                        CompletionItemKind::FUNCTION,
+                        Some(arity(f)),
+                        None,
+                    ));
+                }
+            }
+        }
+        items
+    }
+}
+
+/// The `a::b::c` prefix in front of the cursor
+fn prefix_at(text: &str, pos: Position) -> String {
+    let line = text.lines().nth(pos.line as usize).unwrap_or_default();
+    let before: Vec<char> = line.chars().take(pos.character as usize).collect();
+    let start = before
+        .iter()
+        .rposition(|c| !(c.is_alphanumeric() || *c == '_' || *c == ':'))
+        .map_or(0, |i| i + 1);
+    before[start..].iter().collect()
+}
+
+fn arity(f: &TremorFnWrapper) -> String {
+    let arity = f.arity();
+    match (*arity.start(), *arity.end()) {
+        (start, end) if start == end => format!("{start} arguments"),
+        (start, usize::MAX) => format!("{start} or more arguments"),
+        (start, end) => format!("{start} to {end} arguments"),
+    }
+}
+
+fn item(
+    label: &str,
+    kind: CompletionItemKind,
+    detail: Option<String>,
+    docs: Option<String>,
+) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(kind),
+        detail,
+        documentation: docs.map(|value| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            })
+        }),
+        ..CompletionItem::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::SourceKind;
+
+    fn doc(text: &str) -> Document {
+        Document::new(SourceKind::Tremor, text.to_string())
+    }
+
+    fn modules() -> Modules {
+        let mut modules = Modules::default();
+        modules.add_path("../tremor-script/lib");
+        modules
+    }
+
+    #[test]
+    fn use_targets() {
+        let d = doc("use std::string;\nuse std::{array, type as t};\nuse snot::badger as b;");
+        let uses = uses(&d.tokens());
+        let uses: Vec<_> = uses
+            .iter()
+            .map(|u| (u.alias.as_str(), u.module.join("::")))
+            .collect();
+        assert_eq!(
+            vec![
+                ("string", "std::string".to_string()),
+                ("array", "std::array".to_string()),
+                ("t", "std::type".to_string()),
+                ("b", "snot::badger".to_string()),
+            ],
+            uses
+        );
+    }
+
+    #[test]
+    fn targets() {
+        let d = doc("use std::string;\nstring::len(event)");
+        let tokens = d.tokens();
+        let uses = uses(&tokens);
+        let at = |line, character| {
+            let pos = Position::new(line, character);
+            let (path, _) = path_at(&tokens, pos)?;
+            target(&uses, &path, pos)
+        };
+        let string = vec!["std".to_string(), "string".to_string()];
+        assert_eq!(Some(Target::Module(vec!["std".to_string()])), at(0, 5));
+        assert_eq!(Some(Target::Module(string.clone())), at(0, 10));
+        assert_eq!(Some(Target::Module(string.clone())), at(1, 2));
+        assert_eq!(Some(Target::Item(string, "len".to_string())), at(1, 9));
+        assert_eq!(None, at(1, 14));
+    }
+
+    #[test]
+    fn hover() {
+        let mut modules = modules();
+        let d = doc("use std::string;\nstring::len(event)");
+        let (docs, range) = modules
+            .hover(&d, Position::new(1, 9))
+            .expect("no hover for `len`");
+        assert!(docs.contains("### len(input)"));
+        assert_eq!(Range::new(Position::new(1, 8), Position::new(1, 11)), range);
+        let (docs, _) = modules
+            .hover(&d, Position::new(0, 10))
+            .expect("no hover for `string`");
+        assert!(docs.contains("# string"));
+    }
+
+    #[test]
+    fn definition() {
+        let mut modules = modules();
+        let d = doc("use std::string;\nfn snot(x) with x end;\nsnot(string::len(event))");
+        let (file, range) = modules
+            .definition(&d, Position::new(2, 14))
+            .expect("no definition for `len`");
+        assert!(file
+            .expect("no file")
+            .ends_with("tremor-script/lib/std/string.tremor"));
+        assert!(range.start.line > 0);
+        let (file, range) = modules
+            .definition(&d, Position::new(2, 1))
+            .expect("no definition for `snot`");
+        assert_eq!(None, file);
+        assert_eq!(Range::new(Position::new(1, 3), Position::new(1, 7)), range);
+    }
+
+    #[test]
+    fn completion() {
+        let mut modules = modules();
+        let reg = tremor_script::registry();
+        let d = doc("use std::string;\nstring::le");
+        let items = modules.completion(&reg, &d, Position::new(1, 10));
+        let len = items
+            .iter()
+            .find(|i| i.label == "len")
+            .expect("no completion for `len`");
+        assert_eq!(Some(CompletionItemKind::FUNCTION), len.kind);
+        assert!(len.documentation.is_some());
+
+        let d = doc("use std::string;\nstr");
+        let items = modules.completion(&reg, &d, Position::new(1, 3));
+        assert!(items.iter().any(|i| i.label == "string"));
+        assert!(items.iter().any(|i| i.label == "string::len"));
+    }
+}
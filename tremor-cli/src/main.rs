@@ -46,6 +46,9 @@ mod debug;
 mod doc;
 mod env;
 mod errors;
+mod fmt;
+#[cfg(feature = "lsp")]
+mod lsp;
 mod report;
 mod run;
 mod server;
@@ -129,6 +132,9 @@ async fn run(cli: Cli) -> Result<()> {
         Command::Run(r) => r.run().await,
         Command::Doc(d) => d.run(),
         Command::Archive { command } => command.run().await,
+        Command::Fmt(f) => f.run(),
+        #[cfg(feature = "lsp")]
+        Command::Lsp => lsp::run().await,
         Command::New { name } => create_template(std::env::current_dir()?, &name),
     }
 }
@@ -91,6 +91,7 @@ impl ServerRun {
         }
 
         tremor_script::RECURSION_LIMIT.store(self.recursion_limit, Ordering::Relaxed);
+        tremor_script::COMPILE_SCRIPTS.store(self.compile, Ordering::Relaxed);
 
         let (runtime, handle) = if self.debug_connectors {
             Runtime::builder()
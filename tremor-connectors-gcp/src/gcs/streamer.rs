@@ -1480,6 +1480,7 @@ pub(crate) mod tests {
             postprocessors: None,
             reconnect: Reconnect::None,
             metrics_interval_s: None,
+            spill: None,
         };
         let kill_switch = KillSwitch::dummy();
         let alias = alias::Connector::new("snot", "badger");
@@ -1510,6 +1511,7 @@ pub(crate) mod tests {
             postprocessors: None,
             reconnect: Reconnect::None,
             metrics_interval_s: None,
+            spill: None,
         };
         let kill_switch = KillSwitch::dummy();
         let alias = alias::Connector::new("snot", "badger");
@@ -55,6 +55,53 @@ impl Default for Reconnect {
     }
 }
 
+/// What a spill buffer does with new events once it is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// drop the oldest spilled event to make room for the new one
+    DropOldest,
+    /// drop the new event
+    #[default]
+    DropNewest,
+    /// trigger the circuit breaker until the spill buffer has room again
+    Block,
+}
+
+/// Disk-backed spill buffer that absorbs events while a sink is disconnected
+/// and replays them in order once it is connected again
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spill {
+    /// directory the spill buffer is persisted in
+    pub path: String,
+    /// maximum number of events held in the spill buffer
+    #[serde(default = "default_spill_max_events")]
+    pub max_events: u64,
+    /// soft limit of bytes per spill buffer chunk
+    #[serde(default = "default_spill_chunk_size")]
+    pub chunk_size: u64,
+    /// soft limit of the number of chunks
+    #[serde(default = "default_spill_max_chunks")]
+    pub max_chunks: usize,
+    /// what to do with events once `max_events` is reached
+    #[serde(default)]
+    pub overflow: Overflow,
+}
+
+fn default_spill_max_events() -> u64 {
+    100_000
+}
+
+fn default_spill_chunk_size() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_spill_max_chunks() -> usize {
+    // the number of events is limited by `max_events`, chunks are only a soft limit
+    usize::MAX
+}
+
 /* TODO: currently this is implemented differently in every connector
 
 /// how a connector behaves upon Pause or CB trigger events
@@ -104,6 +151,9 @@ pub struct Connector {
 
     /// Metrics interval in seconds
     pub metrics_interval_s: Option<u64>,
+
+    /// Spill buffer for the sink
+    pub spill: Option<Spill>,
 }
 
 impl Connector {
@@ -183,6 +233,12 @@ impl Connector {
             ValueType::Object,
             connector_alias,
         )?;
+        validate_type(
+            connector_config,
+            ConnectorDefinition::SPILL,
+            ValueType::Object,
+            connector_alias,
+        )?;
         validate_type(
             connector_config,
             ConnectorDefinition::PREPROCESSORS,
@@ -236,6 +292,11 @@ impl Connector {
                 .transpose()?
                 .unwrap_or_default(),
             metrics_interval_s: connector_config.get_u64(ConnectorDefinition::METRICS_INTERVAL_S),
+            spill: connector_config
+                .get(ConnectorDefinition::SPILL)
+                .cloned()
+                .map(tremor_value::structurize)
+                .transpose()?,
             codec: connector_config
                 .get(ConnectorDefinition::CODEC)
                 .map(tremor_codec::Config::try_from)
@@ -61,7 +61,10 @@ pub struct Harness {
 }
 
 impl Harness {
-    pub(crate) async fn new_with_ports(
+    /// Create a new connector harness with a kill switch, linking pipelines to the given ports
+    /// # Errors
+    /// - If the connector harnes could not be created or the linking failed
+    pub async fn new_with_ports(
         alias: &str,
         builder: &dyn crate::ConnectorBuilder,
         defn: &Value<'static>,
@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+/// AMQP 0.9.1
+#[cfg(feature = "amqp")]
+pub mod amqp;
 /// benchmarking connector
 #[cfg(feature = "bench")]
 pub mod bench;
@@ -37,6 +40,9 @@ pub mod dns;
 /// Elasticsearch Connector
 #[cfg(feature = "elasticsearch")]
 pub mod elastic;
+/// Subprocesses
+#[cfg(feature = "exec")]
+pub mod exec;
 
 /// file connector implementation
 #[cfg(feature = "file")]
@@ -44,6 +50,9 @@ pub mod file;
 /// HTTP
 #[cfg(feature = "http")]
 pub mod http;
+/// systemd journal
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub mod journald;
 /// Kafka consumer and producer
 #[cfg(feature = "kafka")]
 pub mod kafka;
@@ -56,9 +65,27 @@ pub mod metrics;
 /// Metronome
 #[cfg(feature = "metronome")]
 pub mod metronome;
+/// MQTT
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+/// NATS and `JetStream`
+#[cfg(feature = "nats")]
+pub mod nats;
 /// Never send any events and swallow all events it receives into the void.
 #[cfg(feature = "null")]
 pub mod null;
+/// `PostgreSQL` inserts, upserts, queries and change data capture
+#[cfg(feature = "postgres")]
+pub mod postgres;
+/// Prometheus remote write
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+/// Redis streams, pub/sub and commands
+#[cfg(feature = "redis")]
+pub mod redis;
+/// Embedded `SQLite` databases
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
 /// `WebSockets`
 #[cfg(feature = "websocket")]
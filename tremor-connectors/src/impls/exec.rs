@@ -0,0 +1,523 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `exec` connector spawns an external command and supervises it.
+//!
+//! Events sent to the connector are serialized with the configured codec and postprocessors and
+//! written to the `stdin` of the process. Its `stdout` is read through the preprocessors and codec
+//! and emitted on the `out` port, its `stderr` on the `stderr` port. When the process exits, an
+//! event describing its exit status is emitted on the `exit` port.
+//!
+//! ## Configuration
+//!
+//! | Option     | Description                                                         | Type                  | Required | Default Value                      |
+//! |------------|---------------------------------------------------------------------|-----------------------|----------|------------------------------------|
+//! | `command`  | The program to run, looked up in the `PATH` unless it is a path.    | string                | yes      |                                    |
+//! | `args`     | Arguments passed to the program.                                    | list of strings       | no       | `[]`                               |
+//! | `env`      | Environment variables set for the process, in addition to tremor's. | record of strings     | no       | `{}`                               |
+//! | `cwd`      | The working directory of the process.                               | string                | no       | the working directory of tremor    |
+//! | `buf_size` | The size of the buffer used to read `stdout` and `stderr`.          | positive integer      | no       | `8192`                             |
+//!
+//! ```tremor title="config.troy"
+//! define connector enrich from exec
+//! with
+//!   codec = "json",
+//!   postprocessors = ["separate"],
+//!   preprocessors = ["separate"],
+//!   config = {
+//!     "command": "python3",
+//!     "args": ["-u", "legacy/enrich.py"],
+//!     "env": {"ENRICH_MODE": "strict"}
+//!   },
+//!   reconnect = {
+//!     "retry": {
+//!       "interval_ms": 1000,
+//!       "growth_rate": 2,
+//!       "max_retries": 5
+//!     }
+//!   }
+//! end;
+//!
+//! create connector enrich;
+//!
+//! connect /pipeline/requests to /connector/enrich;
+//! connect /connector/enrich/out to /pipeline/responses;
+//! connect /connector/enrich/stderr to /pipeline/logs;
+//! connect /connector/enrich/exit to /pipeline/alerts;
+//! ```
+//!
+//! Events emitted from `stdout` and `stderr` carry the process id in their metadata:
+//!
+//! ```js
+//! {
+//!   "exec": {
+//!     "pid": 4242
+//!   }
+//! }
+//! ```
+//!
+//! ## Exit status
+//!
+//! When the process exits, the connector emits an event on the `exit` port and the connection is
+//! considered lost, so the process is restarted according to the `reconnect` policy of the
+//! connector. Without a `reconnect` policy the process is not restarted. With a `retry` policy it
+//! is restarted every time it exits, `max_retries` only limits how often starting it may fail in a
+//! row.
+//!
+//! ```js
+//! {
+//!   "code": 1,        // `null` if the process was terminated by a signal
+//!   "signal": null,   // the signal that terminated the process, if any
+//!   "success": false
+//! }
+//! ```
+//!
+//! The process is killed when the connector is stopped or reconnects.
+//!
+//! ## Guaranteed delivery
+//!
+//! Events are acknowledged once they are written to `stdin`. Data read from the process is not
+//! tracked, failed events from `stdout` or `stderr` are not emitted again.
+
+use crate::{
+    errors::error_connector_def,
+    sink::prelude::*,
+    source::{
+        channel_source::{ChannelSource, ChannelSourceRuntime},
+        prelude::*,
+        StreamReader,
+    },
+    StreamDone,
+};
+use std::{collections::HashMap, process::Stdio, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    process::{ChildStdin, Command},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        oneshot,
+    },
+    task,
+};
+use tremor_common::ports::{Port, ERR, OUT};
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::literal;
+
+const URL_SCHEME: &str = "tremor-exec";
+
+const STDERR: Port<'static> = Port::const_str("stderr");
+const EXIT: Port<'static> = Port::const_str("exit");
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default = "crate::utils::default_buf_size")]
+    buf_size: usize,
+}
+
+impl tremor_config::Impl for Config {}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("The process is not running")]
+    NotRunning,
+    #[error("Failed to open the {0} of the process")]
+    NoPipe(&'static str),
+}
+
+/// Builder for the exec connector
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+impl Builder {
+    const INVALID_COMMAND: &'static str = "`command` must not be empty";
+    const INVALID_BUF_SIZE: &'static str = "`buf_size` must be greater than 0";
+}
+
+#[async_trait::async_trait()]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "exec".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        alias: &alias::Connector,
+        _: &ConnectorConfig,
+        conf: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(conf)?;
+        if config.command.is_empty() {
+            return Err(error_connector_def(alias, Self::INVALID_COMMAND).into());
+        }
+        if config.buf_size == 0 {
+            return Err(error_connector_def(alias, Self::INVALID_BUF_SIZE).into());
+        }
+        let (source_tx, source_rx) = channel(qsize());
+        Ok(Box::new(Exec {
+            config,
+            source_tx,
+            source_rx: Some(source_rx),
+        }))
+    }
+}
+
+pub(crate) struct Exec {
+    config: Config,
+    source_tx: Sender<SourceReply>,
+    source_rx: Option<Receiver<SourceReply>>,
+}
+
+impl Exec {
+    const OUTPUT_PORTS: [Port<'static>; 4] = [OUT, ERR, STDERR, EXIT];
+    const REF_OUTPUT_PORTS: &'static [Port<'static>; 4] = &Self::OUTPUT_PORTS;
+}
+
+#[async_trait::async_trait()]
+impl Connector for Exec {
+    fn output_ports(&self) -> &[Port<'static>] {
+        Self::REF_OUTPUT_PORTS
+    }
+
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Required
+    }
+
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        // the source forwards whatever the process writes to stdout and stderr and its exit status
+        let source = ChannelSource::from_channel(
+            self.source_tx.clone(),
+            self.source_rx
+                .take()
+                .ok_or(GenericImplementationError::AlreadyConnected)?,
+            Arc::default(),
+        );
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    async fn create_sink(
+        &mut self,
+        ctx: SinkContext,
+        builder: SinkManagerBuilder,
+    ) -> anyhow::Result<Option<SinkAddr>> {
+        let sink = ExecSink::new(self.config.clone(), self.source_tx.clone());
+        Ok(Some(builder.spawn(sink, ctx)))
+    }
+}
+
+/// Reads `stdout` or `stderr` of the process
+struct ProcessReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    origin_uri: EventOriginUri,
+    meta: Value<'static>,
+    port: Option<Port<'static>>,
+}
+
+#[async_trait::async_trait()]
+impl<R> StreamReader for ProcessReader<R>
+where
+    R: AsyncRead + Unpin + Send + Sync,
+{
+    async fn quiesce(&mut self, stream: u64) -> Option<SourceReply> {
+        Some(SourceReply::EndStream {
+            origin_uri: self.origin_uri.clone(),
+            stream,
+            meta: Some(self.meta.clone()),
+        })
+    }
+
+    async fn read(&mut self, stream: u64) -> anyhow::Result<SourceReply> {
+        let bytes_read = self.reader.read(&mut self.buffer).await?;
+        if bytes_read == 0 {
+            return Ok(SourceReply::EndStream {
+                origin_uri: self.origin_uri.clone(),
+                stream,
+                meta: Some(self.meta.clone()),
+            });
+        }
+        Ok(SourceReply::Data {
+            origin_uri: self.origin_uri.clone(),
+            // ALLOW: we know bytes_read is smaller than or equal buf_size
+            data: self.buffer[0..bytes_read].to_vec(),
+            meta: Some(self.meta.clone()),
+            stream: Some(stream),
+            port: self.port.clone(),
+            codec_overwrite: None,
+        })
+    }
+
+    async fn on_done(&mut self, _stream: u64) -> StreamDone {
+        // losing the process is noticed once it exits
+        StreamDone::StreamClosed
+    }
+}
+
+struct ExecSink {
+    config: Config,
+    source_runtime: ChannelSourceRuntime,
+    source_tx: Sender<SourceReply>,
+    stdin: Option<ChildStdin>,
+    /// kills the running process when dropped
+    kill_tx: Option<oneshot::Sender<()>>,
+}
+
+impl ExecSink {
+    const STDOUT_STREAM: u64 = DEFAULT_STREAM_ID;
+    const STDERR_STREAM: u64 = 1;
+    const EXIT_STREAM: u64 = 2;
+
+    fn new(config: Config, source_tx: Sender<SourceReply>) -> Self {
+        Self {
+            config,
+            source_runtime: ChannelSourceRuntime::new(source_tx.clone()),
+            source_tx,
+            stdin: None,
+            kill_tx: None,
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.config.command);
+        command
+            .args(&self.config.args)
+            .envs(&self.config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = &self.config.cwd {
+            command.current_dir(cwd);
+        }
+        command
+    }
+
+    async fn write(&mut self, data: Vec<Vec<u8>>) -> anyhow::Result<()> {
+        let stdin = self.stdin.as_mut().ok_or(Error::NotRunning)?;
+        for chunk in data {
+            stdin.write_all(&chunk).await?;
+        }
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    fn stop_process(&mut self) {
+        self.stdin = None;
+        // dropping the sender kills the process if it is still running
+        self.kill_tx = None;
+    }
+}
+
+/// The exit status of the process as event payload
+fn exit_status(status: std::process::ExitStatus) -> Value<'static> {
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(not(unix))]
+    let signal: Option<i32> = None;
+    literal!({
+        "code": status.code(),
+        "signal": signal,
+        "success": status.success()
+    })
+}
+
+#[async_trait::async_trait()]
+impl Sink for ExecSink {
+    async fn connect(&mut self, ctx: &SinkContext, _attempt: &Attempt) -> anyhow::Result<bool> {
+        self.stop_process();
+
+        let mut child = self.command().spawn()?;
+        let pid = child.id();
+        let stdin = child.stdin.take().ok_or(Error::NoPipe("stdin"))?;
+        let stdout = child.stdout.take().ok_or(Error::NoPipe("stdout"))?;
+        let stderr = child.stderr.take().ok_or(Error::NoPipe("stderr"))?;
+        debug!("{ctx} Spawned {} with pid {pid:?}", self.config.command);
+
+        let origin_uri = EventOriginUri {
+            scheme: URL_SCHEME.to_string(),
+            host: crate::utils::hostname(),
+            port: None,
+            path: vec![self.config.command.clone()],
+        };
+        let meta = ctx.meta(literal!({ "pid": pid }));
+        let stdout = ProcessReader {
+            reader: stdout,
+            buffer: vec![0; self.config.buf_size],
+            origin_uri: origin_uri.clone(),
+            meta: meta.clone(),
+            port: None,
+        };
+        let stderr = ProcessReader {
+            reader: stderr,
+            buffer: vec![0; self.config.buf_size],
+            origin_uri: origin_uri.clone(),
+            meta: meta.clone(),
+            port: Some(STDERR),
+        };
+        self.source_runtime
+            .register_stream_reader(Self::STDOUT_STREAM, ctx, stdout);
+        self.source_runtime
+            .register_stream_reader(Self::STDERR_STREAM, ctx, stderr);
+
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let source_tx = self.source_tx.clone();
+        let ctx = ctx.clone();
+        task::spawn(async move {
+            tokio::select! {
+                status = child.wait() => {
+                    match status {
+                        Ok(status) => {
+                            info!("{ctx} Process {pid:?} exited with {status}");
+                            let reply = SourceReply::Structured {
+                                origin_uri,
+                                payload: (exit_status(status), meta).into(),
+                                stream: Self::EXIT_STREAM,
+                                port: Some(EXIT),
+                            };
+                            ctx.swallow_err(
+                                source_tx.send(reply).await,
+                                "Failed to send the exit status",
+                            );
+                        }
+                        Err(e) => error!("{ctx} Error waiting for process {pid:?}: {e}"),
+                    }
+                    ctx.swallow_err(
+                        ctx.notifier().connection_lost().await,
+                        "Failed to notify connector",
+                    );
+                }
+                _ = kill_rx => {
+                    debug!("{ctx} Killing process {pid:?}");
+                    ctx.swallow_err(child.kill().await, "Failed to kill the process");
+                }
+            }
+        });
+        self.stdin = Some(stdin);
+        self.kill_tx = Some(kill_tx);
+        Ok(true)
+    }
+
+    async fn on_event(
+        &mut self,
+        _input: &str,
+        event: tremor_system::event::Event,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+        _start: u64,
+    ) -> anyhow::Result<SinkReply> {
+        let ingest_ns = event.ingest_ns;
+        for (value, meta) in event.value_meta_iter() {
+            let data = serializer.serialize(value, meta, ingest_ns).await?;
+            if let Err(e) = self.write(data).await {
+                error!("{ctx} Error writing to the process: {e}. Restarting it...");
+                self.stop_process();
+                ctx.notifier().connection_lost().await?;
+                return Err(e);
+            }
+        }
+        Ok(SinkReply::NONE)
+    }
+
+    async fn finalize(
+        &mut self,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+    ) -> anyhow::Result<()> {
+        let data = serializer.finish_stream(DEFAULT_STREAM_ID)?;
+        if let Err(e) = self.write(data).await {
+            debug!("{ctx} Error flushing to the process: {e}");
+        }
+        Ok(())
+    }
+
+    async fn on_connection_lost(&mut self, _ctx: &SinkContext) -> anyhow::Result<()> {
+        self.stop_process();
+        Ok(())
+    }
+
+    async fn on_stop(&mut self, _ctx: &SinkContext) -> anyhow::Result<()> {
+        self.stop_process();
+        Ok(())
+    }
+
+    fn auto_ack(&self) -> bool {
+        true
+    }
+
+    fn asynchronous(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Connector as ConnectorConfig;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connector_builder_invalid_config() -> anyhow::Result<()> {
+        let alias = alias::Connector::new("flow", "my_exec");
+        let builder = super::Builder::default();
+        let kill_switch = KillSwitch::dummy();
+        for (config, error) in [
+            (
+                literal!({"config": {"command": ""}}),
+                Builder::INVALID_COMMAND,
+            ),
+            (
+                literal!({"config": {"command": "cat", "buf_size": 0}}),
+                Builder::INVALID_BUF_SIZE,
+            ),
+        ] {
+            let connector_config =
+                ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+            let result = builder.build(&alias, &connector_config, &kill_switch).await;
+            assert_eq!(
+                Some(format!("[{alias}] Invalid definition: {error}")),
+                result.err().map(|e| e.to_string())
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_statuses() {
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(
+            literal!({"code": 0, "signal": null, "success": true}),
+            exit_status(std::process::ExitStatus::from_raw(0))
+        );
+        assert_eq!(
+            literal!({"code": 3, "signal": null, "success": false}),
+            exit_status(std::process::ExitStatus::from_raw(3 << 8))
+        );
+        assert_eq!(
+            literal!({"code": null, "signal": 9, "success": false}),
+            exit_status(std::process::ExitStatus::from_raw(9))
+        );
+    }
+}
@@ -0,0 +1,545 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `journald` connector reads the entries of the local [systemd journal](https://www.freedesktop.org/software/systemd/man/latest/systemd-journald.service.html),
+//! following it with `journalctl --output=json --follow`. It is only available on Linux.
+//!
+//! ## Configuration
+//!
+//! | Option        | Description                                                                                        | Type            | Required | Default Value  |
+//! |---------------|----------------------------------------------------------------------------------------------------|-----------------|----------|----------------|
+//! | `units`       | Only read entries of these systemd units.                                                          | list of strings | no       | `[]`           |
+//! | `priority`    | Only read entries with this priority or a more important one, or within a range, e.g. `"0..3"`.    | string          | no       |                |
+//! | `cursor_file` | Where the cursor of the last acknowledged entry is stored, to resume from after a restart.         | string          | no       |                |
+//! | `from_start`  | Read the whole journal if there is no cursor to resume from, instead of new entries only.          | boolean         | no       | `false`        |
+//! | `directory`   | Read the journal files in this directory instead of the journal of the local system.               | string          | no       |                |
+//! | `journalctl`  | The `journalctl` binary to run.                                                                    | string          | no       | `"journalctl"` |
+//!
+//! Priorities are given as names, `emerg`, `alert`, `crit`, `err`, `warning`, `notice`, `info` and
+//! `debug`, or as numbers from `0` to `7`.
+//!
+//! ```tremor title="config.troy"
+//! define connector journal from journald
+//! with
+//!   config = {
+//!     "units": ["nginx.service", "sshd.service"],
+//!     "priority": "warning",
+//!     "cursor_file": "/var/lib/tremor/journal.cursor"
+//!   }
+//! end;
+//! ```
+//!
+//! The user running tremor needs to be allowed to read the journal, e.g. by being a member of the
+//! `systemd-journal` group.
+//!
+//! ## Events
+//!
+//! Each journal entry is emitted as a record of its fields, named as in the journal. Fields holding
+//! binary data are emitted as bytes, fields with several values as a list.
+//!
+//! ```js
+//! {
+//!   "MESSAGE": "Accepted publickey for snot from 10.0.0.1 port 53222 ssh2",
+//!   "PRIORITY": "6",
+//!   "SYSLOG_IDENTIFIER": "sshd",
+//!   "_PID": "4242",
+//!   "_SYSTEMD_UNIT": "sshd.service",
+//!   "_HOSTNAME": "badger"
+//! }
+//! ```
+//!
+//! The position of the entry in the journal is part of its metadata:
+//!
+//! ```tremor
+//! $journald = {
+//!   "cursor": "s=739ad463348b4ceca5a9e69c95a3c93f;i=4ece7;b=6c7c6013a8674d0c9cda42e4cd0ca7b5;...",
+//!   # nanoseconds since the epoch
+//!   "timestamp": 1718871345000000000
+//! }
+//! ```
+//!
+//! ## Guaranteed delivery
+//!
+//! The cursor moves past an entry once it and all entries before it are acknowledged, and is stored
+//! in the `cursor_file`. After a restart the connector resumes after the stored cursor. A failed
+//! event makes the connector read all entries after the cursor again, so entries are delivered at
+//! least once.
+//!
+//! ## Reconnects
+//!
+//! When `journalctl` exits, the connector restarts it according to its `reconnect` configuration
+//! and resumes after the cursor.
+
+use crate::{
+    errors::{error_connector_def, GenericImplementationError},
+    source::prelude::*,
+    Context,
+};
+use std::{collections::BTreeMap, path::PathBuf, process::Stdio};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader, Lines},
+    process::{Child, ChildStdout, Command},
+};
+use tremor_common::alias;
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::prelude::*;
+
+const URL_SCHEME: &str = "tremor-journald";
+
+const PRIORITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    #[serde(default)]
+    units: Vec<String>,
+    priority: Option<String>,
+    cursor_file: Option<String>,
+    #[serde(default)]
+    from_start: bool,
+    directory: Option<String>,
+    #[serde(default = "default_journalctl")]
+    journalctl: String,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_journalctl() -> String {
+    "journalctl".to_string()
+}
+
+impl Config {
+    /// The arguments to follow the journal with, after `cursor` if there is one
+    fn args(&self, cursor: Option<&str>) -> Vec<String> {
+        let mut args = vec![
+            "--output=json".to_string(),
+            "--follow".to_string(),
+            "--no-pager".to_string(),
+        ];
+        if let Some(directory) = &self.directory {
+            args.push(format!("--directory={directory}"));
+        }
+        for unit in &self.units {
+            args.push(format!("--unit={unit}"));
+        }
+        if let Some(priority) = &self.priority {
+            args.push(format!("--priority={priority}"));
+        }
+        if let Some(cursor) = cursor {
+            args.push(format!("--after-cursor={cursor}"));
+        } else if self.from_start {
+            args.push("--lines=all".to_string());
+        } else {
+            args.push("--lines=0".to_string());
+        }
+        args
+    }
+}
+
+/// Checks a priority, or a range of priorities, as understood by `journalctl --priority`
+fn valid_priority(priority: &str) -> bool {
+    let level = |level: &str| {
+        PRIORITIES.contains(&level) || level.parse::<u8>().is_ok_and(|level| level < 8)
+    };
+    match priority.split_once("..") {
+        Some((from, to)) => level(from) && level(to),
+        None => level(priority),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Journal entry without a cursor")]
+    NoCursor,
+    #[error("`journalctl` exited with {0}")]
+    Exited(std::process::ExitStatus),
+}
+
+/// systemd journal
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+impl Builder {
+    const INVALID_PRIORITY: &'static str =
+        "`priority` must be one of `emerg`, `alert`, `crit`, `err`, `warning`, `notice`, `info`, `debug`, `0` to `7`, or a range of them like `0..3`";
+    const EMPTY_UNIT: &'static str = "`units` can't contain empty names";
+}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "journald".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        if config
+            .priority
+            .as_deref()
+            .is_some_and(|priority| !valid_priority(priority))
+        {
+            return Err(error_connector_def(id, Self::INVALID_PRIORITY).into());
+        }
+        if config.units.iter().any(String::is_empty) {
+            return Err(error_connector_def(id, Self::EMPTY_UNIT).into());
+        }
+        Ok(Box::new(Journald { config }))
+    }
+}
+
+pub(crate) struct Journald {
+    config: Config,
+}
+
+#[async_trait::async_trait]
+impl Connector for Journald {
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        let source = JournaldSource {
+            origin_uri: EventOriginUri {
+                scheme: URL_SCHEME.to_string(),
+                host: crate::utils::hostname(),
+                port: None,
+                path: self.config.directory.iter().cloned().collect(),
+            },
+            config: self.config.clone(),
+            journal: None,
+            cursor: None,
+            unacked: BTreeMap::new(),
+            rewind: false,
+        };
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Structured
+    }
+}
+
+/// A running `journalctl`
+struct Journal {
+    // killed when dropped
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+struct JournaldSource {
+    config: Config,
+    origin_uri: EventOriginUri,
+    journal: Option<Journal>,
+    /// the cursor of the last acknowledged entry
+    cursor: Option<String>,
+    /// the cursors of pulled entries and whether they are acked, by pull id
+    unacked: BTreeMap<u64, (String, bool)>,
+    /// a failed entry needs to be read again
+    rewind: bool,
+}
+
+impl JournaldSource {
+    fn start(&mut self, ctx: &SourceContext) -> anyhow::Result<()> {
+        self.journal = None;
+        self.unacked.clear();
+        self.rewind = false;
+        let args = self.config.args(self.cursor.as_deref());
+        debug!(
+            "{ctx} Running {} {}",
+            self.config.journalctl,
+            args.join(" ")
+        );
+        let mut child = Command::new(&self.config.journalctl)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(GenericImplementationError::ClientNotAvailable("journalctl"))?;
+        self.journal = Some(Journal {
+            child,
+            lines: BufReader::new(stdout).lines(),
+        });
+        Ok(())
+    }
+
+    /// Stores the cursor, replacing the `cursor_file` at once so it is never left half written
+    async fn store_cursor(&self, cursor: &str) -> anyhow::Result<()> {
+        if let Some(path) = &self.config.cursor_file {
+            let tmp = PathBuf::from(format!("{path}.tmp"));
+            tokio::fs::write(&tmp, cursor).await?;
+            tokio::fs::rename(&tmp, path).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns a journal entry into its fields and its position in the journal
+fn entry(mut data: Vec<u8>) -> anyhow::Result<(Value<'static>, Value<'static>, String)> {
+    let entry = tremor_value::parse_to_value(&mut data)?.into_static();
+    let mut fields = Value::object();
+    let mut cursor = None;
+    let mut timestamp = None;
+    if let Value::Object(entry) = entry {
+        for (name, value) in *entry {
+            match name.as_ref() {
+                "__CURSOR" => cursor = value.as_str().map(String::from),
+                "__REALTIME_TIMESTAMP" => {
+                    timestamp = value
+                        .as_str()
+                        .and_then(|us| us.parse::<u64>().ok())
+                        .map(|us| us.saturating_mul(1000));
+                }
+                // the other address fields, like `__MONOTONIC_TIMESTAMP`, are of no use outside the journal
+                name if name.starts_with("__") => {}
+                _ => {
+                    fields.try_insert(name, field(value));
+                }
+            }
+        }
+    }
+    let cursor = cursor.ok_or(Error::NoCursor)?;
+    let meta = literal!({
+        "cursor": cursor.clone(),
+        "timestamp": timestamp
+    });
+    Ok((fields, meta, cursor))
+}
+
+/// journalctl encodes binary values as lists of bytes and fields with several values as lists
+fn field(value: Value<'static>) -> Value<'static> {
+    match value {
+        Value::Array(values) if values.iter().all(|byte| byte.as_u8().is_some()) => {
+            let bytes: Vec<u8> = values.iter().filter_map(ValueAsScalar::as_u8).collect();
+            match String::from_utf8(bytes) {
+                Ok(string) => Value::from(string),
+                Err(e) => Value::Bytes(e.into_bytes().into()),
+            }
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(field).collect()),
+        value => value,
+    }
+}
+
+#[async_trait::async_trait]
+impl Source for JournaldSource {
+    async fn connect(&mut self, ctx: &SourceContext, _attempt: &Attempt) -> anyhow::Result<bool> {
+        if self.cursor.is_none() {
+            if let Some(path) = &self.config.cursor_file {
+                match tokio::fs::read_to_string(path).await {
+                    Ok(cursor) if !cursor.trim().is_empty() => {
+                        self.cursor = Some(cursor.trim().to_string());
+                    }
+                    Ok(_) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        self.start(ctx)?;
+        Ok(true)
+    }
+
+    async fn pull_data(
+        &mut self,
+        pull_id: &mut u64,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<SourceReply> {
+        if self.rewind {
+            self.start(ctx)?;
+        }
+        let journal = self
+            .journal
+            .as_mut()
+            .ok_or(GenericImplementationError::ClientNotAvailable("journalctl"))?;
+        loop {
+            // `next_line` can be cancelled without losing data
+            let Some(line) = journal.lines.next_line().await? else {
+                let status = journal.child.wait().await?;
+                self.journal = None;
+                ctx.notifier().connection_lost().await?;
+                return Err(Error::Exited(status).into());
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (fields, meta, cursor) = entry(line.into_bytes())?;
+            self.unacked.insert(*pull_id, (cursor, false));
+            return Ok(SourceReply::Structured {
+                origin_uri: self.origin_uri.clone(),
+                payload: (fields, ctx.meta(meta)).into(),
+                stream: DEFAULT_STREAM_ID,
+                port: None,
+            });
+        }
+    }
+
+    async fn ack(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let Some((_, acked)) = self.unacked.get_mut(&pull_id) else {
+            return Ok(());
+        };
+        *acked = true;
+        // the cursor moves past the entries that are acked, in order
+        let mut cursor = None;
+        while let Some(entry) = self.unacked.first_entry() {
+            if !entry.get().1 {
+                break;
+            }
+            cursor = Some(entry.remove().0);
+        }
+        if let Some(cursor) = cursor {
+            self.store_cursor(&cursor).await?;
+            self.cursor = Some(cursor);
+        }
+        Ok(())
+    }
+
+    async fn fail(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        // everything after the cursor is read again
+        if self.unacked.contains_key(&pull_id) {
+            self.rewind = true;
+        }
+        Ok(())
+    }
+
+    async fn on_connection_lost(&mut self, _ctx: &SourceContext) -> anyhow::Result<()> {
+        // entries after the cursor are read again after restarting
+        self.unacked.clear();
+        Ok(())
+    }
+
+    fn is_transactional(&self) -> bool {
+        true
+    }
+
+    fn asynchronous(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Connector as ConnectorConfig;
+
+    #[test]
+    fn arguments() -> anyhow::Result<()> {
+        let config = Config::new(&literal!({
+            "units": ["nginx.service", "sshd.service"],
+            "priority": "warning",
+            "directory": "/var/log/journal"
+        }))?;
+        assert_eq!(
+            vec![
+                "--output=json",
+                "--follow",
+                "--no-pager",
+                "--directory=/var/log/journal",
+                "--unit=nginx.service",
+                "--unit=sshd.service",
+                "--priority=warning",
+                "--lines=0"
+            ],
+            config.args(None)
+        );
+        let config = Config::new(&literal!({"from_start": true}))?;
+        assert_eq!(
+            Some("--lines=all"),
+            config.args(None).last().map(String::as_str)
+        );
+        assert_eq!(
+            Some("--after-cursor=s=snot;i=1"),
+            config.args(Some("s=snot;i=1")).last().map(String::as_str)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn priorities() {
+        for priority in ["err", "3", "0..3", "emerg..warning"] {
+            assert!(valid_priority(priority), "{priority}");
+        }
+        for priority in ["", "error", "8", "0..", "..3", "err..badger"] {
+            assert!(!valid_priority(priority), "{priority}");
+        }
+    }
+
+    #[test]
+    fn entries() -> anyhow::Result<()> {
+        let line = r#"{"__CURSOR":"s=snot;i=1","__REALTIME_TIMESTAMP":"1718871345000000","__MONOTONIC_TIMESTAMP":"42","MESSAGE":"badger","PRIORITY":"6","_SYSTEMD_UNIT":"sshd.service","BINARY":[104,105],"RAW":[255,0],"TAGS":["a",[98]]}"#;
+        let (fields, meta, cursor) = entry(line.as_bytes().to_vec())?;
+        assert_eq!("s=snot;i=1", cursor);
+        assert_eq!(
+            literal!({"cursor": "s=snot;i=1", "timestamp": 1_718_871_345_000_000_000_u64}),
+            meta
+        );
+        assert_eq!(
+            literal!({
+                "MESSAGE": "badger",
+                "PRIORITY": "6",
+                "_SYSTEMD_UNIT": "sshd.service",
+                "BINARY": "hi",
+                "RAW": Value::Bytes(vec![255_u8, 0].into()),
+                "TAGS": ["a", "b"]
+            }),
+            fields
+        );
+        assert!(entry(br#"{"MESSAGE":"badger"}"#.to_vec()).is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connector_builder_invalid_config() -> anyhow::Result<()> {
+        let alias = alias::Connector::new("flow", "my_journal");
+        let builder = super::Builder::default();
+        let kill_switch = KillSwitch::dummy();
+        for (config, error) in [
+            (
+                literal!({"config": {"priority": "error"}}),
+                Builder::INVALID_PRIORITY,
+            ),
+            (literal!({"config": {"units": [""]}}), Builder::EMPTY_UNIT),
+        ] {
+            let connector_config =
+                ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+            let result = builder.build(&alias, &connector_config, &kill_switch).await;
+            assert_eq!(
+                Some(format!("[{alias}] Invalid definition: {error}")),
+                result.err().map(|e| e.to_string())
+            );
+        }
+        Ok(())
+    }
+}
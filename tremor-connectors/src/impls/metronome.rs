@@ -246,6 +246,7 @@ mod tests {
             postprocessors: None,
             reconnect: Reconnect::None,
             metrics_interval_s: Some(5),
+            spill: None,
         };
         let kill_switch = KillSwitch::dummy();
         assert!(builder
@@ -0,0 +1,763 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `mqtt` connector connects to an [MQTT](https://mqtt.org/) broker speaking MQTT 3.1.1 or MQTT 5.
+//!
+//! Its source receives messages from the subscribed `topics`, its sink publishes events to the broker.
+//! Both share one connection, so a single connector can be used for request/reply style flows.
+//!
+//! ## Configuration
+//!
+//! | Option          | Description                                                                                   | Type                    | Required | Default Value              |
+//! |-----------------|-----------------------------------------------------------------------------------------------|-------------------------|----------|----------------------------|
+//! | `url`           | The broker to connect to, `mqtts://` urls use TLS.                                            | string                  | yes      |                            |
+//! | `protocol`      | The protocol version, either `"3.1.1"` or `"5"`.                                              | string                  | no       | `"3.1.1"`                  |
+//! | `client_id`     | The client identifier, it names the session on the broker.                                    | string                  | no       | `tremor-<host>-<alias>`    |
+//! | `topics`        | Topic filters to subscribe to, wildcards `+` and `#` are supported.                           | list of strings         | no       | `[]`                       |
+//! | `topic`         | The topic the sink publishes to, unless `$mqtt.topic` is set.                                 | string                  | no       |                            |
+//! | `qos`           | The `QoS` of subscriptions and published messages: `0`, `1` or `2`.                           | integer                 | no       | `1`                        |
+//! | `retain`        | Whether published messages are retained by the broker.                                        | boolean                 | no       | `false`                    |
+//! | `clean_session` | Start a new session on every connect. Called clean start in MQTT 5.                           | boolean                 | no       | `true`                     |
+//! | `keep_alive`    | Keep alive interval in nanoseconds.                                                           | integer                 | no       | 30 seconds                 |
+//! | `username`      | Username to authenticate with.                                                                | string                  | no       |                            |
+//! | `password`      | Password to authenticate with.                                                                | string                  | no       |                            |
+//! | `last_will`     | Message the broker publishes when the connection is lost: `topic`, `message`, `qos`, `retain`.| record                  | no       |                            |
+//! | `tls`           | `true` or a TLS client configuration, see [`TLSClientConfig`].                                | boolean or record       | no       | `true` for `mqtts://` urls |
+//!
+//! ```tremor title="config.troy"
+//! define connector sensors from mqtt
+//! with
+//!   codec = "json",
+//!   config = {
+//!     "url": "mqtts://broker.local:8883",
+//!     "protocol": "5",
+//!     "client_id": "tremor-sensors",
+//!     "topics": ["sensors/+/temperature", "alerts/#"],
+//!     "topic": "aggregates",
+//!     "qos": 1,
+//!     "clean_session": false,
+//!     "last_will": {"topic": "status/tremor", "message": "offline", "retain": true}
+//!   }
+//! end;
+//! ```
+//!
+//! ## Guaranteed delivery
+//!
+//! Received `QoS` 1 and 2 messages are acknowledged to the broker once the event is acknowledged
+//! downstream. When an event fails and `clean_session` is `false` the connector reconnects, so the
+//! broker redelivers everything that was not acknowledged yet. With a clean session the broker would drop
+//! those messages anyway, so failed messages are acknowledged instead.
+//!
+//! Published events are acknowledged once the broker confirmed all their messages, with a `PUBACK` for `QoS` 1 and
+//! a `PUBCOMP` for `QoS` 2. `QoS` 0 messages are acknowledged as soon as they are written to the connection.
+//! Events still waiting for a confirmation when the connection is lost are failed.
+//!
+//! ## Metadata
+//!
+//! Received events carry the following metadata:
+//!
+//! ```js
+//! {
+//!   "$mqtt": {
+//!     "topic": "sensors/kitchen/temperature",
+//!     "qos": 1,
+//!     "retain": false,
+//!     "dup": false,
+//!     # MQTT 5 only, and only those properties the message has
+//!     "properties": {
+//!       "payload_format_indicator": 1,
+//!       "message_expiry_interval": 60,
+//!       "content_type": "application/json",
+//!       "response_topic": "replies",
+//!       "correlation_data": <<"42">>,
+//!       "user_properties": {"source": "kitchen"}
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The sink reads `topic`, `qos`, `retain` and, for MQTT 5, `properties` from the `$mqtt` metadata of an event
+//! and falls back to the connector configuration for anything that is not set.
+
+mod client;
+
+use crate::{
+    errors::error_connector_def,
+    sink::prelude::*,
+    source::prelude::*,
+    spawn_task,
+    utils::{hostname, tls::TLSClientConfig},
+};
+use client::{Client, EventLoop, Message, Notification};
+use either::Either;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Mutex, RwLock,
+    },
+    task::JoinHandle,
+    time::timeout,
+};
+use tremor_common::{
+    time::nanotime,
+    url::{Defaults, Url},
+};
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::prelude::*;
+
+const URL_SCHEME: &str = "tremor-mqtt";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub(crate) struct MqttDefaults;
+impl Defaults for MqttDefaults {
+    const SCHEME: &'static str = "mqtt";
+    const HOST: &'static str = "localhost";
+    const PORT: u16 = 1883;
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    #[default]
+    #[serde(rename = "3.1.1")]
+    V3,
+    #[serde(rename = "5")]
+    V5,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(try_from = "u8")]
+#[allow(clippy::enum_variant_names)] // named like the levels in the MQTT spec
+pub(crate) enum Qos {
+    AtMostOnce,
+    #[default]
+    AtLeastOnce,
+    ExactlyOnce,
+}
+
+impl TryFrom<u8> for Qos {
+    type Error = Error;
+    fn try_from(qos: u8) -> Result<Self, Self::Error> {
+        match qos {
+            0 => Ok(Self::AtMostOnce),
+            1 => Ok(Self::AtLeastOnce),
+            2 => Ok(Self::ExactlyOnce),
+            other => Err(Error::InvalidQos(other)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LastWill {
+    topic: String,
+    message: String,
+    #[serde(default)]
+    qos: Qos,
+    #[serde(default)]
+    retain: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    url: Url<MqttDefaults>,
+    #[serde(default)]
+    protocol: Protocol,
+    client_id: Option<String>,
+    /// topic filters to subscribe to
+    #[serde(default)]
+    topics: Vec<String>,
+    /// default topic to publish to
+    topic: Option<String>,
+    #[serde(default)]
+    qos: Qos,
+    #[serde(default)]
+    retain: bool,
+    #[serde(default = "default_clean_session")]
+    clean_session: bool,
+    /// keep alive interval in nanoseconds
+    #[serde(default = "default_keep_alive")]
+    keep_alive: u64,
+    username: Option<String>,
+    password: Option<String>,
+    last_will: Option<LastWill>,
+    #[serde(with = "either::serde_untagged_optional", default = "Default::default")]
+    tls: Option<Either<TLSClientConfig, bool>>,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_clean_session() -> bool {
+    true
+}
+
+fn default_keep_alive() -> u64 {
+    30_000_000_000 // 30 seconds
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Invalid QoS {0}, expected 0, 1 or 2")]
+    InvalidQos(u8),
+    #[error(
+        "No topic to publish to, set `topic` in the config or `$mqtt.topic` in the event metadata"
+    )]
+    MissingTopic,
+}
+
+/// MQTT connector
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+impl Builder {
+    const INVALID_KEEP_ALIVE: &'static str = "`keep_alive` must be at least one second";
+}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "mqtt".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        // rumqttc rejects keep alive intervals below a second
+        if config.keep_alive < 1_000_000_000 {
+            return Err(error_connector_def(id, Self::INVALID_KEEP_ALIVE).into());
+        }
+        let tls = match config.tls.as_ref() {
+            Some(Either::Left(tls_config)) => Some(Arc::new(tls_config.to_client_config()?)),
+            Some(Either::Right(true)) => {
+                Some(Arc::new(TLSClientConfig::default().to_client_config()?))
+            }
+            None if config.url.scheme() == "mqtts" => {
+                Some(Arc::new(TLSClientConfig::default().to_client_config()?))
+            }
+            Some(Either::Right(false)) | None => None,
+        };
+        let client_id = config
+            .client_id
+            .clone()
+            .unwrap_or_else(|| format!("tremor-{}-{}", hostname(), id.connector_alias()));
+        let origin_uri = EventOriginUri {
+            scheme: URL_SCHEME.to_string(),
+            host: config.url.host_or_local().to_string(),
+            port: Some(config.url.port_or_dflt()),
+            path: vec![],
+        };
+        let (source_tx, source_rx) = channel(qsize());
+        Ok(Box::new(Mqtt {
+            config,
+            client_id,
+            tls,
+            origin_uri,
+            client: Arc::new(RwLock::new(None)),
+            deliveries: Arc::new(Mutex::new(Deliveries::default())),
+            source_tx,
+            source_rx: Some(source_rx),
+            eventloop_task: None,
+        }))
+    }
+}
+
+pub(crate) struct Mqtt {
+    config: Config,
+    client_id: String,
+    tls: Option<Arc<rustls::ClientConfig>>,
+    origin_uri: EventOriginUri,
+    /// the client of the current connection, shared by source and sink
+    client: Arc<RwLock<Option<Client>>>,
+    deliveries: Arc<Mutex<Deliveries>>,
+    source_tx: Sender<Message>,
+    source_rx: Option<Receiver<Message>>,
+    eventloop_task: Option<JoinHandle<()>>,
+}
+
+#[async_trait::async_trait()]
+impl Connector for Mqtt {
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Required
+    }
+
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        let source = MqttSource {
+            rx: self
+                .source_rx
+                .take()
+                .ok_or(GenericImplementationError::AlreadyConnected)?,
+            client: self.client.clone(),
+            unacked: HashMap::new(),
+            origin_uri: self.origin_uri.clone(),
+            clean_session: self.config.clean_session,
+        };
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    async fn create_sink(
+        &mut self,
+        ctx: SinkContext,
+        builder: SinkManagerBuilder,
+    ) -> anyhow::Result<Option<SinkAddr>> {
+        self.deliveries.lock().await.reply_tx = Some(builder.reply_tx());
+        let sink = MqttSink {
+            client: self.client.clone(),
+            deliveries: self.deliveries.clone(),
+            topic: self.config.topic.clone(),
+            qos: self.config.qos,
+            retain: self.config.retain,
+        };
+        Ok(Some(builder.spawn(sink, ctx)))
+    }
+
+    async fn connect(
+        &mut self,
+        ctx: &ConnectorContext,
+        _attempt: &Attempt,
+    ) -> anyhow::Result<bool> {
+        if let Some(task) = self.eventloop_task.take() {
+            task.abort();
+        }
+        // publishes of a previous connection will never be confirmed
+        self.deliveries.lock().await.fail_all();
+
+        let (client, mut eventloop) = Client::new(&self.config, &self.client_id, self.tls.clone());
+        // subscriptions are queued and sent once the connection is established
+        for topic in &self.config.topics {
+            client.subscribe(topic, self.config.qos).await?;
+        }
+        timeout(CONNECT_TIMEOUT, async {
+            loop {
+                if let Notification::Connected = eventloop.poll().await? {
+                    return anyhow::Ok(());
+                }
+            }
+        })
+        .await??;
+        info!(
+            "{ctx} Connected to {} as {}",
+            self.config.url, self.client_id
+        );
+
+        *self.client.write().await = Some(client);
+        self.eventloop_task = Some(spawn_task(
+            ctx.clone(),
+            run(
+                ctx.clone(),
+                eventloop,
+                self.source_tx.clone(),
+                self.deliveries.clone(),
+            ),
+        ));
+        Ok(true)
+    }
+
+    async fn on_stop(&mut self, ctx: &ConnectorContext) -> anyhow::Result<()> {
+        // a clean disconnect keeps the broker from publishing the last will
+        if let Some(client) = self.client.write().await.take() {
+            ctx.swallow_err(client.disconnect().await, "Error disconnecting");
+        }
+        if let Some(mut task) = self.eventloop_task.take() {
+            if timeout(DISCONNECT_TIMEOUT, &mut task).await.is_err() {
+                task.abort();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Polls the connection until it fails or is disconnected
+async fn run(
+    ctx: ConnectorContext,
+    mut eventloop: EventLoop,
+    source_tx: Sender<Message>,
+    deliveries: Arc<Mutex<Deliveries>>,
+) -> anyhow::Result<()> {
+    let res = loop {
+        match eventloop.poll().await {
+            Ok(Notification::Received(message)) => {
+                if source_tx.send(message).await.is_err() {
+                    break Err(GenericImplementationError::ChannelEmpty.into());
+                }
+            }
+            Ok(Notification::Sent(pkid)) => deliveries.lock().await.sent(pkid),
+            Ok(Notification::Delivered(pkid)) => deliveries.lock().await.delivered(pkid),
+            Ok(Notification::Rejected(pkid, reason)) => {
+                warn!("{ctx} Broker rejected message {pkid}: {reason}");
+                deliveries.lock().await.rejected(pkid);
+            }
+            Ok(Notification::Disconnected) => break Ok(()),
+            Ok(Notification::Connected | Notification::Other) => (),
+            Err(e) => break Err(e),
+        }
+    };
+    deliveries.lock().await.fail_all();
+    res
+}
+
+/// An event published by the sink, acknowledged once the broker confirmed all of its messages
+struct Delivery {
+    cf: ContraflowData,
+    start: u64,
+    /// unconfirmed messages, plus one for the sink while it is still publishing
+    outstanding: AtomicUsize,
+    /// whether the event was acknowledged or failed already
+    done: AtomicBool,
+}
+
+impl Delivery {
+    fn new(cf: ContraflowData, start: u64) -> Self {
+        Self {
+            cf,
+            start,
+            outstanding: AtomicUsize::new(1),
+            done: AtomicBool::new(false),
+        }
+    }
+
+    /// Forgets about the event without replying, the sink manager fails it
+    fn abandon(&self) {
+        self.done.store(true, Ordering::Release);
+    }
+}
+
+/// Follows the messages of the sink from the queue of the client, onto the connection,
+/// up to the confirmation of the broker
+#[derive(Default)]
+struct Deliveries {
+    reply_tx: Option<ReplySender>,
+    /// messages handed to the client, in the order they are sent
+    queued: VecDeque<Option<Arc<Delivery>>>,
+    /// `QoS` 1 and 2 messages waiting for a confirmation, by packet id
+    in_flight: HashMap<u16, Arc<Delivery>>,
+}
+
+impl Deliveries {
+    fn queue(&mut self, delivery: Option<&Arc<Delivery>>) {
+        if let Some(delivery) = delivery {
+            delivery.outstanding.fetch_add(1, Ordering::AcqRel);
+        }
+        self.queued.push_back(delivery.cloned());
+    }
+
+    fn sent(&mut self, pkid: u16) {
+        if let Some(Some(delivery)) = self.queued.pop_front() {
+            if pkid == 0 {
+                // QoS 0, there is nothing more to wait for
+                self.confirm(&delivery);
+            } else {
+                self.in_flight.insert(pkid, delivery);
+            }
+        }
+    }
+
+    fn delivered(&mut self, pkid: u16) {
+        if let Some(delivery) = self.in_flight.remove(&pkid) {
+            self.confirm(&delivery);
+        }
+    }
+
+    fn rejected(&mut self, pkid: u16) {
+        if let Some(delivery) = self.in_flight.remove(&pkid) {
+            self.fail(&delivery);
+        }
+    }
+
+    fn confirm(&self, delivery: &Delivery) {
+        if delivery.outstanding.fetch_sub(1, Ordering::AcqRel) == 1
+            && !delivery.done.swap(true, Ordering::AcqRel)
+        {
+            let duration = nanotime() - delivery.start;
+            self.reply(AsyncSinkReply::Ack(delivery.cf.clone(), duration));
+        }
+    }
+
+    fn fail(&self, delivery: &Delivery) {
+        if !delivery.done.swap(true, Ordering::AcqRel) {
+            self.reply(AsyncSinkReply::Fail(delivery.cf.clone()));
+        }
+    }
+
+    fn fail_all(&mut self) {
+        let queued = self.queued.drain(..).flatten();
+        let in_flight = self.in_flight.drain().map(|(_, delivery)| delivery);
+        for delivery in queued.chain(in_flight).collect::<Vec<_>>() {
+            self.fail(&delivery);
+        }
+    }
+
+    fn reply(&self, reply: AsyncSinkReply) {
+        if let Some(reply_tx) = self.reply_tx.as_ref() {
+            if reply_tx.send(reply).is_err() {
+                error!("Error sending MQTT delivery reply");
+            }
+        }
+    }
+}
+
+struct MqttSource {
+    rx: Receiver<Message>,
+    client: Arc<RwLock<Option<Client>>>,
+    /// `QoS` 1 and 2 messages waiting for the event to be acknowledged, by pull id
+    unacked: HashMap<u64, Message>,
+    origin_uri: EventOriginUri,
+    clean_session: bool,
+}
+
+impl MqttSource {
+    async fn ack_message(&self, message: &Message) -> anyhow::Result<()> {
+        if let Some(client) = self.client.read().await.as_ref() {
+            client.ack(message).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait()]
+impl Source for MqttSource {
+    async fn pull_data(
+        &mut self,
+        pull_id: &mut u64,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<SourceReply> {
+        let message = self
+            .rx
+            .recv()
+            .await
+            .ok_or(GenericImplementationError::ChannelEmpty)?;
+        let data = message.payload().to_vec();
+        let meta = ctx.meta(message.meta());
+        if message.needs_ack() {
+            self.unacked.insert(*pull_id, message);
+        }
+        Ok(SourceReply::Data {
+            origin_uri: self.origin_uri.clone(),
+            data,
+            meta: Some(meta),
+            stream: Some(DEFAULT_STREAM_ID),
+            port: None,
+            codec_overwrite: None,
+        })
+    }
+
+    async fn ack(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        if let Some(message) = self.unacked.remove(&pull_id) {
+            self.ack_message(&message).await?;
+        }
+        Ok(())
+    }
+
+    async fn fail(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        if let Some(message) = self.unacked.remove(&pull_id) {
+            if self.clean_session {
+                warn!("{ctx} Acknowledging failed message, set `clean_session` to `false` to have it redelivered");
+                self.ack_message(&message).await?;
+            } else {
+                // the broker resends all unacknowledged messages of the session once we reconnect
+                ctx.notifier().connection_lost().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_connection_lost(&mut self, _ctx: &SourceContext) -> anyhow::Result<()> {
+        // packet ids are only valid on the connection the message was received on
+        self.unacked.clear();
+        Ok(())
+    }
+
+    fn is_transactional(&self) -> bool {
+        true
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+}
+
+struct MqttSink {
+    client: Arc<RwLock<Option<Client>>>,
+    deliveries: Arc<Mutex<Deliveries>>,
+    topic: Option<String>,
+    qos: Qos,
+    retain: bool,
+}
+
+impl MqttSink {
+    async fn publish(
+        &self,
+        client: &Client,
+        event: &Event,
+        delivery: Option<&Arc<Delivery>>,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+    ) -> anyhow::Result<()> {
+        for (value, meta) in event.value_meta_iter() {
+            let mqtt_meta = ctx.extract_meta(meta);
+            let topic = mqtt_meta
+                .get_str("topic")
+                .or(self.topic.as_deref())
+                .ok_or(Error::MissingTopic)?;
+            let qos = mqtt_meta
+                .get_u8("qos")
+                .map(Qos::try_from)
+                .transpose()?
+                .unwrap_or(self.qos);
+            let retain = mqtt_meta.get_bool("retain").unwrap_or(self.retain);
+            let properties = mqtt_meta.get("properties");
+            for payload in serializer
+                .serialize_non_streaming(value, meta, event.ingest_ns)
+                .await?
+            {
+                // the client sends messages in the order they are queued
+                self.deliveries.lock().await.queue(delivery);
+                if let Err(e) = client
+                    .publish(topic, qos, retain, payload, properties)
+                    .await
+                {
+                    error!("{ctx} Error publishing message: {e}. Initiating reconnect...");
+                    ctx.notifier().connection_lost().await?;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait()]
+impl Sink for MqttSink {
+    async fn on_event(
+        &mut self,
+        _input: &str,
+        event: Event,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+        start: u64,
+    ) -> anyhow::Result<SinkReply> {
+        let client = self
+            .client
+            .read()
+            .await
+            .clone()
+            .ok_or(GenericImplementationError::ClientNotAvailable("MQTT"))?;
+        let delivery = event
+            .transactional
+            .then(|| Arc::new(Delivery::new(ContraflowData::from(&event), start)));
+        let res = self
+            .publish(&client, &event, delivery.as_ref(), ctx, serializer)
+            .await;
+        if let Some(delivery) = delivery {
+            if res.is_ok() {
+                // release the hold of the sink, the broker confirms the rest
+                self.deliveries.lock().await.confirm(&delivery);
+            } else {
+                delivery.abandon();
+            }
+        }
+        res.map(|()| SinkReply::NONE)
+    }
+
+    async fn finalize(
+        &mut self,
+        _ctx: &SinkContext,
+        _serializer: &mut EventSerializer,
+    ) -> anyhow::Result<()> {
+        // every message is serialized on its own in `on_event`
+        Ok(())
+    }
+
+    fn auto_ack(&self) -> bool {
+        false
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qos() {
+        assert_eq!(
+            Ok(Qos::ExactlyOnce),
+            Qos::try_from(2).map_err(|e| e.to_string())
+        );
+        assert!(Qos::try_from(3).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deliveries() {
+        let (reply_tx, mut reply_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut deliveries = Deliveries {
+            reply_tx: Some(reply_tx),
+            ..Deliveries::default()
+        };
+        let event = Event {
+            transactional: true,
+            ..Event::default()
+        };
+        // one QoS 0 and one QoS 1 message
+        let delivery = Arc::new(Delivery::new(ContraflowData::from(&event), nanotime()));
+        deliveries.queue(Some(&delivery));
+        deliveries.queue(Some(&delivery));
+        deliveries.confirm(&delivery);
+        deliveries.sent(0);
+        deliveries.sent(7);
+        assert!(reply_rx.try_recv().is_err());
+        deliveries.delivered(7);
+        assert!(matches!(reply_rx.try_recv(), Ok(AsyncSinkReply::Ack(..))));
+
+        // losing the connection fails what is still in flight, once
+        let delivery = Arc::new(Delivery::new(ContraflowData::from(&event), nanotime()));
+        deliveries.queue(Some(&delivery));
+        deliveries.queue(Some(&delivery));
+        deliveries.confirm(&delivery);
+        deliveries.sent(8);
+        deliveries.fail_all();
+        assert!(matches!(reply_rx.try_recv(), Ok(AsyncSinkReply::Fail(..))));
+        assert!(reply_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A wrapper around the `rumqttc` MQTT 3.1.1 and MQTT 5 clients, so the connector
+//! does not need to care which protocol version it speaks.
+
+use super::{Config, Protocol, Qos};
+use rumqttc::v5::{
+    self,
+    mqttbytes::v5::{PubAckReason, PubRecReason, PublishProperties},
+};
+use rumqttc::{TlsConfiguration, Transport};
+use rustls::ClientConfig;
+use std::{sync::Arc, time::Duration};
+use tremor_value::prelude::*;
+
+impl From<Qos> for rumqttc::QoS {
+    fn from(qos: Qos) -> Self {
+        match qos {
+            Qos::AtMostOnce => Self::AtMostOnce,
+            Qos::AtLeastOnce => Self::AtLeastOnce,
+            Qos::ExactlyOnce => Self::ExactlyOnce,
+        }
+    }
+}
+
+impl From<Qos> for v5::mqttbytes::QoS {
+    fn from(qos: Qos) -> Self {
+        match qos {
+            Qos::AtMostOnce => Self::AtMostOnce,
+            Qos::AtLeastOnce => Self::AtLeastOnce,
+            Qos::ExactlyOnce => Self::ExactlyOnce,
+        }
+    }
+}
+
+/// Handle to publish, subscribe and acknowledge messages
+#[derive(Clone)]
+pub(super) enum Client {
+    V3(rumqttc::AsyncClient),
+    V5(v5::AsyncClient),
+}
+
+/// Drives the connection, it has to be polled for anything to happen
+pub(super) enum EventLoop {
+    V3(rumqttc::EventLoop),
+    V5(v5::EventLoop),
+}
+
+/// A message received from the broker, kept around until it is acknowledged
+pub(super) enum Message {
+    V3(rumqttc::Publish),
+    V5(v5::mqttbytes::v5::Publish),
+}
+
+/// What happened on the connection, as far as the connector is concerned
+pub(super) enum Notification {
+    /// The broker accepted the connection
+    Connected,
+    /// A message arrived on one of the subscribed topics
+    Received(Message),
+    /// A publish was written to the connection, `0` is the packet id for `QoS` 0
+    Sent(u16),
+    /// The broker confirmed a `QoS` 1 or 2 publish
+    Delivered(u16),
+    /// The broker refused a `QoS` 1 or 2 publish, this only happens with MQTT 5
+    Rejected(u16, String),
+    /// We asked the broker to close the connection
+    Disconnected,
+    /// Anything else, like pings and subscription acknowledgements
+    Other,
+}
+
+impl Client {
+    pub(super) fn new(
+        config: &Config,
+        client_id: &str,
+        tls: Option<Arc<ClientConfig>>,
+    ) -> (Self, EventLoop) {
+        let host = config.url.host_or_local();
+        let port = config.url.port_or_dflt();
+        let keep_alive = Duration::from_nanos(config.keep_alive);
+        let transport = tls.map_or_else(Transport::tcp, |tls| {
+            Transport::tls_with_config(TlsConfiguration::Rustls(tls))
+        });
+        let cap = tremor_system::qsize();
+        match config.protocol {
+            Protocol::V3 => {
+                let mut options = rumqttc::MqttOptions::new(client_id, host, port);
+                options
+                    .set_keep_alive(keep_alive)
+                    .set_clean_session(config.clean_session)
+                    .set_manual_acks(true)
+                    .set_transport(transport);
+                if let Some(username) = &config.username {
+                    options.set_credentials(username, config.password.as_deref().unwrap_or(""));
+                }
+                if let Some(will) = &config.last_will {
+                    options.set_last_will(rumqttc::LastWill::new(
+                        &will.topic,
+                        will.message.as_bytes(),
+                        will.qos.into(),
+                        will.retain,
+                    ));
+                }
+                let (client, eventloop) = rumqttc::AsyncClient::new(options, cap);
+                (Self::V3(client), EventLoop::V3(eventloop))
+            }
+            Protocol::V5 => {
+                let mut options = v5::MqttOptions::new(client_id, host, port);
+                options
+                    .set_keep_alive(keep_alive)
+                    .set_clean_start(config.clean_session)
+                    .set_manual_acks(true)
+                    .set_transport(transport);
+                if let Some(username) = &config.username {
+                    options.set_credentials(username, config.password.as_deref().unwrap_or(""));
+                }
+                if let Some(will) = &config.last_will {
+                    options.set_last_will(v5::mqttbytes::v5::LastWill::new(
+                        &will.topic,
+                        will.message.as_bytes(),
+                        will.qos.into(),
+                        will.retain,
+                        None,
+                    ));
+                }
+                let (client, eventloop) = v5::AsyncClient::new(options, cap);
+                (Self::V5(client), EventLoop::V5(eventloop))
+            }
+        }
+    }
+
+    pub(super) async fn subscribe(&self, topic: &str, qos: Qos) -> anyhow::Result<()> {
+        match self {
+            Self::V3(client) => client.subscribe(topic, qos.into()).await?,
+            Self::V5(client) => client.subscribe(topic, qos.into()).await?,
+        }
+        Ok(())
+    }
+
+    /// Queues a message for publishing, `properties` are only sent with MQTT 5
+    pub(super) async fn publish(
+        &self,
+        topic: &str,
+        qos: Qos,
+        retain: bool,
+        payload: Vec<u8>,
+        properties: Option<&Value<'_>>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::V3(client) => client.publish(topic, qos.into(), retain, payload).await?,
+            Self::V5(client) => match properties {
+                Some(properties) => {
+                    client
+                        .publish_with_properties(
+                            topic,
+                            qos.into(),
+                            retain,
+                            payload,
+                            to_properties(properties),
+                        )
+                        .await?;
+                }
+                None => client.publish(topic, qos.into(), retain, payload).await?,
+            },
+        }
+        Ok(())
+    }
+
+    /// Acknowledges a received message, only `QoS` 1 and 2 messages are acknowledged
+    pub(super) async fn ack(&self, message: &Message) -> anyhow::Result<()> {
+        match (self, message) {
+            (Self::V3(client), Message::V3(publish)) => client.ack(publish).await?,
+            (Self::V5(client), Message::V5(publish)) => client.ack(publish).await?,
+            // a client never receives messages of the other protocol version
+            _ => (),
+        }
+        Ok(())
+    }
+
+    pub(super) async fn disconnect(&self) -> anyhow::Result<()> {
+        match self {
+            Self::V3(client) => client.disconnect().await?,
+            Self::V5(client) => client.disconnect().await?,
+        }
+        Ok(())
+    }
+}
+
+impl EventLoop {
+    pub(super) async fn poll(&mut self) -> anyhow::Result<Notification> {
+        use rumqttc::{Event, Outgoing, Packet};
+        use v5::{mqttbytes::v5::Packet as Packet5, Event as Event5};
+        Ok(match self {
+            Self::V3(eventloop) => match eventloop.poll().await? {
+                Event::Incoming(Packet::ConnAck(_)) => Notification::Connected,
+                Event::Incoming(Packet::Publish(publish)) => {
+                    Notification::Received(Message::V3(publish))
+                }
+                Event::Incoming(Packet::PubAck(ack)) => Notification::Delivered(ack.pkid),
+                Event::Incoming(Packet::PubComp(comp)) => Notification::Delivered(comp.pkid),
+                Event::Outgoing(Outgoing::Publish(pkid)) => Notification::Sent(pkid),
+                Event::Outgoing(Outgoing::Disconnect) => Notification::Disconnected,
+                Event::Incoming(_) | Event::Outgoing(_) => Notification::Other,
+            },
+            Self::V5(eventloop) => match eventloop.poll().await? {
+                Event5::Incoming(Packet5::ConnAck(_)) => Notification::Connected,
+                Event5::Incoming(Packet5::Publish(publish)) => {
+                    Notification::Received(Message::V5(publish))
+                }
+                Event5::Incoming(Packet5::PubAck(ack)) => match ack.reason {
+                    PubAckReason::Success | PubAckReason::NoMatchingSubscribers => {
+                        Notification::Delivered(ack.pkid)
+                    }
+                    reason => Notification::Rejected(ack.pkid, format!("{reason:?}")),
+                },
+                Event5::Incoming(Packet5::PubRec(rec)) => match rec.reason {
+                    PubRecReason::Success | PubRecReason::NoMatchingSubscribers => {
+                        Notification::Other
+                    }
+                    reason => Notification::Rejected(rec.pkid, format!("{reason:?}")),
+                },
+                Event5::Incoming(Packet5::PubComp(comp)) => Notification::Delivered(comp.pkid),
+                Event5::Outgoing(Outgoing::Publish(pkid)) => Notification::Sent(pkid),
+                Event5::Outgoing(Outgoing::Disconnect) => Notification::Disconnected,
+                Event5::Incoming(_) | Event5::Outgoing(_) => Notification::Other,
+            },
+        })
+    }
+}
+
+impl Message {
+    pub(super) fn payload(&self) -> &[u8] {
+        match self {
+            Self::V3(publish) => &publish.payload,
+            Self::V5(publish) => &publish.payload,
+        }
+    }
+
+    /// Whether the broker expects an acknowledgement for this message
+    pub(super) fn needs_ack(&self) -> bool {
+        match self {
+            Self::V3(publish) => publish.qos != rumqttc::QoS::AtMostOnce,
+            Self::V5(publish) => publish.qos != v5::mqttbytes::QoS::AtMostOnce,
+        }
+    }
+
+    /// The `$mqtt` metadata of this message
+    pub(super) fn meta(&self) -> Value<'static> {
+        match self {
+            Self::V3(publish) => literal!({
+                "topic": publish.topic.clone(),
+                "qos": publish.qos as u8,
+                "retain": publish.retain,
+                "dup": publish.dup,
+            }),
+            Self::V5(publish) => {
+                let mut meta = literal!({
+                    "topic": String::from_utf8_lossy(&publish.topic).to_string(),
+                    "qos": publish.qos as u8,
+                    "retain": publish.retain,
+                    "dup": publish.dup,
+                });
+                if let Some(properties) = &publish.properties {
+                    meta.try_insert("properties", from_properties(properties));
+                }
+                meta
+            }
+        }
+    }
+}
+
+fn from_properties(properties: &PublishProperties) -> Value<'static> {
+    let mut value = Value::object_with_capacity(6);
+    if let Some(indicator) = properties.payload_format_indicator {
+        value.try_insert("payload_format_indicator", indicator);
+    }
+    if let Some(interval) = properties.message_expiry_interval {
+        value.try_insert("message_expiry_interval", interval);
+    }
+    if let Some(content_type) = &properties.content_type {
+        value.try_insert("content_type", content_type.clone());
+    }
+    if let Some(topic) = &properties.response_topic {
+        value.try_insert("response_topic", topic.clone());
+    }
+    if let Some(data) = &properties.correlation_data {
+        value.try_insert("correlation_data", Value::Bytes(data.to_vec().into()));
+    }
+    if !properties.user_properties.is_empty() {
+        let mut user = Value::object_with_capacity(properties.user_properties.len());
+        for (k, v) in &properties.user_properties {
+            user.try_insert(k.clone(), v.clone());
+        }
+        value.try_insert("user_properties", user);
+    }
+    value
+}
+
+fn to_properties(value: &Value) -> PublishProperties {
+    PublishProperties {
+        payload_format_indicator: value.get_u8("payload_format_indicator"),
+        message_expiry_interval: value.get_u32("message_expiry_interval"),
+        content_type: value.get_str("content_type").map(ToString::to_string),
+        response_topic: value.get_str("response_topic").map(ToString::to_string),
+        correlation_data: value
+            .get_bytes("correlation_data")
+            .map(|data| data.to_vec().into()),
+        user_properties: value
+            .get_object("user_properties")
+            .map(|user| {
+                user.iter()
+                    .filter_map(|(k, v)| Some((k.to_string(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ..PublishProperties::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties_roundtrip() {
+        let value = literal!({
+            "payload_format_indicator": 1,
+            "message_expiry_interval": 60,
+            "content_type": "application/json",
+            "response_topic": "replies",
+            "correlation_data": Value::Bytes(b"42".to_vec().into()),
+            "user_properties": {"snot": "badger"}
+        });
+        assert_eq!(value, from_properties(&to_properties(&value)));
+    }
+}
@@ -0,0 +1,839 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `nats` connector connects to a [NATS](https://nats.io/) server or cluster.
+//!
+//! Its source receives messages from core NATS `subscriptions`, optionally as part of a queue group,
+//! and from [JetStream](https://docs.nats.io/nats-concepts/jetstream) `consumers`.
+//! Its sink publishes events to a subject or sends them as requests and emits the responses from the source.
+//!
+//! ## Configuration
+//!
+//! | Option             | Description                                                                              | Type              | Required | Default Value |
+//! |--------------------|------------------------------------------------------------------------------------------|-------------------|----------|---------------|
+//! | `servers`          | The servers to connect to, e.g. `nats://localhost:4222`. `tls://` urls use TLS.          | list of strings   | yes      |               |
+//! | `name`             | The connection name shown in the server monitoring.                                      | string            | no       |               |
+//! | `subscriptions`    | Core NATS subscriptions, each with a `subject` and an optional `queue_group`.            | list of records   | no       | `[]`          |
+//! | `consumers`        | Durable `JetStream` pull consumers, see below.                                           | list of records   | no       | `[]`          |
+//! | `subject`          | The subject the sink publishes to, unless `$nats.subject` is set.                        | string            | no       |               |
+//! | `request_timeout`  | How long to wait for the response to a request, in nanoseconds.                          | integer           | no       | 10 seconds    |
+//! | `token`            | Token to authenticate with.                                                              | string            | no       |               |
+//! | `username`         | Username to authenticate with, requires `password`.                                      | string            | no       |               |
+//! | `password`         | Password to authenticate with.                                                           | string            | no       |               |
+//! | `nkey`             | `NKey` seed to authenticate with.                                                        | string            | no       |               |
+//! | `credentials_file` | Path to a `.creds` file containing a JWT and `NKey` seed.                                | string            | no       |               |
+//! | `tls`              | `true` or a TLS client configuration, see [`TLSClientConfig`].                           | boolean or record | no       | `false`       |
+//!
+//! A `JetStream` consumer is configured with the following options. The consumer is created on the
+//! stream if it does not exist yet, the stream itself needs to exist already.
+//!
+//! | Option           | Description                                                | Type   | Required | Default Value            |
+//! |------------------|------------------------------------------------------------|--------|----------|--------------------------|
+//! | `stream`         | The stream to consume from.                                | string | yes      |                          |
+//! | `consumer`       | The durable name of the consumer.                          | string | yes      |                          |
+//! | `filter_subject` | Only consume messages of the stream matching this subject. | string | no       | all subjects             |
+//!
+//! ```tremor title="config.troy"
+//! define connector orders from nats
+//! with
+//!   codec = "json",
+//!   config = {
+//!     "servers": ["nats://nats-1:4222", "nats://nats-2:4222"],
+//!     "name": "tremor-orders",
+//!     "subscriptions": [
+//!       {"subject": "orders.created", "queue_group": "tremor"}
+//!     ],
+//!     "consumers": [
+//!       {"stream": "PAYMENTS", "consumer": "tremor", "filter_subject": "payments.>"}
+//!     ],
+//!     "subject": "orders.enriched"
+//!   }
+//! end;
+//! ```
+//!
+//! ## Guaranteed delivery
+//!
+//! Messages received from a `JetStream` consumer are acknowledged once their event is acknowledged
+//! downstream, failed events are negatively acknowledged so the server redelivers them. Messages from
+//! core NATS subscriptions are fire and forget.
+//!
+//! Published events are acknowledged once they are handed to the connection, requests once their
+//! response arrived. Requests that time out or find no responders are failed.
+//!
+//! ## Reconnects
+//!
+//! When the connection to the server is lost, the connector reconnects according to its `reconnect`
+//! configuration. `JetStream` messages that were not acknowledged before are redelivered by the server.
+//!
+//! ## Metadata
+//!
+//! Received events carry the following metadata:
+//!
+//! ```js
+//! {
+//!   "$nats": {
+//!     "subject": "orders.created",
+//!     # only present if the sender expects a reply
+//!     "reply": "_INBOX.abc123",
+//!     # only present if the message has headers
+//!     "headers": {"Nats-Msg-Id": ["order-42"]},
+//!     # only present for messages from JetStream consumers
+//!     "jetstream": {
+//!       "stream": "PAYMENTS",
+//!       "consumer": "tremor",
+//!       "stream_sequence": 42,
+//!       "consumer_sequence": 7,
+//!       "delivered": 1,
+//!       "pending": 0
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The sink reads the following fields from the `$nats` metadata of an event:
+//!
+//! * `subject`: the subject to publish to, falls back to the `subject` from the configuration
+//! * `reply`: the reply subject sent along with the message
+//! * `headers`: a record of header names to a string or a list of strings
+//! * `request`: if `true` the message is sent as a request and the response is emitted from the source,
+//!   together with the `$correlation` metadata of the request
+//!
+//! Replying to a request received by the source is done by publishing to its reply subject:
+//!
+//! ```tremor
+//! let $nats = {"subject": $nats.reply};
+//! ```
+
+use crate::{
+    errors::error_connector_def, sink::prelude::*, source::prelude::*, spawn_task,
+    utils::tls::TLSClientConfig,
+};
+use async_nats::{
+    jetstream::{self, consumer::pull, AckKind},
+    Client, ConnectOptions, HeaderMap, ServerAddr,
+};
+use either::Either;
+use futures::StreamExt;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        RwLock,
+    },
+    task::JoinHandle,
+};
+use tremor_common::time::nanotime;
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::prelude::*;
+
+const URL_SCHEME: &str = "tremor-nats";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Subscription {
+    subject: String,
+    queue_group: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct JetStreamConsumer {
+    stream: String,
+    consumer: String,
+    filter_subject: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    servers: Vec<String>,
+    name: Option<String>,
+    #[serde(default)]
+    subscriptions: Vec<Subscription>,
+    #[serde(default)]
+    consumers: Vec<JetStreamConsumer>,
+    /// default subject to publish to
+    subject: Option<String>,
+    /// request timeout in nanoseconds
+    #[serde(default = "default_request_timeout")]
+    request_timeout: u64,
+    token: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    nkey: Option<String>,
+    credentials_file: Option<String>,
+    #[serde(with = "either::serde_untagged_optional", default = "Default::default")]
+    tls: Option<Either<TLSClientConfig, bool>>,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_request_timeout() -> u64 {
+    10_000_000_000 // 10 seconds
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error(
+        "No subject to publish to, set `subject` in the config or `$nats.subject` in the event metadata"
+    )]
+    MissingSubject,
+    #[error("Invalid header `{0}`, expected a string or a list of strings")]
+    InvalidHeader(String),
+}
+
+/// NATS connector
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+impl Builder {
+    const NO_SERVERS: &'static str = "`servers` must contain at least one server";
+    const MISSING_PASSWORD: &'static str = "`username` requires a `password`";
+}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "nats".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        let servers = config
+            .servers
+            .iter()
+            .map(|server| server.parse::<ServerAddr>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| error_connector_def(id, &format!("Invalid server: {e}")))?;
+        let first = servers
+            .first()
+            .ok_or_else(|| error_connector_def(id, Self::NO_SERVERS))?;
+        if config.username.is_some() && config.password.is_none() {
+            return Err(error_connector_def(id, Self::MISSING_PASSWORD).into());
+        }
+        let tls_config = match config.tls.as_ref() {
+            Some(Either::Left(tls_config)) => Some(tls_config.to_client_config()?),
+            // with `true` the client brings its own root certificates
+            Some(Either::Right(_)) | None => None,
+        };
+        let origin_uri = EventOriginUri {
+            scheme: URL_SCHEME.to_string(),
+            host: first.host().to_string(),
+            port: Some(first.port()),
+            path: vec![],
+        };
+        let (source_tx, source_rx) = channel(qsize());
+        Ok(Box::new(Nats {
+            config,
+            servers,
+            tls_config,
+            origin_uri,
+            client: Arc::new(RwLock::new(None)),
+            active: Arc::new(AtomicBool::new(false)),
+            source_tx,
+            source_rx: Some(source_rx),
+            tasks: Vec::new(),
+        }))
+    }
+}
+
+pub(crate) struct Nats {
+    config: Config,
+    servers: Vec<ServerAddr>,
+    tls_config: Option<rustls::ClientConfig>,
+    origin_uri: EventOriginUri,
+    /// the client of the current connection, shared by source and sink
+    client: Arc<RwLock<Option<Client>>>,
+    /// whether the current connection is still in use, it is replaced on every connect
+    active: Arc<AtomicBool>,
+    source_tx: Sender<Incoming>,
+    source_rx: Option<Receiver<Incoming>>,
+    /// forwarding subscriptions and consumers of the current connection to the source
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Nats {
+    async fn options(&self, ctx: &ConnectorContext) -> anyhow::Result<ConnectOptions> {
+        let mut options = match &self.config.credentials_file {
+            Some(path) => ConnectOptions::with_credentials_file(path).await?,
+            None => ConnectOptions::new(),
+        };
+        if let Some(token) = &self.config.token {
+            options = options.token(token.clone());
+        }
+        if let Some((username, password)) = self
+            .config
+            .username
+            .as_ref()
+            .zip(self.config.password.as_ref())
+        {
+            options = options.user_and_password(username.clone(), password.clone());
+        }
+        if let Some(nkey) = &self.config.nkey {
+            options = options.nkey(nkey.clone());
+        }
+        if let Some(name) = &self.config.name {
+            options = options.name(name);
+        }
+        if let Some(tls_config) = &self.tls_config {
+            options = options
+                .require_tls(true)
+                .tls_client_config(tls_config.clone());
+        } else if let Some(Either::Right(true)) = self.config.tls {
+            options = options.require_tls(true);
+        }
+        // tremor takes care of reconnecting, according to the `reconnect` config of the connector
+        let active = self.active.clone();
+        let notifier = ctx.notifier().clone();
+        let ctx = ctx.clone();
+        Ok(options
+            .request_timeout(Some(Duration::from_nanos(self.config.request_timeout)))
+            .event_callback(move |event| {
+                let active = active.clone();
+                let notifier = notifier.clone();
+                let ctx = ctx.clone();
+                async move {
+                    match event {
+                        async_nats::Event::Disconnected => {
+                            // only the first disconnect of the current connection counts
+                            if active.swap(false, Ordering::AcqRel) {
+                                warn!("{ctx} Connection lost");
+                                ctx.swallow_err(
+                                    notifier.connection_lost().await,
+                                    "Error notifying about the lost connection",
+                                );
+                            }
+                        }
+                        async_nats::Event::ServerError(e) => warn!("{ctx} Server error: {e}"),
+                        async_nats::Event::ClientError(e) => warn!("{ctx} Client error: {e}"),
+                        async_nats::Event::SlowConsumer(sid) => {
+                            warn!(
+                                "{ctx} Slow consumer, messages of subscription {sid} were dropped"
+                            );
+                        }
+                        _ => (),
+                    }
+                }
+            }))
+    }
+
+    async fn subscribe(&mut self, ctx: &ConnectorContext, client: &Client) -> anyhow::Result<()> {
+        for subscription in &self.config.subscriptions {
+            let subject = subscription.subject.clone();
+            let mut subscriber = match &subscription.queue_group {
+                Some(group) => client.queue_subscribe(subject, group.clone()).await?,
+                None => client.subscribe(subject).await?,
+            };
+            let tx = self.source_tx.clone();
+            self.tasks.push(spawn_task(ctx.clone(), async move {
+                while let Some(message) = subscriber.next().await {
+                    tx.send(Incoming::Core(message))
+                        .await
+                        .map_err(|_| GenericImplementationError::ChannelEmpty)?;
+                }
+                Ok(())
+            }));
+        }
+        if self.config.consumers.is_empty() {
+            return Ok(());
+        }
+        let context = jetstream::new(client.clone());
+        for consumer in &self.config.consumers {
+            let stream = context.get_stream(&consumer.stream).await?;
+            let config = pull::Config {
+                durable_name: Some(consumer.consumer.clone()),
+                filter_subject: consumer.filter_subject.clone().unwrap_or_default(),
+                ..pull::Config::default()
+            };
+            let mut messages = stream
+                .get_or_create_consumer(&consumer.consumer, config)
+                .await?
+                .messages()
+                .await?;
+            let tx = self.source_tx.clone();
+            self.tasks.push(spawn_task(ctx.clone(), async move {
+                while let Some(message) = messages.next().await {
+                    let message = message?;
+                    tx.send(Incoming::JetStream(Box::new(message)))
+                        .await
+                        .map_err(|_| GenericImplementationError::ChannelEmpty)?;
+                }
+                Ok(())
+            }));
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) {
+        self.active.store(false, Ordering::Release);
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.client.write().await.take();
+    }
+}
+
+#[async_trait::async_trait()]
+impl Connector for Nats {
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Required
+    }
+
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        let source = NatsSource {
+            rx: self
+                .source_rx
+                .take()
+                .ok_or(GenericImplementationError::AlreadyConnected)?,
+            unacked: HashMap::new(),
+            origin_uri: self.origin_uri.clone(),
+            transactional: !self.config.consumers.is_empty(),
+        };
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    async fn create_sink(
+        &mut self,
+        ctx: SinkContext,
+        builder: SinkManagerBuilder,
+    ) -> anyhow::Result<Option<SinkAddr>> {
+        let sink = NatsSink {
+            client: self.client.clone(),
+            subject: self.config.subject.clone(),
+            source_tx: self.source_tx.clone(),
+            reply_tx: builder.reply_tx(),
+        };
+        Ok(Some(builder.spawn(sink, ctx)))
+    }
+
+    async fn connect(
+        &mut self,
+        ctx: &ConnectorContext,
+        _attempt: &Attempt,
+    ) -> anyhow::Result<bool> {
+        // keep the old connection from reporting its own shutdown as a lost connection
+        self.close().await;
+        self.active = Arc::new(AtomicBool::new(true));
+
+        let client = self
+            .options(ctx)
+            .await?
+            .connect(self.servers.as_slice())
+            .await?;
+        self.subscribe(ctx, &client).await?;
+        info!("{ctx} Connected to {}", self.config.servers.join(", "));
+        *self.client.write().await = Some(client);
+        Ok(true)
+    }
+
+    async fn on_stop(&mut self, ctx: &ConnectorContext) -> anyhow::Result<()> {
+        if let Some(client) = self.client.read().await.as_ref() {
+            // pending publishes are sent before we close the connection
+            ctx.swallow_err(client.flush().await, "Error flushing the connection");
+        }
+        self.close().await;
+        Ok(())
+    }
+}
+
+/// Messages for the source
+enum Incoming {
+    /// from a core NATS subscription
+    Core(async_nats::Message),
+    /// from a `JetStream` consumer, acknowledged once the event is
+    JetStream(Box<jetstream::Message>),
+    /// the response to a request of the sink
+    Response(async_nats::Message, Option<Value<'static>>),
+}
+
+fn headers_to_value(headers: &HeaderMap) -> Value<'static> {
+    let mut value = Value::object_with_capacity(headers.len());
+    for (name, values) in headers.iter() {
+        let values: Vec<Value<'static>> = values
+            .iter()
+            .map(|v| Value::from(v.as_str().to_string()))
+            .collect();
+        value.try_insert(name.to_string(), values);
+    }
+    value
+}
+
+fn value_to_headers(value: &Value) -> Result<HeaderMap, Error> {
+    let mut headers = HeaderMap::new();
+    if let Some(object) = value.as_object() {
+        for (name, values) in object {
+            if let Some(single) = values.as_str() {
+                headers.append(name.as_ref(), single);
+            } else if let Some(list) = values.as_array() {
+                for v in list {
+                    let v = v
+                        .as_str()
+                        .ok_or_else(|| Error::InvalidHeader(name.to_string()))?;
+                    headers.append(name.as_ref(), v);
+                }
+            } else {
+                return Err(Error::InvalidHeader(name.to_string()));
+            }
+        }
+    }
+    Ok(headers)
+}
+
+/// The `$nats` metadata of a received message
+fn message_meta(message: &async_nats::Message) -> Value<'static> {
+    let mut meta = literal!({
+        "subject": message.subject.to_string(),
+    });
+    if let Some(reply) = &message.reply {
+        meta.try_insert("reply", reply.to_string());
+    }
+    if let Some(headers) = message.headers.as_ref().filter(|h| !h.is_empty()) {
+        meta.try_insert("headers", headers_to_value(headers));
+    }
+    meta
+}
+
+fn jetstream_meta(message: &jetstream::Message) -> Option<Value<'static>> {
+    let info = message.info().ok()?;
+    Some(literal!({
+        "stream": info.stream.to_string(),
+        "consumer": info.consumer.to_string(),
+        "stream_sequence": info.stream_sequence,
+        "consumer_sequence": info.consumer_sequence,
+        "delivered": info.delivered,
+        "pending": info.pending,
+    }))
+}
+
+struct NatsSource {
+    rx: Receiver<Incoming>,
+    /// `JetStream` messages waiting for their event to be acknowledged, by pull id
+    unacked: HashMap<u64, Box<jetstream::Message>>,
+    origin_uri: EventOriginUri,
+    transactional: bool,
+}
+
+#[async_trait::async_trait()]
+impl Source for NatsSource {
+    async fn pull_data(
+        &mut self,
+        pull_id: &mut u64,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<SourceReply> {
+        let incoming = self
+            .rx
+            .recv()
+            .await
+            .ok_or(GenericImplementationError::ChannelEmpty)?;
+        let (data, meta) = match incoming {
+            Incoming::Core(message) => {
+                let meta = ctx.meta(message_meta(&message));
+                (message.payload.to_vec(), meta)
+            }
+            Incoming::JetStream(message) => {
+                let mut nats_meta = message_meta(&message);
+                if let Some(jetstream) = jetstream_meta(&message) {
+                    nats_meta.try_insert("jetstream", jetstream);
+                }
+                let data = message.payload.to_vec();
+                self.unacked.insert(*pull_id, message);
+                (data, ctx.meta(nats_meta))
+            }
+            Incoming::Response(message, correlation) => {
+                let mut meta = ctx.meta(message_meta(&message));
+                if let Some(correlation) = correlation {
+                    meta.try_insert("correlation", correlation);
+                }
+                (message.payload.to_vec(), meta)
+            }
+        };
+        Ok(SourceReply::Data {
+            origin_uri: self.origin_uri.clone(),
+            data,
+            meta: Some(meta),
+            stream: Some(DEFAULT_STREAM_ID),
+            port: None,
+            codec_overwrite: None,
+        })
+    }
+
+    async fn ack(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        if let Some(message) = self.unacked.remove(&pull_id) {
+            message.ack().await.map_err(|e| anyhow::anyhow!(e))?;
+        }
+        Ok(())
+    }
+
+    async fn fail(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        if let Some(message) = self.unacked.remove(&pull_id) {
+            message
+                .ack_with(AckKind::Nak(None))
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+        }
+        Ok(())
+    }
+
+    async fn on_connection_lost(&mut self, _ctx: &SourceContext) -> anyhow::Result<()> {
+        // the server redelivers those once their ack wait expired
+        self.unacked.clear();
+        Ok(())
+    }
+
+    fn is_transactional(&self) -> bool {
+        self.transactional
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+}
+
+/// A request of the sink, sent in the background
+struct Request {
+    subject: String,
+    headers: HeaderMap,
+    payload: Vec<u8>,
+    correlation: Option<Value<'static>>,
+}
+
+struct NatsSink {
+    client: Arc<RwLock<Option<Client>>>,
+    subject: Option<String>,
+    source_tx: Sender<Incoming>,
+    reply_tx: ReplySender,
+}
+
+/// Sends the requests of one event one after the other, the event is acknowledged once all responses arrived
+async fn send_requests(
+    ctx: SinkContext,
+    client: Client,
+    requests: Vec<Request>,
+    source_tx: Sender<Incoming>,
+    reply_tx: ReplySender,
+    cf: Option<ContraflowData>,
+    start: u64,
+) {
+    let mut failed = false;
+    for Request {
+        subject,
+        headers,
+        payload,
+        correlation,
+    } in requests
+    {
+        match client
+            .request_with_headers(subject, headers, payload.into())
+            .await
+        {
+            Ok(response) => {
+                if source_tx
+                    .send(Incoming::Response(response, correlation))
+                    .await
+                    .is_err()
+                {
+                    error!("{ctx} Error sending response to the source");
+                }
+            }
+            Err(e) => {
+                error!("{ctx} Request failed: {e}");
+                failed = true;
+                break;
+            }
+        }
+    }
+    if let Some(cf) = cf {
+        let reply = if failed {
+            AsyncSinkReply::Fail(cf)
+        } else {
+            AsyncSinkReply::Ack(cf, nanotime() - start)
+        };
+        ctx.swallow_err(reply_tx.send(reply), "Error sending request reply");
+    }
+}
+
+#[async_trait::async_trait()]
+impl Sink for NatsSink {
+    async fn on_event(
+        &mut self,
+        _input: &str,
+        event: Event,
+        ctx: &SinkContext,
+        serializer: &mut EventSerializer,
+        start: u64,
+    ) -> anyhow::Result<SinkReply> {
+        let client = self
+            .client
+            .read()
+            .await
+            .clone()
+            .ok_or(GenericImplementationError::ClientNotAvailable("NATS"))?;
+        let mut requests = Vec::new();
+        for (value, meta) in event.value_meta_iter() {
+            let nats_meta = ctx.extract_meta(meta);
+            let subject = nats_meta
+                .get_str("subject")
+                .or(self.subject.as_deref())
+                .ok_or(Error::MissingSubject)?;
+            let headers = nats_meta
+                .get("headers")
+                .map(value_to_headers)
+                .transpose()?
+                .unwrap_or_default();
+            let reply = nats_meta.get_str("reply");
+            let request = nats_meta.get_bool("request").unwrap_or(false);
+            for payload in serializer.serialize(value, meta, event.ingest_ns).await? {
+                if request {
+                    requests.push(Request {
+                        subject: subject.to_string(),
+                        headers: headers.clone(),
+                        payload,
+                        correlation: meta.get("correlation").map(Value::clone_static),
+                    });
+                    continue;
+                }
+                let subject = subject.to_string();
+                let res = match reply {
+                    Some(reply) => {
+                        client
+                            .publish_with_reply_and_headers(
+                                subject,
+                                reply.to_string(),
+                                headers.clone(),
+                                payload.into(),
+                            )
+                            .await
+                    }
+                    None => {
+                        client
+                            .publish_with_headers(subject, headers.clone(), payload.into())
+                            .await
+                    }
+                };
+                if let Err(e) = res {
+                    error!("{ctx} Error publishing message: {e}. Initiating reconnect...");
+                    ctx.notifier().connection_lost().await?;
+                    return Err(e.into());
+                }
+            }
+        }
+        if requests.is_empty() {
+            return Ok(SinkReply::ack_or_none(event.transactional));
+        }
+        let cf = event.transactional.then(|| ContraflowData::from(&event));
+        tokio::task::spawn(send_requests(
+            ctx.clone(),
+            client,
+            requests,
+            self.source_tx.clone(),
+            self.reply_tx.clone(),
+            cf,
+            start,
+        ));
+        Ok(SinkReply::NONE)
+    }
+
+    async fn finalize(
+        &mut self,
+        _ctx: &SinkContext,
+        _serializer: &mut EventSerializer,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn auto_ack(&self) -> bool {
+        false
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Connector as ConnectorConfig;
+
+    #[test]
+    fn headers() -> anyhow::Result<()> {
+        let value = literal!({
+            "Nats-Msg-Id": "42",
+            "snot": ["badger", "muhaha"]
+        });
+        let headers = value_to_headers(&value)?;
+        assert_eq!(
+            literal!({
+                "Nats-Msg-Id": ["42"],
+                "snot": ["badger", "muhaha"]
+            }),
+            headers_to_value(&headers)
+        );
+        assert!(value_to_headers(&literal!({"snot": 42})).is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connector_builder_empty_servers() -> anyhow::Result<()> {
+        let config = literal!({
+            "config": {
+                "servers": []
+            }
+        });
+        let alias = alias::Connector::new("flow", "my_nats");
+        let builder = super::Builder::default();
+        let connector_config =
+            ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+        let kill_switch = KillSwitch::dummy();
+        assert_eq!(
+            String::from(
+                "[flow::my_nats] Invalid definition: `servers` must contain at least one server"
+            ),
+            builder
+                .build(&alias, &connector_config, &kill_switch)
+                .await
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{BufMut, BytesMut};
+use tokio_postgres::{
+    types::{to_sql_checked, FromSql, IsNull, ToSql, Type},
+    Row,
+};
+use tremor_value::prelude::*;
+use uuid::Uuid;
+
+/// Microseconds between the unix epoch and the postgres epoch, 2000-01-01
+pub(crate) const POSTGRES_EPOCH_US: i64 = 946_684_800_000_000;
+
+type BoxError = Box<dyn std::error::Error + Sync + Send>;
+
+/// Error for postgres conversion
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Can't convert a value of type `{actual}` into `{expected}`")]
+    UnexpectedValue { expected: Type, actual: ValueType },
+    #[error("Unsupported type `{0}`, cast it to a supported type in the query, e.g. `::text`")]
+    UnsupportedType(Type),
+    #[error("Malformed UUID")]
+    MalformedUuid,
+}
+
+/// A tremor value as a parameter of a statement, converted to the type the server expects
+#[derive(Debug)]
+pub(crate) struct Param<'v>(pub(crate) &'v Value<'v>);
+
+impl Param<'_> {
+    fn unexpected(&self, ty: &Type) -> BoxError {
+        Error::UnexpectedValue {
+            expected: ty.clone(),
+            actual: self.0.value_type(),
+        }
+        .into()
+    }
+}
+
+impl ToSql for Param<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+        let value = self.0;
+        if value.is_null() {
+            return Ok(IsNull::Yes);
+        }
+        match *ty {
+            Type::BOOL => value
+                .as_bool()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            Type::INT2 => value
+                .as_i16()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            Type::INT4 => value
+                .as_i32()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            Type::INT8 => value
+                .as_i64()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            Type::OID => value
+                .as_u32()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            #[allow(clippy::cast_possible_truncation)]
+            Type::FLOAT4 => {
+                (value.cast_f64().ok_or_else(|| self.unexpected(ty))? as f32).to_sql(ty, out)
+            }
+            Type::FLOAT8 => value
+                .cast_f64()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => value
+                .as_str()
+                .ok_or_else(|| self.unexpected(ty))?
+                .to_sql(ty, out),
+            Type::BYTEA => {
+                let bytes = value
+                    .as_bytes()
+                    .or_else(|| value.as_str().map(str::as_bytes))
+                    .ok_or_else(|| self.unexpected(ty))?;
+                out.put_slice(bytes);
+                Ok(IsNull::No)
+            }
+            Type::JSON => {
+                out.put_slice(value.encode().as_bytes());
+                Ok(IsNull::No)
+            }
+            Type::JSONB => {
+                // the version of the jsonb binary format
+                out.put_u8(1);
+                out.put_slice(value.encode().as_bytes());
+                Ok(IsNull::No)
+            }
+            Type::TIMESTAMP | Type::TIMESTAMPTZ => {
+                // nanoseconds since the unix epoch, like all timestamps in tremor
+                let nanos = value.as_i64().ok_or_else(|| self.unexpected(ty))?;
+                (nanos / 1000 - POSTGRES_EPOCH_US).to_sql(&Type::INT8, out)
+            }
+            Type::UUID => {
+                let uuid = value
+                    .as_str()
+                    .ok_or_else(|| self.unexpected(ty))
+                    .and_then(|s| Uuid::parse_str(s).map_err(|_| Error::MalformedUuid.into()))?;
+                out.put_slice(uuid.as_bytes());
+                Ok(IsNull::No)
+            }
+            _ => Err(Error::UnsupportedType(ty.clone()).into()),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        // checked when converting
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// A value returned by the server
+struct Cell(Value<'static>);
+
+impl<'a> FromSql<'a> for Cell {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, BoxError> {
+        let value = match *ty {
+            Type::BOOL => Value::from(bool::from_sql(ty, raw)?),
+            Type::INT2 => Value::from(i16::from_sql(ty, raw)?),
+            Type::INT4 => Value::from(i32::from_sql(ty, raw)?),
+            Type::INT8 => Value::from(i64::from_sql(ty, raw)?),
+            Type::OID => Value::from(u32::from_sql(ty, raw)?),
+            Type::FLOAT4 => Value::from(f64::from(f32::from_sql(ty, raw)?)),
+            Type::FLOAT8 => Value::from(f64::from_sql(ty, raw)?),
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => {
+                Value::from(<&str>::from_sql(ty, raw)?.to_string())
+            }
+            Type::BYTEA => Value::Bytes(raw.to_vec().into()),
+            Type::JSON => tremor_value::parse_to_value(&mut raw.to_vec())?.into_static(),
+            // skip the version of the jsonb binary format
+            Type::JSONB => {
+                tremor_value::parse_to_value(&mut raw.get(1..).unwrap_or_default().to_vec())?
+                    .into_static()
+            }
+            Type::TIMESTAMP | Type::TIMESTAMPTZ => {
+                let micros = i64::from_sql(&Type::INT8, raw)?;
+                Value::from(
+                    micros
+                        .saturating_add(POSTGRES_EPOCH_US)
+                        .saturating_mul(1000),
+                )
+            }
+            Type::UUID => Value::from(
+                Uuid::from_slice(raw)
+                    .map_err(|_| Error::MalformedUuid)?
+                    .hyphenated()
+                    .to_string(),
+            ),
+            _ => return Err(Error::UnsupportedType(ty.clone()).into()),
+        };
+        Ok(Cell(value))
+    }
+
+    fn from_sql_null(_ty: &Type) -> Result<Self, BoxError> {
+        Ok(Cell(Value::const_null()))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+/// Converts a value in the text format of its type, as sent by logical replication
+pub(crate) fn text_to_value(type_oid: u32, text: &str) -> Value<'static> {
+    let parsed = match Type::from_oid(type_oid) {
+        Some(Type::BOOL) => Some(Value::from(text == "t")),
+        Some(Type::INT2 | Type::INT4 | Type::INT8 | Type::OID) => {
+            text.parse::<i64>().ok().map(Value::from)
+        }
+        Some(Type::FLOAT4 | Type::FLOAT8) => text
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(Value::from),
+        Some(Type::JSON | Type::JSONB) => {
+            tremor_value::parse_to_value(&mut text.as_bytes().to_vec())
+                .ok()
+                .map(Value::into_static)
+        }
+        _ => None,
+    };
+    // everything else, and values we can't parse, like `NaN`, stay text
+    parsed.unwrap_or_else(|| Value::from(text.to_string()))
+}
+
+/// Turns a row into a record of its columns
+pub(crate) fn row_to_value(row: &Row) -> Result<Value<'static>, tokio_postgres::Error> {
+    let mut record = Value::object_with_capacity(row.len());
+    for (idx, column) in row.columns().iter().enumerate() {
+        let Cell(value) = row.try_get(idx)?;
+        record.try_insert(column.name().to_string(), value);
+    }
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(ty: &Type, value: &Value) -> Result<Value<'static>, BoxError> {
+        let mut buf = BytesMut::new();
+        Param(value).to_sql_checked(ty, &mut buf)?;
+        Ok(Cell::from_sql(ty, &buf)?.0)
+    }
+
+    #[test]
+    fn roundtrips() -> Result<(), BoxError> {
+        assert_eq!(
+            Value::from(true),
+            roundtrip(&Type::BOOL, &Value::from(true))?
+        );
+        assert_eq!(Value::from(42), roundtrip(&Type::INT2, &Value::from(42))?);
+        assert_eq!(Value::from(42), roundtrip(&Type::INT4, &Value::from(42))?);
+        assert_eq!(Value::from(42), roundtrip(&Type::INT8, &Value::from(42))?);
+        assert_eq!(
+            Value::from(0.5),
+            roundtrip(&Type::FLOAT4, &Value::from(0.5))?
+        );
+        assert_eq!(
+            Value::from(42.0),
+            roundtrip(&Type::FLOAT8, &Value::from(42))?
+        );
+        assert_eq!(
+            Value::from("snot"),
+            roundtrip(&Type::TEXT, &Value::from("snot"))?
+        );
+        assert_eq!(
+            Value::Bytes(b"badger".to_vec().into()),
+            roundtrip(&Type::BYTEA, &Value::from("badger"))?
+        );
+        let json = literal!({"snot": ["badger", 42]});
+        assert_eq!(json, roundtrip(&Type::JSON, &json)?);
+        assert_eq!(json, roundtrip(&Type::JSONB, &json)?);
+        // microsecond precision
+        assert_eq!(
+            Value::from(1_718_871_345_123_456_000_i64),
+            roundtrip(
+                &Type::TIMESTAMPTZ,
+                &Value::from(1_718_871_345_123_456_789_i64)
+            )?
+        );
+        let uuid = Value::from("123e4567-e89b-12d3-a456-426614174000");
+        assert_eq!(uuid, roundtrip(&Type::UUID, &uuid)?);
+        Ok(())
+    }
+
+    #[test]
+    fn text_values() {
+        assert_eq!(Value::from(true), text_to_value(Type::BOOL.oid(), "t"));
+        assert_eq!(Value::from(-42), text_to_value(Type::INT8.oid(), "-42"));
+        assert_eq!(Value::from(0.5), text_to_value(Type::FLOAT4.oid(), "0.5"));
+        assert_eq!(
+            literal!({"snot": [1, "badger"]}),
+            text_to_value(Type::JSONB.oid(), r#"{"snot": [1, "badger"]}"#)
+        );
+        assert_eq!(
+            Value::from("12.50"),
+            text_to_value(Type::NUMERIC.oid(), "12.50")
+        );
+        assert_eq!(
+            Value::from("2024-06-20 08:15:45+00"),
+            text_to_value(Type::TIMESTAMPTZ.oid(), "2024-06-20 08:15:45+00")
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        let mut buf = BytesMut::new();
+        assert!(Param(&Value::from("snot"))
+            .to_sql_checked(&Type::INT4, &mut buf)
+            .is_err());
+        assert!(Param(&Value::from(70_000))
+            .to_sql_checked(&Type::INT2, &mut buf)
+            .is_err());
+        assert!(Param(&Value::from("snot"))
+            .to_sql_checked(&Type::UUID, &mut buf)
+            .is_err());
+        assert!(Param(&Value::from("1.0"))
+            .to_sql_checked(&Type::NUMERIC, &mut buf)
+            .is_err());
+        assert!(matches!(
+            Param(&Value::const_null()).to_sql_checked(&Type::INT4, &mut buf),
+            Ok(IsNull::Yes)
+        ));
+    }
+}
@@ -0,0 +1,406 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of the messages of the `pgoutput` logical decoding plugin, protocol version 1
+//!
+//! See <https://www.postgresql.org/docs/current/protocol-logicalrep-message-formats.html>
+
+/// Error decoding a `pgoutput` message
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub(crate) enum Error {
+    #[error("Truncated message")]
+    Truncated,
+    #[error("Invalid string in message")]
+    InvalidString,
+    #[error("Unknown tuple data kind `{0}`")]
+    UnknownTupleKind(char),
+    #[error("Unexpected tuple marker `{0}`")]
+    UnexpectedMarker(char),
+}
+
+/// A column of a relation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Column {
+    pub(crate) name: String,
+    pub(crate) type_oid: u32,
+    /// part of the replica identity of the relation
+    pub(crate) key: bool,
+}
+
+/// A table, sent before the first change to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Relation {
+    pub(crate) id: u32,
+    pub(crate) schema: String,
+    pub(crate) name: String,
+    pub(crate) columns: Vec<Column>,
+}
+
+/// The value of a column in a row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Cell {
+    Null,
+    /// a `TOASTed` value that didn't change
+    Unchanged,
+    Text(String),
+}
+
+/// The old row of an update or delete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Old {
+    /// only the columns of the replica identity are set
+    Key(Vec<Cell>),
+    /// the whole row, with `REPLICA IDENTITY FULL`
+    Row(Vec<Cell>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Message {
+    Begin {
+        final_lsn: u64,
+        /// microseconds since 2000-01-01
+        timestamp: i64,
+        xid: u32,
+    },
+    Commit {
+        commit_lsn: u64,
+        end_lsn: u64,
+        timestamp: i64,
+    },
+    Relation(Relation),
+    Insert {
+        relation: u32,
+        new: Vec<Cell>,
+    },
+    Update {
+        relation: u32,
+        old: Option<Old>,
+        new: Vec<Cell>,
+    },
+    Delete {
+        relation: u32,
+        old: Old,
+    },
+    Truncate {
+        relations: Vec<u32>,
+    },
+    /// origins, types and logical messages, which we don't need
+    Other(u8),
+}
+
+struct Reader<'d>(&'d [u8]);
+
+impl<'d> Reader<'d> {
+    fn take(&mut self, n: usize) -> Result<&'d [u8], Error> {
+        if self.0.len() < n {
+            return Err(Error::Truncated);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(i16::from_be_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    fn i64(&mut self) -> Result<i64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(i64::from_be_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn cstr(&mut self) -> Result<String, Error> {
+        let end = self
+            .0
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or(Error::Truncated)?;
+        let s = std::str::from_utf8(self.take(end)?).map_err(|_| Error::InvalidString)?;
+        self.take(1)?;
+        Ok(s.to_string())
+    }
+
+    fn tuple(&mut self) -> Result<Vec<Cell>, Error> {
+        let columns = usize::try_from(self.i16()?).map_err(|_| Error::Truncated)?;
+        let mut cells = Vec::with_capacity(columns);
+        for _ in 0..columns {
+            cells.push(match self.u8()? {
+                b'n' => Cell::Null,
+                b'u' => Cell::Unchanged,
+                b't' => {
+                    let len = usize::try_from(self.u32()?).map_err(|_| Error::Truncated)?;
+                    let text =
+                        std::str::from_utf8(self.take(len)?).map_err(|_| Error::InvalidString)?;
+                    Cell::Text(text.to_string())
+                }
+                other => return Err(Error::UnknownTupleKind(char::from(other))),
+            });
+        }
+        Ok(cells)
+    }
+
+    fn old(&mut self, marker: u8) -> Result<Old, Error> {
+        match marker {
+            b'K' => Ok(Old::Key(self.tuple()?)),
+            b'O' => Ok(Old::Row(self.tuple()?)),
+            other => Err(Error::UnexpectedMarker(char::from(other))),
+        }
+    }
+
+    fn new_tuple(&mut self) -> Result<Vec<Cell>, Error> {
+        match self.u8()? {
+            b'N' => self.tuple(),
+            other => Err(Error::UnexpectedMarker(char::from(other))),
+        }
+    }
+}
+
+impl Message {
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader(data);
+        Ok(match r.u8()? {
+            b'B' => Message::Begin {
+                final_lsn: r.u64()?,
+                timestamp: r.i64()?,
+                xid: r.u32()?,
+            },
+            b'C' => {
+                // flags, currently unused
+                r.u8()?;
+                Message::Commit {
+                    commit_lsn: r.u64()?,
+                    end_lsn: r.u64()?,
+                    timestamp: r.i64()?,
+                }
+            }
+            b'R' => {
+                let id = r.u32()?;
+                let schema = r.cstr()?;
+                let name = r.cstr()?;
+                // replica identity setting
+                r.u8()?;
+                let count = usize::try_from(r.i16()?).map_err(|_| Error::Truncated)?;
+                let mut columns = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let flags = r.u8()?;
+                    let name = r.cstr()?;
+                    let type_oid = r.u32()?;
+                    // type modifier
+                    r.u32()?;
+                    columns.push(Column {
+                        name,
+                        type_oid,
+                        key: flags & 1 == 1,
+                    });
+                }
+                Message::Relation(Relation {
+                    id,
+                    // an empty namespace is `pg_catalog`
+                    schema: if schema.is_empty() {
+                        "pg_catalog".to_string()
+                    } else {
+                        schema
+                    },
+                    name,
+                    columns,
+                })
+            }
+            b'I' => Message::Insert {
+                relation: r.u32()?,
+                new: r.new_tuple()?,
+            },
+            b'U' => {
+                let relation = r.u32()?;
+                let (old, new) = match r.u8()? {
+                    b'N' => (None, r.tuple()?),
+                    marker => (Some(r.old(marker)?), r.new_tuple()?),
+                };
+                Message::Update { relation, old, new }
+            }
+            b'D' => {
+                let relation = r.u32()?;
+                let marker = r.u8()?;
+                Message::Delete {
+                    relation,
+                    old: r.old(marker)?,
+                }
+            }
+            b'T' => {
+                let count = r.u32()?;
+                // options, `CASCADE` and `RESTART IDENTITY`
+                r.u8()?;
+                let relations = (0..count).map(|_| r.u32()).collect::<Result<_, _>>()?;
+                Message::Truncate { relations }
+            }
+            other => Message::Other(other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuple(buf: &mut Vec<u8>, cells: &[Option<&str>]) {
+        buf.extend_from_slice(&i16::try_from(cells.len()).unwrap_or_default().to_be_bytes());
+        for cell in cells {
+            match cell {
+                Some(text) => {
+                    buf.push(b't');
+                    buf.extend_from_slice(
+                        &u32::try_from(text.len()).unwrap_or_default().to_be_bytes(),
+                    );
+                    buf.extend_from_slice(text.as_bytes());
+                }
+                None => buf.push(b'n'),
+            }
+        }
+    }
+
+    #[test]
+    fn begin_commit() -> Result<(), Error> {
+        let mut buf = vec![b'B'];
+        buf.extend_from_slice(&0x0100_0000_0010_u64.to_be_bytes());
+        buf.extend_from_slice(&42_i64.to_be_bytes());
+        buf.extend_from_slice(&731_u32.to_be_bytes());
+        assert_eq!(
+            Message::Begin {
+                final_lsn: 0x0100_0000_0010,
+                timestamp: 42,
+                xid: 731
+            },
+            Message::parse(&buf)?
+        );
+
+        let mut buf = vec![b'C', 0];
+        buf.extend_from_slice(&16_u64.to_be_bytes());
+        buf.extend_from_slice(&32_u64.to_be_bytes());
+        buf.extend_from_slice(&42_i64.to_be_bytes());
+        assert_eq!(
+            Message::Commit {
+                commit_lsn: 16,
+                end_lsn: 32,
+                timestamp: 42
+            },
+            Message::parse(&buf)?
+        );
+        assert_eq!(Err(Error::Truncated), Message::parse(&buf[..10]));
+        Ok(())
+    }
+
+    #[test]
+    fn relation() -> Result<(), Error> {
+        let mut buf = vec![b'R'];
+        buf.extend_from_slice(&16384_u32.to_be_bytes());
+        buf.extend_from_slice(b"public\0people\0d");
+        buf.extend_from_slice(&2_i16.to_be_bytes());
+        buf.extend_from_slice(b"\x01id\0");
+        buf.extend_from_slice(&23_u32.to_be_bytes());
+        buf.extend_from_slice(&u32::MAX.to_be_bytes());
+        buf.extend_from_slice(b"\x00name\0");
+        buf.extend_from_slice(&25_u32.to_be_bytes());
+        buf.extend_from_slice(&u32::MAX.to_be_bytes());
+        assert_eq!(
+            Message::Relation(Relation {
+                id: 16384,
+                schema: "public".to_string(),
+                name: "people".to_string(),
+                columns: vec![
+                    Column {
+                        name: "id".to_string(),
+                        type_oid: 23,
+                        key: true
+                    },
+                    Column {
+                        name: "name".to_string(),
+                        type_oid: 25,
+                        key: false
+                    }
+                ]
+            }),
+            Message::parse(&buf)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn changes() -> Result<(), Error> {
+        let mut buf = vec![b'I'];
+        buf.extend_from_slice(&16384_u32.to_be_bytes());
+        buf.push(b'N');
+        tuple(&mut buf, &[Some("1"), None]);
+        assert_eq!(
+            Message::Insert {
+                relation: 16384,
+                new: vec![Cell::Text("1".to_string()), Cell::Null]
+            },
+            Message::parse(&buf)?
+        );
+
+        let mut buf = vec![b'U'];
+        buf.extend_from_slice(&16384_u32.to_be_bytes());
+        buf.push(b'K');
+        tuple(&mut buf, &[Some("1"), None]);
+        buf.push(b'N');
+        tuple(&mut buf, &[Some("2"), Some("snot")]);
+        assert_eq!(
+            Message::Update {
+                relation: 16384,
+                old: Some(Old::Key(vec![Cell::Text("1".to_string()), Cell::Null])),
+                new: vec![Cell::Text("2".to_string()), Cell::Text("snot".to_string())]
+            },
+            Message::parse(&buf)?
+        );
+
+        let mut buf = vec![b'D'];
+        buf.extend_from_slice(&16384_u32.to_be_bytes());
+        buf.push(b'O');
+        tuple(&mut buf, &[Some("2"), Some("snot")]);
+        assert_eq!(
+            Message::Delete {
+                relation: 16384,
+                old: Old::Row(vec![
+                    Cell::Text("2".to_string()),
+                    Cell::Text("snot".to_string())
+                ])
+            },
+            Message::parse(&buf)?
+        );
+
+        let mut buf = vec![b'I'];
+        buf.extend_from_slice(&16384_u32.to_be_bytes());
+        buf.push(b'X');
+        assert_eq!(Err(Error::UnexpectedMarker('X')), Message::parse(&buf));
+        Ok(())
+    }
+}
@@ -0,0 +1,384 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A client for the streaming replication protocol, which `tokio_postgres` doesn't support
+//!
+//! See <https://www.postgresql.org/docs/current/protocol-replication.html>
+
+use super::{conversion::POSTGRES_EPOCH_US, quote};
+use bytes::{Buf, Bytes, BytesMut};
+use postgres_protocol::{
+    authentication::{
+        md5_hash,
+        sasl::{ChannelBinding, ScramSha256, SCRAM_SHA_256},
+    },
+    message::frontend,
+};
+use rustls::pki_types::ServerName;
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_postgres::{config::Host, types::PgLsn};
+use tokio_rustls::TlsConnector;
+
+const DEFAULT_PORT: u16 = 5432;
+
+/// Error of a replication connection
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub(crate) enum Error {
+    #[error("No host to connect to")]
+    NoHost,
+    #[error("No user to connect as")]
+    NoUser,
+    #[error("The server asks for a password, but none is configured")]
+    NoPassword,
+    #[error("The server doesn't support TLS")]
+    NoTls,
+    #[error("Unsupported authentication method {0}")]
+    UnsupportedAuthentication(String),
+    #[error("Unexpected message `{0}`")]
+    UnexpectedMessage(char),
+    #[error("Truncated message")]
+    Truncated,
+    #[error("{0}")]
+    Server(String),
+    #[error("The server closed the replication stream")]
+    Closed,
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync> Stream for T {}
+
+/// A message of the replication stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Replicated {
+    /// a message of the output plugin, starting at `lsn`
+    Data { lsn: u64, data: Bytes },
+    /// the server checks that we are alive, it wants a status update if `reply` is set
+    Keepalive { reply: bool },
+}
+
+/// A connection in logical replication mode
+pub(crate) struct Replication {
+    stream: Box<dyn Stream>,
+    /// received bytes that aren't a complete message yet
+    buf: BytesMut,
+    out: BytesMut,
+}
+
+impl Replication {
+    /// Connects to the database and authenticates
+    pub(crate) async fn connect(
+        pg_config: &tokio_postgres::Config,
+        tls_config: Option<&rustls::ClientConfig>,
+    ) -> anyhow::Result<Self> {
+        let host = match pg_config.get_hosts().first() {
+            Some(Host::Tcp(host)) => host.clone(),
+            #[cfg(unix)]
+            Some(Host::Unix(path)) => path.to_string_lossy().to_string(),
+            #[cfg(not(unix))]
+            Some(_) => return Err(Error::NoHost.into()),
+            None => return Err(Error::NoHost.into()),
+        };
+        let port = pg_config
+            .get_ports()
+            .first()
+            .copied()
+            .unwrap_or(DEFAULT_PORT);
+        let stream: Box<dyn Stream> = match pg_config.get_hosts().first() {
+            #[cfg(unix)]
+            Some(Host::Unix(path)) => Box::new(
+                tokio::net::UnixStream::connect(path.join(format!(".s.PGSQL.{port}"))).await?,
+            ),
+            _ => {
+                let mut tcp = TcpStream::connect((host.as_str(), port)).await?;
+                if let Some(tls_config) = tls_config {
+                    let mut out = BytesMut::new();
+                    frontend::ssl_request(&mut out);
+                    tcp.write_all(&out).await?;
+                    if tcp.read_u8().await? != b'S' {
+                        return Err(Error::NoTls.into());
+                    }
+                    let connector = TlsConnector::from(Arc::new(tls_config.clone()));
+                    Box::new(connector.connect(ServerName::try_from(host)?, tcp).await?)
+                } else {
+                    Box::new(tcp)
+                }
+            }
+        };
+        let mut replication = Self {
+            stream,
+            buf: BytesMut::new(),
+            out: BytesMut::new(),
+        };
+        replication.startup(pg_config).await?;
+        Ok(replication)
+    }
+
+    /// Sends the startup message and authenticates, until the server is ready for queries
+    async fn startup(&mut self, pg_config: &tokio_postgres::Config) -> anyhow::Result<()> {
+        let user = pg_config.get_user().ok_or(Error::NoUser)?;
+        let mut parameters = vec![
+            ("user", user),
+            ("replication", "database"),
+            ("client_encoding", "UTF8"),
+        ];
+        if let Some(dbname) = pg_config.get_dbname() {
+            parameters.push(("database", dbname));
+        }
+        if let Some(application_name) = pg_config.get_application_name() {
+            parameters.push(("application_name", application_name));
+        }
+        frontend::startup_message(parameters, &mut self.out)?;
+        self.flush().await?;
+
+        let mut scram = None;
+        loop {
+            let (tag, mut body) = self.message().await?;
+            match tag {
+                b'R' => {
+                    if body.remaining() < 4 {
+                        return Err(Error::Truncated.into());
+                    }
+                    let password = || pg_config.get_password().ok_or(Error::NoPassword);
+                    match body.get_i32() {
+                        // ok
+                        0 => (),
+                        // cleartext password
+                        3 => frontend::password_message(password()?, &mut self.out)?,
+                        // md5 password
+                        5 => {
+                            if body.remaining() < 4 {
+                                return Err(Error::Truncated.into());
+                            }
+                            let mut salt = [0; 4];
+                            body.copy_to_slice(&mut salt);
+                            let hash = md5_hash(user.as_bytes(), password()?, salt);
+                            frontend::password_message(hash.as_bytes(), &mut self.out)?;
+                        }
+                        // SASL, with the list of mechanisms
+                        10 => {
+                            let mechanisms = body
+                                .split(|b| *b == 0)
+                                .filter(|m| !m.is_empty())
+                                .map(|m| String::from_utf8_lossy(m).to_string())
+                                .collect::<Vec<_>>();
+                            if !mechanisms.iter().any(|m| m == SCRAM_SHA_256) {
+                                return Err(Error::UnsupportedAuthentication(
+                                    mechanisms.join(", "),
+                                )
+                                .into());
+                            }
+                            let s = ScramSha256::new(password()?, ChannelBinding::unsupported());
+                            frontend::sasl_initial_response(
+                                SCRAM_SHA_256,
+                                s.message(),
+                                &mut self.out,
+                            )?;
+                            scram = Some(s);
+                        }
+                        // SASL continue
+                        11 => {
+                            let s = scram.as_mut().ok_or(Error::UnexpectedMessage('R'))?;
+                            s.update(&body)?;
+                            frontend::sasl_response(s.message(), &mut self.out)?;
+                        }
+                        // SASL final
+                        12 => {
+                            let s = scram.as_mut().ok_or(Error::UnexpectedMessage('R'))?;
+                            s.finish(&body)?;
+                        }
+                        other => {
+                            return Err(Error::UnsupportedAuthentication(other.to_string()).into())
+                        }
+                    }
+                    self.flush().await?;
+                }
+                // ready for query
+                b'Z' => return Ok(()),
+                other => return Err(Error::UnexpectedMessage(char::from(other)).into()),
+            }
+        }
+    }
+
+    /// Starts streaming the changes of `slot` after `lsn` with the `pgoutput` plugin
+    pub(crate) async fn start(
+        &mut self,
+        slot: &str,
+        lsn: u64,
+        publication: &str,
+    ) -> anyhow::Result<()> {
+        let query = format!(
+            "START_REPLICATION SLOT {} LOGICAL {} (\"proto_version\" '1', \"publication_names\" '{}')",
+            quote(slot),
+            PgLsn::from(lsn),
+            quote(publication).replace('\'', "''")
+        );
+        frontend::query(&query, &mut self.out)?;
+        self.flush().await?;
+        match self.message().await? {
+            // copy both response
+            (b'W', _) => Ok(()),
+            (other, _) => Err(Error::UnexpectedMessage(char::from(other)).into()),
+        }
+    }
+
+    /// Receives the next message of the replication stream
+    ///
+    /// This is cancel safe, a partially received message is kept until the next call.
+    pub(crate) async fn recv(&mut self) -> anyhow::Result<Replicated> {
+        loop {
+            match self.message().await? {
+                // copy data
+                (b'd', mut body) => match body.first().copied() {
+                    Some(b'w') => {
+                        if body.remaining() < 25 {
+                            return Err(Error::Truncated.into());
+                        }
+                        body.advance(1);
+                        let lsn = body.get_u64();
+                        // end of the WAL on the server and the time the message was sent
+                        body.advance(16);
+                        return Ok(Replicated::Data { lsn, data: body });
+                    }
+                    Some(b'k') => {
+                        if body.remaining() < 18 {
+                            return Err(Error::Truncated.into());
+                        }
+                        return Ok(Replicated::Keepalive {
+                            reply: body[17] == 1,
+                        });
+                    }
+                    _ => continue,
+                },
+                // copy done
+                (b'c', _) => return Err(Error::Closed.into()),
+                (other, _) => return Err(Error::UnexpectedMessage(char::from(other)).into()),
+            }
+        }
+    }
+
+    /// Tells the server that everything up to `lsn` is processed, the slot moves past it
+    pub(crate) async fn send_status(&mut self, lsn: u64) -> anyhow::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| i64::try_from(d.as_micros()).unwrap_or(i64::MAX))
+            .unwrap_or_default()
+            .saturating_sub(POSTGRES_EPOCH_US);
+        let mut status = Vec::with_capacity(34);
+        status.push(b'r');
+        // written, flushed and applied
+        for _ in 0..3 {
+            status.extend_from_slice(&lsn.to_be_bytes());
+        }
+        status.extend_from_slice(&now.to_be_bytes());
+        // no reply requested
+        status.push(0);
+        frontend::CopyData::new(status.as_slice())?.write(&mut self.out);
+        self.flush().await
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        self.stream.write_all(&self.out).await?;
+        self.stream.flush().await?;
+        self.out.clear();
+        Ok(())
+    }
+
+    /// Receives the next message with its tag, skipping notices and parameters
+    async fn message(&mut self) -> anyhow::Result<(u8, Bytes)> {
+        loop {
+            if let Some((tag, body)) = split_message(&mut self.buf)? {
+                match tag {
+                    b'E' => return Err(Error::Server(error_message(&body)).into()),
+                    // notice, parameter status and backend key data
+                    b'N' | b'S' | b'K' => continue,
+                    _ => return Ok((tag, body)),
+                }
+            }
+            if self.stream.read_buf(&mut self.buf).await? == 0 {
+                return Err(Error::Closed.into());
+            }
+        }
+    }
+}
+
+/// Splits the first message off `buf` if it is complete
+fn split_message(buf: &mut BytesMut) -> Result<Option<(u8, Bytes)>, Error> {
+    if buf.len() < 5 {
+        return Ok(None);
+    }
+    let len = usize::try_from(u32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]))
+        .map_err(|_| Error::Truncated)?;
+    if len < 4 {
+        return Err(Error::Truncated);
+    }
+    if buf.len() <= len {
+        return Ok(None);
+    }
+    let mut message = buf.split_to(len + 1).freeze();
+    let tag = message.get_u8();
+    message.advance(4);
+    Ok(Some((tag, message)))
+}
+
+/// The severity and message of an error response
+fn error_message(body: &[u8]) -> String {
+    let mut severity = "ERROR".to_string();
+    let mut message = String::new();
+    for field in body.split(|b| *b == 0) {
+        match field.split_first() {
+            Some((b'S', value)) => severity = String::from_utf8_lossy(value).to_string(),
+            Some((b'M', value)) => message = String::from_utf8_lossy(value).to_string(),
+            _ => (),
+        }
+    }
+    format!("{severity}: {message}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages() -> Result<(), Error> {
+        let mut buf = BytesMut::from(&b"Z\0\0\0\x05I"[..]);
+        buf.extend_from_slice(b"d\0\0\0");
+        assert_eq!(
+            Some((b'Z', Bytes::from_static(b"I"))),
+            split_message(&mut buf)?
+        );
+        assert_eq!(None, split_message(&mut buf)?);
+        buf.extend_from_slice(b"\x06kk");
+        assert_eq!(
+            Some((b'd', Bytes::from_static(b"kk"))),
+            split_message(&mut buf)?
+        );
+        assert!(buf.is_empty());
+        assert_eq!(
+            Err(Error::Truncated),
+            split_message(&mut BytesMut::from(&b"Z\0\0\0\x01"[..]))
+        );
+        assert_eq!(
+            "FATAL: no pg_hba.conf entry",
+            error_message(b"SFATAL\0C28000\0Mno pg_hba.conf entry\0\0")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `prometheus` connectors speak the [Prometheus remote write](https://prometheus.io/docs/specs/remote_write_spec/)
+//! protocol, snappy compressed protobuf `WriteRequest`s sent over HTTP.
+//!
+//! The [`prometheus_receiver`](receiver) accepts remote write requests from Prometheus agents and
+//! emits their series as events, the [`prometheus_sender`](sender) sends metric events to
+//! Prometheus compatible backends like Prometheus itself, Cortex, Mimir or Thanos. Together they
+//! put tremor between Prometheus agents and their long-term storage, e.g. to relabel or filter
+//! series.
+//!
+//! ```tremor title="config.troy"
+//! define flow relabel
+//! flow
+//!   define connector agents from prometheus_receiver
+//!   with
+//!     config = {"url": "http://0.0.0.0:9201"}
+//!   end;
+//!
+//!   define connector storage from prometheus_sender
+//!   with
+//!     config = {"url": "http://mimir:9009/api/v1/push"}
+//!   end;
+//!
+//!   define pipeline relabel
+//!   pipeline
+//!     define operator batch from generic::batch
+//!     with
+//!       count = 500,
+//!       timeout = nanos::from_seconds(1)
+//!     end;
+//!     create operator batch;
+//!
+//!     select patch event of
+//!       merge "labels" => {"cluster": "eu-west-1"}
+//!     end
+//!     from in where event.labels["__name__"] != "go_gc_duration_seconds" into batch;
+//!
+//!     select event from batch into out;
+//!   end;
+//!
+//!   create connector agents;
+//!   create connector storage;
+//!   create pipeline relabel;
+//!
+//!   connect /connector/agents to /pipeline/relabel;
+//!   connect /pipeline/relabel to /connector/storage;
+//! end;
+//! ```
+//!
+//! ## Events
+//!
+//! A series is a record of its `labels` and its `samples`, with timestamps in milliseconds since
+//! the epoch, as in Prometheus:
+//!
+//! ```js
+//! {
+//!   "labels": {"__name__": "http_requests_total", "job": "api", "code": "200"},
+//!   "samples": [{"value": 1027.0, "timestamp": 1718871345000}]
+//! }
+//! ```
+//!
+//! A single sample has a `value` and a `timestamp` instead:
+//!
+//! ```js
+//! {
+//!   "labels": {"__name__": "http_requests_total", "job": "api", "code": "200"},
+//!   "value": 1027.0,
+//!   "timestamp": 1718871345000
+//! }
+//! ```
+
+/// Receiving remote write requests
+pub mod receiver;
+/// Sending remote write requests
+pub mod sender;
+
+mod remote_write;
@@ -0,0 +1,305 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `prometheus_receiver` connector accepts Prometheus remote write requests over HTTP and emits
+//! their series as events.
+//!
+//! ## Configuration
+//!
+//! | Option                   | Description                                                       | Type    | Required | Default Value |
+//! |--------------------------|-------------------------------------------------------------------|---------|----------|---------------|
+//! | `url`                    | The address to listen on. Requests are accepted on any path.      | string  | yes      |               |
+//! | `split`                  | Emit an event per `series` with all its samples, or per `sample`. | string  | no       | `"series"`    |
+//! | `max_body_bytes`         | The largest compressed request body accepted, in bytes.           | integer | no       | `10485760`    |
+//! | `max_decompressed_bytes` | The largest request accepted once decompressed, in bytes.         | integer | no       | `67108864`    |
+//!
+//! ```tremor title="config.troy"
+//! define connector agents from prometheus_receiver
+//! with
+//!   config = {
+//!     "url": "http://0.0.0.0:9201",
+//!     "split": "sample"
+//!   }
+//! end;
+//! ```
+//!
+//! Point the Prometheus agents at it in their configuration:
+//!
+//! ```yaml
+//! remote_write:
+//!   - url: http://tremor:9201/api/v1/write
+//! ```
+//!
+//! The connector exchanges structured data, no codec is needed.
+//!
+//! ## Guaranteed delivery
+//!
+//! Requests are answered with `204 No Content` once all their events are handed to the connected
+//! pipelines, requests that can't be decoded with `400 Bad Request` and requests exceeding
+//! `max_body_bytes` or `max_decompressed_bytes` with `413 Payload Too Large`. The events are not
+//! tracked after that. While the pipelines apply backpressure, requests are answered only once their
+//! events could be handed over, which makes the agents slow down and buffer samples themselves.
+
+use super::remote_write;
+use crate::{errors::GenericImplementationError, source::prelude::*, spawn_task, utils::socket};
+use http::{Method, Response, StatusCode};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use hyper::{body::Incoming, service::service_fn, Request};
+use hyper_util::rt::TokioIo;
+use std::{convert::Infallible, net::ToSocketAddrs};
+use tokio::{
+    net::TcpListener,
+    sync::mpsc::{channel, Receiver, Sender},
+    task::JoinHandle,
+};
+use tremor_common::{alias, url::Url};
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::prelude::*;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Split {
+    #[default]
+    Series,
+    Sample,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    url: Url,
+    #[serde(default)]
+    split: Split,
+    #[serde(default = "default_max_body_bytes")]
+    max_body_bytes: usize,
+    #[serde(default = "default_max_decompressed_bytes")]
+    max_decompressed_bytes: usize,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_max_body_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_max_decompressed_bytes() -> usize {
+    64 * 1024 * 1024
+}
+
+/// The limits on the size of a request
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    body: usize,
+    decompressed: usize,
+}
+
+/// Prometheus remote write receiver
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "prometheus_receiver".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        _id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        Ok(Box::new(PrometheusReceiver { config }))
+    }
+}
+
+pub(crate) struct PrometheusReceiver {
+    config: Config,
+}
+
+#[async_trait::async_trait]
+impl Connector for PrometheusReceiver {
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        let (tx, rx) = channel(qsize());
+        let source = PrometheusReceiverSource {
+            origin_uri: EventOriginUri {
+                scheme: "tremor-prometheus-receiver".to_string(),
+                host: self.config.url.host_or_local().to_string(),
+                port: self.config.url.port(),
+                path: vec![],
+            },
+            config: self.config.clone(),
+            tx,
+            rx,
+            server_task: None,
+        };
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Structured
+    }
+}
+
+struct PrometheusReceiverSource {
+    config: Config,
+    origin_uri: EventOriginUri,
+    tx: Sender<Value<'static>>,
+    rx: Receiver<Value<'static>>,
+    server_task: Option<JoinHandle<()>>,
+}
+
+fn response(status: StatusCode, body: String) -> Response<String> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle_request(
+    req: Request<Incoming>,
+    split: Split,
+    limits: Limits,
+    tx: Sender<Value<'static>>,
+) -> Result<Response<String>, Infallible> {
+    if req.method() != Method::POST {
+        return Ok(response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Only remote write requests are accepted".to_string(),
+        ));
+    }
+    let body = match Limited::new(req.into_body(), limits.body).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            return Ok(response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "The request body exceeds the limit of {} bytes",
+                    limits.body
+                ),
+            ))
+        }
+        Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+    let request = match remote_write::decode(&body, limits.decompressed) {
+        Ok(request) => request,
+        Err(e) if e.is::<remote_write::TooLarge>() => {
+            return Ok(response(StatusCode::PAYLOAD_TOO_LARGE, e.to_string()))
+        }
+        Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+    for series in request.timeseries {
+        let sent = match split {
+            Split::Series => tx.send(remote_write::series_value(series)).await.is_ok(),
+            Split::Sample => {
+                let mut sent = true;
+                for sample in remote_write::sample_values(series) {
+                    sent &= tx.send(sample).await.is_ok();
+                }
+                sent
+            }
+        };
+        if !sent {
+            return Ok(response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The receiver is shutting down".to_string(),
+            ));
+        }
+    }
+    Ok(response(StatusCode::NO_CONTENT, String::new()))
+}
+
+#[async_trait::async_trait]
+impl Source for PrometheusReceiverSource {
+    async fn connect(&mut self, ctx: &SourceContext, _attempt: &Attempt) -> anyhow::Result<bool> {
+        // dropping the previous listener
+        if let Some(server_task) = self.server_task.take() {
+            server_task.abort();
+        }
+        let host = self.config.url.host_or_local().to_string();
+        let port = self.config.url.port_or_dflt();
+        let addr = (host.as_str(), port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or(socket::Error::InvalidAddress(host, port))?;
+        let listener = TcpListener::bind(addr).await?;
+        info!(
+            "{ctx} Listening for remote write requests on {:?}",
+            listener.local_addr()
+        );
+
+        let split = self.config.split;
+        let limits = Limits {
+            body: self.config.max_body_bytes,
+            decompressed: self.config.max_decompressed_bytes,
+        };
+        let tx = self.tx.clone();
+        self.server_task = Some(spawn_task(ctx.clone(), async move {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                let tx = tx.clone();
+                tokio::task::spawn(async move {
+                    let service = service_fn(move |req: Request<Incoming>| {
+                        handle_request(req, split, limits, tx.clone())
+                    });
+                    if let Err(e) = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        error!("Error serving connection: {e}");
+                    }
+                });
+            }
+        }));
+        Ok(true)
+    }
+
+    async fn pull_data(
+        &mut self,
+        _pull_id: &mut u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<SourceReply> {
+        let value = self
+            .rx
+            .recv()
+            .await
+            .ok_or(GenericImplementationError::ChannelEmpty)?;
+        Ok(SourceReply::Structured {
+            origin_uri: self.origin_uri.clone(),
+            payload: (value, Value::object()).into(),
+            stream: DEFAULT_STREAM_ID,
+            port: None,
+        })
+    }
+
+    async fn on_stop(&mut self, _ctx: &SourceContext) -> anyhow::Result<()> {
+        if let Some(server_task) = self.server_task.take() {
+            server_task.abort();
+        }
+        Ok(())
+    }
+
+    fn is_transactional(&self) -> bool {
+        false
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+}
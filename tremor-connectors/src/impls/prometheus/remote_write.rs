@@ -0,0 +1,293 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The remote write 1.0 `WriteRequest` and its conversion from and to events
+
+use prost::Message;
+use tremor_value::prelude::*;
+
+/// `prometheus.WriteRequest`, metadata is not supported
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) timeseries: Vec<TimeSeries>,
+}
+
+/// `prometheus.TimeSeries`
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    pub(crate) samples: Vec<Sample>,
+}
+
+/// `prometheus.Label`
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Label {
+    #[prost(string, tag = "1")]
+    pub(crate) name: String,
+    #[prost(string, tag = "2")]
+    pub(crate) value: String,
+}
+
+/// `prometheus.Sample`
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Sample {
+    #[prost(double, tag = "1")]
+    pub(crate) value: f64,
+    /// milliseconds since the epoch
+    #[prost(int64, tag = "2")]
+    pub(crate) timestamp: i64,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub(crate) enum Error {
+    #[error("Expected a record with `labels`, got a value of type `{0}`")]
+    NotARecord(ValueType),
+    #[error("`labels` must be a non-empty record of strings")]
+    InvalidLabels,
+    #[error("Expected `samples` or a `value`")]
+    NoSamples,
+    #[error("Samples need a numeric `value` and an integer `timestamp`")]
+    InvalidSample,
+}
+
+/// A request that is too large once decompressed
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("The decompressed request of {0} bytes exceeds the limit of {1} bytes")]
+pub(crate) struct TooLarge(pub(crate) usize, pub(crate) usize);
+
+/// Decodes the body of a remote write request, that is at most `max_len` bytes decompressed
+pub(crate) fn decode(body: &[u8], max_len: usize) -> anyhow::Result<WriteRequest> {
+    // the length is read from the header of the body, before allocating anything
+    let len = snap::raw::decompress_len(body)?;
+    if len > max_len {
+        return Err(TooLarge(len, max_len).into());
+    }
+    let data = snap::raw::Decoder::new().decompress_vec(body)?;
+    Ok(WriteRequest::decode(data.as_slice())?)
+}
+
+/// Encodes the body of a remote write request
+pub(crate) fn encode(request: &WriteRequest) -> anyhow::Result<Vec<u8>> {
+    Ok(snap::raw::Encoder::new().compress_vec(&request.encode_to_vec())?)
+}
+
+fn labels_value(labels: Vec<Label>) -> Value<'static> {
+    let mut record = Value::object_with_capacity(labels.len());
+    for Label { name, value } in labels {
+        record.try_insert(name, value);
+    }
+    record
+}
+
+/// A series as an event
+pub(crate) fn series_value(series: TimeSeries) -> Value<'static> {
+    let samples: Vec<_> = series
+        .samples
+        .into_iter()
+        .map(|Sample { value, timestamp }| literal!({"value": value, "timestamp": timestamp}))
+        .collect();
+    literal!({
+        "labels": labels_value(series.labels),
+        "samples": samples
+    })
+}
+
+/// The samples of a series, each as an event
+pub(crate) fn sample_values(series: TimeSeries) -> impl Iterator<Item = Value<'static>> {
+    let labels = labels_value(series.labels);
+    series
+        .samples
+        .into_iter()
+        .map(move |Sample { value, timestamp }| {
+            literal!({
+                "labels": labels.clone(),
+                "value": value,
+                "timestamp": timestamp
+            })
+        })
+}
+
+fn sample(value: &Value, timestamp: Option<i64>) -> Result<Sample, Error> {
+    Ok(Sample {
+        value: value.cast_f64().ok_or(Error::InvalidSample)?,
+        timestamp: timestamp.ok_or(Error::InvalidSample)?,
+    })
+}
+
+/// A series or a single sample from an event, samples without a `timestamp` are taken at `now_ms`
+pub(crate) fn series(event: &Value, now_ms: i64) -> Result<TimeSeries, Error> {
+    if !event.is_object() {
+        return Err(Error::NotARecord(event.value_type()));
+    }
+    let record = event
+        .get_object("labels")
+        .filter(|labels| !labels.is_empty())
+        .ok_or(Error::InvalidLabels)?;
+    let mut labels = record
+        .iter()
+        .map(|(name, value)| {
+            Ok(Label {
+                name: name.to_string(),
+                value: value.as_str().ok_or(Error::InvalidLabels)?.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    // the protocol requires labels to be sorted by name
+    labels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let samples = if let Some(samples) = event.get_array("samples") {
+        samples
+            .iter()
+            .map(|s| {
+                sample(
+                    s.get("value").ok_or(Error::InvalidSample)?,
+                    s.get_i64("timestamp"),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+    } else if let Some(value) = event.get("value") {
+        let timestamp = match event.get("timestamp") {
+            Some(timestamp) => Some(timestamp.as_i64().ok_or(Error::InvalidSample)?),
+            None => Some(now_ms),
+        };
+        vec![sample(value, timestamp)?]
+    } else {
+        return Err(Error::NoSamples);
+    };
+    Ok(TimeSeries { labels, samples })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        let request = WriteRequest {
+            timeseries: vec![TimeSeries {
+                labels: vec![
+                    Label {
+                        name: "__name__".to_string(),
+                        value: "up".to_string(),
+                    },
+                    Label {
+                        name: "job".to_string(),
+                        value: "api".to_string(),
+                    },
+                ],
+                samples: vec![
+                    Sample {
+                        value: 1.0,
+                        timestamp: 1_718_871_345_000,
+                    },
+                    Sample {
+                        value: 0.0,
+                        timestamp: 1_718_871_360_000,
+                    },
+                ],
+            }],
+        };
+        let body = encode(&request)?;
+        let len = request.encode_to_vec().len();
+        assert_eq!(request, decode(&body, len)?);
+        assert_eq!(
+            Some(&TooLarge(len, len - 1)),
+            decode(&body, len - 1)
+                .err()
+                .as_ref()
+                .and_then(|e| e.downcast_ref())
+        );
+        assert!(decode(b"snot", 1024).is_err());
+
+        let series = request.timeseries[0].clone();
+        let value = series_value(series.clone());
+        assert_eq!(
+            literal!({
+                "labels": {"__name__": "up", "job": "api"},
+                "samples": [
+                    {"value": 1.0, "timestamp": 1_718_871_345_000_i64},
+                    {"value": 0.0, "timestamp": 1_718_871_360_000_i64}
+                ]
+            }),
+            value
+        );
+        assert_eq!(Ok(series.clone()), self::series(&value, 0));
+
+        let samples: Vec<_> = sample_values(series).collect();
+        assert_eq!(
+            vec![
+                literal!({"labels": {"__name__": "up", "job": "api"}, "value": 1.0, "timestamp": 1_718_871_345_000_i64}),
+                literal!({"labels": {"__name__": "up", "job": "api"}, "value": 0.0, "timestamp": 1_718_871_360_000_i64}),
+            ],
+            samples
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn events() {
+        let expected = TimeSeries {
+            labels: vec![
+                Label {
+                    name: "__name__".to_string(),
+                    value: "up".to_string(),
+                },
+                Label {
+                    name: "job".to_string(),
+                    value: "api".to_string(),
+                },
+            ],
+            samples: vec![Sample {
+                value: 42.0,
+                timestamp: 23,
+            }],
+        };
+        // labels are sorted, integers are fine as values and the timestamp defaults to now
+        assert_eq!(
+            Ok(expected),
+            series(
+                &literal!({"labels": {"job": "api", "__name__": "up"}, "value": 42}),
+                23
+            )
+        );
+
+        for (event, error) in [
+            (literal!([1]), Error::NotARecord(ValueType::Array)),
+            (literal!({"value": 1}), Error::InvalidLabels),
+            (literal!({"labels": {}, "value": 1}), Error::InvalidLabels),
+            (
+                literal!({"labels": {"job": 1}, "value": 1}),
+                Error::InvalidLabels,
+            ),
+            (literal!({"labels": {"job": "api"}}), Error::NoSamples),
+            (
+                literal!({"labels": {"job": "api"}, "value": "1"}),
+                Error::InvalidSample,
+            ),
+            (
+                literal!({"labels": {"job": "api"}, "value": 1, "timestamp": "now"}),
+                Error::InvalidSample,
+            ),
+            (
+                literal!({"labels": {"job": "api"}, "samples": [{"value": 1}]}),
+                Error::InvalidSample,
+            ),
+        ] {
+            assert_eq!(Err(error), series(&event, 0), "{event}");
+        }
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `prometheus_sender` connector sends metric events to a Prometheus compatible backend as
+//! remote write requests.
+//!
+//! ## Configuration
+//!
+//! | Option       | Description                                                                            | Type              | Required | Default Value |
+//! |--------------|----------------------------------------------------------------------------------------|-------------------|----------|---------------|
+//! | `url`        | The remote write endpoint, e.g. `http://prometheus:9090/api/v1/write`.                 | string            | yes      |               |
+//! | `auth`       | Authentication, as for the [`http_client`](crate::impls::http), e.g. `{"bearer": "…"}`. | record or string  | no       | `"none"`      |
+//! | `headers`    | Additional headers, e.g. `{"X-Scope-OrgID": "tenant-1"}` for multi-tenant backends.    | record of strings | no       | `{}`          |
+//! | `tls`        | `true` or a TLS client configuration, see [`TLSClientConfig`]. Required for `https`.   | boolean or record | no       | `false`       |
+//! | `timeout_ms` | How long to wait for the backend to answer a request, in milliseconds.                 | integer           | no       | `30000`       |
+//!
+//! ```tremor title="config.troy"
+//! define connector storage from prometheus_sender
+//! with
+//!   config = {
+//!     "url": "https://mimir:9009/api/v1/push",
+//!     "tls": true,
+//!     "auth": {"basic": {"username": "tremor", "password": "snot"}},
+//!     "headers": {"X-Scope-OrgID": "eu-west-1"}
+//!   }
+//! end;
+//! ```
+//!
+//! The connector exchanges structured data, no codec is needed.
+//!
+//! ## Batching
+//!
+//! Every event is sent as one request. Batch events with the `generic::batch` operator to send
+//! many series at once, as the backends expect. Samples without a `timestamp` are taken at the time
+//! they are sent.
+//!
+//! ## Guaranteed delivery
+//!
+//! Events are acknowledged once the backend accepted them. Events that aren't valid series or
+//! samples, or that the backend rejected or didn't answer in time, are failed.
+
+use super::remote_write::{self, WriteRequest};
+use crate::{
+    errors::{error_connector_def, GenericImplementationError},
+    impls::http::auth::Auth,
+    sink::prelude::*,
+    utils::tls::TLSClientConfig,
+};
+use either::Either;
+use http::{header, Method, Request, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client as HyperClient},
+    rt::TokioExecutor,
+};
+use std::{collections::HashMap, time::Duration};
+use tremor_common::time::nanotime;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    url: String,
+    #[serde(default)]
+    auth: Auth,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(with = "either::serde_untagged_optional", default)]
+    tls: Option<Either<TLSClientConfig, bool>>,
+    #[serde(default = "default_timeout_ms")]
+    timeout_ms: u64,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_timeout_ms() -> u64 {
+    30_000
+}
+
+/// Prometheus remote write sender
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+impl Builder {
+    const MISSING_TLS: &'static str =
+        "missing tls config with 'https' url. Set 'tls' to 'true' or provide a full tls config.";
+}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "prometheus_sender".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        let url = config
+            .url
+            .parse::<Uri>()
+            .map_err(|e| error_connector_def(id, &format!("Invalid `url`: {e}")))?;
+        let tls_config = match config.tls.as_ref() {
+            Some(Either::Left(tls_config)) => Some(tls_config.to_client_config()?),
+            Some(Either::Right(true)) => Some(TLSClientConfig::default().to_client_config()?),
+            Some(Either::Right(false)) | None => None,
+        };
+        if url.scheme_str() == Some("https") && tls_config.is_none() {
+            return Err(error_connector_def(id, Self::MISSING_TLS).into());
+        }
+        Ok(Box::new(PrometheusSender {
+            config,
+            url,
+            tls_config,
+        }))
+    }
+}
+
+pub(crate) struct PrometheusSender {
+    config: Config,
+    url: Uri,
+    tls_config: Option<rustls::ClientConfig>,
+}
+
+#[async_trait::async_trait]
+impl Connector for PrometheusSender {
+    async fn create_sink(
+        &mut self,
+        ctx: SinkContext,
+        builder: SinkManagerBuilder,
+    ) -> anyhow::Result<Option<SinkAddr>> {
+        let sink = PrometheusSenderSink {
+            config: self.config.clone(),
+            url: self.url.clone(),
+            tls_config: self.tls_config.clone(),
+            client: None,
+        };
+        Ok(Some(builder.spawn(sink, ctx)))
+    }
+
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Structured
+    }
+}
+
+struct PrometheusSenderSink {
+    config: Config,
+    url: Uri,
+    tls_config: Option<rustls::ClientConfig>,
+    client: Option<HyperClient<HttpsConnector<HttpConnector>, Full<Bytes>>>,
+}
+
+impl PrometheusSenderSink {
+    async fn send(&self, request: &WriteRequest) -> anyhow::Result<()> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or(GenericImplementationError::ClientNotAvailable("Prometheus"))?;
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.clone())
+            .header(header::CONTENT_ENCODING, "snappy")
+            .header(header::CONTENT_TYPE, "application/x-protobuf")
+            .header(header::USER_AGENT, "tremor")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0");
+        if let Some(auth) = self.config.auth.as_header_value()? {
+            builder = builder.header(header::AUTHORIZATION, auth);
+        }
+        for (name, value) in &self.config.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let body = Full::new(Bytes::from(remote_write::encode(request)?));
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let response = tokio::time::timeout(timeout, client.request(builder.body(body)?))
+            .await
+            .map_err(|_| GenericImplementationError::Timeout(timeout))??;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.into_body().collect().await?.to_bytes();
+        Err(Error::Rejected(status.as_u16(), String::from_utf8_lossy(&body).to_string()).into())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("The backend rejected the request with status {0}: {1}")]
+    Rejected(u16, String),
+}
+
+#[async_trait::async_trait]
+impl Sink for PrometheusSenderSink {
+    async fn connect(&mut self, _ctx: &SinkContext, _attempt: &Attempt) -> anyhow::Result<bool> {
+        let https = if let Some(tls_config) = self.tls_config.clone() {
+            HttpsConnectorBuilder::new()
+                .with_tls_config(tls_config)
+                .https_or_http()
+                .enable_http1()
+                .enable_http2()
+                .build()
+        } else {
+            HttpsConnectorBuilder::new()
+                .with_native_roots()?
+                .https_or_http()
+                .enable_http1()
+                .enable_http2()
+                .build()
+        };
+        self.client = Some(HyperClient::builder(TokioExecutor::new()).build(https));
+        Ok(true)
+    }
+
+    async fn on_event(
+        &mut self,
+        _input: &str,
+        event: Event,
+        ctx: &SinkContext,
+        _serializer: &mut EventSerializer,
+        _start: u64,
+    ) -> anyhow::Result<SinkReply> {
+        let now_ms = i64::try_from(nanotime() / 1_000_000).unwrap_or(i64::MAX);
+        let timeseries = event
+            .value_iter()
+            .map(|value| remote_write::series(value, now_ms))
+            .collect::<Result<Vec<_>, _>>();
+        let result = match timeseries {
+            Ok(timeseries) => self.send(&WriteRequest { timeseries }).await,
+            Err(e) => Err(e.into()),
+        };
+        Ok(match result {
+            Ok(()) => SinkReply::ack_or_none(event.transactional),
+            Err(e) => {
+                error!("{ctx} Error sending remote write request: {e}");
+                SinkReply::fail_or_none(event.transactional)
+            }
+        })
+    }
+
+    async fn finalize(
+        &mut self,
+        _ctx: &SinkContext,
+        _serializer: &mut EventSerializer,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn auto_ack(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Connector as ConnectorConfig;
+    use tremor_value::literal;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connector_builder_invalid_config() -> anyhow::Result<()> {
+        let alias = alias::Connector::new("flow", "my_sender");
+        let builder = super::Builder::default();
+        let kill_switch = KillSwitch::dummy();
+        let config = literal!({"config": {"url": "https://mimir:9009/api/v1/push"}});
+        let connector_config =
+            ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+        let result = builder.build(&alias, &connector_config, &kill_switch).await;
+        assert_eq!(
+            Some(format!(
+                "[{alias}] Invalid definition: {}",
+                Builder::MISSING_TLS
+            )),
+            result.err().map(|e| e.to_string())
+        );
+
+        let config = literal!({"config": {"url": "not a url"}});
+        let connector_config =
+            ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+        assert!(builder
+            .build(&alias, &connector_config, &kill_switch)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
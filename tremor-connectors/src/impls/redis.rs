@@ -0,0 +1,817 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `redis` connector connects to a [Redis](https://redis.io/) server.
+//!
+//! Its source consumes [Redis Streams](https://redis.io/docs/latest/develop/data-types/streams/) as part
+//! of a consumer group and receives messages from pub/sub `channels` and `patterns`.
+//! Its sink runs the commands described in the `$redis` metadata of events and emits their replies
+//! from the source, much like the [`kv`](super::kv) connector, but with the state shared by all tremor
+//! instances connected to the same server.
+//!
+//! ## Configuration
+//!
+//! | Option     | Description                                                                                     | Type            | Required | Default Value               |
+//! |------------|-------------------------------------------------------------------------------------------------|-----------------|----------|-----------------------------|
+//! | `url`      | The server to connect to, e.g. `redis://localhost:6379/0`. `rediss://` urls use TLS.            | string          | yes      |                             |
+//! | `streams`  | Streams to consume from as part of the consumer `group`.                                        | list of strings | no       | `[]`                        |
+//! | `group`    | The consumer group, created on every stream if it does not exist yet.                           | string          | no       | `tremor`                    |
+//! | `consumer` | The name of this consumer within the `group`.                                                   | string          | no       | `<hostname>-<connector id>` |
+//! | `start_id` | Where a newly created consumer group starts, `$` for new entries only, `0` for the full stream. | string          | no       | `$`                         |
+//! | `count`    | The maximum number of stream entries read at once.                                              | integer         | no       | `100`                       |
+//! | `block_ms` | How long a read waits for new stream entries, in milliseconds.                                  | integer         | no       | `1000`                      |
+//! | `channels` | Pub/sub channels to subscribe to.                                                               | list of strings | no       | `[]`                        |
+//! | `patterns` | Pub/sub channel patterns to subscribe to, e.g. `orders.*`.                                      | list of strings | no       | `[]`                        |
+//!
+//! ```tremor title="config.troy"
+//! define connector orders from redis
+//! with
+//!   config = {
+//!     "url": "redis://redis:6379",
+//!     "streams": ["orders"],
+//!     "group": "enrichment",
+//!     "channels": ["notifications"]
+//!   }
+//! end;
+//! ```
+//!
+//! The connector exchanges structured data, no codec is needed. A stream entry is emitted as a record
+//! of its fields, a pub/sub message as a string, or as bytes if it is no valid UTF-8.
+//!
+//! ## Guaranteed delivery
+//!
+//! Stream entries are acknowledged with `XACK` once their event is acknowledged downstream. Failed
+//! events are emitted again. Entries that were not acknowledged when the connection was lost or the
+//! connector stopped are read again by the next consumer with the same name. Pub/sub messages are
+//! fire and forget.
+//!
+//! Events sent to the sink are acknowledged once all their commands succeeded and failed otherwise.
+//!
+//! ## Commands
+//!
+//! The sink runs the command in the `$redis` metadata of an event. The event payload, unless it is
+//! `null`, is appended as the last argument. Strings and bytes are sent as they are, numbers in
+//! their textual form and everything else encoded as JSON.
+//!
+//! ```tremor
+//! let $redis = {"command": "SET", "args": ["snot"]};
+//! let event = "badger";
+//! ```
+//!
+//! ```tremor
+//! let $redis = {"command": "XADD", "args": ["orders", "*", "id", "42", "status"]};
+//! let event = "created";
+//! ```
+//!
+//! ```tremor
+//! let $redis = {"command": "EXPIRE", "args": ["snot", 60]};
+//! let event = null;
+//! ```
+//!
+//! The reply of every command is emitted from the `out` port of the source, together with the
+//! `$correlation` metadata of the event. A failed command emits an event on the `err` port
+//! carrying the error in `$error`.
+//!
+//! ## Reconnects
+//!
+//! When the connection to the server is lost, the connector reconnects according to its `reconnect`
+//! configuration and resubscribes to all channels and patterns.
+//!
+//! ## Metadata
+//!
+//! Stream entries carry the following metadata:
+//!
+//! ```js
+//! {
+//!   "$redis": {
+//!     "stream": "orders",
+//!     "id": "1718871345000-0"
+//!   }
+//! }
+//! ```
+//!
+//! Pub/sub messages carry the following metadata:
+//!
+//! ```js
+//! {
+//!   "$redis": {
+//!     "channel": "notifications",
+//!     # only present if the message matched one of the `patterns`
+//!     "pattern": "notif*"
+//!   }
+//! }
+//! ```
+//!
+//! Command replies carry the following metadata:
+//!
+//! ```js
+//! {
+//!   "$redis": {
+//!     "command": "SET"
+//!   },
+//!   # only present if the event of the command had it
+//!   "$correlation": "some-correlating-unique-data"
+//! }
+//! ```
+
+use crate::{
+    errors::error_connector_def, sink::prelude::*, source::prelude::*, spawn_task, utils::hostname,
+};
+use futures::StreamExt;
+use redis::{
+    aio::MultiplexedConnection,
+    streams::{StreamId, StreamReadReply},
+    Client, ConnectionAddr, RedisError,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        RwLock,
+    },
+    task::JoinHandle,
+};
+use tremor_common::ports::{Port, ERR, OUT};
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::prelude::*;
+
+const URL_SCHEME: &str = "tremor-redis";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    url: String,
+    #[serde(default)]
+    streams: Vec<String>,
+    #[serde(default = "default_group")]
+    group: String,
+    consumer: Option<String>,
+    #[serde(default = "default_start_id")]
+    start_id: String,
+    #[serde(default = "default_count")]
+    count: usize,
+    #[serde(default = "default_block_ms")]
+    block_ms: u64,
+    #[serde(default)]
+    channels: Vec<String>,
+    #[serde(default)]
+    patterns: Vec<String>,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_group() -> String {
+    "tremor".to_string()
+}
+
+fn default_start_id() -> String {
+    "$".to_string()
+}
+
+fn default_count() -> usize {
+    100
+}
+
+fn default_block_ms() -> u64 {
+    1000
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Missing `$redis.command`")]
+    MissingCommand,
+    #[error("Invalid `$redis.args`, expected a list")]
+    InvalidArgs,
+    #[error("The connection to the server was closed")]
+    ConnectionClosed,
+}
+
+/// Redis connector
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "redis".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        let client = Client::open(config.url.as_str())
+            .map_err(|e| error_connector_def(id, &format!("Invalid `url`: {e}")))?;
+        let origin_uri = match &client.get_connection_info().addr {
+            ConnectionAddr::Tcp(host, port) | ConnectionAddr::TcpTls { host, port, .. } => {
+                EventOriginUri {
+                    scheme: URL_SCHEME.to_string(),
+                    host: host.clone(),
+                    port: Some(*port),
+                    path: vec![],
+                }
+            }
+            ConnectionAddr::Unix(path) => EventOriginUri {
+                scheme: URL_SCHEME.to_string(),
+                host: hostname(),
+                port: None,
+                path: path
+                    .iter()
+                    .map(|part| part.to_string_lossy().to_string())
+                    .collect(),
+            },
+        };
+        let consumer = config
+            .consumer
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}", hostname(), id.connector_alias()));
+        let (source_tx, source_rx) = channel(qsize());
+        Ok(Box::new(Redis {
+            config,
+            client,
+            consumer,
+            origin_uri,
+            connection: Arc::new(RwLock::new(None)),
+            source_tx,
+            source_rx: Some(source_rx),
+            tasks: Vec::new(),
+        }))
+    }
+}
+
+pub(crate) struct Redis {
+    config: Config,
+    client: Client,
+    consumer: String,
+    origin_uri: EventOriginUri,
+    /// the connection for commands and acknowledgements, shared by source and sink
+    connection: Arc<RwLock<Option<MultiplexedConnection>>>,
+    source_tx: Sender<Incoming>,
+    source_rx: Option<Receiver<Incoming>>,
+    /// reading streams and pub/sub messages of the current connection for the source
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Redis {
+    async fn read_streams(
+        &mut self,
+        ctx: &ConnectorContext,
+        connection: &mut MultiplexedConnection,
+    ) -> anyhow::Result<()> {
+        for stream in &self.config.streams {
+            let created: Result<(), RedisError> = redis::cmd("XGROUP")
+                .arg("CREATE")
+                .arg(stream)
+                .arg(&self.config.group)
+                .arg(&self.config.start_id)
+                .arg("MKSTREAM")
+                .query_async(connection)
+                .await;
+            match created {
+                Err(e) if e.code() != Some("BUSYGROUP") => return Err(e.into()),
+                _ => (),
+            }
+        }
+        // reads block, they get a connection of their own to not hold up commands
+        let mut reader = self.client.get_multiplexed_async_connection().await?;
+        let streams = self.config.streams.clone();
+        let group = self.config.group.clone();
+        let consumer = self.consumer.clone();
+        let count = self.config.count;
+        let block_ms = self.config.block_ms;
+        let tx = self.source_tx.clone();
+        self.tasks.push(spawn_task(ctx.clone(), async move {
+            // first the entries we read before but never acknowledged, then new ones
+            let mut read_ids: Vec<String> = vec!["0".to_string(); streams.len()];
+            loop {
+                let reply: Option<StreamReadReply> = redis::cmd("XREADGROUP")
+                    .arg("GROUP")
+                    .arg(&group)
+                    .arg(&consumer)
+                    .arg("COUNT")
+                    .arg(count)
+                    .arg("BLOCK")
+                    .arg(block_ms)
+                    .arg("STREAMS")
+                    .arg(&streams)
+                    .arg(&read_ids)
+                    .query_async(&mut reader)
+                    .await?;
+                let Some(reply) = reply else {
+                    continue;
+                };
+                for key in reply.keys {
+                    let Some(idx) = streams.iter().position(|s| s == &key.key) else {
+                        continue;
+                    };
+                    if read_ids[idx] != ">" {
+                        // the pending entries of this stream are drained once none are left
+                        read_ids[idx] = key
+                            .ids
+                            .last()
+                            .map_or_else(|| ">".to_string(), |entry| entry.id.clone());
+                    }
+                    for entry in key.ids {
+                        if tx
+                            .send(Incoming::Entry(key.key.clone(), entry))
+                            .await
+                            .is_err()
+                        {
+                            // the source is gone
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, ctx: &ConnectorContext) -> anyhow::Result<()> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        for channel in &self.config.channels {
+            pubsub.subscribe(channel).await?;
+        }
+        for pattern in &self.config.patterns {
+            pubsub.psubscribe(pattern).await?;
+        }
+        let tx = self.source_tx.clone();
+        self.tasks.push(spawn_task(ctx.clone(), async move {
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = messages.next().await {
+                if tx.send(Incoming::Message(message)).await.is_err() {
+                    return Ok(());
+                }
+            }
+            Err(Error::ConnectionClosed.into())
+        }));
+        Ok(())
+    }
+
+    async fn close(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.connection.write().await.take();
+    }
+}
+
+#[async_trait::async_trait()]
+impl Connector for Redis {
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Structured
+    }
+
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        let source = RedisSource {
+            rx: self
+                .source_rx
+                .take()
+                .ok_or(GenericImplementationError::AlreadyConnected)?,
+            connection: self.connection.clone(),
+            group: self.config.group.clone(),
+            unacked: HashMap::new(),
+            failed: VecDeque::new(),
+            origin_uri: self.origin_uri.clone(),
+            transactional: !self.config.streams.is_empty(),
+        };
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    async fn create_sink(
+        &mut self,
+        ctx: SinkContext,
+        builder: SinkManagerBuilder,
+    ) -> anyhow::Result<Option<SinkAddr>> {
+        let sink = RedisSink {
+            connection: self.connection.clone(),
+            source_tx: self.source_tx.clone(),
+        };
+        Ok(Some(builder.spawn(sink, ctx)))
+    }
+
+    async fn connect(
+        &mut self,
+        ctx: &ConnectorContext,
+        _attempt: &Attempt,
+    ) -> anyhow::Result<bool> {
+        self.close().await;
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        if !self.config.streams.is_empty() {
+            self.read_streams(ctx, &mut connection).await?;
+        }
+        if !self.config.channels.is_empty() || !self.config.patterns.is_empty() {
+            self.subscribe(ctx).await?;
+        }
+        info!("{ctx} Connected to {}", self.origin_uri);
+        *self.connection.write().await = Some(connection);
+        Ok(true)
+    }
+
+    async fn on_stop(&mut self, _ctx: &ConnectorContext) -> anyhow::Result<()> {
+        self.close().await;
+        Ok(())
+    }
+}
+
+/// Messages for the source
+enum Incoming {
+    /// an entry of a stream, acknowledged once the event is
+    Entry(String, StreamId),
+    /// a pub/sub message
+    Message(redis::Msg),
+    /// the reply to a command of the sink
+    Reply(EventPayload, Port<'static>),
+}
+
+/// Converts a reply from the server
+fn from_redis(value: redis::Value) -> Value<'static> {
+    match value {
+        redis::Value::Nil => Value::const_null(),
+        redis::Value::Int(i) => Value::from(i),
+        redis::Value::Double(f) => Value::from(f),
+        redis::Value::Boolean(b) => Value::from(b),
+        redis::Value::Okay => Value::from("OK"),
+        redis::Value::BulkString(bytes) => from_bytes(bytes),
+        redis::Value::SimpleString(s) | redis::Value::VerbatimString { text: s, .. } => {
+            Value::from(s)
+        }
+        redis::Value::BigNumber(n) => Value::from(n.to_string()),
+        redis::Value::Array(values)
+        | redis::Value::Set(values)
+        | redis::Value::Push { data: values, .. } => {
+            Value::from(values.into_iter().map(from_redis).collect::<Vec<_>>())
+        }
+        redis::Value::Map(entries) => {
+            let mut object = Value::object_with_capacity(entries.len());
+            for (key, value) in entries {
+                let key = match from_redis(key) {
+                    Value::String(key) => key.to_string(),
+                    other => other.encode(),
+                };
+                object.try_insert(key, from_redis(value));
+            }
+            object
+        }
+        redis::Value::Attribute { data, .. } => from_redis(*data),
+        redis::Value::ServerError(e) => Value::from(RedisError::from(e).to_string()),
+    }
+}
+
+/// Binary safe strings are turned into strings where possible
+fn from_bytes(bytes: Vec<u8>) -> Value<'static> {
+    match String::from_utf8(bytes) {
+        Ok(s) => Value::from(s),
+        Err(e) => Value::Bytes(e.into_bytes().into()),
+    }
+}
+
+/// Turns an event value into a command argument
+fn to_arg(value: &Value) -> Vec<u8> {
+    if let Some(s) = value.as_str() {
+        s.as_bytes().to_vec()
+    } else if let Some(bytes) = value.as_bytes() {
+        bytes.to_vec()
+    } else {
+        value.encode().into_bytes()
+    }
+}
+
+fn to_cmd(redis_meta: Option<&Value>, value: &Value) -> Result<(String, redis::Cmd), Error> {
+    let name = redis_meta
+        .get_str("command")
+        .ok_or(Error::MissingCommand)?
+        .to_uppercase();
+    let mut cmd = redis::cmd(&name);
+    if let Some(args) = redis_meta.get("args") {
+        for arg in args.as_array().ok_or(Error::InvalidArgs)? {
+            cmd.arg(to_arg(arg));
+        }
+    }
+    if !value.is_null() {
+        cmd.arg(to_arg(value));
+    }
+    Ok((name, cmd))
+}
+
+struct RedisSource {
+    rx: Receiver<Incoming>,
+    connection: Arc<RwLock<Option<MultiplexedConnection>>>,
+    group: String,
+    /// stream entries waiting for their event to be acknowledged, by pull id
+    unacked: HashMap<u64, (String, StreamId)>,
+    /// stream entries of failed events, emitted again
+    failed: VecDeque<(String, StreamId)>,
+    origin_uri: EventOriginUri,
+    transactional: bool,
+}
+
+impl RedisSource {
+    fn entry(
+        &mut self,
+        pull_id: u64,
+        stream: String,
+        entry: StreamId,
+        ctx: &SourceContext,
+    ) -> SourceReply {
+        let mut data = Value::object_with_capacity(entry.map.len());
+        for (field, value) in &entry.map {
+            data.try_insert(field.clone(), from_redis(value.clone()));
+        }
+        let meta = ctx.meta(literal!({
+            "stream": stream.clone(),
+            "id": entry.id.clone(),
+        }));
+        self.unacked.insert(pull_id, (stream, entry));
+        SourceReply::Structured {
+            origin_uri: self.origin_uri.clone(),
+            payload: (data, meta).into(),
+            stream: DEFAULT_STREAM_ID,
+            port: None,
+        }
+    }
+}
+
+#[async_trait::async_trait()]
+impl Source for RedisSource {
+    async fn pull_data(
+        &mut self,
+        pull_id: &mut u64,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<SourceReply> {
+        if let Some((stream, entry)) = self.failed.pop_front() {
+            return Ok(self.entry(*pull_id, stream, entry, ctx));
+        }
+        let incoming = self
+            .rx
+            .recv()
+            .await
+            .ok_or(GenericImplementationError::ChannelEmpty)?;
+        Ok(match incoming {
+            Incoming::Entry(stream, entry) => self.entry(*pull_id, stream, entry, ctx),
+            Incoming::Message(message) => {
+                let mut redis_meta = literal!({
+                    "channel": message.get_channel_name().to_string(),
+                });
+                if message.from_pattern() {
+                    if let Ok(pattern) = message.get_pattern::<String>() {
+                        redis_meta.try_insert("pattern", pattern);
+                    }
+                }
+                let data = from_bytes(message.get_payload_bytes().to_vec());
+                SourceReply::Structured {
+                    origin_uri: self.origin_uri.clone(),
+                    payload: (data, ctx.meta(redis_meta)).into(),
+                    stream: DEFAULT_STREAM_ID,
+                    port: None,
+                }
+            }
+            Incoming::Reply(payload, port) => SourceReply::Structured {
+                origin_uri: self.origin_uri.clone(),
+                payload,
+                stream: DEFAULT_STREAM_ID,
+                port: Some(port),
+            },
+        })
+    }
+
+    async fn ack(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        if let Some((stream, entry)) = self.unacked.remove(&pull_id) {
+            let mut connection = self
+                .connection
+                .read()
+                .await
+                .clone()
+                .ok_or(GenericImplementationError::ClientNotAvailable("Redis"))?;
+            redis::cmd("XACK")
+                .arg(stream)
+                .arg(&self.group)
+                .arg(entry.id)
+                .exec_async(&mut connection)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn fail(
+        &mut self,
+        _stream_id: u64,
+        pull_id: u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        // the entry stays pending for this consumer, so we can just emit it again
+        if let Some(entry) = self.unacked.remove(&pull_id) {
+            self.failed.push_back(entry);
+        }
+        Ok(())
+    }
+
+    async fn on_connection_lost(&mut self, _ctx: &SourceContext) -> anyhow::Result<()> {
+        // pending entries are read again after reconnecting
+        self.unacked.clear();
+        self.failed.clear();
+        Ok(())
+    }
+
+    fn is_transactional(&self) -> bool {
+        self.transactional
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+}
+
+struct RedisSink {
+    connection: Arc<RwLock<Option<MultiplexedConnection>>>,
+    source_tx: Sender<Incoming>,
+}
+
+impl RedisSink {
+    async fn reply(&self, payload: EventPayload, port: Port<'static>, ctx: &SinkContext) {
+        ctx.swallow_err(
+            self.source_tx.send(Incoming::Reply(payload, port)).await,
+            "Error sending reply to the source",
+        );
+    }
+}
+
+#[async_trait::async_trait()]
+impl StructuredSink for RedisSink {
+    async fn on_event(
+        &mut self,
+        _input: &str,
+        event: Event,
+        ctx: &SinkContext,
+        _start: u64,
+    ) -> anyhow::Result<SinkReply> {
+        let mut connection = self
+            .connection
+            .read()
+            .await
+            .clone()
+            .ok_or(GenericImplementationError::ClientNotAvailable("Redis"))?;
+        let mut failed = false;
+        for (value, meta) in event.value_meta_iter() {
+            let redis_meta = ctx.extract_meta(meta);
+            let correlation = meta.get("correlation");
+            let result = match to_cmd(redis_meta, value) {
+                Ok((name, cmd)) => cmd
+                    .query_async::<redis::Value>(&mut connection)
+                    .await
+                    .map(|reply| (name.clone(), reply))
+                    .map_err(|e| (Some(name), anyhow::Error::from(e))),
+                Err(e) => Err((None, e.into())),
+            };
+            match result {
+                Ok((name, reply)) => {
+                    let mut meta = ctx.meta(literal!({ "command": name }));
+                    if let Some(correlation) = correlation {
+                        meta.try_insert("correlation", correlation.clone_static());
+                    }
+                    self.reply((from_redis(reply), meta).into(), OUT, ctx).await;
+                }
+                Err((name, e)) => {
+                    error!("{ctx} Command failed: {e}");
+                    let lost = e
+                        .downcast_ref::<RedisError>()
+                        .is_some_and(|e| e.is_connection_dropped() || e.is_io_error());
+                    if lost {
+                        ctx.notifier().connection_lost().await?;
+                        return Err(e);
+                    }
+                    let mut meta = ctx.meta(literal!({ "command": name }));
+                    meta.try_insert("error", e.to_string());
+                    if let Some(correlation) = correlation {
+                        meta.try_insert("correlation", correlation.clone_static());
+                    }
+                    self.reply((Value::const_null(), meta).into(), ERR, ctx)
+                        .await;
+                    failed = true;
+                }
+            }
+        }
+        Ok(if failed {
+            SinkReply::fail_or_none(event.transactional)
+        } else {
+            SinkReply::ack_or_none(event.transactional)
+        })
+    }
+
+    fn auto_ack(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Connector as ConnectorConfig;
+
+    #[test]
+    fn replies() {
+        let reply = redis::Value::Array(vec![
+            redis::Value::Okay,
+            redis::Value::Nil,
+            redis::Value::Int(42),
+            redis::Value::BulkString(b"badger".to_vec()),
+            redis::Value::BulkString(vec![0xff, 0xfe]),
+            redis::Value::Map(vec![(
+                redis::Value::SimpleString("snot".to_string()),
+                redis::Value::Boolean(true),
+            )]),
+        ]);
+        assert_eq!(
+            literal!([
+                "OK",
+                null,
+                42,
+                "badger",
+                Value::Bytes(vec![0xff, 0xfe].into()),
+                {"snot": true}
+            ]),
+            from_redis(reply)
+        );
+    }
+
+    #[test]
+    fn commands() -> anyhow::Result<()> {
+        let meta = literal!({"command": "set", "args": ["snot", 42]});
+        let (name, cmd) = to_cmd(Some(&meta), &literal!({"badger": true}))?;
+        assert_eq!("SET", name);
+        let args: Vec<Vec<u8>> = cmd
+            .args_iter()
+            .map(|arg| match arg {
+                redis::Arg::Simple(arg) => arg.to_vec(),
+                redis::Arg::Cursor => Vec::new(),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                b"SET".to_vec(),
+                b"snot".to_vec(),
+                b"42".to_vec(),
+                br#"{"badger":true}"#.to_vec()
+            ],
+            args
+        );
+
+        let meta = literal!({"args": ["snot"]});
+        assert!(matches!(
+            to_cmd(Some(&meta), &Value::const_null()),
+            Err(Error::MissingCommand)
+        ));
+        let meta = literal!({"command": "GET", "args": "snot"});
+        assert!(matches!(
+            to_cmd(Some(&meta), &Value::const_null()),
+            Err(Error::InvalidArgs)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connector_builder_invalid_url() -> anyhow::Result<()> {
+        let config = literal!({
+            "config": {
+                "url": "snot://badger"
+            }
+        });
+        let alias = alias::Connector::new("flow", "my_redis");
+        let builder = super::Builder::default();
+        let connector_config =
+            ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+        let kill_switch = KillSwitch::dummy();
+        assert!(builder
+            .build(&alias, &connector_config, &kill_switch)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,722 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `sqlite` connector runs SQL against a local [SQLite](https://www.sqlite.org/) database file.
+//! It is useful for reference data too large for constants in scripts, that needs indexed lookups
+//! or aggregations, and for local state shared across pipelines.
+//!
+//! ## Configuration
+//!
+//! | Option  | Description                                                                          | Type   | Required | Default Value |
+//! |---------|--------------------------------------------------------------------------------------|--------|----------|---------------|
+//! | `path`  | The database file, created if missing.                                               | string | yes      |               |
+//! | `table` | The table to insert records into, optionally qualified with its schema.              | string | no       |               |
+//! | `poll`  | A query to poll for new rows, see below.                                             | record | no       |               |
+//!
+//! ```tremor title="config.troy"
+//! define connector reference from sqlite
+//! with
+//!   config = {
+//!     "path": "/var/lib/tremor/reference.db",
+//!     "table": "customers"
+//!   }
+//! end;
+//! ```
+//!
+//! ## Inserts
+//!
+//! Every record sent to the sink is inserted as a row of `table`, with a column for each field.
+//!
+//! ## Queries
+//!
+//! Events with a `$sqlite.query` run that statement instead. `$sqlite.params` are its parameters,
+//! either a list for positional parameters `?1`, `?2`, ... or a record for named parameters like
+//! `:customer`. Every returned row is emitted as a record from the `out` port of the source, together
+//! with the `$correlation` metadata of the event.
+//!
+//! ```tremor
+//! let $sqlite = {
+//!   "query": "SELECT region, count(*) AS customers FROM customers WHERE tier = :tier GROUP BY region",
+//!   "params": {"tier": "gold"}
+//! };
+//! let $correlation = event.request_id;
+//! let event = null;
+//! ```
+//!
+//! All inserts and statements of an event, including all events of a batch, run in one transaction.
+//!
+//! ## Polling
+//!
+//! With `poll`, the source runs a query periodically and emits its rows. The query selects the rows
+//! after a high-water mark, bound to `:hwm`, ordered by the high-water mark `column`. The value of
+//! `column` of the last row is the high-water mark of the next poll.
+//!
+//! | Option        | Description                                                          | Type              | Required | Default Value |
+//! |---------------|----------------------------------------------------------------------|-------------------|----------|---------------|
+//! | `query`       | The query to poll, using `:hwm`.                                     | string            | yes      |               |
+//! | `column`      | The column holding the high-water mark.                              | string            | yes      |               |
+//! | `start`       | The high-water mark of the first poll.                               | integer or string | no       | `0`           |
+//! | `interval_ms` | How long to wait before polling again, once no rows are returned.    | integer           | no       | `1000`        |
+//!
+//! ```tremor title="config.troy"
+//! define connector new_orders from sqlite
+//! with
+//!   config = {
+//!     "path": "/var/lib/tremor/shop.db",
+//!     "poll": {
+//!       "query": "SELECT * FROM orders WHERE id > :hwm ORDER BY id LIMIT 1000",
+//!       "column": "id"
+//!     }
+//!   }
+//! end;
+//! ```
+//!
+//! The high-water mark is only kept in memory, polling starts from `start` again after a restart.
+//!
+//! ## Value conversion
+//!
+//! | Tremor value      | SQLite value                  |
+//! |-------------------|-------------------------------|
+//! | `null`            | `NULL`                        |
+//! | boolean           | `0` or `1`                    |
+//! | integer           | `INTEGER`                     |
+//! | float             | `REAL`                        |
+//! | string            | `TEXT`                        |
+//! | bytes             | `BLOB`                        |
+//! | array or record   | `TEXT` holding its JSON       |
+//!
+//! Returned values are converted the other way around, booleans and JSON stay integers and text.
+//!
+//! ## Guaranteed delivery
+//!
+//! Events are acknowledged once their transaction is committed. Events whose transaction is rolled
+//! back are failed and emit an event on the `err` port of the source, carrying the error in `$error`.
+
+use crate::{errors::error_connector_def, sink::prelude::*, source::prelude::*};
+use rusqlite::{
+    params_from_iter,
+    types::{ToSqlOutput, Value as SqlValue, ValueRef},
+    Connection, ToSql, Transaction,
+};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Mutex,
+    },
+    task,
+    time::Instant,
+};
+use tremor_common::ports::{Port, ERR, OUT};
+use tremor_system::event::DEFAULT_STREAM_ID;
+use tremor_value::prelude::*;
+
+const URL_SCHEME: &str = "tremor-sqlite";
+
+/// How long to wait for a lock on the database, held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    path: String,
+    table: Option<String>,
+    poll: Option<Poll>,
+}
+
+impl tremor_config::Impl for Config {}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Poll {
+    query: String,
+    column: String,
+    #[serde(default = "default_start")]
+    start: simd_json::OwnedValue,
+    #[serde(default = "default_interval_ms")]
+    interval_ms: u64,
+}
+
+fn default_start() -> simd_json::OwnedValue {
+    simd_json::OwnedValue::from(0)
+}
+
+fn default_interval_ms() -> u64 {
+    1000
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("No `table` configured to insert into")]
+    NoTable,
+    #[error("Expected a record to insert, got a value of type `{0}`")]
+    NotARecord(ValueType),
+    #[error("Invalid `$sqlite.params`, expected a list or a record")]
+    InvalidParams,
+    #[error("Expected {0} params for the query, got {1}")]
+    ParamCount(usize, usize),
+    #[error("Unknown parameter `:{0}`")]
+    UnknownParam(String),
+    #[error("Integer {0} is too large for SQLite")]
+    IntegerOverflow(u64),
+    #[error("The polled rows have no column `{0}`")]
+    NoHighWaterMark(String),
+}
+
+/// A tremor value as the parameter of a statement
+struct Param<'v>(&'v Value<'v>);
+
+impl ToSql for Param<'_> {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let value = self.0;
+        let sql_value = if value.is_null() {
+            SqlValue::Null
+        } else if let Some(b) = value.as_bool() {
+            SqlValue::Integer(i64::from(b))
+        } else if let Some(i) = value.as_i64() {
+            SqlValue::Integer(i)
+        } else if let Some(u) = value.as_u64() {
+            return Err(rusqlite::Error::ToSqlConversionFailure(Box::new(
+                Error::IntegerOverflow(u),
+            )));
+        } else if let Some(f) = value.as_f64() {
+            SqlValue::Real(f)
+        } else if let Some(s) = value.as_str() {
+            return Ok(ToSqlOutput::Borrowed(ValueRef::Text(s.as_bytes())));
+        } else if let Some(b) = value.as_bytes() {
+            return Ok(ToSqlOutput::Borrowed(ValueRef::Blob(b)));
+        } else {
+            SqlValue::Text(value.encode())
+        };
+        Ok(ToSqlOutput::Owned(sql_value))
+    }
+}
+
+fn from_sql(value: ValueRef) -> Value<'static> {
+    match value {
+        ValueRef::Null => Value::const_null(),
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(text) => Value::from(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(blob) => Value::Bytes(blob.to_vec().into()),
+    }
+}
+
+/// Quotes an identifier, keeping the schema of qualified table names apart
+fn quote(identifier: &str) -> String {
+    identifier
+        .split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// A connection to the database. `rusqlite` blocks, up to `BUSY_TIMEOUT` while the database is
+/// locked, so the connection is only used from blocking tasks
+type SharedConnection = Arc<Mutex<Connection>>;
+
+/// Opens the database
+async fn open(path: &str) -> anyhow::Result<SharedConnection> {
+    let path = path.to_string();
+    task::spawn_blocking(move || {
+        let connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Arc::new(Mutex::new(connection)))
+    })
+    .await?
+}
+
+/// Runs the query of a poll, returning its rows
+async fn poll(
+    connection: SharedConnection,
+    sql: String,
+    params: Value<'static>,
+) -> anyhow::Result<Vec<Value<'static>>> {
+    task::spawn_blocking(move || {
+        let mut connection = connection.blocking_lock();
+        let transaction = connection.transaction()?;
+        let rows = query(&transaction, &sql, Some(&params))?;
+        transaction.commit()?;
+        Ok(rows)
+    })
+    .await?
+}
+
+/// Runs all inserts and statements of an event in one transaction, returning the rows of the
+/// statements
+fn write(
+    connection: &mut Connection,
+    table: Option<&str>,
+    event: &Event,
+    ctx: &SinkContext,
+) -> anyhow::Result<Vec<QueryResult>> {
+    let transaction = connection.transaction()?;
+    let mut results = Vec::new();
+    for (value, meta) in event.value_meta_iter() {
+        let sqlite_meta = ctx.extract_meta(meta);
+        if let Some(statement) = sqlite_meta.and_then(|m| m.get_str("query")) {
+            let rows = query(
+                &transaction,
+                statement,
+                sqlite_meta.and_then(|m| m.get("params")),
+            )?;
+            results.push((rows, meta.get("correlation").map(Value::clone_static)));
+        } else {
+            insert(&transaction, table.ok_or(Error::NoTable)?, value)?;
+        }
+    }
+    transaction.commit()?;
+    Ok(results)
+}
+
+/// Runs a statement, returning its rows
+fn query(
+    transaction: &Transaction,
+    query: &str,
+    params: Option<&Value>,
+) -> anyhow::Result<Vec<Value<'static>>> {
+    let mut statement = transaction.prepare_cached(query)?;
+    match params {
+        None => (),
+        Some(Value::Array(params)) => {
+            if params.len() != statement.parameter_count() {
+                return Err(Error::ParamCount(statement.parameter_count(), params.len()).into());
+            }
+            for (idx, param) in params.iter().enumerate() {
+                statement.raw_bind_parameter(idx + 1, Param(param))?;
+            }
+        }
+        Some(Value::Object(params)) => {
+            for (name, param) in params.iter() {
+                let idx = statement
+                    .parameter_index(&format!(":{name}"))?
+                    .ok_or_else(|| Error::UnknownParam(name.to_string()))?;
+                statement.raw_bind_parameter(idx, Param(param))?;
+            }
+        }
+        Some(_) => return Err(Error::InvalidParams.into()),
+    }
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let mut rows = statement.raw_query();
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut record = Value::object_with_capacity(columns.len());
+        for (idx, column) in columns.iter().enumerate() {
+            record.try_insert(column.clone(), from_sql(row.get_ref(idx)?));
+        }
+        result.push(record);
+    }
+    Ok(result)
+}
+
+fn insert(transaction: &Transaction, table: &str, value: &Value) -> anyhow::Result<()> {
+    let record = value
+        .as_object()
+        .ok_or(Error::NotARecord(value.value_type()))?;
+    let columns = record
+        .keys()
+        .map(|column| quote(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let params = (1..=record.len())
+        .map(|idx| format!("?{idx}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!("INSERT INTO {} ({columns}) VALUES ({params})", quote(table));
+    transaction
+        .prepare_cached(&sql)?
+        .execute(params_from_iter(record.values().map(Param)))?;
+    Ok(())
+}
+
+/// `SQLite` connector
+#[derive(Debug, Default)]
+pub struct Builder {}
+
+impl Builder {
+    const EMPTY_PATH: &'static str = "`path` can't be empty";
+    const NO_HWM: &'static str = "`poll.query` needs to use the `:hwm` parameter";
+}
+
+#[async_trait::async_trait]
+impl ConnectorBuilder for Builder {
+    fn connector_type(&self) -> ConnectorType {
+        "sqlite".into()
+    }
+
+    async fn build_cfg(
+        &self,
+        id: &alias::Connector,
+        _: &ConnectorConfig,
+        config: &Value,
+        _kill_switch: &KillSwitch,
+    ) -> anyhow::Result<Box<dyn Connector>> {
+        let config = Config::new(config)?;
+        if config.path.is_empty() {
+            return Err(error_connector_def(id, Self::EMPTY_PATH).into());
+        }
+        if config
+            .poll
+            .as_ref()
+            .is_some_and(|poll| !poll.query.contains(":hwm"))
+        {
+            return Err(error_connector_def(id, Self::NO_HWM).into());
+        }
+        let origin_uri = EventOriginUri {
+            scheme: URL_SCHEME.to_string(),
+            host: crate::utils::hostname(),
+            port: None,
+            path: config.path.split('/').map(ToString::to_string).collect(),
+        };
+        let (tx, rx) = channel(qsize());
+        Ok(Box::new(Sqlite {
+            config,
+            origin_uri,
+            tx,
+            rx: Some(rx),
+            source_is_connected: Arc::default(),
+        }))
+    }
+}
+
+pub(crate) struct Sqlite {
+    config: Config,
+    origin_uri: EventOriginUri,
+    tx: Sender<SourceReply>,
+    rx: Option<Receiver<SourceReply>>,
+    source_is_connected: Arc<AtomicBool>,
+}
+
+#[async_trait::async_trait]
+impl Connector for Sqlite {
+    async fn create_source(
+        &mut self,
+        ctx: SourceContext,
+        builder: SourceManagerBuilder,
+    ) -> anyhow::Result<Option<SourceAddr>> {
+        let hwm = match &self.config.poll {
+            Some(poll) => tremor_value::to_value(&poll.start)?,
+            None => Value::const_null(),
+        };
+        let source = SqliteSource {
+            path: self.config.path.clone(),
+            poll: self.config.poll.clone(),
+            origin_uri: self.origin_uri.clone(),
+            rx: self
+                .rx
+                .take()
+                .ok_or(GenericImplementationError::AlreadyConnected)?,
+            connection: None,
+            hwm,
+            next_poll: None,
+            polled: VecDeque::new(),
+            is_connected: self.source_is_connected.clone(),
+        };
+        Ok(Some(builder.spawn(source, ctx)))
+    }
+
+    async fn create_sink(
+        &mut self,
+        ctx: SinkContext,
+        builder: SinkManagerBuilder,
+    ) -> anyhow::Result<Option<SinkAddr>> {
+        let sink = SqliteSink {
+            path: self.config.path.clone(),
+            table: self.config.table.clone(),
+            origin_uri: self.origin_uri.clone(),
+            connection: None,
+            tx: self.tx.clone(),
+            source_is_connected: self.source_is_connected.clone(),
+        };
+        Ok(Some(builder.spawn(sink, ctx)))
+    }
+
+    fn codec_requirements(&self) -> CodecReq {
+        CodecReq::Structured
+    }
+}
+
+/// Emits the replies of the sink and the polled rows
+struct SqliteSource {
+    path: String,
+    poll: Option<Poll>,
+    origin_uri: EventOriginUri,
+    rx: Receiver<SourceReply>,
+    /// the connection to poll with
+    connection: Option<SharedConnection>,
+    hwm: Value<'static>,
+    next_poll: Option<Instant>,
+    polled: VecDeque<Value<'static>>,
+    is_connected: Arc<AtomicBool>,
+}
+
+impl SqliteSource {
+    async fn poll(&mut self) -> anyhow::Result<()> {
+        let Some(poll) = &self.poll else {
+            return Ok(());
+        };
+        self.next_poll = Some(Instant::now() + Duration::from_millis(poll.interval_ms));
+        let connection = self
+            .connection
+            .clone()
+            .ok_or(GenericImplementationError::ClientNotAvailable("SQLite"))?;
+        let params = literal!({ "hwm": self.hwm.clone() });
+        let rows = self::poll(connection, poll.query.clone(), params).await?;
+        if let Some(last) = rows.last() {
+            self.hwm = last
+                .get(poll.column.as_str())
+                .ok_or_else(|| Error::NoHighWaterMark(poll.column.clone()))?
+                .clone_static();
+            // there might be more rows right away
+            self.next_poll = Some(Instant::now());
+        }
+        self.polled.extend(rows);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait()]
+impl Source for SqliteSource {
+    async fn connect(&mut self, _ctx: &SourceContext, _attempt: &Attempt) -> anyhow::Result<bool> {
+        if self.poll.is_some() {
+            self.connection = Some(open(&self.path).await?);
+            self.next_poll = Some(Instant::now());
+        }
+        Ok(true)
+    }
+
+    async fn pull_data(
+        &mut self,
+        _pull_id: &mut u64,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<SourceReply> {
+        loop {
+            if let Some(row) = self.polled.pop_front() {
+                return Ok(SourceReply::Structured {
+                    origin_uri: self.origin_uri.clone(),
+                    payload: row.into(),
+                    stream: DEFAULT_STREAM_ID,
+                    port: None,
+                });
+            }
+            let reply = match self.next_poll {
+                Some(next_poll) => tokio::time::timeout_at(next_poll, self.rx.recv()).await,
+                None => Ok(self.rx.recv().await),
+            };
+            match reply {
+                Ok(reply) => return Ok(reply.ok_or(GenericImplementationError::ChannelEmpty)?),
+                Err(_elapsed) => self.poll().await?,
+            }
+        }
+    }
+
+    fn is_transactional(&self) -> bool {
+        false
+    }
+
+    fn asynchronous(&self) -> bool {
+        true
+    }
+
+    async fn on_cb_restore(&mut self, _ctx: &SourceContext) -> anyhow::Result<()> {
+        // replies are only sent once pipelines are connected to the source
+        self.is_connected.store(true, Ordering::Release);
+        Ok(())
+    }
+}
+
+struct SqliteSink {
+    path: String,
+    table: Option<String>,
+    origin_uri: EventOriginUri,
+    connection: Option<SharedConnection>,
+    tx: Sender<SourceReply>,
+    source_is_connected: Arc<AtomicBool>,
+}
+
+/// The rows returned by a query, with the correlation of its event
+type QueryResult = (Vec<Value<'static>>, Option<Value<'static>>);
+
+impl SqliteSink {
+    /// Writes the event on a blocking task, handing the event back with the result
+    async fn write(
+        &self,
+        event: Event,
+        ctx: &SinkContext,
+    ) -> anyhow::Result<(Event, anyhow::Result<Vec<QueryResult>>)> {
+        let connection = self
+            .connection
+            .clone()
+            .ok_or(GenericImplementationError::ClientNotAvailable("SQLite"))?;
+        let table = self.table.clone();
+        let ctx = ctx.clone();
+        Ok(task::spawn_blocking(move || {
+            let result = write(
+                &mut connection.blocking_lock(),
+                table.as_deref(),
+                &event,
+                &ctx,
+            );
+            (event, result)
+        })
+        .await?)
+    }
+
+    async fn reply(&self, payload: EventPayload, port: Port<'static>, ctx: &SinkContext) {
+        let reply = SourceReply::Structured {
+            origin_uri: self.origin_uri.clone(),
+            payload,
+            stream: DEFAULT_STREAM_ID,
+            port: Some(port),
+        };
+        ctx.swallow_err(self.tx.send(reply).await, "Failed to send to source");
+    }
+}
+
+#[async_trait::async_trait]
+impl StructuredSink for SqliteSink {
+    async fn connect(&mut self, _ctx: &SinkContext, _attempt: &Attempt) -> anyhow::Result<bool> {
+        self.connection = Some(open(&self.path).await?);
+        Ok(true)
+    }
+
+    async fn on_event(
+        &mut self,
+        _input: &str,
+        event: Event,
+        ctx: &SinkContext,
+        _start: u64,
+    ) -> anyhow::Result<SinkReply> {
+        let send_replies = self.source_is_connected.load(Ordering::Acquire);
+        let (event, result) = self.write(event, ctx).await?;
+        match result {
+            Ok(results) => {
+                if send_replies {
+                    for (rows, correlation) in results {
+                        for row in rows {
+                            let mut meta = Value::object();
+                            if let Some(correlation) = &correlation {
+                                meta.try_insert("correlation", correlation.clone());
+                            }
+                            self.reply((row, meta).into(), OUT, ctx).await;
+                        }
+                    }
+                }
+                Ok(SinkReply::ack_or_none(event.transactional))
+            }
+            Err(e) => {
+                error!("{ctx} Error writing event: {e}");
+                if send_replies {
+                    let mut meta = literal!({ "error": e.to_string() });
+                    if let Some(correlation) = event.data.suffix().meta().get("correlation") {
+                        meta.try_insert("correlation", correlation.clone_static());
+                    }
+                    self.reply((Value::const_null(), meta).into(), ERR, ctx)
+                        .await;
+                }
+                Ok(SinkReply::fail_or_none(event.transactional))
+            }
+        }
+    }
+
+    fn auto_ack(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Connector as ConnectorConfig;
+
+    #[test]
+    fn statements() -> anyhow::Result<()> {
+        let mut connection = Connection::open_in_memory()?;
+        let transaction = connection.transaction()?;
+        query(
+            &transaction,
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT, tags TEXT, photo BLOB)",
+            None,
+        )?;
+        insert(
+            &transaction,
+            "main.people",
+            &literal!({"id": 1, "name": "snot", "tags": ["a"], "photo": Value::Bytes(b"badger".to_vec().into())}),
+        )?;
+        insert(&transaction, "people", &literal!({"id": 2, "name": null}))?;
+        assert!(insert(&transaction, "people", &literal!([1])).is_err());
+
+        let rows = query(
+            &transaction,
+            "SELECT * FROM people WHERE id >= ?1 ORDER BY id",
+            Some(&literal!([1])),
+        )?;
+        assert_eq!(
+            vec![
+                literal!({
+                    "id": 1,
+                    "name": "snot",
+                    "tags": "[\"a\"]",
+                    "photo": Value::Bytes(b"badger".to_vec().into())
+                }),
+                literal!({"id": 2, "name": null, "tags": null, "photo": null}),
+            ],
+            rows
+        );
+        let rows = query(
+            &transaction,
+            "SELECT count(*) AS people, sum(id) > 2 AS big FROM people WHERE name IS :name",
+            Some(&literal!({"name": null})),
+        )?;
+        assert_eq!(vec![literal!({"people": 1, "big": 0})], rows);
+
+        assert!(query(&transaction, "SELECT ?1", Some(&literal!([]))).is_err());
+        assert!(query(&transaction, "SELECT :a", Some(&literal!({"b": 1}))).is_err());
+        assert!(query(&transaction, "SELECT ?1", Some(&literal!([u64::MAX]))).is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn connector_builder_invalid_config() -> anyhow::Result<()> {
+        let alias = alias::Connector::new("flow", "my_sqlite");
+        let builder = super::Builder::default();
+        let kill_switch = KillSwitch::dummy();
+        for config in [
+            literal!({"config": {"path": ""}}),
+            literal!({"config": {
+                "path": "snot.db",
+                "poll": {"query": "SELECT * FROM orders", "column": "id"}
+            }}),
+        ] {
+            let connector_config =
+                ConnectorConfig::from_config(&alias, builder.connector_type(), &config)?;
+            assert!(builder
+                .build(&alias, &connector_config, &kill_switch)
+                .await
+                .is_err());
+        }
+        Ok(())
+    }
+}
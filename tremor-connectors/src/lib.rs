@@ -1065,6 +1065,28 @@ pub fn builtin_connector_types() -> Vec<Box<dyn ConnectorBuilder + 'static>> {
         Box::<impls::kafka::consumer::Builder>::default(),
         #[cfg(feature = "kafka")]
         Box::<impls::kafka::producer::Builder>::default(),
+        #[cfg(feature = "mqtt")]
+        Box::<impls::mqtt::Builder>::default(),
+        #[cfg(feature = "nats")]
+        Box::<impls::nats::Builder>::default(),
+        #[cfg(feature = "amqp")]
+        Box::<impls::amqp::Builder>::default(),
+        #[cfg(feature = "redis")]
+        Box::<impls::redis::Builder>::default(),
+        #[cfg(feature = "postgres")]
+        Box::<impls::postgres::Builder>::default(),
+        #[cfg(feature = "postgres")]
+        Box::<impls::postgres::cdc::Builder>::default(),
+        #[cfg(feature = "prometheus")]
+        Box::<impls::prometheus::receiver::Builder>::default(),
+        #[cfg(feature = "prometheus")]
+        Box::<impls::prometheus::sender::Builder>::default(),
+        #[cfg(feature = "sqlite")]
+        Box::<impls::sqlite::Builder>::default(),
+        #[cfg(feature = "exec")]
+        Box::<impls::exec::Builder>::default(),
+        #[cfg(all(target_os = "linux", feature = "journald"))]
+        Box::<impls::journald::Builder>::default(),
         #[cfg(all(unix, feature = "unix-socket"))]
         Box::<impls::unix_socket::server::Builder>::default(),
         #[cfg(all(unix, feature = "unix-socket"))]
@@ -22,13 +22,17 @@ pub mod concurrency_cap;
 /// Imports that are required by all sinks
 pub mod prelude;
 
+/// Disk-backed spill buffer for sinks that are disconnected
+pub(crate) mod spill;
+
 use super::{metrics::SinkReporter, ConnectionLostNotifier, Msg, QuiescenceBeacon};
 use crate::{
     channel::{unbounded, UnboundedReceiver, UnboundedSender},
-    config::Connector as ConnectorConfig,
+    config::{self, Connector as ConnectorConfig},
     pipeline, CodecReq, ConnectorType, Context, Error, StreamDone,
 };
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
+use spill::{Pushed, Spill};
 use std::{
     borrow::Borrow,
     collections::{
@@ -39,14 +43,16 @@ use std::{
     sync::Arc,
 };
 use tokio::{
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::mpsc::{channel, Sender},
     task,
+    time::{timeout, Duration, Instant},
 };
 use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
 use tremor_codec::{self as codec, Codec};
 use tremor_common::{
     alias,
     ids::{SinkId, SourceId},
+    ports::Port,
     primerge::PriorityMerge,
     time::nanotime,
 };
@@ -543,6 +549,7 @@ pub struct SinkManagerBuilder {
     reply_tx: ReplySender,
     reply_rx: UnboundedReceiver<AsyncSinkReply>,
     metrics_reporter: SinkReporter,
+    spill: Option<config::Spill>,
 }
 
 impl SinkManagerBuilder {
@@ -561,8 +568,18 @@ impl SinkManagerBuilder {
         S: Sink + Send + 'static,
     {
         let (sink_tx, sink_rx) = channel(qsize());
-        let manager = SinkManager::new(sink, ctx, self, sink_rx);
-        task::spawn(manager.run());
+        let SinkManagerBuilder {
+            serializer,
+            reply_rx,
+            metrics_reporter,
+            spill,
+            ..
+        } = self;
+        let manager = SinkManager::new(sink, ctx, serializer, metrics_reporter, spill);
+        task::spawn(manager.run(
+            ReceiverStream::new(sink_rx),
+            UnboundedReceiverStream::new(reply_rx),
+        ));
 
         sink::Addr::new(sink_tx)
     }
@@ -598,6 +615,7 @@ pub fn builder(
         reply_tx,
         reply_rx,
         metrics_reporter,
+        spill: config.spill.clone(),
     })
 }
 
@@ -797,14 +815,27 @@ impl std::fmt::Display for SinkState {
     }
 }
 
+/// number of spilled events replayed before we check for new messages
+const SPILL_REPLAY_BATCH: usize = 64;
+/// how long to wait before replaying a spilled event the sink failed again
+const SPILL_REPLAY_RETRY: Duration = Duration::from_secs(1);
+
+/// outcome of replaying a spilled event
+enum Replay {
+    /// the sink took the event
+    Done,
+    /// the sink failed the event
+    Failed,
+    /// the sink replies asynchronously for the event with this id
+    Pending(EventId),
+}
+
 pub(crate) struct SinkManager<S>
 where
     S: Sink,
 {
     sink: S,
     ctx: SinkContext,
-    rx: ReceiverStream<sink::Msg>,
-    reply_rx: UnboundedReceiverStream<AsyncSinkReply>,
     serializer: EventSerializer,
     metrics_reporter: SinkReporter,
     /// tracking which operators incoming events visited
@@ -817,6 +848,16 @@ where
     drains_received: HashSet<SourceId>, // TODO: use a bitset for both?
     drain_channel: Option<Sender<Msg>>,
     state: SinkState,
+    // spill buffer config, the buffer is opened once the sink task runs
+    spill_config: Option<config::Spill>,
+    spill: Option<Spill>,
+    // true if the circuit breaker got triggered because the spill buffer is full
+    spill_blocked: bool,
+    // don't replay spilled events before this time, set if the sink failed a replayed event
+    replay_retry: Option<Instant>,
+    // replayed event and its spill buffer id we expect an asynchronous reply for
+    replay_in_flight: Option<(EventId, u64)>,
+    connected: bool,
 }
 
 impl<S> SinkManager<S>
@@ -826,20 +867,13 @@ where
     fn new(
         sink: S,
         ctx: SinkContext,
-        builder: SinkManagerBuilder,
-        rx: Receiver<sink::Msg>,
+        serializer: EventSerializer,
+        metrics_reporter: SinkReporter,
+        spill_config: Option<config::Spill>,
     ) -> Self {
-        let SinkManagerBuilder {
-            serializer,
-            reply_rx,
-            metrics_reporter,
-            ..
-        } = builder;
         Self {
             sink,
             ctx,
-            rx: ReceiverStream::new(rx),
-            reply_rx: UnboundedReceiverStream::new(reply_rx),
             serializer,
             metrics_reporter,
             merged_operator_meta: OpMeta::default(),
@@ -848,15 +882,268 @@ where
             drains_received: HashSet::new(),
             drain_channel: None,
             state: SinkState::Initialized,
+            spill_config,
+            spill: None,
+            spill_blocked: false,
+            replay_retry: None,
+            replay_in_flight: None,
+            connected: false,
         }
     }
+
+    /// true if there are spilled events we can replay to the connected sink
+    fn replay_pending(&self) -> bool {
+        self.connected
+            && self.replay_in_flight.is_none()
+            && self.spill.as_ref().is_some_and(|spill| !spill.is_empty())
+            && self
+                .replay_retry
+                .map_or(true, |retry| retry <= Instant::now())
+    }
+
+    /// time left until we retry replaying spilled events the sink failed
+    fn replay_delay(&self) -> Option<Duration> {
+        self.replay_retry
+            .filter(|_| self.connected)
+            .map(|retry| retry.saturating_duration_since(Instant::now()))
+    }
+
+    /// Hand an event to the sink and send the resulting contraflow back to the pipelines
+    async fn deliver(&mut self, port: &str, event: Event) {
+        let cf_builder = ContraflowData::from(&event);
+        let transactional = event.transactional;
+        let start = nanotime();
+
+        let res = self
+            .sink
+            .on_event(port, event, &self.ctx, &mut self.serializer, start)
+            .await;
+
+        let duration = nanotime() - start;
+        match res {
+            Ok(replies) => {
+                // TODO: send metric for duration
+                handle_replies(
+                    replies,
+                    duration,
+                    cf_builder,
+                    &self.pipelines,
+                    &self.ctx,
+                    transactional && self.sink.auto_ack(),
+                );
+            }
+            Err(e) => {
+                // sink error that is not signalled via SinkReply::Fail (not handled)
+                // This could fill the logs quickly.
+                // TODO: Rather emit a metrics event with the logging info?
+                error!("{} Error: {e}", &self.ctx);
+                if transactional {
+                    let cf = cf_builder.into_fail();
+                    send_contraflow(&self.pipelines, &self.ctx, cf);
+                }
+            }
+        };
+    }
+
+    /// Hand a spilled event to the sink
+    ///
+    /// Replayed events have already been acknowledged when they were spilled,
+    /// so only circuit breaker replies are sent for them.
+    async fn replay(&mut self, port: &str, event: Event) -> Replay {
+        let cf_builder = ContraflowData::from(&event);
+        let transactional = event.transactional;
+        let start = nanotime();
+        let res = self
+            .sink
+            .on_event(port, event, &self.ctx, &mut self.serializer, start)
+            .await;
+        match res {
+            Ok(replies) => {
+                if replies.cb != CbAction::None {
+                    send_contraflow(&self.pipelines, &self.ctx, cf_builder.cb(replies.cb));
+                }
+                match replies.ack {
+                    SinkAck::Fail => Replay::Failed,
+                    SinkAck::None if transactional && !self.sink.auto_ack() => {
+                        Replay::Pending(cf_builder.event_id)
+                    }
+                    SinkAck::Ack | SinkAck::None => Replay::Done,
+                }
+            }
+            Err(e) => {
+                error!("{} Error replaying spilled event: {e}", &self.ctx);
+                Replay::Failed
+            }
+        }
+    }
+
+    /// Settle a replayed event in the spill buffer, failed events are replayed again later
+    async fn replayed(&mut self, id: u64, done: bool) {
+        let Some(spill) = self.spill.as_mut() else {
+            return;
+        };
+        let res = if done {
+            spill.ack(id).await
+        } else {
+            debug!(
+                "{} Sink failed a spilled event, replaying it again in {SPILL_REPLAY_RETRY:?}.",
+                &self.ctx
+            );
+            self.replay_retry = Some(Instant::now() + SPILL_REPLAY_RETRY);
+            spill.revert().await
+        };
+        if let Err(e) = res {
+            self.disable_spill(&e);
+        }
+    }
+
+    /// Persist an event and its port in the spill buffer, it is acknowledged once it is stored
+    async fn spill_event(&mut self, port: &Port<'static>, event: Event) {
+        let Some(spill) = self.spill.as_mut() else {
+            return;
+        };
+        let cf_builder = ContraflowData::from(&event);
+        let transactional = event.transactional;
+        let start = nanotime();
+        match spill.push(port, event).await {
+            Ok(Pushed::Stored | Pushed::DroppedOldest) => {
+                if transactional {
+                    let cf = cf_builder.into_ack(nanotime() - start);
+                    send_contraflow(&self.pipelines, &self.ctx, cf);
+                }
+            }
+            Ok(Pushed::DroppedNewest) => {
+                if transactional {
+                    send_contraflow(&self.pipelines, &self.ctx, cf_builder.into_fail());
+                }
+            }
+            Err(e) => {
+                error!("{} Error spilling event: {e}", &self.ctx);
+                if transactional {
+                    send_contraflow(&self.pipelines, &self.ctx, cf_builder.into_fail());
+                }
+            }
+        }
+        if !self.spill_blocked && spill.overflow() == config::Overflow::Block && spill.is_full() {
+            debug!(
+                "{} Spill buffer full, triggering circuit breaker.",
+                &self.ctx
+            );
+            self.spill_blocked = true;
+            let cf = Event::cb_close(nanotime(), self.merged_operator_meta.clone());
+            send_contraflow(&self.pipelines, &self.ctx, cf);
+        }
+    }
+
+    /// Replay a batch of spilled events to the sink
+    ///
+    /// Events are removed from the spill buffer once the sink took them, an event the sink
+    /// failed is replayed again after `SPILL_REPLAY_RETRY`.
+    async fn replay_spilled(&mut self) {
+        self.replay_retry = None;
+        for _ in 0..SPILL_REPLAY_BATCH {
+            let Some(spill) = self.spill.as_mut() else {
+                return;
+            };
+            let (id, (port, event)) = match spill.read().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => {
+                    self.disable_spill(&e);
+                    return;
+                }
+            };
+            match self.replay(port.borrow(), event).await {
+                Replay::Done => self.replayed(id, true).await,
+                Replay::Failed => {
+                    self.replayed(id, false).await;
+                    break;
+                }
+                Replay::Pending(event_id) => {
+                    // wait for the reply before replaying the next event, to keep the order
+                    self.replay_in_flight = Some((event_id, id));
+                    break;
+                }
+            }
+        }
+        if let Some(spill) = self.spill.as_ref() {
+            if self.spill_blocked && !spill.is_full() {
+                debug!(
+                    "{} Spill buffer has room again, restoring circuit breaker.",
+                    &self.ctx
+                );
+                self.spill_blocked = false;
+                let cf = Event::cb_open(nanotime(), self.merged_operator_meta.clone());
+                send_contraflow(&self.pipelines, &self.ctx, cf);
+            }
+        }
+    }
+
+    /// Stop spilling events once the spill buffer can't make progress anymore
+    fn disable_spill(&mut self, e: &anyhow::Error) {
+        error!(
+            "{} Error replaying spilled events, disabling spill buffer: {e}",
+            &self.ctx
+        );
+        self.spill = None;
+        self.replay_retry = None;
+        self.replay_in_flight = None;
+        // we only replay while connected, so the circuit breaker can be restored
+        if self.spill_blocked {
+            self.spill_blocked = false;
+            let cf = Event::cb_open(nanotime(), self.merged_operator_meta.clone());
+            send_contraflow(&self.pipelines, &self.ctx, cf);
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
-    async fn run(mut self) -> Result<(), Error> {
+    async fn run(
+        mut self,
+        rx: ReceiverStream<sink::Msg>,
+        reply_rx: UnboundedReceiverStream<AsyncSinkReply>,
+    ) -> Result<(), Error> {
         use SinkState::{Drained, Draining, Initialized, Paused, Running, Stopped};
-        let from_sink = self.reply_rx.map(SinkMsgWrapper::FromSink);
-        let to_sink = self.rx.map(SinkMsgWrapper::ToSink);
+        if let Some(config) = self.spill_config.take() {
+            match Spill::open(&config).await {
+                Ok(spill) => {
+                    info!(
+                        "{} Spill buffer opened at {} with {} events.",
+                        &self.ctx,
+                        config.path,
+                        spill.len()
+                    );
+                    self.spill = Some(spill);
+                }
+                Err(e) => error!(
+                    "{} Error opening spill buffer at {}: {e}",
+                    &self.ctx, config.path
+                ),
+            }
+        }
+        let from_sink = reply_rx.map(SinkMsgWrapper::FromSink);
+        let to_sink = rx.map(SinkMsgWrapper::ToSink);
         let mut from_and_to_sink_channel = PriorityMerge::new(to_sink, from_sink);
-        while let Some(msg_wrapper) = from_and_to_sink_channel.next().await {
+        loop {
+            let next = if self.replay_pending() {
+                // replay spilled events as long as there is nothing else to do
+                if let Some(next) = from_and_to_sink_channel.next().now_or_never() {
+                    next
+                } else {
+                    self.replay_spilled().await;
+                    continue;
+                }
+            } else if let Some(delay) = self.replay_delay() {
+                // wake up to retry replaying spilled events the sink failed
+                match timeout(delay, from_and_to_sink_channel.next()).await {
+                    Ok(next) => next,
+                    Err(_) => continue,
+                }
+            } else {
+                from_and_to_sink_channel.next().await
+            };
+            let Some(msg_wrapper) = next else {
+                break;
+            };
             match msg_wrapper {
                 SinkMsgWrapper::ToSink(sink_msg) => {
                     match sink_msg {
@@ -909,6 +1196,10 @@ where
                             {
                                 error!("{} Error during finalize: {e}", &self.ctx);
                             }
+                            if let Some(spill) = self.spill.as_mut() {
+                                self.ctx
+                                    .swallow_err(spill.close().await, "Error closing spill buffer");
+                            }
                             self.state = Stopped;
                             self.ctx.swallow_err(
                                 sender.send(self.sink.on_stop(&self.ctx).await).await,
@@ -960,76 +1251,68 @@ where
                         }
                         sink::Msg::ConnectionEstablished => {
                             debug!("{} Connection established", self.ctx);
+                            self.connected = true;
+                            self.replay_retry = None;
+                            // a reply for the replayed event won't come anymore, replay it again
+                            if let Some((_, id)) = self.replay_in_flight.take() {
+                                self.replayed(id, false).await;
+                                self.replay_retry = None;
+                            }
                             self.ctx.swallow_err(
                                 self.sink.on_connection_established(&self.ctx).await,
                                 "Error during on_connection_established",
                             );
-                            let cf = Event::cb_open(nanotime(), self.merged_operator_meta.clone());
-                            // send CB restore to all pipes
-                            send_contraflow(&self.pipelines, &self.ctx, cf);
+                            // a full spill buffer keeps the circuit breaker closed until it has room again
+                            if !self.spill_blocked {
+                                let cf =
+                                    Event::cb_open(nanotime(), self.merged_operator_meta.clone());
+                                // send CB restore to all pipes
+                                send_contraflow(&self.pipelines, &self.ctx, cf);
+                            }
                         }
                         sink::Msg::ConnectionLost => {
+                            self.connected = false;
                             // clean out all pending stream data from EventSerializer - we assume all streams closed at this point
                             self.serializer.clear();
                             self.ctx.swallow_err(
                                 self.sink.on_connection_lost(&self.ctx).await,
                                 "Error during on_connection_lost",
                             );
-                            // send CB trigger to all pipes
-                            let cf = Event::cb_close(nanotime(), self.merged_operator_meta.clone());
-                            send_contraflow(&self.pipelines, &self.ctx, cf);
+                            // with a spill buffer we keep accepting events until it is full
+                            if self.spill.is_none() {
+                                // send CB trigger to all pipes
+                                let cf =
+                                    Event::cb_close(nanotime(), self.merged_operator_meta.clone());
+                                send_contraflow(&self.pipelines, &self.ctx, cf);
+                            }
                         }
                         sink::Msg::Event { event, port } => {
-                            let cf_builder = ContraflowData::from(&event);
-
                             self.metrics_reporter.increment_in();
                             if let Some(t) = self.metrics_reporter.periodic_flush(event.ingest_ns) {
                                 self.metrics_reporter
                                     .send_sink_metrics(self.sink.metrics(t, &self.ctx).await);
+                                if let Some(spill) = self.spill.as_ref() {
+                                    self.metrics_reporter.send_sink_metrics(vec![
+                                        spill.metrics(t, self.ctx.alias())
+                                    ]);
+                                }
                             }
 
                             // TODO: fix additional clones here for merge
                             //       (hg) - I don't think we can do this w/o a clone since we need
                             //              them here and in the on_event
                             self.merged_operator_meta.merge(event.op_meta.clone());
-                            let transactional = event.transactional;
-                            let start = nanotime();
 
-                            let res = self
-                                .sink
-                                .on_event(
-                                    port.borrow(),
-                                    event,
-                                    &self.ctx,
-                                    &mut self.serializer,
-                                    start,
-                                )
-                                .await;
-
-                            let duration = nanotime() - start;
-                            match res {
-                                Ok(replies) => {
-                                    // TODO: send metric for duration
-                                    handle_replies(
-                                        replies,
-                                        duration,
-                                        cf_builder,
-                                        &self.pipelines,
-                                        &self.ctx,
-                                        transactional && self.sink.auto_ack(),
-                                    );
-                                }
-                                Err(e) => {
-                                    // sink error that is not signalled via SinkReply::Fail (not handled)
-                                    // This could fill the logs quickly.
-                                    // TODO: Rather emit a metrics event with the logging info?
-                                    error!("{} Error: {e}", &self.ctx);
-                                    if transactional {
-                                        let cf = cf_builder.into_fail();
-                                        send_contraflow(&self.pipelines, &self.ctx, cf);
-                                    }
-                                }
-                            };
+                            // spill while disconnected and until all spilled events are replayed, to keep the order
+                            if self
+                                .spill
+                                .as_ref()
+                                .is_some_and(|spill| !self.connected || !spill.is_empty())
+                            {
+                                self.spill_event(&port, event).await;
+                            } else {
+                                self.deliver(port.borrow(), event).await;
+                            }
                         }
                         sink::Msg::Signal { signal } => {
                             // special treatment
@@ -1092,6 +1375,19 @@ where
                     }
                 }
                 SinkMsgWrapper::FromSink(reply) => {
+                    // replayed events were acknowledged when they got spilled
+                    if let AsyncSinkReply::Ack(data, _) | AsyncSinkReply::Fail(data) = &reply {
+                        if matches!(
+                            &self.replay_in_flight,
+                            Some((event_id, _)) if *event_id == data.event_id
+                        ) {
+                            if let Some((_, id)) = self.replay_in_flight.take() {
+                                let done = matches!(reply, AsyncSinkReply::Ack(..));
+                                self.replayed(id, done).await;
+                                continue;
+                            }
+                        }
+                    }
                     // handle asynchronous sink replies
                     let cf = match reply {
                         AsyncSinkReply::Ack(data, duration) => Event::cb_ack_with_timing(
@@ -1228,6 +1524,9 @@ mod test {
     use tremor_common::ids::Id as _;
 
     use super::*;
+    use tremor_common::ports::IN;
+    use tremor_system::dataplane::contraflow;
+    use tremor_value::prelude::*;
 
     #[test]
     fn sink_state_display() {
@@ -1409,4 +1708,268 @@ mod test {
             .is_ok());
         Ok(())
     }
+
+    /// records the events it receives and their ports, fails them while `fail` is set
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        received: Arc<std::sync::Mutex<Vec<u64>>>,
+        ports: Arc<std::sync::Mutex<Vec<String>>>,
+        fail: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl RecordingSink {
+        fn received(&self) -> Vec<u64> {
+            self.received.lock().map(|r| r.clone()).unwrap_or_default()
+        }
+
+        fn ports(&self) -> Vec<String> {
+            self.ports.lock().map(|p| p.clone()).unwrap_or_default()
+        }
+
+        async fn wait_for(&self, n: usize) -> Vec<u64> {
+            for _ in 0..200 {
+                if self.received().len() >= n {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            self.received()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Sink for RecordingSink {
+        async fn on_event(
+            &mut self,
+            input: &str,
+            event: Event,
+            _ctx: &SinkContext,
+            _serializer: &mut EventSerializer,
+            _start: u64,
+        ) -> anyhow::Result<SinkReply> {
+            if let Ok(mut ports) = self.ports.lock() {
+                ports.push(input.to_string());
+            }
+            if let Ok(mut received) = self.received.lock() {
+                received.extend(event.data.suffix().value().as_u64());
+            }
+            if self.fail.load(std::sync::atomic::Ordering::Acquire) {
+                Ok(SinkReply::fail_or_none(event.transactional))
+            } else {
+                Ok(SinkReply::NONE)
+            }
+        }
+        fn auto_ack(&self) -> bool {
+            true
+        }
+        async fn finalize(
+            &mut self,
+            _ctx: &SinkContext,
+            _serializer: &mut EventSerializer,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// a sink manager with a spill buffer, linked to a pipeline we receive the contraflow of
+    async fn spilling_sink(
+        sink: RecordingSink,
+        spill: config::Spill,
+    ) -> anyhow::Result<(Sender<sink::Msg>, UnboundedReceiver<contraflow::Msg>)> {
+        let alias = alias::Connector::new("snot", "badger");
+        let (lost_tx, _lost_rx) = tokio::sync::mpsc::channel(1);
+        let notifier = ConnectionLostNotifier::new(&alias, lost_tx);
+        let ctx = SinkContext::new(
+            SinkId::new(1),
+            alias.clone(),
+            ConnectorType::default(),
+            QuiescenceBeacon::default(),
+            notifier,
+        );
+        let serializer = EventSerializer::new(
+            Some(tremor_codec::Config::from("json")),
+            CodecReq::Required,
+            vec![],
+            &"test".into(),
+            &alias,
+        )?;
+        let reporter = SinkReporter::new(alias, tremor_pipeline::METRICS_CHANNEL.tx(), None);
+        let manager = SinkManager::new(sink, ctx, serializer, reporter, Some(spill));
+        let (tx, rx) = channel(qsize());
+        let (_reply_tx, reply_rx) = unbounded();
+        task::spawn(manager.run(
+            ReceiverStream::new(rx),
+            UnboundedReceiverStream::new(reply_rx),
+        ));
+
+        let (pipeline_tx, _pipeline_rx) = channel(qsize());
+        let (cf_tx, cf_rx) = unbounded();
+        let (mgmt_tx, _mgmt_rx) = channel(qsize());
+        let pipeline_alias = alias::Pipeline::new(alias::Flow::new("snot"), "pipe");
+        let pipeline = pipeline::Addr::new(pipeline_tx, cf_tx, mgmt_tx, pipeline_alias);
+        let endpoint = DeployEndpoint::new(
+            &"pipe",
+            IN,
+            &tremor_script::NodeMeta::new(
+                tremor_script::lexer::Location::yolo(),
+                tremor_script::lexer::Location::yolo(),
+            ),
+        );
+        tx.send(sink::Msg::Link {
+            pipelines: vec![(endpoint, pipeline)],
+        })
+        .await?;
+        tx.send(sink::Msg::Start).await?;
+        Ok((tx, cf_rx))
+    }
+
+    fn spill_config(
+        path: &std::path::Path,
+        max_events: u64,
+        overflow: config::Overflow,
+    ) -> config::Spill {
+        config::Spill {
+            path: path.to_string_lossy().to_string(),
+            max_events,
+            chunk_size: 1024,
+            max_chunks: usize::MAX,
+            overflow,
+        }
+    }
+
+    fn event(id: u64) -> sink::Msg {
+        event_on(IN, id)
+    }
+
+    fn event_on(port: Port<'static>, id: u64) -> sink::Msg {
+        sink::Msg::Event {
+            event: Event {
+                id: EventId::from_id(1, 1, id),
+                data: (Value::from(id), Value::object()).into(),
+                transactional: true,
+                ..Event::default()
+            },
+            port,
+        }
+    }
+
+    async fn contraflow(rx: &mut UnboundedReceiver<contraflow::Msg>) -> anyhow::Result<Event> {
+        let msg = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await?
+            .ok_or(crate::errors::GenericImplementationError::ChannelEmpty)?;
+        let contraflow::Msg::Insight(event) = msg;
+        Ok(event)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn spill_while_disconnected_and_replay_in_order() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sink = RecordingSink::default();
+        let (tx, mut cf) = spilling_sink(
+            sink.clone(),
+            spill_config(dir.path(), 10, config::Overflow::DropNewest),
+        )
+        .await?;
+        assert!(matches!(
+            contraflow(&mut cf).await?.cb,
+            CbAction::SinkStart(_)
+        ));
+
+        // disconnected, events are acked once they are spilled
+        for id in 1..=3 {
+            tx.send(event(id)).await?;
+            let ack = contraflow(&mut cf).await?;
+            assert_eq!(CbAction::Ack, ack.cb);
+            assert_eq!(EventId::from_id(1, 1, id), ack.id);
+        }
+        assert!(sink.received().is_empty());
+
+        // once connected spilled events are replayed before new ones
+        tx.send(sink::Msg::ConnectionEstablished).await?;
+        assert_eq!(CbAction::Restore, contraflow(&mut cf).await?.cb);
+        tx.send(event(4)).await?;
+        assert_eq!(vec![1, 2, 3, 4], sink.wait_for(4).await);
+        let ack = contraflow(&mut cf).await?;
+        assert_eq!(CbAction::Ack, ack.cb);
+        assert_eq!(EventId::from_id(1, 1, 4), ack.id);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn spill_replays_to_original_port() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sink = RecordingSink::default();
+        let (tx, mut cf) = spilling_sink(
+            sink.clone(),
+            spill_config(dir.path(), 10, config::Overflow::DropNewest),
+        )
+        .await?;
+        contraflow(&mut cf).await?;
+        tx.send(event_on(Port::from("stderr"), 1)).await?;
+        contraflow(&mut cf).await?;
+        tx.send(event(2)).await?;
+        contraflow(&mut cf).await?;
+
+        tx.send(sink::Msg::ConnectionEstablished).await?;
+        assert_eq!(CbAction::Restore, contraflow(&mut cf).await?.cb);
+        assert_eq!(vec![1, 2], sink.wait_for(2).await);
+        assert_eq!(vec!["stderr".to_string(), "in".to_string()], sink.ports());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn spill_keeps_failed_replays() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sink = RecordingSink::default();
+        let (tx, mut cf) = spilling_sink(
+            sink.clone(),
+            spill_config(dir.path(), 10, config::Overflow::DropNewest),
+        )
+        .await?;
+        contraflow(&mut cf).await?;
+        for id in 1..=2 {
+            tx.send(event(id)).await?;
+            contraflow(&mut cf).await?;
+        }
+
+        // the sink fails the first replayed event, it stays in the spill buffer
+        sink.fail.store(true, std::sync::atomic::Ordering::Release);
+        tx.send(sink::Msg::ConnectionEstablished).await?;
+        assert_eq!(CbAction::Restore, contraflow(&mut cf).await?.cb);
+        assert_eq!(vec![1], sink.wait_for(1).await);
+
+        // and is replayed again, followed by the rest
+        sink.fail.store(false, std::sync::atomic::Ordering::Release);
+        assert_eq!(vec![1, 1, 2], sink.wait_for(3).await);
+        // replayed events were already acked when they got spilled
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cf.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn spill_block_overflow_triggers_circuit_breaker() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sink = RecordingSink::default();
+        let (tx, mut cf) = spilling_sink(
+            sink.clone(),
+            spill_config(dir.path(), 2, config::Overflow::Block),
+        )
+        .await?;
+        contraflow(&mut cf).await?;
+        tx.send(event(1)).await?;
+        assert_eq!(CbAction::Ack, contraflow(&mut cf).await?.cb);
+        tx.send(event(2)).await?;
+        assert_eq!(CbAction::Ack, contraflow(&mut cf).await?.cb);
+        // the spill buffer is full
+        assert_eq!(CbAction::Trigger, contraflow(&mut cf).await?.cb);
+
+        // the circuit breaker is only restored once the spill buffer has room again
+        tx.send(sink::Msg::ConnectionEstablished).await?;
+        assert_eq!(CbAction::Restore, contraflow(&mut cf).await?.cb);
+        assert_eq!(vec![1, 2], sink.wait_for(2).await);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cf.try_recv().is_err());
+        Ok(())
+    }
 }
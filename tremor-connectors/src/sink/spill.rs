@@ -0,0 +1,378 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Disk-backed spill buffer for sinks.
+//!
+//! Without a spill buffer a sink that lost its connection triggers the circuit breaker and
+//! upstream sources get paused. Sources like `udp` or `http_server` can't be paused, so their
+//! events are lost. With a spill buffer configured, the sink persists incoming events in a
+//! write-ahead log while it is disconnected and replays them in order once it is connected again.
+//! Spilled events stay in the write-ahead log until the sink took them, an event the sink fails is
+//! replayed again.
+//!
+//! ## Configuration
+//!
+//! ```tremor
+//! define connector my_sink from http_client
+//! with
+//!   "spill": {
+//!     # required - directory where the spill buffer is persisted
+//!     "path": "state/my_sink",
+//!     # maximum number of events held in the spill buffer
+//!     "max_events": 100000,
+//!     # one of `drop_oldest`, `drop_newest` or `block`
+//!     "overflow": "drop_oldest"
+//!   },
+//!   ...
+//! end;
+//! ```
+
+use crate::{config, metrics::make_metrics_payload};
+use simd_json::ObjectHasher;
+use simd_json_derive::{Deserialize, Serialize};
+use std::{borrow::Borrow, collections::VecDeque};
+use tremor_common::{alias, ports::Port};
+use tremor_script::EventPayload;
+use tremor_system::event::Event;
+use tremor_value::prelude::*;
+
+/// An event and the port it was received on, persisted in a `qwal::Wal`
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    port: String,
+    event: Event,
+}
+
+impl qwal::Entry for Payload {
+    type Output = (Port<'static>, Event);
+    type Error = simd_json::Error;
+
+    fn serialize(self) -> std::result::Result<Vec<u8>, Self::Error> {
+        Ok(self.json_vec()?)
+    }
+
+    fn deserialize(mut data: Vec<u8>) -> std::result::Result<Self::Output, Self::Error> {
+        let Payload { port, event } = Payload::from_slice(&mut data)?;
+        Ok((Port::from(port), event))
+    }
+}
+
+/// Outcome of pushing an event into the spill buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Pushed {
+    /// the event was persisted
+    Stored,
+    /// the event was persisted, the oldest event was dropped to make room for it
+    DroppedOldest,
+    /// the buffer is full, the event was dropped
+    DroppedNewest,
+}
+
+/// Disk-backed spill buffer
+pub(crate) struct Spill {
+    wal: qwal::Wal,
+    max_events: u64,
+    overflow: config::Overflow,
+    /// number of events currently held in the buffer
+    len: u64,
+    /// number of events dropped due to overflow
+    dropped: u64,
+    /// ids of the events that were read but not yet acked or reverted
+    read: VecDeque<u64>,
+}
+
+impl Spill {
+    /// Open the spill buffer at the configured path, creating it if necessary.
+    ///
+    /// Events left over from a previous run are kept and will be replayed.
+    ///
+    /// # Errors
+    ///   * if the directory can't be created or contains an invalid write-ahead log
+    pub(crate) async fn open(config: &config::Spill) -> anyhow::Result<Self> {
+        tokio::fs::create_dir_all(&config.path).await?;
+        let mut wal = qwal::Wal::open(&config.path, config.chunk_size, config.max_chunks).await?;
+        // count the events we haven't replayed in a previous run
+        let mut len = 0;
+        while wal.pop::<Vec<u8>>().await?.is_some() {
+            len += 1;
+        }
+        wal.revert().await?;
+        Ok(Self {
+            wal,
+            max_events: config.max_events,
+            overflow: config.overflow,
+            len,
+            dropped: 0,
+            read: VecDeque::new(),
+        })
+    }
+
+    /// number of events in the spill buffer
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// true if there are no events in the spill buffer
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// true if the spill buffer reached `max_events`
+    pub(crate) fn is_full(&self) -> bool {
+        self.len >= self.max_events
+    }
+
+    /// the configured overflow policy
+    pub(crate) fn overflow(&self) -> config::Overflow {
+        self.overflow
+    }
+
+    /// Persist an event received on `port`, applying the overflow policy if the buffer is full
+    ///
+    /// # Errors
+    ///   * if the event can't be written to or the oldest event can't be removed from disk
+    pub(crate) async fn push(&mut self, port: &Port<'_>, event: Event) -> anyhow::Result<Pushed> {
+        let mut res = Pushed::Stored;
+        if self.is_full() {
+            match self.overflow {
+                config::Overflow::DropNewest => {
+                    self.dropped += 1;
+                    return Ok(Pushed::DroppedNewest);
+                }
+                // the oldest event is being replayed, it can't be dropped anymore
+                config::Overflow::DropOldest if !self.read.is_empty() => {
+                    self.dropped += 1;
+                    return Ok(Pushed::DroppedNewest);
+                }
+                config::Overflow::DropOldest => {
+                    if let Some((id, _)) = self.wal.pop::<Vec<u8>>().await? {
+                        self.wal.ack(id).await?;
+                        self.len -= 1;
+                        self.dropped += 1;
+                    }
+                    res = Pushed::DroppedOldest;
+                }
+                // events that were in flight when the circuit breaker got triggered are still stored
+                config::Overflow::Block => (),
+            }
+        }
+        let port: &str = port.borrow();
+        let port = port.to_string();
+        self.wal.push(Payload { port, event }).await?;
+        self.len += 1;
+        Ok(res)
+    }
+
+    /// Read the oldest event that wasn't read yet and the port it was received on,
+    /// it stays in the spill buffer until it is acked
+    ///
+    /// # Errors
+    ///   * if the event can't be read from disk
+    pub(crate) async fn read(&mut self) -> anyhow::Result<Option<(u64, (Port<'static>, Event))>> {
+        let entry = self.wal.pop::<Payload>().await?;
+        if let Some((id, _)) = &entry {
+            self.read.push_back(*id);
+        }
+        Ok(entry)
+    }
+
+    /// Remove all read events up to and including `id` from the spill buffer
+    ///
+    /// # Errors
+    ///   * if `id` wasn't read yet or is already acked
+    pub(crate) async fn ack(&mut self, id: u64) -> anyhow::Result<()> {
+        self.wal.ack(id).await?;
+        while self.read.front().is_some_and(|read| *read <= id) {
+            self.read.pop_front();
+            self.len = self.len.saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    /// Read all events that weren't acked again, starting with the oldest
+    ///
+    /// # Errors
+    ///   * if the write-ahead log can't be read from disk
+    pub(crate) async fn revert(&mut self) -> anyhow::Result<()> {
+        self.wal.revert().await?;
+        self.read.clear();
+        Ok(())
+    }
+
+    /// Persist the progress of the spill buffer
+    ///
+    /// # Errors
+    ///   * if the acknowledgement can't be written to disk
+    pub(crate) async fn close(&mut self) -> anyhow::Result<()> {
+        self.wal.preserve_ack().await?;
+        Ok(())
+    }
+
+    /// Spill buffer metrics
+    pub(crate) fn metrics(&self, timestamp: u64, alias: &alias::Connector) -> EventPayload {
+        let mut tags = Object::with_capacity_and_hasher(2, ObjectHasher::default());
+        tags.insert("flow".into(), Value::from(alias.flow_alias().to_string()));
+        tags.insert("connector".into(), Value::from(alias.to_string()));
+        let mut fields = Object::with_capacity_and_hasher(2, ObjectHasher::default());
+        fields.insert("size".into(), Value::from(self.len));
+        fields.insert("dropped".into(), Value::from(self.dropped));
+        make_metrics_payload("connector_spill", fields, tags, timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tremor_common::ports::IN;
+    use tremor_system::event::EventId;
+
+    fn event(id: u64) -> Event {
+        Event {
+            id: EventId::from_id(1, 1, id),
+            data: (Value::from(id), Value::object()).into(),
+            ..Event::default()
+        }
+    }
+
+    fn config(
+        path: &std::path::Path,
+        max_events: u64,
+        overflow: config::Overflow,
+    ) -> config::Spill {
+        config::Spill {
+            path: path.to_string_lossy().to_string(),
+            max_events,
+            chunk_size: 1024,
+            max_chunks: usize::MAX,
+            overflow,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_in_order() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut spill = Spill::open(&config(dir.path(), 10, config::Overflow::Block)).await?;
+        assert!(spill.is_empty());
+        for i in 1..=3 {
+            assert_eq!(Pushed::Stored, spill.push(&IN, event(i)).await?);
+        }
+        assert_eq!(3, spill.len());
+        // events that weren't acked are read again
+        let (_, (_, e)) = spill.read().await?.expect("spilled event");
+        assert_eq!(Some(1), e.data.suffix().value().as_u64());
+        spill.revert().await?;
+        assert_eq!(3, spill.len());
+        for i in 1..=3 {
+            let (id, (_, e)) = spill.read().await?.expect("spilled event");
+            assert_eq!(Some(i), e.data.suffix().value().as_u64());
+            assert_eq!(EventId::from_id(1, 1, i), e.id);
+            spill.ack(id).await?;
+        }
+        assert!(spill.read().await?.is_none());
+        assert!(spill.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn overflow_policies() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut spill = Spill::open(&config(dir.path(), 2, config::Overflow::DropNewest)).await?;
+        spill.push(&IN, event(1)).await?;
+        spill.push(&IN, event(2)).await?;
+        assert_eq!(Pushed::DroppedNewest, spill.push(&IN, event(3)).await?);
+        assert_eq!(2, spill.len());
+        assert_eq!(1, spill.dropped);
+
+        let dir = tempfile::tempdir()?;
+        let mut spill = Spill::open(&config(dir.path(), 2, config::Overflow::DropOldest)).await?;
+        spill.push(&IN, event(1)).await?;
+        spill.push(&IN, event(2)).await?;
+        assert_eq!(Pushed::DroppedOldest, spill.push(&IN, event(3)).await?);
+        assert_eq!(2, spill.len());
+        let (id, (_, e)) = spill.read().await?.expect("spilled event");
+        assert_eq!(Some(2), e.data.suffix().value().as_u64());
+        // the event being replayed is kept, the new one is dropped instead
+        assert_eq!(Pushed::DroppedNewest, spill.push(&IN, event(4)).await?);
+        spill.ack(id).await?;
+        let (_, (_, e)) = spill.read().await?.expect("spilled event");
+        assert_eq!(Some(3), e.data.suffix().value().as_u64());
+
+        let dir = tempfile::tempdir()?;
+        let mut spill = Spill::open(&config(dir.path(), 1, config::Overflow::Block)).await?;
+        spill.push(&IN, event(1)).await?;
+        assert!(spill.is_full());
+        assert_eq!(Pushed::Stored, spill.push(&IN, event(2)).await?);
+        assert_eq!(2, spill.len());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn batched_acks() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut spill = Spill::open(&config(dir.path(), 3, config::Overflow::DropNewest)).await?;
+        for i in 1..=3 {
+            spill.push(&IN, event(i)).await?;
+        }
+        spill.read().await?;
+        let (id, _) = spill.read().await?.expect("spilled event");
+        spill.read().await?;
+        // acks the first two events, the third one is still in flight
+        spill.ack(id).await?;
+        assert_eq!(1, spill.len());
+        assert_eq!(Pushed::Stored, spill.push(&IN, event(4)).await?);
+        assert_eq!(Pushed::Stored, spill.push(&IN, event(5)).await?);
+        assert_eq!(Pushed::DroppedNewest, spill.push(&IN, event(6)).await?);
+        spill.revert().await?;
+        let (_, (_, e)) = spill.read().await?.expect("spilled event");
+        assert_eq!(Some(3), e.data.suffix().value().as_u64());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn survives_restart() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cfg = config(dir.path(), 10, config::Overflow::DropNewest);
+        {
+            let mut spill = Spill::open(&cfg).await?;
+            for i in 1..=3 {
+                spill.push(&IN, event(i)).await?;
+            }
+            let (id, _) = spill.read().await?.expect("spilled event");
+            spill.ack(id).await?;
+            // read but not acked, this one is replayed after a restart
+            spill.read().await?;
+            spill.close().await?;
+        }
+        let mut spill = Spill::open(&cfg).await?;
+        assert_eq!(2, spill.len());
+        let (_, (port, e)) = spill.read().await?.expect("spilled event");
+        assert_eq!(IN, port);
+        assert_eq!(Some(2), e.data.suffix().value().as_u64());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn keeps_ports() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut spill = Spill::open(&config(dir.path(), 10, config::Overflow::Block)).await?;
+        spill.push(&Port::from("stderr"), event(1)).await?;
+        spill.push(&IN, event(2)).await?;
+        let (id, (port, _)) = spill.read().await?.expect("spilled event");
+        assert_eq!(Port::from("stderr"), port);
+        spill.ack(id).await?;
+        let (_, (port, _)) = spill.read().await?.expect("spilled event");
+        assert_eq!(IN, port);
+        Ok(())
+    }
+}
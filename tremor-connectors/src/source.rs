@@ -26,7 +26,7 @@ use crate::{
     utils::reconnect::ConnectionLostNotifier,
     CodecReq, ConnectorType, Context, Error, QuiescenceBeacon, StreamDone,
 };
-use std::collections::{btree_map::Entry, BTreeMap, HashSet};
+use std::collections::{btree_map::Entry, BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
 use tokio::{sync::mpsc::Sender, task};
 use tremor_codec::{self as codec, Codec};
@@ -508,6 +508,8 @@ where
     addr: source::Addr,
     pipelines_out: Vec<(DeployEndpoint, pipeline::Addr)>,
     pipelines_err: Vec<(DeployEndpoint, pipeline::Addr)>,
+    /// pipelines connected to additional output ports of the connector
+    pipelines_other: HashMap<Port<'static>, Vec<(DeployEndpoint, pipeline::Addr)>>,
     streams: Streams,
     metrics_reporter: SourceReporter,
     // `Paused` is used for both explicitly pausing and CB close/open
@@ -563,6 +565,7 @@ where
             metrics_reporter: source_metrics_reporter,
             pipelines_out: Vec::with_capacity(1),
             pipelines_err: Vec::with_capacity(1),
+            pipelines_other: HashMap::new(),
             state: SourceState::Initialized,
             connectivity: Connectivity::Disconnected, // we always start as disconnected until `.connect()` connects us
             is_transactional,
@@ -796,11 +799,8 @@ where
         } else if port == ERR {
             &mut self.pipelines_err
         } else {
-            error!("{} Tried to connect to invalid port: {}", &self.ctx, &port);
-            tx.send(Err(anyhow::format_err!("Connecting to invalid port")))
-                .await
-                .map_err(|_| Error::ControlplaneReply(self.ctx.alias().clone()))?;
-            return Ok(Control::Continue);
+            // the connector only links ports it declared in `Connector::output_ports`
+            self.pipelines_other.entry(port.clone()).or_default()
         };
         // We can not move this to the system flow since we need to know about transactionality
         let (pipeline_url, p) = &pipeline;
@@ -828,6 +828,7 @@ where
             .as_slice()
             .iter()
             .chain(self.pipelines_err.as_slice().iter())
+            .chain(self.pipelines_other.values().flatten())
         /* */
         {
             addr.send(Box::new(dataplane::Msg::Signal(signal.clone())))
@@ -854,6 +855,9 @@ where
             } else if port == ERR {
                 self.metrics_reporter.increment_err();
                 &mut self.pipelines_err
+            } else if let Some(pipelines) = self.pipelines_other.get_mut(&port) {
+                self.metrics_reporter.increment_out();
+                pipelines
             } else {
                 error!("{ctx} Trying to send event to invalid port: {port}");
                 continue;
@@ -0,0 +1,123 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(all(unix, feature = "integration-tests-exec"))]
+
+use tremor_common::ports::{Port, ERR, IN, OUT};
+use tremor_connectors::{harness::Harness, impls::exec};
+use tremor_system::{
+    controlplane::CbAction,
+    event::{Event, EventId},
+    killswitch::KillSwitch,
+};
+use tremor_value::{literal, prelude::*, Value};
+
+const STDERR: Port<'static> = Port::const_str("stderr");
+const EXIT: Port<'static> = Port::const_str("exit");
+
+async fn harness(defn: &Value<'static>) -> anyhow::Result<Harness> {
+    Harness::new_with_ports(
+        "test",
+        &exec::Builder::default(),
+        defn,
+        KillSwitch::dummy(),
+        vec![IN],
+        vec![OUT, ERR, STDERR, EXIT],
+    )
+    .await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exec_stdin_to_stdout() -> anyhow::Result<()> {
+    let defn = literal!({
+        "codec": "json",
+        "preprocessors": ["separate"],
+        "postprocessors": ["separate"],
+        "config": {
+            "command": "cat"
+        }
+    });
+    let mut harness = harness(&defn).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let event = Event {
+        id: EventId::from_id(1, 1, 1),
+        data: (literal!({"snot": "badger"}), Value::object()).into(),
+        transactional: true,
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+
+    let event = harness.out()?.get_event().await?;
+    assert_eq!(&literal!({"snot": "badger"}), event.data.suffix().value());
+    assert!(event
+        .data
+        .suffix()
+        .meta()
+        .get("exec")
+        .get_u64("pid")
+        .is_some());
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exec_stderr_and_restarts() -> anyhow::Result<()> {
+    let temp_dir = tempfile::Builder::new().tempdir()?;
+    let defn = literal!({
+        "codec": "string",
+        "preprocessors": ["separate"],
+        "reconnect": {
+            "retry": {
+                "interval_ms": 100,
+                "max_retries": 1
+            }
+        },
+        "config": {
+            "command": "sh",
+            "args": ["-c", "echo \"$GREETING from $(pwd)\" >&2; exit 3"],
+            "env": {"GREETING": "snot"},
+            "cwd": temp_dir.path().display().to_string()
+        }
+    });
+    let mut harness = harness(&defn).await?;
+    harness.start().await?;
+
+    let cwd = temp_dir.path().canonicalize()?;
+    // the process is started once more after it exited
+    for _ in 0..2 {
+        let stderr = harness.get_pipe(STDERR)?.get_event().await?;
+        assert_eq!(
+            &Value::from(format!("snot from {}", cwd.display())),
+            stderr.data.suffix().value()
+        );
+        let exit = harness.get_pipe(EXIT)?.get_event().await?;
+        assert_eq!(
+            &literal!({"code": 3, "signal": null, "success": false}),
+            exit.data.suffix().value()
+        );
+    }
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    Ok(())
+}
@@ -0,0 +1,127 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(all(target_os = "linux", feature = "integration-tests-journald"))]
+
+use std::{os::unix::fs::PermissionsExt, path::Path};
+use tremor_connectors::{harness::Harness, impls::journald};
+use tremor_system::controlplane::CbAction;
+use tremor_value::{literal, prelude::*};
+
+/// A stand-in for `journalctl` that records its arguments and follows a tiny journal
+fn fake_journalctl(dir: &Path) -> anyhow::Result<String> {
+    let path = dir.join("journalctl");
+    let script = format!(
+        r#"#!/bin/sh
+echo "$@" >> {args}
+case "$*" in
+  *--after-cursor=c2*)
+    echo '{{"__CURSOR":"c3","__REALTIME_TIMESTAMP":"1718871347000000","MESSAGE":"three","_SYSTEMD_UNIT":"sshd.service"}}'
+    ;;
+  *)
+    echo '{{"__CURSOR":"c1","__REALTIME_TIMESTAMP":"1718871345000000","MESSAGE":"one","_SYSTEMD_UNIT":"sshd.service"}}'
+    echo '{{"__CURSOR":"c2","__REALTIME_TIMESTAMP":"1718871346000000","MESSAGE":"two","_SYSTEMD_UNIT":"sshd.service"}}'
+    ;;
+esac
+exec sleep 60
+"#,
+        args = dir.join("args").display()
+    );
+    std::fs::write(&path, script)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(path.display().to_string())
+}
+
+fn message(event: &tremor_system::event::Event) -> Option<String> {
+    event
+        .data
+        .suffix()
+        .value()
+        .get_str("MESSAGE")
+        .map(String::from)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn journald_resumes_after_acked_cursor() -> anyhow::Result<()> {
+    let temp_dir = tempfile::Builder::new().tempdir()?;
+    let cursor_file = temp_dir.path().join("cursor");
+    let config = literal!({
+        "config": {
+            "journalctl": fake_journalctl(temp_dir.path())?,
+            "units": ["sshd.service"],
+            "priority": "info",
+            "cursor_file": cursor_file.display().to_string()
+        }
+    });
+
+    let mut harness = Harness::new("test", &journald::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+
+    let one = harness.out()?.get_event().await?;
+    let two = harness.out()?.get_event().await?;
+    assert_eq!(Some("one".to_string()), message(&one));
+    assert_eq!(Some("two".to_string()), message(&two));
+    let meta = one.data.suffix().meta().get("journald");
+    assert_eq!(Some("c1"), meta.get_str("cursor"));
+    assert_eq!(Some(1_718_871_345_000_000_000), meta.get_u64("timestamp"));
+
+    // nothing was acked yet, the whole journal is read again
+    harness.send_contraflow(CbAction::Fail, one.id.clone())?;
+    let one = harness.out()?.get_event().await?;
+    let two = harness.out()?.get_event().await?;
+    assert_eq!(Some("one".to_string()), message(&one));
+    assert_eq!(Some("two".to_string()), message(&two));
+
+    // acks out of order only move the cursor once all entries before are acked
+    harness.send_contraflow(CbAction::Ack, two.id.clone())?;
+    harness.send_contraflow(CbAction::Ack, one.id.clone())?;
+    let mut cursor = String::new();
+    for _ in 0..50 {
+        cursor = std::fs::read_to_string(&cursor_file).unwrap_or_default();
+        if cursor == "c2" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!("c2", cursor);
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+
+    // a new connector continues after the stored cursor
+    let mut harness = Harness::new("test", &journald::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    let three = harness.out()?.get_event().await?;
+    assert_eq!(Some("three".to_string()), message(&three));
+    harness.send_contraflow(CbAction::Ack, three.id.clone())?;
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+
+    let args = std::fs::read_to_string(temp_dir.path().join("args"))?;
+    let args: Vec<_> = args.lines().collect();
+    assert_eq!(
+        vec![
+            "--output=json --follow --no-pager --unit=sshd.service --priority=info --lines=0",
+            "--output=json --follow --no-pager --unit=sshd.service --priority=info --lines=0",
+            "--output=json --follow --no-pager --unit=sshd.service --priority=info --after-cursor=c2",
+        ],
+        args
+    );
+    Ok(())
+}
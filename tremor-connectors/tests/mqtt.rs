@@ -0,0 +1,141 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "integration-tests-mqtt")]
+
+use serial_test::serial;
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt,
+};
+use tremor_common::ports::IN;
+use tremor_connectors::{harness::Harness, impls::mqtt};
+use tremor_connectors_test_helpers::free_port::find_free_tcp_port;
+use tremor_system::{
+    controlplane::CbAction,
+    event::{Event, EventId},
+};
+use tremor_value::{literal, Value};
+use value_trait::prelude::*;
+
+const IMAGE: &str = "eclipse-mosquitto";
+const VERSION: &str = "2.0.18";
+
+async fn mosquitto_container() -> anyhow::Result<ContainerAsync<GenericImage>> {
+    let port = find_free_tcp_port().await?;
+    // the image ships a config allowing anonymous access on all interfaces
+    let image = GenericImage::new(IMAGE, VERSION)
+        .with_wait_for(WaitFor::message_on_stderr("running"))
+        .with_cmd(["mosquitto", "-c", "/mosquitto-no-auth.conf"])
+        .with_mapped_port(port, 1883_u16.tcp());
+    Ok(image.start().await?)
+}
+
+async fn roundtrip(protocol: &'static str) -> anyhow::Result<()> {
+    let container = mosquitto_container().await?;
+    let port = container.get_host_port_ipv4(1883).await?;
+
+    let config = literal!({
+        "reconnect": {
+            "retry": {
+                "interval_ms": 1000,
+                "max_retries": 10
+            }
+        },
+        "codec": {"name": "json", "config": {"mode": "sorted"}},
+        "config": {
+            "url": format!("mqtt://127.0.0.1:{port}"),
+            "protocol": protocol,
+            "topics": ["tremor/test/#"],
+            "topic": "tremor/test/default",
+            "qos": 1
+        }
+    });
+    let mut harness = Harness::new("test", &mqtt::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    // published to the configured topic, received by our own subscription
+    let id = EventId::from_id(1, 1, 1);
+    let event = Event {
+        id: id.clone(),
+        data: (literal!({"snot": "badger"}), Value::object()).into(),
+        transactional: true,
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    assert_eq!(id, cf.id);
+
+    let received = harness.out()?.get_event().await?;
+    assert_eq!(
+        &literal!({"snot": "badger"}),
+        received.data.suffix().value()
+    );
+    assert_eq!(
+        Some("tremor/test/default"),
+        received.data.suffix().meta().get("mqtt").get_str("topic")
+    );
+    assert_eq!(
+        Some(1),
+        received.data.suffix().meta().get("mqtt").get_u8("qos")
+    );
+    harness.send_contraflow(CbAction::Ack, received.id.clone())?;
+
+    // the topic from the metadata takes precedence
+    let id = EventId::from_id(1, 1, 2);
+    let event = Event {
+        id: id.clone(),
+        data: (
+            Value::from(42),
+            literal!({"mqtt": {"topic": "tremor/test/meta", "qos": 2}}),
+        )
+            .into(),
+        transactional: true,
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    assert_eq!(id, cf.id);
+
+    let received = harness.out()?.get_event().await?;
+    assert_eq!(Some(42), received.data.suffix().value().as_u64());
+    assert_eq!(
+        Some("tremor/test/meta"),
+        received.data.suffix().meta().get("mqtt").get_str("topic")
+    );
+    harness.send_contraflow(CbAction::Ack, received.id.clone())?;
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    drop(container);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(mqtt)]
+async fn mqtt_v3_roundtrip() -> anyhow::Result<()> {
+    roundtrip("3.1.1").await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(mqtt)]
+async fn mqtt_v5_roundtrip() -> anyhow::Result<()> {
+    roundtrip("5").await
+}
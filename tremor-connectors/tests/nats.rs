@@ -0,0 +1,282 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "integration-tests-nats")]
+
+use async_nats::jetstream;
+use futures::StreamExt;
+use serial_test::serial;
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt,
+};
+use tremor_common::ports::IN;
+use tremor_connectors::{harness::Harness, impls::nats};
+use tremor_connectors_test_helpers::free_port::find_free_tcp_port;
+use tremor_system::{
+    controlplane::CbAction,
+    event::{Event, EventId},
+};
+use tremor_value::{literal, Value};
+use value_trait::prelude::*;
+
+const IMAGE: &str = "nats";
+const VERSION: &str = "2.10";
+
+async fn nats_container() -> anyhow::Result<(ContainerAsync<GenericImage>, String)> {
+    let port = find_free_tcp_port().await?;
+    let image = GenericImage::new(IMAGE, VERSION)
+        .with_wait_for(WaitFor::message_on_stderr("Server is ready"))
+        .with_cmd(["--jetstream"])
+        .with_mapped_port(port, 4222_u16.tcp());
+    let container = image.start().await?;
+    let port = container.get_host_port_ipv4(4222).await?;
+    Ok((container, format!("nats://127.0.0.1:{port}")))
+}
+
+fn connector_config(server: &str, config: Value<'static>) -> Value<'static> {
+    let mut connector_config = literal!({
+        "reconnect": {
+            "retry": {
+                "interval_ms": 1000,
+                "max_retries": 10
+            }
+        },
+        "codec": {"name": "json", "config": {"mode": "sorted"}},
+        "config": config
+    });
+    if let Some(config) = connector_config.get_mut("config") {
+        config.try_insert("servers", vec![server.to_string()]);
+    }
+    connector_config
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(nats)]
+async fn nats_pub_sub() -> anyhow::Result<()> {
+    let (container, server) = nats_container().await?;
+
+    let config = connector_config(
+        &server,
+        literal!({
+            "subscriptions": [{"subject": "tremor.test.>", "queue_group": "tremor"}],
+            "subject": "tremor.test.default"
+        }),
+    );
+    let mut harness = Harness::new("test", &nats::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let id = EventId::from_id(1, 1, 1);
+    let event = Event {
+        id: id.clone(),
+        data: (
+            literal!({"snot": "badger"}),
+            literal!({"nats": {"headers": {"snot": "badger"}}}),
+        )
+            .into(),
+        transactional: true,
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    assert_eq!(id, cf.id);
+
+    let received = harness.out()?.get_event().await?;
+    assert_eq!(
+        &literal!({"snot": "badger"}),
+        received.data.suffix().value()
+    );
+    assert_eq!(
+        &literal!({
+            "nats": {
+                "subject": "tremor.test.default",
+                "headers": {"snot": ["badger"]}
+            }
+        }),
+        received.data.suffix().meta()
+    );
+
+    // the subject from the metadata takes precedence
+    let event = Event {
+        id: EventId::from_id(1, 1, 2),
+        data: (
+            Value::from(42),
+            literal!({"nats": {"subject": "tremor.test.meta"}}),
+        )
+            .into(),
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let received = harness.out()?.get_event().await?;
+    assert_eq!(Some(42), received.data.suffix().value().as_u64());
+    assert_eq!(
+        Some("tremor.test.meta"),
+        received.data.suffix().meta().get("nats").get_str("subject")
+    );
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    drop(container);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(nats)]
+async fn nats_request_reply() -> anyhow::Result<()> {
+    let (container, server) = nats_container().await?;
+
+    // a service answering requests by echoing them
+    let client = async_nats::connect(&server).await?;
+    let mut requests = client.subscribe("tremor.echo").await?;
+    let responder = client.clone();
+    let service = tokio::spawn(async move {
+        while let Some(request) = requests.next().await {
+            if let Some(reply) = request.reply {
+                responder.publish(reply, request.payload).await?;
+            }
+        }
+        anyhow::Ok(())
+    });
+
+    let config = connector_config(&server, literal!({}));
+    let mut harness = Harness::new("test", &nats::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let id = EventId::from_id(1, 1, 1);
+    let event = Event {
+        id: id.clone(),
+        data: (
+            literal!({"snot": "badger"}),
+            literal!({
+                "nats": {"subject": "tremor.echo", "request": true},
+                "correlation": 42
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let response = harness.out()?.get_event().await?;
+    assert_eq!(
+        &literal!({"snot": "badger"}),
+        response.data.suffix().value()
+    );
+    assert_eq!(
+        Some(42),
+        response.data.suffix().meta().get_u64("correlation")
+    );
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    assert_eq!(id, cf.id);
+
+    // nobody answers on this subject
+    let id = EventId::from_id(1, 1, 2);
+    let event = Event {
+        id: id.clone(),
+        data: (
+            Value::from("snot"),
+            literal!({"nats": {"subject": "tremor.nobody", "request": true}}),
+        )
+            .into(),
+        transactional: true,
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Fail, cf.cb);
+    assert_eq!(id, cf.id);
+
+    harness.stop().await?;
+    service.abort();
+    drop(container);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(nats)]
+async fn nats_jetstream() -> anyhow::Result<()> {
+    let (container, server) = nats_container().await?;
+
+    let client = async_nats::connect(&server).await?;
+    let context = jetstream::new(client);
+    context
+        .create_stream(jetstream::stream::Config {
+            name: "TREMOR".to_string(),
+            subjects: vec!["tremor.js.>".to_string()],
+            ..jetstream::stream::Config::default()
+        })
+        .await?;
+
+    let config = connector_config(
+        &server,
+        literal!({
+            "consumers": [{"stream": "TREMOR", "consumer": "tremor"}]
+        }),
+    );
+    let mut harness = Harness::new("test", &nats::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    context
+        .publish("tremor.js.one", "\"snot\"".into())
+        .await?
+        .await?;
+
+    let event = harness.out()?.get_event().await?;
+    assert_eq!(Some("snot"), event.data.suffix().value().as_str());
+    let jetstream_meta = event
+        .data
+        .suffix()
+        .meta()
+        .get("nats")
+        .and_then(|nats| nats.get("jetstream"));
+    assert_eq!(Some("TREMOR"), jetstream_meta.get_str("stream"));
+    assert_eq!(Some(1), jetstream_meta.get_u64("stream_sequence"));
+    assert_eq!(Some(1), jetstream_meta.get_u64("delivered"));
+
+    // failing the event makes the server redeliver the message
+    harness.send_contraflow(CbAction::Fail, event.id.clone())?;
+    let event = harness.out()?.get_event().await?;
+    assert_eq!(Some("snot"), event.data.suffix().value().as_str());
+    let jetstream_meta = event
+        .data
+        .suffix()
+        .meta()
+        .get("nats")
+        .and_then(|nats| nats.get("jetstream"));
+    assert_eq!(Some(1), jetstream_meta.get_u64("stream_sequence"));
+    assert_eq!(Some(2), jetstream_meta.get_u64("delivered"));
+
+    // once acknowledged it is gone
+    harness.send_contraflow(CbAction::Ack, event.id.clone())?;
+    harness
+        .out()?
+        .expect_no_event_for(std::time::Duration::from_secs(1))
+        .await?;
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    drop(container);
+    Ok(())
+}
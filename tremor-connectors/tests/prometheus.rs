@@ -0,0 +1,212 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "integration-tests-prometheus")]
+
+use tremor_common::ports::IN;
+use tremor_connectors::{
+    harness::Harness,
+    impls::prometheus::{receiver, sender},
+};
+use tremor_connectors_test_helpers::free_port::find_free_tcp_port;
+use tremor_system::{
+    controlplane::CbAction,
+    event::{Event, EventId},
+};
+use tremor_value::{literal, prelude::*, Value};
+
+fn batch(id: u64, values: Value<'static>) -> Event {
+    let values = values
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .map(|value| literal!({"data": {"value": value.clone(), "meta": {}}}))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Event {
+        id: EventId::from_id(1, 1, id),
+        is_batch: true,
+        transactional: true,
+        data: (Value::from(values), Value::object()).into(),
+        ..Event::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prometheus_remote_write_roundtrip() -> anyhow::Result<()> {
+    let port = find_free_tcp_port().await?;
+    for (split, expected) in [
+        (
+            "series",
+            vec![
+                literal!({
+                    "labels": {"__name__": "up", "job": "api"},
+                    "samples": [
+                        {"value": 1.0, "timestamp": 1_718_871_345_000_i64},
+                        {"value": 0.0, "timestamp": 1_718_871_360_000_i64}
+                    ]
+                }),
+                literal!({
+                    "labels": {"__name__": "http_requests_total", "code": "200"},
+                    "samples": [{"value": 1027.0, "timestamp": 1_718_871_345_000_i64}]
+                }),
+            ],
+        ),
+        (
+            "sample",
+            vec![
+                literal!({"labels": {"__name__": "up", "job": "api"}, "value": 1.0, "timestamp": 1_718_871_345_000_i64}),
+                literal!({"labels": {"__name__": "up", "job": "api"}, "value": 0.0, "timestamp": 1_718_871_360_000_i64}),
+                literal!({"labels": {"__name__": "http_requests_total", "code": "200"}, "value": 1027.0, "timestamp": 1_718_871_345_000_i64}),
+            ],
+        ),
+    ] {
+        let config = literal!({
+            "config": {
+                "url": format!("http://127.0.0.1:{port}"),
+                "split": split
+            }
+        });
+        let mut receiver = Harness::new("receiver", &receiver::Builder::default(), &config).await?;
+        receiver.start().await?;
+        receiver.wait_for_connected().await?;
+
+        let config = literal!({
+            "config": {
+                "url": format!("http://127.0.0.1:{port}/api/v1/write"),
+                "headers": {"X-Scope-OrgID": "tremor"}
+            }
+        });
+        let mut sender = Harness::new("sender", &sender::Builder::default(), &config).await?;
+        sender.start().await?;
+        sender.wait_for_connected().await?;
+        sender.consume_initial_sink_contraflow().await?;
+
+        // both series and single samples can be sent, labels come out sorted
+        sender
+            .send_to_sink(batch(
+                1,
+                literal!([
+                    {
+                        "labels": {"job": "api", "__name__": "up"},
+                        "samples": [
+                            {"value": 1, "timestamp": 1_718_871_345_000_i64},
+                            {"value": 0, "timestamp": 1_718_871_360_000_i64}
+                        ]
+                    },
+                    {
+                        "labels": {"code": "200", "__name__": "http_requests_total"},
+                        "value": 1027.0,
+                        "timestamp": 1_718_871_345_000_i64
+                    }
+                ]),
+            ))
+            .await?;
+        let cf = sender.get_pipe(IN)?.get_contraflow().await?;
+        assert_eq!(CbAction::Ack, cf.cb);
+
+        for expected in expected {
+            let event = receiver.out()?.get_event().await?;
+            assert_eq!(&expected, event.data.suffix().value());
+        }
+
+        // events that aren't series are failed without sending anything
+        sender
+            .send_to_sink(batch(2, literal!([{"labels": {"job": "api"}}])))
+            .await?;
+        let cf = sender.get_pipe(IN)?.get_contraflow().await?;
+        assert_eq!(CbAction::Fail, cf.cb);
+
+        let (out, err) = sender.stop().await?;
+        assert!(out.is_empty(), "unexpected events: {out:?}");
+        assert!(err.is_empty(), "unexpected errors: {err:?}");
+        let (out, err) = receiver.stop().await?;
+        assert!(out.is_empty(), "unexpected events: {out:?}");
+        assert!(err.is_empty(), "unexpected errors: {err:?}");
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prometheus_sender_fails_without_backend() -> anyhow::Result<()> {
+    // nothing listens on the port, the request is failed
+    let port = find_free_tcp_port().await?;
+    let config = literal!({
+        "config": {
+            "url": format!("http://127.0.0.1:{port}/api/v1/write"),
+            "timeout_ms": 1000
+        }
+    });
+    let mut sender = Harness::new("sender", &sender::Builder::default(), &config).await?;
+    sender.start().await?;
+    sender.wait_for_connected().await?;
+    sender.consume_initial_sink_contraflow().await?;
+    sender
+        .send_to_sink(batch(
+            1,
+            literal!([{"labels": {"__name__": "up"}, "value": 1}]),
+        ))
+        .await?;
+    let cf = sender.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Fail, cf.cb);
+
+    let (out, err) = sender.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn prometheus_receiver_rejects_large_requests() -> anyhow::Result<()> {
+    let port = find_free_tcp_port().await?;
+    let url = format!("http://127.0.0.1:{port}");
+    for config in [
+        literal!({"config": {"url": url.clone(), "max_body_bytes": 16}}),
+        literal!({"config": {"url": url.clone(), "max_decompressed_bytes": 16}}),
+    ] {
+        let receiver = Harness::new("receiver", &receiver::Builder::default(), &config).await?;
+        receiver.start().await?;
+        receiver.wait_for_connected().await?;
+
+        let config = literal!({
+            "config": {
+                "url": format!("http://127.0.0.1:{port}/api/v1/write")
+            }
+        });
+        let mut sender = Harness::new("sender", &sender::Builder::default(), &config).await?;
+        sender.start().await?;
+        sender.wait_for_connected().await?;
+        sender.consume_initial_sink_contraflow().await?;
+
+        // the request is answered with 413 and failed by the sender
+        sender
+            .send_to_sink(batch(
+                1,
+                literal!([{"labels": {"__name__": "http_requests_total", "job": "api"}, "value": 1}]),
+            ))
+            .await?;
+        let cf = sender.get_pipe(IN)?.get_contraflow().await?;
+        assert_eq!(CbAction::Fail, cf.cb);
+
+        let (out, err) = sender.stop().await?;
+        assert!(out.is_empty(), "unexpected events: {out:?}");
+        assert!(err.is_empty(), "unexpected errors: {err:?}");
+        let (out, err) = receiver.stop().await?;
+        assert!(out.is_empty(), "unexpected events: {out:?}");
+        assert!(err.is_empty(), "unexpected errors: {err:?}");
+    }
+    Ok(())
+}
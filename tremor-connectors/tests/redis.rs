@@ -0,0 +1,306 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "integration-tests-redis")]
+
+use serial_test::serial;
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt,
+};
+use tremor_common::ports::IN;
+use tremor_connectors::{harness::Harness, impls::redis};
+use tremor_connectors_test_helpers::free_port::find_free_tcp_port;
+use tremor_system::{
+    controlplane::CbAction,
+    event::{Event, EventId},
+};
+use tremor_value::{literal, Value};
+use value_trait::prelude::*;
+
+const IMAGE: &str = "redis";
+const VERSION: &str = "7.2";
+
+async fn redis_container() -> anyhow::Result<(ContainerAsync<GenericImage>, String)> {
+    let port = find_free_tcp_port().await?;
+    let image = GenericImage::new(IMAGE, VERSION)
+        .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"))
+        .with_mapped_port(port, 6379_u16.tcp());
+    let container = image.start().await?;
+    let port = container.get_host_port_ipv4(6379).await?;
+    Ok((container, format!("redis://127.0.0.1:{port}")))
+}
+
+fn connector_config(url: &str, config: Value<'static>) -> Value<'static> {
+    let mut connector_config = literal!({
+        "reconnect": {
+            "retry": {
+                "interval_ms": 1000,
+                "max_retries": 10
+            }
+        },
+        "config": config
+    });
+    if let Some(config) = connector_config.get_mut("config") {
+        config.try_insert("url", url.to_string());
+    }
+    connector_config
+}
+
+fn command(id: u64, value: Value<'static>, redis_meta: Value<'static>) -> Event {
+    Event {
+        id: EventId::from_id(1, 1, id),
+        data: (value, literal!({"redis": redis_meta, "correlation": id})).into(),
+        transactional: true,
+        ..Event::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(redis)]
+async fn redis_commands() -> anyhow::Result<()> {
+    let (container, url) = redis_container().await?;
+
+    let config = connector_config(&url, literal!({}));
+    let mut harness = Harness::new("test", &redis::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    harness
+        .send_to_sink(command(
+            1,
+            literal!({"badger": true}),
+            literal!({"command": "SET", "args": ["snot"]}),
+        ))
+        .await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    let reply = harness.out()?.get_event().await?;
+    assert_eq!(Some("OK"), reply.data.suffix().value().as_str());
+    assert_eq!(
+        &literal!({"redis": {"command": "SET"}, "correlation": 1}),
+        reply.data.suffix().meta()
+    );
+
+    harness
+        .send_to_sink(command(
+            2,
+            Value::const_null(),
+            literal!({"command": "GET", "args": ["snot"]}),
+        ))
+        .await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    let reply = harness.out()?.get_event().await?;
+    assert_eq!(
+        Some(r#"{"badger":true}"#),
+        reply.data.suffix().value().as_str()
+    );
+
+    harness
+        .send_to_sink(command(
+            3,
+            Value::const_null(),
+            literal!({"command": "HGETALL", "args": ["nothing"]}),
+        ))
+        .await?;
+    harness.get_pipe(IN)?.get_contraflow().await?;
+    let reply = harness.out()?.get_event().await?;
+    assert_eq!(
+        Some(0),
+        reply.data.suffix().value().as_array().map(Vec::len)
+    );
+
+    // the server rejects this one
+    harness
+        .send_to_sink(command(
+            4,
+            Value::const_null(),
+            literal!({"command": "SNOT", "args": ["badger"]}),
+        ))
+        .await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Fail, cf.cb);
+    let error = harness.err()?.get_event().await?;
+    let meta = error.data.suffix().meta();
+    assert_eq!(Some("SNOT"), meta.get("redis").get_str("command"));
+    assert_eq!(Some(4), meta.get_u64("correlation"));
+    assert!(meta.get_str("error").is_some());
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    drop(container);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(redis)]
+async fn redis_streams() -> anyhow::Result<()> {
+    let (container, url) = redis_container().await?;
+
+    let config = connector_config(
+        &url,
+        literal!({
+            "streams": ["orders"],
+            "consumer": "tremor-test",
+            "block_ms": 100
+        }),
+    );
+    let mut harness = Harness::new("test", &redis::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    harness
+        .send_to_sink(command(
+            1,
+            Value::from("created"),
+            literal!({"command": "XADD", "args": ["orders", "*", "id", 42, "status"]}),
+        ))
+        .await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+
+    // the reply to XADD and the entry itself, in no particular order
+    let mut events = vec![
+        harness.out()?.get_event().await?,
+        harness.out()?.get_event().await?,
+    ];
+    events.sort_by_key(|event| {
+        event
+            .data
+            .suffix()
+            .meta()
+            .get("redis")
+            .contains_key("stream")
+    });
+    let entry = events.pop().expect("two events");
+    let reply = events.pop().expect("two events");
+    let id = reply
+        .data
+        .suffix()
+        .value()
+        .as_str()
+        .expect("XADD replies with the entry id")
+        .to_string();
+    assert_eq!(
+        &literal!({"id": "42", "status": "created"}),
+        entry.data.suffix().value()
+    );
+    assert_eq!(
+        &literal!({"redis": {"stream": "orders", "id": id.clone()}}),
+        entry.data.suffix().meta()
+    );
+
+    // failed entries are emitted again
+    harness.send_contraflow(CbAction::Fail, entry.id.clone())?;
+    let entry = harness.out()?.get_event().await?;
+    assert_eq!(
+        Some(id.as_str()),
+        entry.data.suffix().meta().get("redis").get_str("id")
+    );
+    harness.send_contraflow(CbAction::Ack, entry.id.clone())?;
+
+    // nothing is pending for the group anymore, once the ack went through
+    let mut pending = 1;
+    for i in 5..100 {
+        harness
+            .send_to_sink(command(
+                i,
+                Value::const_null(),
+                literal!({"command": "XPENDING", "args": ["orders", "tremor"]}),
+            ))
+            .await?;
+        harness.get_pipe(IN)?.get_contraflow().await?;
+        let reply = harness.out()?.get_event().await?;
+        pending = reply
+            .data
+            .suffix()
+            .value()
+            .get_idx(0)
+            .and_then(ValueAsScalar::as_u64)
+            .unwrap_or(1);
+        if pending == 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(0, pending);
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    drop(container);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial(redis)]
+async fn redis_pub_sub() -> anyhow::Result<()> {
+    let (container, url) = redis_container().await?;
+
+    let config = connector_config(
+        &url,
+        literal!({
+            "channels": ["notifications"],
+            "patterns": ["orders.*"]
+        }),
+    );
+    let mut harness = Harness::new("test", &redis::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    for (id, channel) in [(1, "notifications"), (2, "orders.created")] {
+        harness
+            .send_to_sink(command(
+                id,
+                Value::from("snot"),
+                literal!({"command": "PUBLISH", "args": [channel]}),
+            ))
+            .await?;
+        let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+        assert_eq!(CbAction::Ack, cf.cb);
+        let mut events = vec![
+            harness.out()?.get_event().await?,
+            harness.out()?.get_event().await?,
+        ];
+        events.sort_by_key(|event| {
+            event
+                .data
+                .suffix()
+                .meta()
+                .get("redis")
+                .contains_key("channel")
+        });
+        let message = events.pop().expect("two events");
+        let reply = events.pop().expect("two events");
+        // the number of subscribers that received the message
+        assert_eq!(Some(1), reply.data.suffix().value().as_u64());
+        assert_eq!(Some("snot"), message.data.suffix().value().as_str());
+        assert_eq!(
+            Some(channel),
+            message.data.suffix().meta().get("redis").get_str("channel")
+        );
+    }
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    drop(container);
+    Ok(())
+}
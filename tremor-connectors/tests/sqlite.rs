@@ -0,0 +1,185 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(feature = "integration-tests-sqlite")]
+
+use tremor_common::ports::IN;
+use tremor_connectors::{harness::Harness, impls::sqlite};
+use tremor_system::{
+    controlplane::CbAction,
+    event::{Event, EventId},
+};
+use tremor_value::{literal, prelude::*, Value};
+
+fn query(id: u64, query: &str, params: Value<'static>) -> Event {
+    Event {
+        id: EventId::from_id(1, 1, id),
+        data: (
+            Value::const_null(),
+            literal!({
+                "sqlite": {"query": query.to_string(), "params": params},
+                "correlation": id
+            }),
+        )
+            .into(),
+        transactional: true,
+        ..Event::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_inserts_and_queries() -> anyhow::Result<()> {
+    let temp_dir = tempfile::Builder::new().tempdir()?;
+    let path = temp_dir.path().join("test.db");
+
+    let config = literal!({
+        "config": {
+            "path": path.display().to_string(),
+            "table": "people"
+        }
+    });
+    let mut harness = Harness::new("test", &sqlite::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    harness
+        .send_to_sink(query(
+            1,
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL, team TEXT)",
+            literal!([]),
+        ))
+        .await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+
+    // a batch is inserted in one transaction
+    let event = Event {
+        id: EventId::from_id(1, 1, 2),
+        is_batch: true,
+        transactional: true,
+        data: (
+            literal!([
+                {"data": {"value": {"id": 1, "name": "snot", "team": "a"}, "meta": {}}},
+                {"data": {"value": {"id": 2, "name": "badger", "team": "a"}, "meta": {}}},
+                {"data": {"value": {"id": 3, "name": "muhaha"}, "meta": {}}}
+            ]),
+            Value::object(),
+        )
+            .into(),
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+
+    harness
+        .send_to_sink(query(
+            3,
+            "SELECT team, count(*) AS people FROM people WHERE team = :team GROUP BY team",
+            literal!({"team": "a"}),
+        ))
+        .await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Ack, cf.cb);
+    let row = harness.out()?.get_event().await?;
+    assert_eq!(
+        &literal!({"team": "a", "people": 2}),
+        row.data.suffix().value()
+    );
+    assert_eq!(Some(3), row.data.suffix().meta().get_u64("correlation"));
+
+    // `name` can't be null, the whole batch is rolled back
+    let event = Event {
+        id: EventId::from_id(1, 1, 4),
+        is_batch: true,
+        transactional: true,
+        data: (
+            literal!([
+                {"data": {"value": {"id": 4, "name": "snot"}, "meta": {}}},
+                {"data": {"value": {"id": 5}, "meta": {}}}
+            ]),
+            literal!({"correlation": 4}),
+        )
+            .into(),
+        ..Event::default()
+    };
+    harness.send_to_sink(event).await?;
+    let cf = harness.get_pipe(IN)?.get_contraflow().await?;
+    assert_eq!(CbAction::Fail, cf.cb);
+    let error = harness.err()?.get_event().await?;
+    assert!(error.data.suffix().meta().get_str("error").is_some());
+
+    harness
+        .send_to_sink(query(5, "SELECT max(id) AS id FROM people", literal!([])))
+        .await?;
+    harness.get_pipe(IN)?.get_contraflow().await?;
+    let row = harness.out()?.get_event().await?;
+    assert_eq!(&literal!({"id": 3}), row.data.suffix().value());
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_poll() -> anyhow::Result<()> {
+    let temp_dir = tempfile::Builder::new().tempdir()?;
+    let path = temp_dir.path().join("test.db");
+    let connection = rusqlite::Connection::open(&path)?;
+    connection.execute_batch(
+        "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL);
+         INSERT INTO orders VALUES (1, 10.5), (2, 42.0);",
+    )?;
+
+    let config = literal!({
+        "config": {
+            "path": path.display().to_string(),
+            "poll": {
+                "query": "SELECT * FROM orders WHERE id > :hwm ORDER BY id",
+                "column": "id",
+                "interval_ms": 100
+            }
+        }
+    });
+    let mut harness = Harness::new("test", &sqlite::Builder::default(), &config).await?;
+    harness.start().await?;
+    harness.wait_for_connected().await?;
+    harness.consume_initial_sink_contraflow().await?;
+
+    let first = harness.out()?.get_event().await?;
+    let second = harness.out()?.get_event().await?;
+    assert_eq!(
+        &literal!({"id": 1, "total": 10.5}),
+        first.data.suffix().value()
+    );
+    assert_eq!(
+        &literal!({"id": 2, "total": 42.0}),
+        second.data.suffix().value()
+    );
+
+    // only rows after the high-water mark are emitted
+    connection.execute("INSERT INTO orders VALUES (3, 23.0)", [])?;
+    let third = harness.out()?.get_event().await?;
+    assert_eq!(
+        &literal!({"id": 3, "total": 23.0}),
+        third.data.suffix().value()
+    );
+
+    let (out, err) = harness.stop().await?;
+    assert!(out.is_empty(), "unexpected events: {out:?}");
+    assert!(err.is_empty(), "unexpected errors: {err:?}");
+    Ok(())
+}
@@ -18,6 +18,7 @@ pub mod debug;
 pub mod generic;
 pub mod grouper;
 pub mod identity;
+pub(crate) mod key;
 pub mod prelude;
 pub mod qos;
 pub mod trickle;
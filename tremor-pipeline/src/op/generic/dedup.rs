@@ -0,0 +1,269 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The dedup operator drops events whose key was already seen. The key is either computed by a `key` expression or, if none is configured, is the whole event payload.
+//!
+//! Memory is bounded: only the last `capacity` keys are kept, keys that were not seen for the longest time are forgotten first. With a `window` configured, a key is only considered a duplicate if it was first seen no longer than `window` nanoseconds ago.
+//!
+//! This operator preserves event metadata.
+//!
+//! **Configuration options**:
+//!
+//! - `key` - A tremor-script expression evaluated against each event to compute its key. (default: the event payload)
+//! - `window` - Time in nanoseconds during which a key is considered a duplicate. (optional)
+//! - `capacity` - Maximum number of keys kept track of. (default: `10000`)
+//!
+//! **Outputs**:
+//!
+//! - `out`
+//! - `err` - Events for which the `key` expression could not be evaluated
+//!
+//! **Example**:
+//!
+//! ```tremor
+//! use std::time::nanos;
+//!
+//! define operator dedup from generic::dedup
+//! with
+//!   key = "$kafka_consumer.key",
+//!   window = nanos::from_minutes(5),
+//!   capacity = 100000
+//! end;
+//! ```
+//!
+//! **Metrics**:
+//!
+//! The dedup operator generates additional metrics (as an example):
+//!
+//! ```json
+//! {"measurement":"dedup",
+//!  "tags":{
+//!    "action":"duplicate",
+//!    "node":"dedup",
+//!    "pipeline":"main"
+//!  },
+//!  "fields":{"count":12},
+//!  "timestamp":1553012903452340000
+//! }
+//! ```
+//!
+//! The `action` is either `unique` for events that passed or `duplicate` for dropped events.
+
+use crate::{
+    metrics::value_count,
+    op::{
+        key::{self, Key},
+        prelude::*,
+    },
+};
+use beef::Cow;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use tremor_script::prelude::*;
+
+const DEDUP: Cow<'static, str> = Cow::const_str("dedup");
+const ACTION: Cow<'static, str> = Cow::const_str("action");
+const UNIQUE: Cow<'static, str> = Cow::const_str("unique");
+const DUPLICATE: Cow<'static, str> = Cow::const_str("duplicate");
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Expression to compute the key of an event
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Time in nanoseconds a key is considered a duplicate after it was first seen
+    #[serde(default)]
+    pub window: Option<u64>,
+    /// Maximum number of keys kept track of
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_capacity() -> usize {
+    10_000
+}
+
+struct Dedup {
+    key: Option<Key>,
+    window: Option<u64>,
+    /// the seen keys with the time they were first seen
+    seen: LruCache<String, u64>,
+    unique: u64,
+    duplicate: u64,
+}
+
+impl std::fmt::Debug for Dedup {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Dedup")
+    }
+}
+
+impl TryFrom<Config> for Dedup {
+    type Error = Error;
+    fn try_from(config: Config) -> Result<Self> {
+        let capacity = NonZeroUsize::new(config.capacity).ok_or_else(|| {
+            ErrorKind::BadOpConfig("`capacity` needs to be greater than 0".to_string())
+        })?;
+        Ok(Self {
+            key: config.key.as_deref().map(Key::new).transpose()?,
+            window: config.window,
+            seen: LruCache::new(capacity),
+            unique: 0,
+            duplicate: 0,
+        })
+    }
+}
+
+op!(DedupFactory(_uid, node) {
+    let config = match &node.config {
+        Some(map) => Config::new(map)?,
+        None => Config { key: None, window: None, capacity: default_capacity() },
+    };
+    Ok(Box::new(Dedup::try_from(config)?))
+});
+
+impl Dedup {
+    fn is_duplicate(&mut self, key: String, ingest_ns: u64) -> bool {
+        if let Some(first_seen) = self.seen.get_mut(&key) {
+            let expired = self
+                .window
+                .is_some_and(|window| ingest_ns.saturating_sub(*first_seen) > window);
+            if expired {
+                *first_seen = ingest_ns;
+            }
+            !expired
+        } else {
+            self.seen.put(key, ingest_ns);
+            false
+        }
+    }
+}
+
+impl Operator for Dedup {
+    fn on_event(
+        &mut self,
+        _uid: OperatorId,
+        _port: &Port<'static>,
+        _state: &mut Value<'static>,
+        mut event: Event,
+    ) -> Result<EventAndInsights> {
+        let key = match &self.key {
+            Some(key) => match key.eval(&mut event) {
+                Ok(key) => key,
+                Err(_) => return Ok(vec![(ERR, event)].into()),
+            },
+            None => match key::of_value(event.data.suffix().value()) {
+                Ok(key) => key,
+                Err(_) => return Ok(vec![(ERR, event)].into()),
+            },
+        };
+        if self.is_duplicate(key, event.ingest_ns) {
+            self.duplicate += 1;
+            Ok(EventAndInsights::default())
+        } else {
+            self.unique += 1;
+            Ok(event.into())
+        }
+    }
+
+    fn metrics(&self, tags: &Object<'static>, timestamp: u64) -> Result<Vec<Value<'static>>> {
+        let mut tags = tags.clone();
+        tags.insert(ACTION, UNIQUE.into());
+        let unique = value_count(DEDUP, tags.clone(), self.unique, timestamp);
+        tags.insert(ACTION, DUPLICATE.into());
+        let duplicate = value_count(DEDUP, tags, self.duplicate, timestamp);
+        Ok(vec![unique, duplicate])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use simd_json::ObjectHasher;
+    use tremor_common::ids::Id;
+
+    fn op(config: &Value<'static>) -> Result<Dedup> {
+        Dedup::try_from(Config::new(config)?)
+    }
+
+    fn event(ingest_ns: u64, data: Value<'static>) -> Event {
+        Event {
+            id: (1, 1, ingest_ns).into(),
+            ingest_ns,
+            data: data.into(),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn bad_config() {
+        assert!(op(&literal!({"capacity": 0})).is_err());
+        assert!(op(&literal!({"key": "event."})).is_err());
+        assert!(op(&literal!({"snot": "badger"})).is_err());
+    }
+
+    #[test]
+    fn payload() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({}))?;
+        let r = op.on_event(uid, &IN, &mut state, event(1, literal!({"a": 1})))?;
+        assert_eq!(1, r.events.len());
+        let r = op.on_event(uid, &IN, &mut state, event(2, literal!({"a": 2})))?;
+        assert_eq!(1, r.events.len());
+        let r = op.on_event(uid, &IN, &mut state, event(3, literal!({"a": 1})))?;
+        assert!(r.events.is_empty());
+        // the order of fields doesn't make a record unique
+        let r = op.on_event(uid, &IN, &mut state, event(4, literal!({"b": 1, "a": 1})))?;
+        assert_eq!(1, r.events.len());
+        let r = op.on_event(uid, &IN, &mut state, event(5, literal!({"a": 1, "b": 1})))?;
+        assert!(r.events.is_empty());
+
+        let m = op.metrics(&Object::with_hasher(ObjectHasher::default()), 0)?;
+        assert_eq!(m[0]["tags"]["action"], "unique");
+        assert_eq!(m[0]["fields"]["count"], 3);
+        assert_eq!(m[1]["tags"]["action"], "duplicate");
+        assert_eq!(m[1]["fields"]["count"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn key_window_and_capacity() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"key": "event.id", "window": 10, "capacity": 2}))?;
+        let r = op.on_event(uid, &IN, &mut state, event(1, literal!({"id": 1, "n": 1})))?;
+        assert_eq!(1, r.events.len());
+        let r = op.on_event(uid, &IN, &mut state, event(5, literal!({"id": 1, "n": 2})))?;
+        assert!(r.events.is_empty());
+        // outside of the window
+        let r = op.on_event(uid, &IN, &mut state, event(12, literal!({"id": 1, "n": 3})))?;
+        assert_eq!(1, r.events.len());
+        let r = op.on_event(uid, &IN, &mut state, event(13, literal!({"id": 1, "n": 4})))?;
+        assert!(r.events.is_empty());
+
+        // `1` is evicted once we have seen two other keys
+        op.on_event(uid, &IN, &mut state, event(14, literal!({"id": 2})))?;
+        op.on_event(uid, &IN, &mut state, event(15, literal!({"id": 3})))?;
+        let r = op.on_event(uid, &IN, &mut state, event(16, literal!({"id": 1})))?;
+        assert_eq!(1, r.events.len());
+
+        let r = op.on_event(uid, &IN, &mut state, event(17, literal!({"snot": 1})))?;
+        assert_eq!("err", r.events[0].0);
+        Ok(())
+    }
+}
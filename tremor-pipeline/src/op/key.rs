@@ -0,0 +1,115 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Script expressions operators use to group events by a key
+
+use crate::errors::{ErrorKind, Result};
+use tremor_script::{prelude::*, AggrType, EventContext, Return, Script, FN_REGISTRY};
+use tremor_system::event::Event;
+use tremor_value::utils::sorted_serialize;
+
+/// A tremor-script expression evaluated against every event to compute its key,
+/// e.g. `event.tenant` or `[$kafka_consumer.topic, event.id]`
+#[derive(Debug)]
+pub(crate) struct Key {
+    src: String,
+    script: Script,
+}
+
+impl Key {
+    /// Compiles the key expression
+    ///
+    /// # Errors
+    /// if `src` isn't a valid tremor-script expression
+    pub(crate) fn new(src: &str) -> Result<Self> {
+        let script = Script::parse(src, &*FN_REGISTRY.read()?)?;
+        Ok(Self {
+            src: src.to_string(),
+            script,
+        })
+    }
+
+    /// Evaluates the expression against the event, string keys are taken as they are,
+    /// any other value is json encoded with sorted record keys.
+    ///
+    /// # Errors
+    /// if the expression fails to evaluate or drops the event
+    pub(crate) fn eval(&self, event: &mut Event) -> Result<String> {
+        let context = EventContext::new(event.ingest_ns, event.origin_uri.as_ref());
+        let mut state = Value::null();
+        event.data.rent_mut(|data| {
+            let (unwind_event, event_meta): (&mut Value, &mut Value) = data.parts_mut();
+            match self.script.run(
+                &context,
+                AggrType::Tick,
+                unwind_event,
+                &mut state,
+                event_meta,
+            )? {
+                Return::Emit { value, .. } => of_value(&value),
+                Return::EmitEvent { .. } => of_value(unwind_event),
+                Return::Drop => Err(ErrorKind::BadOpConfig(format!(
+                    "key `{}` dropped the event",
+                    self.src
+                ))
+                .into()),
+            }
+        })
+    }
+}
+
+/// The key of a value, strings are taken as they are, any other value is json encoded
+/// with sorted record keys, so records with the same fields get the same key
+///
+/// # Errors
+/// if the value can't be serialized
+pub(crate) fn of_value(value: &Value) -> Result<String> {
+    if let Some(s) = value.as_str() {
+        Ok(s.to_string())
+    } else {
+        Ok(String::from_utf8(sorted_serialize(value)?)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eval() -> Result<()> {
+        let mut event = Event {
+            data: (
+                literal!({"tenant": "snot", "id": 42}),
+                literal!({"class": "badger"}),
+            )
+                .into(),
+            ..Event::default()
+        };
+        assert_eq!("snot", Key::new("event.tenant")?.eval(&mut event)?);
+        assert_eq!("42", Key::new("event.id")?.eval(&mut event)?);
+        assert_eq!("badger", Key::new("$class")?.eval(&mut event)?);
+        assert_eq!(
+            r#"["snot","badger"]"#,
+            Key::new("[event.tenant, $class]")?.eval(&mut event)?
+        );
+        assert!(Key::new("drop").and_then(|k| k.eval(&mut event)).is_err());
+        // the order of record fields doesn't matter
+        assert_eq!(
+            of_value(&literal!({"a": 1, "b": [2, {"d": 3, "c": 4}]}))?,
+            of_value(&literal!({"b": [2, {"c": 4, "d": 3}], "a": 1}))?
+        );
+        assert!(Key::new("event.").is_err());
+        Ok(())
+    }
+}
@@ -14,11 +14,15 @@
 
 pub mod backpressure;
 pub mod percentile;
+pub mod ratelimit;
 pub mod roundrobin;
+pub mod sample;
 
 pub use backpressure::BackpressureFactory;
 pub use percentile::PercentileFactory;
+pub use ratelimit::RateLimitFactory;
 pub use roundrobin::RoundRobinFactory;
+pub use sample::SampleFactory;
 
 use crate::op::prelude::*;
 
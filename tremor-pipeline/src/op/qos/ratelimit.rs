@@ -0,0 +1,491 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The ratelimit operator enforces a hard quota on the events passing through it using a token bucket. The bucket holds up to `burst` tokens and is refilled with `rate` tokens per second, every event passing the operator takes one token.
+//!
+//! With a `key` expression every distinct key gets its own bucket, so quotas can be enforced per tenant, host or any other property of the event.
+//!
+//! Events that exceed the limit are handled according to `method`:
+//!
+//! - `drop` - the event is dropped.
+//! - `overflow` - the standard, the event is sent to the `overflow` output port.
+//! - `delay` - the event is held back and emitted on the `out` port on a later tick once tokens are available again. Events of the same key keep their order. If more than `max_delayed` events are held back, further events are sent to the `overflow` output port.
+//!
+//! This operator preserves event metadata.
+//!
+//! **Configuration options**:
+//!
+//! - `rate` - Number of events per second allowed to pass.
+//! - `burst` - Maximum number of events allowed to pass at once, the size of the bucket. (default: `rate`)
+//! - `key` - A tremor-script expression evaluated against each event, events with the same key share a bucket. (optional)
+//! - `method` - One of `drop`, `overflow` or `delay` (default: `overflow`)
+//! - `cardinality` - Maximum number of keys kept track of at the same time. (default: `1000`)
+//! - `max_delayed` - Maximum number of events held back with the `delay` method. (default: `1000`)
+//!
+//! **Outputs**:
+//!
+//! - `out`
+//! - `err` - Events for which the `key` expression could not be evaluated
+//! - `overflow` - Events that exceed the rate limit
+//!
+//! **Example**:
+//!
+//! ```tremor
+//! define operator quota from qos::ratelimit
+//! with
+//!   rate = 100,
+//!   burst = 200,
+//!   key = "event.tenant",
+//!   method = "delay"
+//! end;
+//! ```
+//!
+//! **Metrics**:
+//!
+//! The ratelimit operator generates additional metrics. For each key the following statistics are generated (as an example):
+//!
+//! ```json
+//! {"measurement":"ratelimit",
+//!  "tags":{
+//!    "action":"pass",
+//!    "key":"tenant-a",
+//!    "node":"quota",
+//!    "pipeline":"main"
+//!  },
+//!  "fields":{"count":93},
+//!  "timestamp":1553012903452340000
+//! }
+//! ```
+//!
+//! The `action` is one of `pass`, `limited` (dropped or sent to `overflow`) and `delayed`. The `key` tag is only present if a `key` is configured.
+
+use crate::{
+    metrics::value_count,
+    op::{key::Key, prelude::*},
+};
+use beef::Cow;
+use lru::LruCache;
+use std::{collections::VecDeque, num::NonZeroUsize};
+use tremor_script::prelude::*;
+
+const RATELIMIT: Cow<'static, str> = Cow::const_str("ratelimit");
+const KEY: Cow<'static, str> = Cow::const_str("key");
+const ACTION: Cow<'static, str> = Cow::const_str("action");
+const PASS: Cow<'static, str> = Cow::const_str("pass");
+const LIMITED: Cow<'static, str> = Cow::const_str("limited");
+const DELAYED: Cow<'static, str> = Cow::const_str("delayed");
+
+/// a token, measured in nanoseconds it takes to refill it at a rate of 1 event per second
+const TOKEN: u64 = 1_000_000_000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// events exceeding the limit are dropped
+    Drop,
+    /// events exceeding the limit are sent to the `overflow` port
+    #[default]
+    Overflow,
+    /// events exceeding the limit are emitted once there are tokens available again
+    Delay,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Number of events per second
+    pub rate: u64,
+    /// Maximum number of events allowed to pass at once
+    ///
+    /// default: `rate`
+    #[serde(default)]
+    pub burst: Option<u64>,
+    /// Expression to compute the key for per-key limits
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Defines how events exceeding the limit are handled
+    #[serde(default)]
+    pub method: Method,
+    /// Maximum number of keys kept track of
+    #[serde(default = "default_cardinality")]
+    pub cardinality: usize,
+    /// Maximum number of events held back for the `delay` method
+    #[serde(default = "default_max_delayed")]
+    pub max_delayed: usize,
+}
+
+impl tremor_config::Impl for Config {}
+
+fn default_cardinality() -> usize {
+    1000
+}
+
+fn default_max_delayed() -> usize {
+    1000
+}
+
+/// A token bucket, tokens are measured in `TOKEN` units to refill in integer steps
+#[derive(Debug)]
+struct Bucket {
+    tokens: u64,
+    last_ns: u64,
+}
+
+impl Bucket {
+    fn new(capacity: u64, now: u64) -> Self {
+        Self {
+            tokens: capacity,
+            last_ns: now,
+        }
+    }
+
+    fn refill(&mut self, now: u64, rate: u64, capacity: u64) {
+        if now > self.last_ns {
+            let refill = (now - self.last_ns).saturating_mul(rate);
+            self.tokens = self.tokens.saturating_add(refill).min(capacity);
+            self.last_ns = now;
+        }
+    }
+
+    fn take(&mut self) -> bool {
+        if self.tokens >= TOKEN {
+            self.tokens -= TOKEN;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Limiter {
+    bucket: Bucket,
+    delayed: VecDeque<Event>,
+    pass: u64,
+    limited: u64,
+    delayed_total: u64,
+}
+
+impl Limiter {
+    fn new(capacity: u64, now: u64) -> Self {
+        Self {
+            bucket: Bucket::new(capacity, now),
+            delayed: VecDeque::new(),
+            pass: 0,
+            limited: 0,
+            delayed_total: 0,
+        }
+    }
+
+    /// emit held back events for which we have tokens again
+    fn release(&mut self, res: &mut Vec<(Port<'static>, Event)>) -> usize {
+        let mut released = 0;
+        while !self.delayed.is_empty() && self.bucket.take() {
+            if let Some(event) = self.delayed.pop_front() {
+                self.pass += 1;
+                released += 1;
+                res.push((OUT, event));
+            }
+        }
+        released
+    }
+}
+
+struct RateLimit {
+    key: Option<Key>,
+    method: Method,
+    /// refill rate in tokens per nanosecond, `TOKEN` units
+    rate: u64,
+    /// bucket capacity in `TOKEN` units
+    capacity: u64,
+    max_delayed: usize,
+    /// number of events currently held back
+    delayed: usize,
+    limiters: LruCache<String, Limiter>,
+}
+
+impl std::fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "RateLimit")
+    }
+}
+
+impl TryFrom<Config> for RateLimit {
+    type Error = Error;
+    fn try_from(config: Config) -> Result<Self> {
+        let burst = config.burst.unwrap_or(config.rate);
+        if config.rate == 0 || burst == 0 {
+            return Err(ErrorKind::BadOpConfig(
+                "`rate` and `burst` need to be greater than 0".to_string(),
+            )
+            .into());
+        }
+        let cardinality = NonZeroUsize::new(config.cardinality).ok_or_else(|| {
+            ErrorKind::BadOpConfig("`cardinality` needs to be greater than 0".to_string())
+        })?;
+        Ok(Self {
+            key: config.key.as_deref().map(Key::new).transpose()?,
+            method: config.method,
+            rate: config.rate,
+            capacity: burst.saturating_mul(TOKEN),
+            max_delayed: config.max_delayed,
+            delayed: 0,
+            limiters: LruCache::new(cardinality),
+        })
+    }
+}
+
+op!(RateLimitFactory(_uid, node) {
+    if let Some(map) = &node.config {
+        let config: Config = Config::new(map)?;
+        Ok(Box::new(RateLimit::try_from(config)?))
+    } else {
+        Err(ErrorKind::MissingOpConfig(node.id.clone()).into())
+    }
+});
+
+impl Operator for RateLimit {
+    fn on_event(
+        &mut self,
+        _uid: OperatorId,
+        _port: &Port<'static>,
+        _state: &mut Value<'static>,
+        mut event: Event,
+    ) -> Result<EventAndInsights> {
+        let key = match self.key.as_ref().map(|k| k.eval(&mut event)).transpose() {
+            Ok(key) => key.unwrap_or_default(),
+            Err(_) => return Ok(vec![(ERR, event)].into()),
+        };
+        let (rate, capacity, now) = (self.rate, self.capacity, event.ingest_ns);
+        let mut res = Vec::with_capacity(1);
+        if !self.limiters.contains(&key) {
+            // evicted keys can't release their held back events any more
+            if let Some((_, evicted)) = self.limiters.push(key.clone(), Limiter::new(capacity, now))
+            {
+                self.delayed -= evicted.delayed.len();
+                res.extend(evicted.delayed.into_iter().map(|e| (OVERFLOW, e)));
+            }
+        }
+        let Some(limiter) = self.limiters.get_mut(&key) else {
+            // ALLOW: we just made sure the limiter exists
+            unreachable!()
+        };
+        limiter.bucket.refill(now, rate, capacity);
+        if self.method == Method::Delay {
+            self.delayed -= limiter.release(&mut res);
+        }
+        // to keep the order, events have to wait if there are events held back already
+        if limiter.delayed.is_empty() && limiter.bucket.take() {
+            limiter.pass += 1;
+            res.push((OUT, event));
+        } else {
+            match self.method {
+                Method::Delay if self.delayed < self.max_delayed => {
+                    limiter.delayed_total += 1;
+                    limiter.delayed.push_back(event);
+                    self.delayed += 1;
+                }
+                Method::Drop => limiter.limited += 1,
+                Method::Overflow | Method::Delay => {
+                    limiter.limited += 1;
+                    res.push((OVERFLOW, event));
+                }
+            }
+        }
+        Ok(res.into())
+    }
+
+    fn handles_signal(&self) -> bool {
+        self.method == Method::Delay
+    }
+
+    fn on_signal(
+        &mut self,
+        _uid: OperatorId,
+        _state: &mut Value<'static>,
+        signal: &mut Event,
+    ) -> Result<EventAndInsights> {
+        let mut res = Vec::new();
+        if self.delayed > 0 {
+            let (rate, capacity, now) = (self.rate, self.capacity, signal.ingest_ns);
+            for (_, limiter) in &mut self.limiters {
+                if !limiter.delayed.is_empty() {
+                    limiter.bucket.refill(now, rate, capacity);
+                    self.delayed -= limiter.release(&mut res);
+                }
+            }
+        }
+        Ok(res.into())
+    }
+
+    fn metrics(&self, tags: &Object<'static>, timestamp: u64) -> Result<Vec<Value<'static>>> {
+        let mut res = Vec::with_capacity(self.limiters.len() * 3);
+        let mut tags = tags.clone();
+        for (key, limiter) in &self.limiters {
+            if self.key.is_some() {
+                tags.insert(KEY, Value::from(key.clone()));
+            }
+            tags.insert(ACTION, PASS.into());
+            res.push(value_count(
+                RATELIMIT,
+                tags.clone(),
+                limiter.pass,
+                timestamp,
+            ));
+            tags.insert(ACTION, LIMITED.into());
+            res.push(value_count(
+                RATELIMIT,
+                tags.clone(),
+                limiter.limited,
+                timestamp,
+            ));
+            if self.method == Method::Delay {
+                tags.insert(ACTION, DELAYED.into());
+                res.push(value_count(
+                    RATELIMIT,
+                    tags.clone(),
+                    limiter.delayed_total,
+                    timestamp,
+                ));
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use simd_json::ObjectHasher;
+    use std::borrow::Borrow;
+    use tremor_common::ids::Id;
+
+    fn op(config: &Value<'static>) -> Result<RateLimit> {
+        RateLimit::try_from(Config::new(config)?)
+    }
+
+    fn event(ingest_ns: u64, tenant: &str) -> Event {
+        Event {
+            id: (1, 1, ingest_ns).into(),
+            ingest_ns,
+            data: literal!({ "tenant": tenant.to_string() }).into(),
+            ..Event::default()
+        }
+    }
+
+    fn ports(r: &EventAndInsights) -> Vec<&str> {
+        r.events.iter().map(|(p, _)| p.borrow()).collect()
+    }
+
+    #[test]
+    fn bad_config() {
+        assert!(op(&literal!({"rate": 0})).is_err());
+        assert!(op(&literal!({"rate": 1, "burst": 0})).is_err());
+        assert!(op(&literal!({"rate": 1, "cardinality": 0})).is_err());
+        assert!(op(&literal!({"rate": 1, "key": "event."})).is_err());
+        assert!(op(&literal!({"rate": 1, "method": "snot"})).is_err());
+    }
+
+    #[test]
+    fn overflow() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"rate": 2}))?;
+        let r = op.on_event(uid, &IN, &mut state, event(0, "a"))?;
+        assert_eq!(vec!["out"], ports(&r));
+        let r = op.on_event(uid, &IN, &mut state, event(1, "a"))?;
+        assert_eq!(vec!["out"], ports(&r));
+        let r = op.on_event(uid, &IN, &mut state, event(2, "a"))?;
+        assert_eq!(vec!["overflow"], ports(&r));
+        // half a second later we have one token again
+        let r = op.on_event(uid, &IN, &mut state, event(500_000_002, "a"))?;
+        assert_eq!(vec!["out"], ports(&r));
+        let r = op.on_event(uid, &IN, &mut state, event(500_000_003, "a"))?;
+        assert_eq!(vec!["overflow"], ports(&r));
+
+        let mut m = op.metrics(&Object::with_hasher(ObjectHasher::default()), 0)?;
+        let limited = m.pop().ok_or("no data")?;
+        let pass = m.pop().ok_or("no data")?;
+        assert!(m.is_empty());
+        assert_eq!(limited["tags"]["action"], "limited");
+        assert_eq!(limited["fields"]["count"], 2);
+        assert!(limited["tags"].get("key").is_none());
+        assert_eq!(pass["tags"]["action"], "pass");
+        assert_eq!(pass["fields"]["count"], 3);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_per_key() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"rate": 1, "key": "event.tenant", "method": "drop"}))?;
+        let r = op.on_event(uid, &IN, &mut state, event(0, "a"))?;
+        assert_eq!(vec!["out"], ports(&r));
+        let r = op.on_event(uid, &IN, &mut state, event(1, "a"))?;
+        assert!(r.events.is_empty());
+        let r = op.on_event(uid, &IN, &mut state, event(2, "b"))?;
+        assert_eq!(vec!["out"], ports(&r));
+
+        let r = op.on_event(uid, &IN, &mut state, Event::default())?;
+        assert_eq!(vec!["err"], ports(&r));
+
+        let m = op.metrics(&Object::with_hasher(ObjectHasher::default()), 0)?;
+        assert_eq!(4, m.len());
+        for v in &m {
+            let key = v["tags"]["key"].as_str().unwrap_or_default();
+            let action = v["tags"]["action"].as_str().unwrap_or_default();
+            let expected = match (key, action) {
+                ("b", "limited") => 0,
+                _ => 1,
+            };
+            assert_eq!(v["fields"]["count"], expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn delay() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"rate": 1, "method": "delay", "max_delayed": 2}))?;
+        assert!(op.handles_signal());
+        let r = op.on_event(uid, &IN, &mut state, event(0, "a"))?;
+        assert_eq!(vec!["out"], ports(&r));
+        let r = op.on_event(uid, &IN, &mut state, event(1, "b"))?;
+        assert!(r.events.is_empty());
+        let r = op.on_event(uid, &IN, &mut state, event(2, "c"))?;
+        assert!(r.events.is_empty());
+        let r = op.on_event(uid, &IN, &mut state, event(3, "d"))?;
+        assert_eq!(vec!["overflow"], ports(&r));
+
+        let mut signal = Event::signal_tick();
+        signal.ingest_ns = 500_000_000;
+        let r = op.on_signal(uid, &mut state, &mut signal)?;
+        assert!(r.events.is_empty());
+
+        signal.ingest_ns = 1_000_000_000;
+        let r = op.on_signal(uid, &mut state, &mut signal)?;
+        assert_eq!(vec!["out"], ports(&r));
+        assert_eq!(r.events[0].1.ingest_ns, 1);
+
+        // the next event has to wait for the held back one
+        let r = op.on_event(uid, &IN, &mut state, event(2_000_000_000, "e"))?;
+        assert_eq!(vec!["out"], ports(&r));
+        assert_eq!(r.events[0].1.ingest_ns, 2);
+        let r = op.on_event(uid, &IN, &mut state, event(2_000_000_001, "f"))?;
+        assert!(r.events.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The sample operator forwards a sample of the events passing through it and drops the rest. The sampling strategy is selected with `method`:
+//!
+//! - `probabilistic` - the standard, every event is kept with the probability `rate`.
+//! - `consistent` - the `key` expression is evaluated against every event and hashed using the same jump hash as `chash::jump`. A `rate` fraction of all keys is kept, all events with the same key are either kept or dropped together. This keeps traces intact when sampling on a trace id.
+//! - `reservoir` - out of all events arriving within an `interval`, a uniform random sample of at most `size` events is kept. The sample is emitted, in order of arrival, once the interval is over.
+//!
+//! This operator preserves event metadata.
+//!
+//! **Configuration options**:
+//!
+//! - `method` - One of `probabilistic`, `consistent` or `reservoir`. (default: `probabilistic`)
+//! - `rate` - Fraction of events (or keys) to keep, between `0.0` and `1.0`. (required for `probabilistic` and `consistent`)
+//! - `key` - A tremor-script expression evaluated against each event to compute its key. (required for `consistent`)
+//! - `size` - Maximum number of events kept per interval. (required for `reservoir`)
+//! - `interval` - Length of an interval in nanoseconds. (required for `reservoir`)
+//!
+//! **Outputs**:
+//!
+//! - `out`
+//! - `err` - Events for which the `key` expression could not be evaluated
+//!
+//! **Example**:
+//!
+//! ```tremor
+//! define operator traces from qos::sample
+//! with
+//!   method = "consistent",
+//!   rate = 0.1,
+//!   key = "event.trace_id"
+//! end;
+//!
+//! define operator debug_logs from qos::sample
+//! with
+//!   method = "reservoir",
+//!   size = 100,
+//!   interval = nanos::from_seconds(10)
+//! end;
+//! ```
+//!
+//! **Metrics**:
+//!
+//! The sample operator generates additional metrics (as an example):
+//!
+//! ```json
+//! {"measurement":"sample",
+//!  "tags":{
+//!    "action":"dropped",
+//!    "node":"traces",
+//!    "pipeline":"main"
+//!  },
+//!  "fields":{"count":901},
+//!  "timestamp":1553012903452340000
+//! }
+//! ```
+//!
+//! The `action` is either `sampled` for events that were kept or `dropped`.
+
+use crate::{
+    metrics::value_count,
+    op::{key::Key, prelude::*},
+};
+use beef::Cow;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::mem;
+use tremor_script::{prelude::*, utils::jump_hash};
+
+const SAMPLE: Cow<'static, str> = Cow::const_str("sample");
+const ACTION: Cow<'static, str> = Cow::const_str("action");
+const SAMPLED: Cow<'static, str> = Cow::const_str("sampled");
+const DROPPED: Cow<'static, str> = Cow::const_str("dropped");
+
+/// number of slots keys are hashed into for `consistent` sampling
+const SLOTS: u32 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// every event is kept with a fixed probability
+    #[default]
+    Probabilistic,
+    /// a fixed fraction of keys is kept
+    Consistent,
+    /// a fixed number of events is kept per interval
+    Reservoir,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Sampling strategy
+    #[serde(default)]
+    pub method: Method,
+    /// Fraction of events or keys to keep
+    #[serde(default)]
+    pub rate: Option<f64>,
+    /// Expression to compute the key for `consistent` sampling
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Maximum number of events kept per interval for `reservoir` sampling
+    #[serde(default)]
+    pub size: Option<usize>,
+    /// Interval in nanoseconds for `reservoir` sampling
+    #[serde(default)]
+    pub interval: Option<u64>,
+}
+
+impl tremor_config::Impl for Config {}
+
+#[derive(Debug)]
+enum Strategy {
+    Probabilistic {
+        rate: f64,
+    },
+    Consistent {
+        key: Key,
+        /// keys hashed into a slot below this threshold are kept
+        threshold: u32,
+    },
+    Reservoir {
+        size: usize,
+        interval: u64,
+        /// start of the current interval
+        start_ns: u64,
+        /// number of events seen in the current interval
+        seen: u64,
+        reservoir: Vec<Event>,
+    },
+}
+
+#[derive(Debug)]
+struct Sample {
+    strategy: Strategy,
+    rng: SmallRng,
+    sampled: u64,
+    dropped: u64,
+}
+
+fn missing(option: &str, method: &str) -> Error {
+    ErrorKind::BadOpConfig(format!("`{option}` is required for the `{method}` method")).into()
+}
+
+fn rate(config: &Config, method: &str) -> Result<f64> {
+    let rate = config.rate.ok_or_else(|| missing("rate", method))?;
+    if (0.0..=1.0).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(ErrorKind::BadOpConfig("`rate` needs to be between 0.0 and 1.0".to_string()).into())
+    }
+}
+
+impl TryFrom<Config> for Sample {
+    type Error = Error;
+    fn try_from(config: Config) -> Result<Self> {
+        let strategy = match config.method {
+            Method::Probabilistic => Strategy::Probabilistic {
+                rate: rate(&config, "probabilistic")?,
+            },
+            Method::Consistent => {
+                let rate = rate(&config, "consistent")?;
+                let key = config
+                    .key
+                    .as_deref()
+                    .ok_or_else(|| missing("key", "consistent"))?;
+                // ALLOW: rate is between 0.0 and 1.0 so this fits in a u32
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let threshold = (rate * f64::from(SLOTS)).round() as u32;
+                Strategy::Consistent {
+                    key: Key::new(key)?,
+                    threshold,
+                }
+            }
+            Method::Reservoir => {
+                let size = config.size.ok_or_else(|| missing("size", "reservoir"))?;
+                let interval = config
+                    .interval
+                    .ok_or_else(|| missing("interval", "reservoir"))?;
+                if size == 0 || interval == 0 {
+                    return Err(ErrorKind::BadOpConfig(
+                        "`size` and `interval` need to be greater than 0".to_string(),
+                    )
+                    .into());
+                }
+                Strategy::Reservoir {
+                    size,
+                    interval,
+                    start_ns: 0,
+                    seen: 0,
+                    reservoir: Vec::with_capacity(size),
+                }
+            }
+        };
+        Ok(Self {
+            strategy,
+            rng: SmallRng::from_entropy(),
+            sampled: 0,
+            dropped: 0,
+        })
+    }
+}
+
+op!(SampleFactory(_uid, node) {
+    if let Some(map) = &node.config {
+        let config: Config = Config::new(map)?;
+        Ok(Box::new(Sample::try_from(config)?))
+    } else {
+        Err(ErrorKind::MissingOpConfig(node.id.clone()).into())
+    }
+});
+
+impl Sample {
+    /// emits the reservoir if the current interval is over at `now`
+    fn flush(&mut self, now: u64) -> Vec<(Port<'static>, Event)> {
+        if let Strategy::Reservoir {
+            interval,
+            start_ns,
+            seen,
+            reservoir,
+            ..
+        } = &mut self.strategy
+        {
+            if *seen > 0 && now.saturating_sub(*start_ns) >= *interval {
+                self.sampled += reservoir.len() as u64;
+                self.dropped += *seen - reservoir.len() as u64;
+                *seen = 0;
+                let mut res = mem::take(reservoir);
+                res.sort_by_key(|e| e.ingest_ns);
+                return res.into_iter().map(|e| (OUT, e)).collect();
+            }
+        }
+        Vec::new()
+    }
+}
+
+impl Operator for Sample {
+    fn on_event(
+        &mut self,
+        _uid: OperatorId,
+        _port: &Port<'static>,
+        _state: &mut Value<'static>,
+        mut event: Event,
+    ) -> Result<EventAndInsights> {
+        let keep = match &mut self.strategy {
+            Strategy::Probabilistic { rate } => self.rng.gen_bool(*rate),
+            Strategy::Consistent { key, threshold } => match key.eval(&mut event) {
+                Ok(key) => jump_hash(&key, SLOTS) < *threshold,
+                Err(_) => return Ok(vec![(ERR, event)].into()),
+            },
+            Strategy::Reservoir { .. } => {
+                let res = self.flush(event.ingest_ns);
+                if let Strategy::Reservoir {
+                    size,
+                    start_ns,
+                    seen,
+                    reservoir,
+                    ..
+                } = &mut self.strategy
+                {
+                    if *seen == 0 {
+                        *start_ns = event.ingest_ns;
+                    }
+                    *seen += 1;
+                    if reservoir.len() < *size {
+                        reservoir.push(event);
+                    } else {
+                        // ALLOW: idx < size, which is a usize
+                        #[allow(clippy::cast_possible_truncation)]
+                        let idx = self.rng.gen_range(0..*seen) as usize;
+                        if let Some(slot) = reservoir.get_mut(idx) {
+                            *slot = event;
+                        }
+                    }
+                }
+                return Ok(res.into());
+            }
+        };
+        if keep {
+            self.sampled += 1;
+            Ok(event.into())
+        } else {
+            self.dropped += 1;
+            Ok(EventAndInsights::default())
+        }
+    }
+
+    fn handles_signal(&self) -> bool {
+        matches!(self.strategy, Strategy::Reservoir { .. })
+    }
+
+    fn on_signal(
+        &mut self,
+        _uid: OperatorId,
+        _state: &mut Value<'static>,
+        signal: &mut Event,
+    ) -> Result<EventAndInsights> {
+        Ok(self.flush(signal.ingest_ns).into())
+    }
+
+    fn metrics(&self, tags: &Object<'static>, timestamp: u64) -> Result<Vec<Value<'static>>> {
+        let mut tags = tags.clone();
+        tags.insert(ACTION, SAMPLED.into());
+        let sampled = value_count(SAMPLE, tags.clone(), self.sampled, timestamp);
+        tags.insert(ACTION, DROPPED.into());
+        let dropped = value_count(SAMPLE, tags, self.dropped, timestamp);
+        Ok(vec![sampled, dropped])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use simd_json::ObjectHasher;
+    use tremor_common::ids::Id;
+    use tremor_system::dataplane::SignalKind;
+
+    fn op(config: &Value<'static>) -> Result<Sample> {
+        let mut op = Sample::try_from(Config::new(config)?)?;
+        op.rng = SmallRng::seed_from_u64(42);
+        Ok(op)
+    }
+
+    fn event(ingest_ns: u64, trace: u64) -> Event {
+        Event {
+            id: (1, 1, ingest_ns).into(),
+            ingest_ns,
+            data: literal!({ "trace": trace, "n": ingest_ns }).into(),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn bad_config() {
+        assert!(op(&literal!({})).is_err());
+        assert!(op(&literal!({"rate": 1.5})).is_err());
+        assert!(op(&literal!({"rate": -0.1})).is_err());
+        assert!(op(&literal!({"method": "consistent", "rate": 0.5})).is_err());
+        assert!(op(&literal!({"method": "consistent", "rate": 0.5, "key": "event."})).is_err());
+        assert!(op(&literal!({"method": "reservoir", "size": 10})).is_err());
+        assert!(op(&literal!({"method": "reservoir", "size": 0, "interval": 10})).is_err());
+        assert!(op(&literal!({"method": "snot", "rate": 0.5})).is_err());
+    }
+
+    #[test]
+    fn probabilistic() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"rate": 0.25}))?;
+        let mut kept = 0;
+        for i in 0..10_000 {
+            kept += op.on_event(uid, &IN, &mut state, event(i, i))?.events.len();
+        }
+        assert!((2_000..3_000).contains(&kept), "{kept}");
+
+        let m = op.metrics(&Object::with_hasher(ObjectHasher::default()), 0)?;
+        assert_eq!(m[0]["tags"]["action"], "sampled");
+        assert_eq!(m[0]["fields"]["count"], kept);
+        assert_eq!(m[1]["tags"]["action"], "dropped");
+        assert_eq!(m[1]["fields"]["count"], 10_000 - kept);
+
+        let mut op = self::op(&literal!({"rate": 0.0}))?;
+        assert!(op
+            .on_event(uid, &IN, &mut state, event(0, 0))?
+            .events
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn consistent() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"method": "consistent", "rate": 0.5, "key": "event.trace"}))?;
+        let mut kept = Vec::new();
+        for trace in 0..100 {
+            kept.push(
+                !op.on_event(uid, &IN, &mut state, event(0, trace))?
+                    .events
+                    .is_empty(),
+            );
+        }
+        let n = kept.iter().filter(|k| **k).count();
+        assert!((30..70).contains(&n), "{n}");
+        // every event of a trace shares the decision of the first one
+        for i in 1..5 {
+            for (trace, keep) in (0..100).zip(&kept) {
+                let r = op.on_event(uid, &IN, &mut state, event(i, trace))?;
+                assert_eq!(*keep, !r.events.is_empty());
+            }
+        }
+
+        let r = op.on_event(uid, &IN, &mut state, Event::default())?;
+        assert_eq!("err", r.events[0].0);
+        Ok(())
+    }
+
+    #[test]
+    fn reservoir() -> Result<()> {
+        let uid = OperatorId::new(0);
+        let mut state = Value::null();
+        let mut op = op(&literal!({"method": "reservoir", "size": 3, "interval": 100}))?;
+        for i in 10..20 {
+            assert!(op
+                .on_event(uid, &IN, &mut state, event(i, i))?
+                .events
+                .is_empty());
+        }
+        let mut signal = Event {
+            ingest_ns: 50,
+            kind: Some(SignalKind::Tick),
+            ..Event::default()
+        };
+        assert!(op
+            .on_signal(uid, &mut state, &mut signal)?
+            .events
+            .is_empty());
+        signal.ingest_ns = 110;
+        let r = op.on_signal(uid, &mut state, &mut signal)?;
+        assert_eq!(3, r.events.len());
+        let ns: Vec<_> = r.events.iter().map(|(_, e)| e.ingest_ns).collect();
+        assert!(ns.windows(2).all(|w| w[0] < w[1]), "{ns:?}");
+        assert!(op
+            .on_signal(uid, &mut state, &mut signal)?
+            .events
+            .is_empty());
+
+        // an event arriving after the interval emits the previous sample
+        op.on_event(uid, &IN, &mut state, event(200, 0))?;
+        let r = op.on_event(uid, &IN, &mut state, event(300, 0))?;
+        assert_eq!(1, r.events.len());
+        assert_eq!(200, r.events[0].1.ingest_ns);
+
+        let m = op.metrics(&Object::with_hasher(ObjectHasher::default()), 0)?;
+        assert_eq!(m[0]["fields"]["count"], 4);
+        assert_eq!(m[1]["fields"]["count"], 7);
+        Ok(())
+    }
+}
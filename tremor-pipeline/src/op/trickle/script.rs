@@ -22,7 +22,10 @@ pub(crate) struct Script {
 }
 
 impl Script {
-    pub(crate) fn new(script: tremor_script::Script) -> Self {
+    pub(crate) fn new(mut script: tremor_script::Script) -> Self {
+        if tremor_script::compile_scripts() {
+            script.compile();
+        }
         Self { script }
     }
 }
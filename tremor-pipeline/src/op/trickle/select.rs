@@ -21,20 +21,41 @@ use super::window::{self, Group, Window};
 use crate::op::prelude::*;
 use crate::op::trickle::window::{GroupWindow, SelectCtx, Trait};
 use halfbrown::Entry;
+use std::borrow::Cow;
 use tremor_common::stry;
 use tremor_script::{
     ast::{self, ImutExpr, RunConsts, SelectStmt},
     errors::{err_generic, Result as TSResult},
     interpreter::{Env, LocalStack},
     prelude::*,
+    vm::Program,
     NO_AGGRS,
 };
 use tremor_system::dataplane::SignalKind;
 use tremor_value::utils::sorted_serialize;
 
+/// Bytecode for the select target and the where and having clauses
+#[derive(Debug)]
+pub(crate) struct Programs {
+    target: Program<'static>,
+    maybe_where: Option<Program<'static>>,
+    maybe_having: Option<Program<'static>>,
+}
+
+impl Programs {
+    fn compile(select: &ast::Select<'static>) -> Self {
+        Self {
+            target: Program::compile(&select.target),
+            maybe_where: select.maybe_where.as_ref().map(Program::compile),
+            maybe_having: select.maybe_having.as_ref().map(Program::compile),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Select {
     stmt: ast::SelectStmt<'static>,
+    programs: Option<Programs>,
     windows: Vec<Window>,
     groups: HashMap<String, Group>,
     recursion_limit: u32,
@@ -66,9 +87,11 @@ impl Select {
             .map(|w| w.window_impl.max_groups())
             .min()
             .unwrap_or(0);
+        let programs = tremor_script::compile_scripts().then(|| Programs::compile(&select.stmt));
         Self {
             windows,
             stmt: select.clone(),
+            programs,
             groups: HashMap::new(),
             recursion_limit: tremor_script::recursion_limit(),
             dflt_group,
@@ -91,11 +114,12 @@ pub(crate) fn execute_select_and_having(
 ) -> TSResult<Option<(Port<'static>, Event)>> {
     let (event_payload, event_meta) = data.parts();
 
-    let value = ctx.select.target.run(
+    let value = run_target(
+        ctx.select,
+        ctx.programs,
         ctx.opts,
         env,
         event_payload,
-        &NULL,
         event_meta,
         ctx.local_stack,
     )?;
@@ -105,6 +129,7 @@ pub(crate) fn execute_select_and_having(
     let having = stry!(run_guard(
         ctx.select,
         &ctx.select.maybe_having,
+        ctx.programs.and_then(|p| p.maybe_having.as_ref()),
         ctx.opts,
         env,
         &result,
@@ -175,13 +200,14 @@ impl Operator for Select {
     ) -> Result<EventAndInsights> {
         let Self {
             stmt: select,
+            programs,
             windows,
             groups,
             recursion_limit,
             dflt_group,
             max_groups,
-            ..
         } = self;
+        let programs = programs.as_ref();
         let Event {
             ingest_ns,
             ref mut data,
@@ -211,7 +237,8 @@ impl Operator for Select {
             //
             let guard = &select.maybe_where;
             let e = env(&ctx, consts.run(), *recursion_limit);
-            if !run_guard(select, guard, opts, &e, data, meta, &locals)? {
+            let program = programs.and_then(|p| p.maybe_where.as_ref());
+            if !run_guard(select, guard, program, opts, &e, data, meta, &locals)? {
                 return Ok(Res::None);
             };
 
@@ -229,8 +256,9 @@ impl Operator for Select {
                 consts.group = Value::from(vec![Value::const_null(), Value::from("[null]")]);
 
                 let e = env(&ctx, consts.run(), *recursion_limit);
-                let value = stry!(select.target.run(opts, &e, data, &NULL, meta, &locals));
-                let h_guard = run_guard(select, &select.maybe_having, opts, &e, &value, meta, &locals);
+                let value = stry!(run_target(select, programs, opts, &e, data, meta, &locals));
+                let program = programs.and_then(|p| p.maybe_having.as_ref());
+                let h_guard = run_guard(select, &select.maybe_having, program, opts, &e, &value, meta, &locals);
                 return if stry!(h_guard) {
                     *data = value.into_owned();
                     Ok(Res::Event)
@@ -254,6 +282,7 @@ impl Operator for Select {
 
                 let sel_ctx = SelectCtx {
                     select,
+                    programs,
                     local_stack: &locals,
                     opts,
                     ctx: &ctx,
@@ -315,12 +344,14 @@ impl Operator for Select {
         // we only react on ticks and when we have windows
         let Self {
             stmt: select,
+            programs,
             windows,
             groups,
             recursion_limit,
             ..
         } = self;
         let recursion_limit = *recursion_limit;
+        let programs = programs.as_ref();
 
         // if it isn't a tick or we do not have any windows, or have no
         // recorded groups, we can just return
@@ -371,6 +402,7 @@ impl Operator for Select {
 
                     let mut ctx = SelectCtx {
                         select,
+                        programs,
                         local_stack: &local_stack,
                         opts,
                         ctx: &ctx,
@@ -419,9 +451,29 @@ impl Operator for Select {
     }
 }
 
+fn run_target<'run, 'event>(
+    select: &'run ast::Select<'event>,
+    programs: Option<&'run Programs>,
+    opts: ExecOpts,
+    env: &'run Env<'run, 'event>,
+    data: &'run Value<'event>,
+    meta: &'run Value<'event>,
+    local_stack: &'run LocalStack<'event>,
+) -> TSResult<Cow<'run, Value<'event>>> {
+    if let Some(programs) = programs {
+        programs
+            .target
+            .run(opts, env, data, &NULL, meta, local_stack)
+    } else {
+        select.target.run(opts, env, data, &NULL, meta, local_stack)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_guard(
     select: &ast::Select,
     guard: &Option<ImutExpr>,
+    program: Option<&Program>,
     opts: ExecOpts,
     env: &Env,
     data: &Value,
@@ -429,7 +481,11 @@ fn run_guard(
     local_stack: &LocalStack,
 ) -> TSResult<bool> {
     if let Some(guard) = guard {
-        let test = stry!(guard.run(opts, env, data, &NULL, meta, local_stack));
+        let test = if let Some(program) = program {
+            stry!(program.run(opts, env, data, &NULL, meta, local_stack))
+        } else {
+            stry!(guard.run(opts, env, data, &NULL, meta, local_stack))
+        };
         test.as_bool()
             .ok_or_else(|| tremor_script::errors::query_guard_not_bool_err(select, guard, &test))
     } else {
@@ -223,7 +223,7 @@ fn select_stmt_from_query(query_str: &str) -> Result<Select> {
                 .get(win_defn.id.id())
                 .ok_or("no data")?
                 .clone();
-            let mut f = ConstFolder { helper: &h };
+            let mut f = ConstFolder::new(&h);
             f.walk_window_defn(&mut window_defn)?;
             Ok((window_defn.id.clone(), window_defn_to_impl(&window_defn)?))
         })
@@ -1015,3 +1015,62 @@ fn tumbling_window_on_number_emit() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn compiled_select() -> Result<()> {
+    let query = r#"
+        define window window1 from tumbling
+        with
+            size = 2
+        end;
+        select {"g": group[0], "count": aggr::stats::count(), "first": aggr::win::first(event.v) + 1}
+        from in[window1]
+        where event.v > 1
+        group by event.g
+        into out
+        having event.count > 1;
+        "#;
+    let mut interpreted = select_stmt_from_query(query)?;
+    let mut compiled = select_stmt_from_query(query)?;
+    compiled.programs = Some(Programs::compile(&compiled.stmt.stmt));
+
+    let uid = test_uid();
+    let mut state = Value::null();
+    let mut emitted = 0;
+    for (i, v) in [1, 2, 3, 4, 5].into_iter().enumerate() {
+        let event = Event {
+            id: (1, 1, i as u64).into(),
+            ingest_ns: i as u64,
+            data: literal!({"g": "snot", "v": v}).into(),
+            ..Event::default()
+        };
+        let r1 = interpreted.on_event(uid, &Port::In, &mut state, event.clone())?;
+        let r2 = compiled.on_event(uid, &Port::In, &mut state, event)?;
+        assert_eq!(r1.events.len(), r2.events.len());
+        emitted += r1.events.len();
+        for ((p1, e1), (p2, e2)) in r1.events.iter().zip(r2.events.iter()) {
+            assert_eq!(p1, p2);
+            assert_eq!(e1.data.suffix().value(), e2.data.suffix().value());
+        }
+    }
+    assert_eq!(2, emitted);
+
+    let mut op = select_stmt_from_query(
+        "select event.v * 2 from in where event.v > 1 into out having event > 4;",
+    )?;
+    op.programs = Some(Programs::compile(&op.stmt.stmt));
+    for v in [1, 2] {
+        let event = Event {
+            data: literal!({ "v": v }).into(),
+            ..Event::default()
+        };
+        assert!(try_enqueue(&mut op, event)?.is_none());
+    }
+    let event = Event {
+        data: literal!({"v": 3}).into(),
+        ..Event::default()
+    };
+    let (_, event) = try_enqueue(&mut op, event)?.expect("no event");
+    assert_eq!(*event.data.suffix().value(), 6);
+    Ok(())
+}
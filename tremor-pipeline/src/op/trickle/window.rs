@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::select::execute_select_and_having;
+use super::select::{execute_select_and_having, Programs};
 use std::borrow::Cow as SCow;
 use tremor_common::{ids::OperatorId, ports::Port, stry};
 use tremor_script::{
@@ -29,6 +29,7 @@ use tremor_system::{
 
 pub(crate) struct SelectCtx<'run, 'script, 'local> {
     pub(crate) select: &'run Select<'script>,
+    pub(crate) programs: Option<&'run Programs>,
     pub(crate) local_stack: &'run LocalStack<'local>,
     pub(crate) opts: ExecOpts,
     pub(crate) ctx: &'run EventContext<'run>,
@@ -19,6 +19,7 @@ use tremor_script::ast::{Expr, ImutExpr};
 use tremor_script::interpreter::{Env, LocalStack};
 use tremor_script::module::Manager;
 use tremor_script::prelude::ExecOpts;
+use tremor_script::vm::Program;
 use tremor_value::prelude::*;
 
 fn do_run<'script>(bencher: &mut Bencher, invoke_event: &(ImutExpr<'script>, Value<'script>)) {
@@ -43,6 +44,28 @@ fn do_run<'script>(bencher: &mut Bencher, invoke_event: &(ImutExpr<'script>, Val
     });
 }
 
+fn do_run_vm<'script>(bencher: &mut Bencher, program_event: &(Program<'script>, Value<'script>)) {
+    let (program, event) = program_event;
+    let opts = ExecOpts {
+        result_needed: true,
+        aggr: tremor_script::AggrType::Tick,
+    };
+    let env = Env::default();
+    let local_stack = LocalStack::default();
+    bencher.iter(|| {
+        program
+            .run(
+                opts,
+                &env,
+                event,
+                &Value::const_null(),
+                &Value::const_null(),
+                &local_stack,
+            )
+            .expect("program to work");
+    });
+}
+
 fn calculate(c: &mut Criterion) {
     let registry = tremor_script::registry();
 
@@ -71,6 +94,8 @@ fn calculate(c: &mut Criterion) {
                 .expect("Invalid script");
         let first_expr = script.script.exprs.remove(0);
         if let Expr::Imut(expr) = first_expr {
+            let program = (Program::compile(&expr), input.clone());
+            group.bench_with_input(BenchmarkId::new("vm", label), &program, do_run_vm);
             let input = (expr, input);
 
             group.bench_with_input(BenchmarkId::from_parameter(label), &input, do_run);
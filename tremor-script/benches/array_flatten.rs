@@ -20,6 +20,7 @@ use tremor_script::ast::ImutExpr;
 use tremor_script::interpreter::{Env, LocalStack};
 use tremor_script::module::Manager;
 use tremor_script::prelude::ExecOpts;
+use tremor_script::vm::Program;
 use tremor_value::prelude::*;
 
 fn do_array_flatten<'script>(
@@ -47,6 +48,31 @@ fn do_array_flatten<'script>(
     });
 }
 
+fn do_array_flatten_vm<'script>(
+    bencher: &mut Bencher,
+    program_event: &(Program<'script>, Value<'script>),
+) {
+    let (program, event) = program_event;
+    let opts = ExecOpts {
+        result_needed: true,
+        aggr: tremor_script::AggrType::Tick,
+    };
+    let env = Env::default();
+    let local_stack = LocalStack::default();
+    bencher.iter(|| {
+        program
+            .run(
+                opts,
+                &env,
+                event,
+                &Value::const_null(),
+                &Value::const_null(),
+                &local_stack,
+            )
+            .expect("Expected array::flatten call to work");
+    });
+}
+
 fn array_flatten(c: &mut Criterion) {
     let registry = tremor_script::registry();
 
@@ -89,6 +115,8 @@ fn array_flatten(c: &mut Criterion) {
         let first_expr = script.script.exprs.remove(0);
         let invoke = first_expr.as_invoke().expect("No invoke");
         let expr = ImutExpr::Invoke1(invoke.clone());
+        let program = (Program::compile(&expr), input.clone());
+        group.bench_with_input(BenchmarkId::new("vm", label), &program, do_array_flatten_vm);
         let input = (expr, input);
 
         group.bench_with_input(BenchmarkId::from_parameter(label), &input, do_array_flatten);
@@ -83,7 +83,7 @@ impl Arena {
             if e.version == idx.version {
                 e.version += 1;
                 e.src = None;
-                eprintln!("[ARENA] Freed arena index {}", idx);
+                eprintln!("[ARENA] Freed arena index {idx}");
                 Ok(())
             } else {
                 Err("Invalid version to delete".into())
@@ -110,7 +110,7 @@ impl Arena {
                 version: e.version,
             };
             #[cfg(feature = "arena-delete")]
-            eprintln!("[ARENA] Reclaimed arena index {}", idx);
+            eprintln!("[ARENA] Reclaimed arena index {idx}");
             idx
         } else {
             let idx = self.sources.len();
@@ -120,7 +120,7 @@ impl Arena {
             });
             let idx = Index { idx, version: 0 };
             #[cfg(feature = "arena-delete")]
-            eprintln!("[ARENA] Added arena index {}", idx);
+            eprintln!("[ARENA] Added arena index {idx}");
             idx
         }
     }
@@ -172,8 +172,16 @@ impl Arena {
     /// server where we know that we really only parse the script to check for errors and
     /// warnings.
     /// That's also why it's behind a feature falg
+    ///
+    /// # Safety
+    /// Nothing may reference the source after it was freed
+    ///
+    /// # Errors
+    /// if the index was already freed
     #[cfg(feature = "arena-delete")]
     pub unsafe fn delte_index_this_is_really_unsafe_dont_use_it(id: Index) -> Result<()> {
+        // modules loaded from the source must not outlive it
+        crate::ast::module::Manager::delete_arena_index(id)?;
         let mut a = ARENA.write()?;
         a.delte_index_this_is_really_unsafe_dont_use_it_(id)
     }
@@ -60,8 +60,10 @@ use crate::{
     lexer::Span,
     pos::Location,
     prelude::*,
-    registry::{CustomFn, FResult, TremorAggrFnWrapper},
-    stry, Value,
+    registry::{CustomAggr, CustomFn, FResult, TremorAggrFnWrapper},
+    stry,
+    vm::ScriptProgram,
+    Value,
 };
 pub(crate) use analyzer::*;
 pub use base_expr::BaseExpr;
@@ -279,6 +281,9 @@ pub struct Script<'script> {
     #[serde(skip)]
     /// Documentation from the script
     pub docs: docs::Docs,
+    #[serde(skip)]
+    /// Bytecode for the expressions, if the script was compiled
+    pub(crate) program: Option<ScriptProgram<'script>>,
 }
 impl_expr!(Script);
 
@@ -290,11 +295,24 @@ impl Default for Script<'static> {
             docs: docs::Docs::default(),
             mid: NodeMeta::new_box(Location::default(), Location::default()),
             state: None,
+            program: None,
         }
     }
 }
 
 impl<'script> Script<'script> {
+    /// Compiles the expressions of the script to bytecode, from then on
+    /// `run` uses the compiled program
+    pub fn compile(&mut self) {
+        self.program = Some(ScriptProgram::compile(&self.exprs));
+    }
+
+    /// If the script was compiled to bytecode
+    #[must_use]
+    pub fn is_compiled(&self) -> bool {
+        self.program.is_some()
+    }
+
     /// Runs the script and evaluates to a resulting event.
     /// This expects the script to be imutable!
     ///
@@ -347,6 +365,9 @@ impl<'script> Script<'script> {
     where
         'script: 'event,
     {
+        if let Some(program) = &self.program {
+            return program.run(context, aggr, event, state, meta, self.locals);
+        }
         let mut local = LocalStack::with_size(self.locals);
 
         let mut exprs = self.exprs.iter().peekable();
@@ -542,6 +563,18 @@ pub struct FnDefn<'script> {
 }
 impl_expr!(FnDefn);
 
+/// A user defined aggregate function
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AggrDefn {
+    pub(crate) mid: Box<NodeMeta>,
+    /// name of the aggregate
+    pub name: String,
+    /// The aggregate function
+    #[serde(skip)]
+    pub aggr: CustomAggr,
+}
+impl_expr_no_lt!(AggrDefn);
+
 /// A Constant
 #[derive(Clone, Debug, PartialEq, Serialize, Eq)]
 pub struct Const<'script> {
@@ -581,6 +614,8 @@ pub enum Expr<'script> {
     },
     /// A structure comprehension
     Comprehension(Box<Comprehension<'script, Self>>),
+    /// A try / catch expression
+    Try(Box<Try<'script, Self>>),
     /// A drop expression
     Drop {
         /// Id
@@ -677,6 +712,8 @@ pub enum ImutExpr<'script> {
     Match(Box<Match<'script, ImutExpr<'script>>>),
     /// Comprehension
     Comprehension(Box<Comprehension<'script, Self>>),
+    /// Try / catch
+    Try(Box<Try<'script, Self>>),
     /// Merge
     Merge(Box<Merge<'script>>),
     /// Path
@@ -1619,6 +1656,26 @@ pub struct ComprehensionCase<'script, Ex: Expression + 'script> {
 }
 impl_expr_ex!(ComprehensionCase);
 
+#[derive(Clone, Debug, PartialEq, Serialize)]
+/// Encapsulates a try / catch expression
+pub struct Try<'script, Ex: Expression + 'script> {
+    /// Id
+    pub(crate) mid: Box<NodeMeta>,
+    /// Guarded expressions
+    pub exprs: Vec<Ex>,
+    /// Last guarded expression, its value is the value of the try
+    pub last_expr: Ex,
+    /// Name of the error binding
+    pub error_name: Cow<'script, str>,
+    /// Local index of the error binding
+    pub error_idx: usize,
+    /// Expressions of the catch clause
+    pub catch_exprs: Vec<Ex>,
+    /// Last expression of the catch clause
+    pub catch_last_expr: Ex,
+}
+impl_expr_ex!(Try);
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 /// Encapsulates predicate pattern form
 pub enum Pattern<'script> {
@@ -166,6 +166,7 @@ impl<'script> BaseExpr for ImutExpr<'script> {
         match self {
             ImutExpr::Binary(e) => e.meta(),
             ImutExpr::Comprehension(e) => e.meta(),
+            ImutExpr::Try(e) => e.meta(),
             ImutExpr::Invoke(e)
             | ImutExpr::Invoke1(e)
             | ImutExpr::Invoke2(e)
@@ -197,6 +198,7 @@ impl<'script> BaseExpr for Expr<'script> {
             | Expr::AssignMoveLocal { mid, .. }
             | Expr::Drop { mid, .. } => mid,
             Expr::Comprehension(e) => e.meta(),
+            Expr::Try(e) => e.meta(),
             Expr::Emit(e) => e.meta(),
             Expr::Imut(e) => e.meta(),
             Expr::Match(e) => e.meta(),
@@ -253,6 +255,7 @@ impl<'script> BaseExpr for ImutExprRaw<'script> {
         match self {
             ImutExprRaw::Binary(e) => &e.mid,
             ImutExprRaw::Comprehension(e) => &e.mid,
+            ImutExprRaw::Try(e) => &e.mid,
             ImutExprRaw::Invoke(e) => e.meta(),
             ImutExprRaw::List(e) => e.meta(),
             ImutExprRaw::Literal(e) => e.meta(),
@@ -308,6 +311,7 @@ impl<'script> BaseExpr for ExprRaw<'script> {
             ExprRaw::MatchExpr(e) => e.meta(),
             ExprRaw::Assign(e) => e.meta(),
             ExprRaw::Comprehension(e) => e.meta(),
+            ExprRaw::Try(e) => e.meta(),
             ExprRaw::Emit(e) => e.meta(),
             ExprRaw::Imut(e) => e.meta(),
         }
@@ -128,14 +128,17 @@ impl<'script> ConnectorDefinition<'script> {
     pub const METRICS_INTERVAL_S: &'static str = "metrics_interval_s";
     /// param name for reconnct configuration
     pub const RECONNECT: &'static str = "reconnect";
+    /// param name for the sink spill buffer configuration
+    pub const SPILL: &'static str = "spill";
 
-    const AVAILABLE_PARAMS: [&'static str; 6] = [
+    const AVAILABLE_PARAMS: [&'static str; 7] = [
         Self::CODEC,
         Self::CONFIG,
         Self::METRICS_INTERVAL_S,
         Self::POSTPROCESSORS,
         Self::PREPROCESSORS,
         Self::RECONNECT,
+        Self::SPILL,
     ];
 }
 
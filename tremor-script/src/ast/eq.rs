@@ -20,7 +20,7 @@ use super::{
     Expression, Field, ImutExpr, Invocable, Invoke, InvokeAggr, List, Literal, LocalPath, Match,
     Merge, MetadataPath, NodeId, Patch, PatchOperation, Path, Pattern, PredicateClause,
     PredicatePattern, Record, RecordPattern, Recur, ReservedPath, Segment, StatePath,
-    StrLitElement, StringLit, TestExpr, TuplePattern, UnaryExpr,
+    StrLitElement, StringLit, TestExpr, Try, TuplePattern, UnaryExpr,
 };
 
 /// some special kind of equivalence between expressions
@@ -66,7 +66,7 @@ impl<'script> AstEq for ImutExpr<'script> {
     fn ast_eq(&self, other: &Self) -> bool {
         use ImutExpr::{
             Binary, Bytes, Comprehension, Invoke, Invoke1, Invoke2, Invoke3, InvokeAggr, List,
-            Literal, Local, Match, Merge, Patch, Path, Present, Record, Recur, String, Unary,
+            Literal, Local, Match, Merge, Patch, Path, Present, Record, Recur, String, Try, Unary,
         };
         match (self, other) {
             (Record(r1), Record(r2)) => r1.ast_eq(r2),
@@ -76,6 +76,7 @@ impl<'script> AstEq for ImutExpr<'script> {
             (Patch(p1), Patch(p2)) => p1.ast_eq(p2),
             (Match(m1), Match(m2)) => m1.ast_eq(m2),
             (Comprehension(c1), Comprehension(c2)) => c1.ast_eq(c2),
+            (Try(t1), Try(t2)) => t1.ast_eq(t2),
             (Merge(m1), Merge(m2)) => m1.ast_eq(m2),
             (Path(p1), Path(p2)) => p1.ast_eq(p2),
             // special case for `Path`(i.e. `LocalPath`) and `Local`
@@ -406,6 +407,19 @@ where
     }
 }
 
+impl<'script, Ex> AstEq for Try<'script, Ex>
+where
+    Ex: Expression + AstEq + 'script,
+{
+    fn ast_eq(&self, other: &Self) -> bool {
+        self.error_idx == other.error_idx
+            && self.exprs.ast_eq(&other.exprs)
+            && self.last_expr.ast_eq(&other.last_expr)
+            && self.catch_exprs.ast_eq(&other.catch_exprs)
+            && self.catch_last_expr.ast_eq(&other.catch_last_expr)
+    }
+}
+
 impl<'script, Ex> AstEq for ComprehensionCase<'script, Ex>
 where
     Ex: Expression + AstEq + 'script,
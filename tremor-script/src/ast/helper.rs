@@ -18,7 +18,7 @@ use super::{
     query::{OperatorDefinition, PipelineDefinition, ScriptDefinition, WindowDefinition},
     raw::LocalPathRaw,
     warning::{self, Warning, Warnings},
-    ConnectorDefinition, Const, DeployFlow, FlowDefinition, FnDefn, InvokeAggrFn, NodeId,
+    AggrDefn, ConnectorDefinition, Const, DeployFlow, FlowDefinition, FnDefn, InvokeAggrFn, NodeId,
 };
 use crate::{
     errors::Result, module::PreCachedNodes, pos::Span, prelude::*, registry::Aggr as AggrRegistry,
@@ -66,6 +66,9 @@ impl<'script> Scope<'script> {
     pub(crate) fn insert_function(&mut self, f: FnDefn<'script>) -> Result<()> {
         self.content.insert_function(f)
     }
+    pub(crate) fn insert_aggregate(&mut self, a: AggrDefn) -> Result<()> {
+        self.content.insert_aggregate(a)
+    }
     pub(crate) fn insert_pipeline(&mut self, pipeline: PipelineDefinition<'script>) -> Result<()> {
         self.content.insert_pipeline(pipeline)
     }
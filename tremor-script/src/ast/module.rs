@@ -21,10 +21,10 @@ use super::{
     query::raw::{
         OperatorDefinitionRaw, PipelineDefinitionRaw, ScriptDefinitionRaw, WindowDefinitionRaw,
     },
-    raw::{AnyFnRaw, ConstRaw, IdentRaw, UseRaw},
+    raw::{AggrDefnRaw, AnyFnRaw, ConstRaw, IdentRaw, UseRaw},
     upable::Upable,
-    BaseExpr, ConnectorDefinition, Const, FlowDefinition, FnDefn, Helper, NodeId, NodeMeta,
-    OperatorDefinition, PipelineDefinition, ScriptDefinition, WindowDefinition,
+    AggrDefn, BaseExpr, ConnectorDefinition, Const, FlowDefinition, FnDefn, Helper, NodeId,
+    NodeMeta, OperatorDefinition, PipelineDefinition, ScriptDefinition, WindowDefinition,
 };
 use crate::{
     arena::{self, Arena},
@@ -59,6 +59,8 @@ pub enum ModuleStmtRaw<'script> {
     /// we're forced to make this pub because of lalrpop
     FnDefn(AnyFnRaw<'script>),
     /// we're forced to make this pub because of lalrpop
+    Aggregate(AggrDefnRaw<'script>),
+    /// we're forced to make this pub because of lalrpop
     Pipeline(PipelineDefinitionRaw<'script>),
     /// we're forced to make this pub because of lalrpop
     Use(UseRaw),
@@ -76,6 +78,7 @@ impl<'script> BaseExpr for ModuleStmtRaw<'script> {
             ModuleStmtRaw::Connector(e) => e.meta(),
             ModuleStmtRaw::Const(e) => e.meta(),
             ModuleStmtRaw::FnDefn(e) => e.meta(),
+            ModuleStmtRaw::Aggregate(e) => e.meta(),
             ModuleStmtRaw::Pipeline(e) => e.meta(),
             ModuleStmtRaw::Use(e) => e.meta(),
             ModuleStmtRaw::Window(e) => e.meta(),
@@ -122,6 +125,8 @@ pub struct Content<'script> {
     pub consts: NamedEnteties<Const<'script>>,
     /// functions in this module
     pub functions: NamedEnteties<FnDefn<'script>>,
+    /// aggregate functions in this module
+    pub aggregates: NamedEnteties<AggrDefn>,
 }
 
 impl<'script> Debug for Content<'script> {
@@ -135,6 +140,7 @@ impl<'script> Debug for Content<'script> {
             .field("flows", &self.flows.keys())
             .field("consts", &self.consts.keys())
             .field("functions", &self.functions.keys())
+            .field("aggregates", &self.aggregates.keys())
             .finish()
     }
 }
@@ -176,6 +182,15 @@ impl<'script> Content<'script> {
             Err(already_defined_err(&elem, "function"))
         }
     }
+    pub(crate) fn insert_aggregate(&mut self, elem: AggrDefn) -> Result<()> {
+        let name = elem.name.clone();
+        if let Entry::Vacant(e) = self.aggregates.entry(name) {
+            e.insert(elem);
+            Ok(())
+        } else {
+            Err(already_defined_err(&elem, "aggregate"))
+        }
+    }
     pub(crate) fn insert_pipeline(&mut self, elem: PipelineDefinition<'script>) -> Result<()> {
         let name = elem.id.clone();
         if let Entry::Vacant(e) = self.pipelines.entry(name) {
@@ -308,6 +323,10 @@ impl Module {
                     let e = e.up(&mut helper)?;
                     helper.scope.insert_function(e)?;
                 }
+                ModuleStmtRaw::Aggregate(e) => {
+                    let e = e.up(&mut helper)?;
+                    helper.scope.insert_aggregate(e)?;
+                }
                 ModuleStmtRaw::Pipeline(e) => {
                     let e = e.up(&mut helper)?;
                     helper.scope.insert_pipeline(e)?;
@@ -411,6 +430,12 @@ impl<'module> GetMod<FnDefn<'module>> for Content<'module> {
     }
 }
 
+impl<'module> GetMod<AggrDefn> for Content<'module> {
+    fn get(&self, name: &str) -> Option<AggrDefn> {
+        self.aggregates.get(name).cloned()
+    }
+}
+
 impl<'module> GetMod<PipelineDefinition<'module>> for Content<'module> {
     fn get(&self, name: &str) -> Option<PipelineDefinition<'module>> {
         self.pipelines.get(name).cloned()
@@ -655,6 +680,52 @@ mod test {
         Ok(())
     }
     #[test]
+    fn load_aggregate() -> Result<()> {
+        use crate::registry::TremorAggrFn;
+        use tremor_value::Value;
+
+        Manager::add_path(&"./lib")?;
+        Manager::add_path(&"./tests/modules")?;
+        let id = Manager::load(
+            &NodeId {
+                id: "aggregates".to_string(),
+                module: vec![],
+                mid: NodeMeta::dummy(),
+            },
+            &PreCachedNodes::new(),
+        )?;
+        let defn: AggrDefn = Manager::get(id, "spread")?.ok_or("aggregate not found")?;
+        assert_eq!(defn.aggr.arity(), 1..=1);
+
+        let mut a = defn.aggr.clone();
+        let mut b = defn.aggr;
+        a.accumulate(&[&Value::from(3)])
+            .map_err(|e| format!("{e:?}"))?;
+        a.accumulate(&[&Value::from(7)])
+            .map_err(|e| format!("{e:?}"))?;
+        assert_eq!(a.emit().ok(), Some(Value::from(4)));
+        b.accumulate(&[&Value::from(-1)])
+            .map_err(|e| format!("{e:?}"))?;
+        a.merge(&b).map_err(|e| format!("{e:?}"))?;
+        assert_eq!(a.emit().ok(), Some(Value::from(8)));
+        a.init();
+        assert_eq!(a.emit().ok(), Some(Value::from(0)));
+
+        let err = Manager::load(
+            &NodeId {
+                id: "bad_aggregate".to_string(),
+                module: vec![],
+                mid: NodeMeta::dummy(),
+            },
+            &PreCachedNodes::new(),
+        )
+        .err()
+        .map(|e| e.to_string())
+        .unwrap_or_default();
+        assert!(err.contains("Missing aggregate function `merge`"));
+        Ok(())
+    }
+    #[test]
     fn load_from_id() -> Result<()> {
         Manager::add_path(&"./lib")?;
 
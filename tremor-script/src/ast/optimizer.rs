@@ -26,8 +26,8 @@ use crate::ast::{
     MetadataPath, OperatorCreate, OperatorDefinition, Patch, PatchOperation, Path, Pattern,
     PipelineCreate, PipelineDefinition, PredicateClause, PredicatePattern, Query, Record,
     RecordPattern, Recur, ReservedPath, Script, ScriptCreate, ScriptDefinition, Segment, Select,
-    SelectStmt, StatePath, Stmt, StrLitElement, StreamCreate, StringLit, TestExpr, TuplePattern,
-    UnaryExpr, WindowDefinition, WithExpr,
+    SelectStmt, StatePath, Stmt, StrLitElement, StreamCreate, StringLit, TestExpr, Try,
+    TuplePattern, UnaryExpr, WindowDefinition, WithExpr,
 };
 use crate::errors::Result;
 use crate::module::Content;
@@ -42,6 +42,15 @@ struct CombinedVisitor<First, Second> {
     second: Second,
 }
 
+/// Only walks a subtree if both visitors want to walk it
+fn combine(first: VisitRes, second: VisitRes) -> VisitRes {
+    if first == VisitRes::Stop || second == VisitRes::Stop {
+        VisitRes::Stop
+    } else {
+        VisitRes::Walk
+    }
+}
+
 impl<'script, First, Second> ExprWalker<'script> for CombinedVisitor<First, Second>
 where
     First: ExprVisitor<'script> + ImutExprVisitor<'script>,
@@ -101,6 +110,20 @@ where
         Ok(())
     }
 
+    fn visit_try(&mut self, try_expr: &mut Try<'script, Expr<'script>>) -> Result<VisitRes> {
+        let first = ExprVisitor::visit_try(&mut self.first, try_expr)?;
+        let second = ExprVisitor::visit_try(&mut self.second, try_expr)?;
+
+        Ok(combine(first, second))
+    }
+
+    fn leave_try(&mut self, try_expr: &mut Try<'script, Expr<'script>>) -> Result<()> {
+        ExprVisitor::leave_try(&mut self.first, try_expr)?;
+        ExprVisitor::leave_try(&mut self.second, try_expr)?;
+
+        Ok(())
+    }
+
     fn visit_emit(&mut self, emit: &mut EmitExpr<'script>) -> Result<VisitRes> {
         self.first.visit_emit(emit)?;
         self.second.visit_emit(emit)?;
@@ -533,6 +556,20 @@ where
         Ok(())
     }
 
+    fn visit_try(&mut self, try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<VisitRes> {
+        let first = self.first.visit_try(try_expr)?;
+        let second = self.second.visit_try(try_expr)?;
+
+        Ok(combine(first, second))
+    }
+
+    fn leave_try(&mut self, try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<()> {
+        self.first.leave_try(try_expr)?;
+        self.second.leave_try(try_expr)?;
+
+        Ok(())
+    }
+
     fn visit_merge(&mut self, merge: &mut Merge<'script>) -> Result<VisitRes> {
         self.first.visit_merge(merge)?;
         self.second.visit_merge(merge)?;
@@ -1406,3 +1443,25 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{registry, Script};
+
+    use super::*;
+
+    #[test]
+    fn optimizes_inside_try() -> Result<()> {
+        let reg = registry::registry();
+        let script = Script::parse("try [1] + [2] catch e => [] end", &reg)?;
+        let Some(Expr::Try(try_expr)) = script.script.exprs.first() else {
+            panic!("expected a try expression");
+        };
+        // constants aren't folded so errors are raised at runtime, array additions are still optimized
+        assert!(matches!(
+            &try_expr.last_expr,
+            Expr::Imut(ImutExpr::ArrayAppend(_))
+        ));
+        Ok(())
+    }
+}
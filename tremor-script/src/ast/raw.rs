@@ -22,19 +22,23 @@ use crate::ast::optimizer::Optimizer;
 use crate::ast::{BooleanBinExpr, BooleanBinOpKind};
 use crate::{
     ast::{
-        base_expr, query, upable::Upable, ArrayPattern, ArrayPredicatePattern, AssignPattern,
-        BinExpr, BinOpKind, Bytes, BytesPart, ClauseGroup, Comprehension, ComprehensionCase,
-        Costly, DefaultCase, EmitExpr, EventPath, Expr, ExprPath, Expression, Field, FnDefn,
-        Helper, Ident, IfElse, ImutExpr, Invocable, Invoke, InvokeAggr, InvokeAggrFn, List,
-        Literal, LocalPath, Match, Merge, MetadataPath, Patch, PatchOperation, Path, Pattern,
-        PredicateClause, PredicatePattern, Record, RecordPattern, Recur, ReservedPath, Script,
-        Segment, StatePath, StrLitElement, StringLit, TestExpr, TuplePattern, UnaryExpr,
-        UnaryOpKind,
+        base_expr, query, upable::Upable, AggrDefn, ArrayPattern, ArrayPredicatePattern,
+        AssignPattern, BinExpr, BinOpKind, Bytes, BytesPart, ClauseGroup, Comprehension,
+        ComprehensionCase, Costly, DefaultCase, EmitExpr, EventPath, Expr, ExprPath, Expression,
+        Field, FnDefn, Helper, Ident, IfElse, ImutExpr, Invocable, Invoke, InvokeAggr,
+        InvokeAggrFn, List, Literal, LocalPath, Match, Merge, MetadataPath, Patch, PatchOperation,
+        Path, Pattern, PredicateClause, PredicatePattern, Record, RecordPattern, Recur,
+        ReservedPath, Script, Segment, StatePath, StrLitElement, StringLit, TestExpr, Try,
+        TuplePattern, UnaryExpr, UnaryOpKind,
+    },
+    errors::{
+        already_defined_err, err_generic, error_generic, error_missing_effector, Kind as ErrorKind,
+        Result,
     },
-    errors::{err_generic, error_generic, error_missing_effector, Kind as ErrorKind, Result},
     extractor::Extractor,
     impl_expr, impl_expr_exraw, impl_expr_no_lt,
     prelude::*,
+    registry::{CustomAggr, CustomAggrFns, TremorAggrFnWrapper},
 };
 pub use base_expr::BaseExpr;
 use beef::Cow;
@@ -144,6 +148,7 @@ impl<'script> ScriptRaw<'script> {
             state: None,
             locals: helper.locals.len(),
             docs: helper.docs.clone(),
+            program: None,
         })
     }
 }
@@ -501,6 +506,8 @@ pub enum ExprRaw<'script> {
     Assign(Box<AssignRaw<'script>>),
     /// we're forced to make this pub because of lalrpop
     Comprehension(Box<ComprehensionRaw<'script, Self>>),
+    /// we're forced to make this pub because of lalrpop
+    Try(Box<TryRaw<'script, Self>>),
     Drop {
         mid: Box<NodeMeta>,
     },
@@ -583,6 +590,7 @@ impl<'script> Upable<'script> for ExprRaw<'script> {
                 }
             }
             ExprRaw::Comprehension(c) => Expr::Comprehension(Box::new(c.up(helper)?)),
+            ExprRaw::Try(t) => Expr::Try(Box::new(t.up(helper)?)),
             ExprRaw::Drop { mid } => {
                 if !helper.can_emit {
                     return Err(ErrorKind::InvalidDrop(mid.range.expand_lines(2), mid.range).into());
@@ -804,6 +812,110 @@ impl<'script> Upable<'script> for MatchFnDefnRaw<'script> {
     }
 }
 
+/// we're forced to make this pub because of lalrpop
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AggrDefnRaw<'script> {
+    pub(crate) name: IdentRaw<'script>,
+    pub(crate) fns: Vec<AnyFnRaw<'script>>,
+    pub(crate) doc: Option<Vec<Cow<'script, str>>>,
+    pub(crate) mid: Box<NodeMeta>,
+}
+impl_expr!(AggrDefnRaw);
+
+impl<'script> AggrDefnRaw<'script> {
+    pub(crate) fn doc(&self, accumulate: &FnDefn) -> FnDoc {
+        FnDoc {
+            name: self.name.to_string(),
+            // the first argument of `accumulate` is the state
+            args: accumulate
+                .args
+                .iter()
+                .skip(1)
+                .map(ToString::to_string)
+                .collect(),
+            open: accumulate.open,
+            doc: self
+                .doc
+                .clone()
+                .map(|d| d.iter().map(|l| l.trim()).collect::<Vec<_>>().join("\n")),
+        }
+    }
+}
+
+// aggregates can only be defined in modules and those are always `'static`
+impl Upable<'static> for AggrDefnRaw<'static> {
+    type Target = AggrDefn;
+    fn up(mut self, helper: &mut Helper<'static, '_>) -> Result<Self::Target> {
+        let extent = self.extent();
+        // the functions of an aggregate are not functions of the module so we
+        // don't keep their documentation
+        let fn_docs = helper.docs.fns.len();
+        let mut init = None;
+        let mut accumulate = None;
+        let mut merge = None;
+        let mut emit = None;
+        for f in std::mem::take(&mut self.fns) {
+            let f = f.up(helper)?;
+            let (slot, valid_args, expected) = match f.name.as_str() {
+                "init" => (&mut init, f.args.is_empty(), "no arguments"),
+                "accumulate" => (
+                    &mut accumulate,
+                    !f.args.is_empty(),
+                    "the state followed by the arguments of the aggregate",
+                ),
+                "merge" => (&mut merge, f.args.len() == 2, "two states"),
+                "emit" => (&mut emit, f.args.len() == 1, "the state"),
+                other => {
+                    return Err(error_generic(
+                        &extent,
+                        &f,
+                        &format!("Unknown aggregate function `{other}`, only `init`, `accumulate`, `merge` and `emit` can be defined"),
+                    ))
+                }
+            };
+            if slot.is_some() {
+                return Err(already_defined_err(&f, "aggregate function"));
+            }
+            if !valid_args || (f.open && f.name != "accumulate") {
+                return Err(error_generic(
+                    &extent,
+                    &f,
+                    &format!("The aggregate function `{}` takes {expected}", f.name),
+                ));
+            }
+            *slot = Some(f);
+        }
+        helper.docs.fns.truncate(fn_docs);
+
+        let missing = |name| {
+            error_generic(
+                &extent,
+                &extent,
+                &format!("Missing aggregate function `{name}`"),
+            )
+        };
+        let init = init.ok_or_else(|| missing("init"))?;
+        let accumulate = accumulate.ok_or_else(|| missing("accumulate"))?;
+        let merge = merge.ok_or_else(|| missing("merge"))?;
+        let emit = emit.ok_or_else(|| missing("emit"))?;
+        helper.docs.fns.push(self.doc(&accumulate));
+
+        let aggr = CustomAggr::new(CustomAggrFns {
+            name: self.name.id.to_string(),
+            init: init.into(),
+            accumulate: accumulate.into(),
+            merge: merge.into(),
+            emit: emit.into(),
+        })
+        .map_err(|e| e.into_err(&extent, &extent, Some(helper.reg)))?;
+        Ok(AggrDefn {
+            mid: self.mid.box_with_name(&self.name.id),
+            name: self.name.id.to_string(),
+            aggr,
+        })
+    }
+}
+
 /// A raw expression
 pub trait ExpressionRaw<'script>:
     Clone + std::fmt::Debug + PartialEq + Serialize + Upable<'script>
@@ -828,6 +940,8 @@ pub enum ImutExprRaw<'script> {
     /// we're forced to make this pub because of lalrpop
     Comprehension(Box<ComprehensionRaw<'script, Self>>),
     /// we're forced to make this pub because of lalrpop
+    Try(Box<TryRaw<'script, Self>>),
+    /// we're forced to make this pub because of lalrpop
     Path(PathRaw<'script>),
     /// we're forced to make this pub because of lalrpop
     Binary(Box<BinExprRaw<'script>>),
@@ -908,6 +1022,7 @@ impl<'script> Upable<'script> for ImutExprRaw<'script> {
                 ImutExpr::Match(Box::new(m.up(helper)?))
             }
             ImutExprRaw::Comprehension(c) => ImutExpr::Comprehension(Box::new(c.up(helper)?)),
+            ImutExprRaw::Try(t) => ImutExpr::Try(Box::new(t.up(helper)?)),
             ImutExprRaw::Bytes(b) => ImutExpr::Bytes(b.up(helper)?),
         };
         helper.possible_leaf = was_leaf;
@@ -1355,6 +1470,57 @@ pub struct ImutComprehensionCaseRaw<'script> {
     pub(crate) mid: Box<NodeMeta>,
 }
 
+/// we're forced to make this pub because of lalrpop
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TryRaw<'script, Ex>
+where
+    <Ex as Upable<'script>>::Target: Expression + 'script,
+    Ex: ExpressionRaw<'script> + 'script,
+{
+    pub(crate) exprs: Vec<Ex>,
+    pub(crate) error_name: Cow<'script, str>,
+    pub(crate) catch_exprs: Vec<Ex>,
+    pub(crate) mid: Box<NodeMeta>,
+}
+
+impl_expr_exraw!(TryRaw);
+
+impl<'script, Ex> Upable<'script> for TryRaw<'script, Ex>
+where
+    <Ex as Upable<'script>>::Target: Expression + 'script,
+    Ex: ExpressionRaw<'script> + 'script,
+{
+    type Target = Try<'script, Ex::Target>;
+    fn up<'registry>(self, helper: &mut Helper<'script, 'registry>) -> Result<Self::Target> {
+        let span = self.mid.range;
+        let mut exprs = self.exprs.up(helper)?;
+        let last_expr = exprs
+            .pop()
+            .ok_or_else(|| error_missing_effector(&span, &span))?;
+
+        // the error is only visible inside of the catch clause
+        let error_idx = helper.register_shadow_var(&self.error_name);
+        let mut catch_exprs = self.catch_exprs.up(helper)?;
+        if let Some(expr) = catch_exprs.last_mut() {
+            expr.replace_last_shadow_use(error_idx);
+        };
+        helper.end_shadow_var();
+        let catch_last_expr = catch_exprs
+            .pop()
+            .ok_or_else(|| error_missing_effector(&span, &span))?;
+
+        Ok(Try {
+            mid: self.mid,
+            exprs,
+            last_expr,
+            error_name: self.error_name,
+            error_idx,
+            catch_exprs,
+            catch_last_expr,
+        })
+    }
+}
+
 /// we're forced to make this pub because of lalrpop
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum PatternRaw<'script> {
@@ -2315,6 +2481,14 @@ impl<'script> InvokeRaw<'script> {
         if self.module.first() == Some(&String::from("aggr")) && self.module.len() == 2 {
             let module = self.module.get(1).cloned().unwrap_or_default();
             helper.aggr_reg.find(&module, &self.fun).is_ok()
+                || matches!(
+                    helper.get::<AggrDefn>(&NodeId {
+                        id: self.fun.clone(),
+                        module: vec![module],
+                        mid: self.mid.clone(),
+                    }),
+                    Ok(Some(_))
+                )
         } else {
             false
         }
@@ -2348,11 +2522,21 @@ impl<'script> Upable<'script> for InvokeAggrRaw<'script> {
             return Err(ErrorKind::AggrInAggr(self.extent(), self.extent().expand_lines(2)).into());
         };
         helper.is_in_aggr = true;
-        let invocable = helper
-            .aggr_reg
-            .find(&self.module, &self.fun)
-            .map_err(|e| e.into_err(&self, &self, Some(helper.reg)))?
-            .clone();
+        let invocable = match helper.aggr_reg.find(&self.module, &self.fun) {
+            Ok(invocable) => invocable.clone(),
+            Err(e) => {
+                // aggregates defined with `define aggregate` in a used module
+                let node_id = NodeId {
+                    id: self.fun.clone(),
+                    module: vec![self.module.clone()],
+                    mid: self.mid.clone(),
+                };
+                let defn = helper
+                    .get::<AggrDefn>(&node_id)?
+                    .ok_or_else(|| e.into_err(&self, &self, Some(helper.reg)))?;
+                TremorAggrFnWrapper::new(self.module.clone(), self.fun.clone(), Box::new(defn.aggr))
+            }
+        };
         if !invocable.valid_arity(self.args.len()) {
             return Err(ErrorKind::BadArity(
                 self.extent(),
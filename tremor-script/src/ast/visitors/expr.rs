@@ -73,6 +73,22 @@ pub trait Visitor<'script> {
         Ok(())
     }
 
+    /// visit a try expression
+    ///
+    /// # Errors
+    /// if the walker function fails
+    fn visit_try(&mut self, _try_expr: &mut Try<'script, Expr<'script>>) -> Result<VisitRes> {
+        Ok(Walk)
+    }
+
+    /// leave a try expression
+    ///
+    /// # Errors
+    /// if the walker function fails
+    fn leave_try(&mut self, _try_expr: &mut Try<'script, Expr<'script>>) -> Result<()> {
+        Ok(())
+    }
+
     /// visit a `EmitExpr`
     ///
     /// # Errors
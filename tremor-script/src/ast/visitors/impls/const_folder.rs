@@ -32,6 +32,8 @@ use tremor_value::KnownKey;
 pub struct ConstFolder<'run, 'script> {
     /// Function Registry
     pub helper: &'run Helper<'script, 'run>,
+    /// number of `try` expressions we are in, nothing gets folded inside of them
+    try_depth: usize,
 }
 
 fn fake_path(mid: &NodeMeta) -> Path {
@@ -51,12 +53,37 @@ impl<'run, 'script: 'run> ExprWalker<'script> for ConstFolder<'run, 'script> {}
 impl<'run, 'script: 'run> ImutExprWalker<'script> for ConstFolder<'run, 'script> {}
 impl<'run, 'script: 'run> DeployVisitor<'script> for ConstFolder<'run, 'script> {}
 impl<'run, 'script: 'run> QueryVisitor<'script> for ConstFolder<'run, 'script> {}
-impl<'run, 'script: 'run> ExprVisitor<'script> for ConstFolder<'run, 'script> {}
+impl<'run, 'script: 'run> ExprVisitor<'script> for ConstFolder<'run, 'script> {
+    // errors raised while folding have to happen at runtime so they can be caught
+    fn visit_try(&mut self, _try_expr: &mut Try<'script, Expr<'script>>) -> Result<VisitRes> {
+        self.try_depth += 1;
+        Ok(VisitRes::Walk)
+    }
+
+    fn leave_try(&mut self, _try_expr: &mut Try<'script, Expr<'script>>) -> Result<()> {
+        self.try_depth -= 1;
+        Ok(())
+    }
+}
 
 impl<'run, 'script: 'run> ImutExprVisitor<'script> for ConstFolder<'run, 'script> {
+    // errors raised while folding have to happen at runtime so they can be caught
+    fn visit_try(&mut self, _try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<VisitRes> {
+        self.try_depth += 1;
+        Ok(VisitRes::Walk)
+    }
+
+    fn leave_try(&mut self, _try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<()> {
+        self.try_depth -= 1;
+        Ok(())
+    }
+
     #[allow(clippy::too_many_lines, clippy::match_same_arms)]
     fn leave_expr(&mut self, e: &mut ImutExpr<'script>) -> Result<()> {
         use ImutExpr::Literal as Lit;
+        if self.in_try() {
+            return Ok(());
+        }
         let mut buf = Lit(Literal {
             value: Value::const_null(),
             mid: Box::new(e.meta().clone()),
@@ -238,6 +265,7 @@ impl<'run, 'script: 'run> ImutExprVisitor<'script> for ConstFolder<'run, 'script
             | ImutExpr::Patch(_)
             | ImutExpr::Match(_)
             | ImutExpr::Comprehension(_)
+            | ImutExpr::Try(_)
             | ImutExpr::Merge(_)
             | ImutExpr::Local { .. }
             | ImutExpr::Present { .. }
@@ -259,6 +287,9 @@ impl<'run, 'script: 'run> ImutExprVisitor<'script> for ConstFolder<'run, 'script
     /// 2) Element lookups, if the expressionis a literal string, becomes a key lookup
     /// 2) Element lookups, if the expressionis a literal usize, becomes a idx lookup
     fn leave_segment(&mut self, segment: &mut Segment<'script>) -> Result<()> {
+        if self.in_try() {
+            return Ok(());
+        }
         let mut buf = Segment::Idx {
             mid: Box::new(segment.meta().clone()),
             idx: 0,
@@ -310,6 +341,9 @@ impl<'run, 'script: 'run> ImutExprVisitor<'script> for ConstFolder<'run, 'script
     /// Reduce a string element by turning all expressions that hold a literal expression
     /// into literal strings
     fn leave_string_element(&mut self, string: &mut StrLitElement<'script>) -> Result<()> {
+        if self.in_try() {
+            return Ok(());
+        }
         let mut old = StrLitElement::Lit("placeholder".into());
         std::mem::swap(&mut old, string);
         match old {
@@ -364,6 +398,9 @@ impl<'run, 'script: 'run> ImutExprVisitor<'script> for ConstFolder<'run, 'script
     /// Reduce a record by taking all fields that have a constant string as key, and a literal as
     /// value and move them into the base
     fn leave_record(&mut self, record: &mut Record<'script>) -> Result<()> {
+        if self.in_try() {
+            return Ok(());
+        }
         let mut old_fields = Vec::with_capacity(record.fields.len());
         std::mem::swap(&mut old_fields, &mut record.fields);
 
@@ -395,7 +432,14 @@ where
     /// New folder
     #[must_use]
     pub fn new(helper: &'run Helper<'script, '_>) -> Self {
-        ConstFolder { helper }
+        ConstFolder {
+            helper,
+            try_depth: 0,
+        }
+    }
+
+    fn in_try(&self) -> bool {
+        self.try_depth > 0
     }
 }
 
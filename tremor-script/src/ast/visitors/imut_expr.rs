@@ -386,6 +386,22 @@ pub trait Visitor<'script> {
         Ok(())
     }
 
+    /// visit a try expression
+    ///
+    /// # Errors
+    /// if the walker function fails
+    fn visit_try(&mut self, _try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<VisitRes> {
+        Ok(Walk)
+    }
+
+    /// leave a try expression
+    ///
+    /// # Errors
+    /// if the walker function fails
+    fn leave_try(&mut self, _try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<()> {
+        Ok(())
+    }
+
     /// visit a merge expr
     ///
     /// # Errors
@@ -53,6 +53,9 @@ pub trait Walker<'script>: ExprVisitor<'script> + ImutExprWalker<'script> {
             Expr::Comprehension(c) => {
                 Walker::walk_comprehension(self, c.as_mut())?;
             }
+            Expr::Try(t) => {
+                Walker::walk_try(self, t.as_mut())?;
+            }
             Expr::Drop { .. } => {}
             Expr::Emit(e) => {
                 self.walk_emit(e.as_mut())?;
@@ -105,6 +108,26 @@ pub trait Walker<'script>: ExprVisitor<'script> + ImutExprWalker<'script> {
         ExprVisitor::leave_comprehension(self, comp)
     }
 
+    /// walk a try expression
+    ///
+    /// # Errors
+    /// if the walker function fails
+    fn walk_try(&mut self, try_expr: &mut Try<'script, Expr<'script>>) -> Result<()> {
+        stop!(
+            ExprVisitor::visit_try(self, try_expr),
+            ExprVisitor::leave_try(self, try_expr)
+        );
+        for expr in &mut try_expr.exprs {
+            Walker::walk_expr(self, expr)?;
+        }
+        Walker::walk_expr(self, &mut try_expr.last_expr)?;
+        for expr in &mut try_expr.catch_exprs {
+            Walker::walk_expr(self, expr)?;
+        }
+        Walker::walk_expr(self, &mut try_expr.catch_last_expr)?;
+        ExprVisitor::leave_try(self, try_expr)
+    }
+
     /// walk a emit expr
     ///
     /// # Errors
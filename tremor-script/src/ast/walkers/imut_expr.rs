@@ -447,6 +447,23 @@ pub trait Walker<'script>: ImutExprVisitor<'script> {
         self.leave_comprehension(comp)
     }
 
+    /// walk a `Try`
+    ///
+    /// # Errors
+    /// if the walker function fails
+    fn walk_try(&mut self, try_expr: &mut Try<'script, ImutExpr<'script>>) -> Result<()> {
+        stop!(self.visit_try(try_expr), self.leave_try(try_expr));
+        for expr in &mut try_expr.exprs {
+            self.walk_expr(expr)?;
+        }
+        self.walk_expr(&mut try_expr.last_expr)?;
+        for expr in &mut try_expr.catch_exprs {
+            self.walk_expr(expr)?;
+        }
+        self.walk_expr(&mut try_expr.catch_last_expr)?;
+        self.leave_try(try_expr)
+    }
+
     /// walk a `Merge`
     ///
     /// # Errors
@@ -725,6 +742,9 @@ pub trait Walker<'script>: ImutExprVisitor<'script> {
             ImutExpr::Comprehension(comp) => {
                 self.walk_comprehension(comp.as_mut())?;
             }
+            ImutExpr::Try(try_expr) => {
+                self.walk_try(try_expr.as_mut())?;
+            }
             ImutExpr::Merge(merge) => {
                 self.walk_merge(merge.as_mut())?;
             }
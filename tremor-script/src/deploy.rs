@@ -53,6 +53,12 @@ where
     /// server where we know that we really only parse the script to check for errors and
     /// warnings.
     /// That's also why it's behind a feature falg
+    ///
+    /// # Safety
+    /// Nothing may reference the source after it was freed
+    ///
+    /// # Errors
+    /// if the index was already freed
     #[cfg(feature = "arena-delete")]
     pub unsafe fn consume_and_free(self) -> Result<()> {
         let Deploy { aid, deploy, .. } = self;
@@ -133,7 +139,8 @@ where
         S: ToString + ?Sized,
     {
         let (aid, src) = Arena::insert(src)?;
-        Self::parse_(aid, src, reg, aggr_reg).map_err(|e| crate::errors::ErrorWithIndex(aid, e))
+        Self::parse_(aid, src, reg, aggr_reg, &PreCachedNodes::new())
+            .map_err(|e| crate::errors::ErrorWithIndex(aid, e))
     }
 
     /// Parses a string into a deployment
@@ -230,6 +230,108 @@ impl ErrorKind {
     pub(crate) fn aid(&self) -> arena::Index {
         self.expr().0.map(Span::aid).unwrap_or_default()
     }
+    /// The name of the error variant, this is the `kind` of an error bound in a `catch` clause
+    #[allow(clippy::too_many_lines)]
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AccessError { .. } => "AccessError",
+            Self::AggrInAggr { .. } => "AggrInAggr",
+            Self::ArrayOutOfRange { .. } => "ArrayOutOfRange",
+            Self::AssignIntoArray { .. } => "AssignIntoArray",
+            Self::AssignToConst { .. } => "AssignToConst",
+            Self::BadAccessInEvent { .. } => "BadAccessInEvent",
+            Self::BadAccessInGlobal { .. } => "BadAccessInGlobal",
+            Self::BadAccessInLocal { .. } => "BadAccessInLocal",
+            Self::BadAccessInState { .. } => "BadAccessInState",
+            Self::BadArity { .. } => "BadArity",
+            Self::BadArrayIndex { .. } => "BadArrayIndex",
+            Self::BadType { .. } => "BadType",
+            Self::BinaryDrop { .. } => "BinaryDrop",
+            Self::BinaryEmit { .. } => "BinaryEmit",
+            Self::CantSetArgsConst { .. } => "CantSetArgsConst",
+            Self::CantSetGroupConst { .. } => "CantSetGroupConst",
+            Self::CantSetWindowConst { .. } => "CantSetWindowConst",
+            Self::CodecError { .. } => "CodecError",
+            Self::Common { .. } => "Common",
+            Self::CyclicUse { .. } => "CyclicUse",
+            Self::DecreasingRange { .. } => "DecreasingRange",
+            Self::DeployArtefactNotDefined { .. } => "DeployArtefactNotDefined",
+            Self::DeployRequiredArgDoesNotResolve { .. } => "DeployRequiredArgDoesNotResolve",
+            Self::DivisionByZero { .. } => "DivisionByZero",
+            Self::DoubleConst { .. } => "DoubleConst",
+            Self::DoublePipelineCreate { .. } => "DoublePipelineCreate",
+            Self::DoubleStream { .. } => "DoubleStream",
+            Self::EmptyInterpolation { .. } => "EmptyInterpolation",
+            Self::EmptyScript { .. } => "EmptyScript",
+            Self::ExtraToken { .. } => "ExtraToken",
+            Self::FromUtf8Error { .. } => "FromUtf8Error",
+            Self::Generic { .. } => "Generic",
+            Self::Grok { .. } => "Grok",
+            Self::InvalidAssign { .. } => "InvalidAssign",
+            Self::InvalidBinary { .. } => "InvalidBinary",
+            Self::InvalidBinaryBoolean { .. } => "InvalidBinaryBoolean",
+            Self::InvalidBitshift { .. } => "InvalidBitshift",
+            Self::InvalidConst { .. } => "InvalidConst",
+            Self::InvalidDefinitionalWithParam { .. } => "InvalidDefinitionalWithParam",
+            Self::InvalidDrop { .. } => "InvalidDrop",
+            Self::InvalidEmit { .. } => "InvalidEmit",
+            Self::InvalidExtractor { .. } => "InvalidExtractor",
+            Self::InvalidFloatLiteral { .. } => "InvalidFloatLiteral",
+            Self::InvalidFn { .. } => "InvalidFn",
+            Self::InvalidHexLiteral { .. } => "InvalidHexLiteral",
+            Self::InvalidIntLiteral { .. } => "InvalidIntLiteral",
+            Self::InvalidPP { .. } => "InvalidPP",
+            Self::InvalidRecur { .. } => "InvalidRecur",
+            Self::InvalidToken { .. } => "InvalidToken",
+            Self::InvalidUnary { .. } => "InvalidUnary",
+            Self::InvalidUtf8Sequence { .. } => "InvalidUtf8Sequence",
+            Self::Io { .. } => "Io",
+            Self::JsonError { .. } => "JsonError",
+            Self::MergeTypeConflict { .. } => "MergeTypeConflict",
+            Self::MissingEffectors { .. } => "MissingEffectors",
+            Self::MissingFunction { .. } => "MissingFunction",
+            Self::MissingModule { .. } => "MissingModule",
+            Self::ModuleNotFound { .. } => "ModuleNotFound",
+            Self::Msg { .. } => "Msg",
+            Self::NoClauseHit { .. } => "NoClauseHit",
+            Self::NoConstsAllowed { .. } => "NoConstsAllowed",
+            Self::NoEventReferencesAllowed { .. } => "NoEventReferencesAllowed",
+            Self::NoLocalsAllowed { .. } => "NoLocalsAllowed",
+            Self::NoObjectError { .. } => "NoObjectError",
+            Self::NotConstant { .. } => "NotConstant",
+            Self::NotFound { .. } => "NotFound",
+            Self::Oops { .. } => "Oops",
+            Self::Overflow { .. } => "Overflow",
+            Self::ParseIntError { .. } => "ParseIntError",
+            Self::ParserError { .. } => "ParserError",
+            Self::PatchKeyExists { .. } => "PatchKeyExists",
+            Self::PipelineUnknownPort { .. } => "PipelineUnknownPort",
+            Self::QueryNodeDuplicateName { .. } => "QueryNodeDuplicateName",
+            Self::QueryNodeReservedName { .. } => "QueryNodeReservedName",
+            Self::QueryStreamNotDefined { .. } => "QueryStreamNotDefined",
+            Self::RecursionLimit { .. } => "RecursionLimit",
+            Self::RuntimeError { .. } => "RuntimeError",
+            Self::TailingHereDoc { .. } => "TailingHereDoc",
+            Self::TypeConflict { .. } => "TypeConflict",
+            Self::TypeError { .. } => "TypeError",
+            Self::UnexpectedCharacter { .. } => "UnexpectedCharacter",
+            Self::UnexpectedEndOfStream { .. } => "UnexpectedEndOfStream",
+            Self::UnexpectedEscapeCode { .. } => "UnexpectedEscapeCode",
+            Self::UnknownLocal { .. } => "UnknownLocal",
+            Self::UnrecognizedToken { .. } => "UnrecognizedToken",
+            Self::UnterminatedExtractor { .. } => "UnterminatedExtractor",
+            Self::UnterminatedHereDoc { .. } => "UnterminatedHereDoc",
+            Self::UnterminatedIdentLiteral { .. } => "UnterminatedIdentLiteral",
+            Self::UnterminatedInterpolation { .. } => "UnterminatedInterpolation",
+            Self::UnterminatedStringLiteral { .. } => "UnterminatedStringLiteral",
+            Self::UpdateKeyMissing { .. } => "UpdateKeyMissing",
+            Self::Utf8Error { .. } => "Utf8Error",
+            Self::ValueError { .. } => "ValueError",
+            Self::WithParamNoArg { .. } => "WithParamNoArg",
+            Self::__Nonexhaustive { .. } => "Nonexhaustive",
+        }
+    }
     #[allow(clippy::too_many_lines)]
     pub(crate) fn expr(&self) -> ErrorLocation {
         use ErrorKind::{
@@ -473,6 +575,27 @@ impl Error {
         self.0.token()
     }
 
+    /// The error as a record of its `kind`, `message` and `location`, this is what
+    /// the error in a `catch` clause is bound to
+    #[must_use]
+    pub fn to_value(&self) -> Value<'static> {
+        let (outer, inner) = self.context();
+        let location = inner
+            .or(outer)
+            .filter(|span| span.aid() != arena::Index::INVALID)
+            .map_or_else(Value::null, |span| {
+                literal!({
+                    "start": {"line": span.start().line(), "column": span.start().column()},
+                    "end": {"line": span.end().line(), "column": span.end().column()},
+                })
+            });
+        literal!({
+            "kind": self.0.name(),
+            "message": self.to_string(),
+            "location": location,
+        })
+    }
+
     /// If possible locate this error inside the given source.
     /// This is done without highlighting, for this, use an instance of `tremor_script::highlighter::Highlighter`,
     /// but it needs some more shenanigans than this in order to do proper highlighting.
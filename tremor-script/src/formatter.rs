@@ -0,0 +1,634 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The formatter works on the token stream so comments survive formatting.
+//! Line breaks are kept as written, every line is re-indented by the blocks
+//! it is in and the spacing between tokens is normalized. Strings and heredocs
+//! are copied verbatim.
+
+use crate::{
+    arena::{self, Arena},
+    errors::{Error, Result},
+    lexer::{Lexer, Token, TokenSpan},
+    parser::g as grammar,
+};
+
+const INDENT: &str = "  ";
+
+/// Kind of source to format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A tremor script or module
+    Tremor,
+    /// A trickle query
+    Trickle,
+    /// A troy deployment
+    Troy,
+}
+
+/// Formats a source canonically
+///
+/// # Errors
+/// if the source can not be parsed
+pub fn format(src: &str, kind: Kind) -> Result<String> {
+    let (aid, src) = Arena::insert(src)?;
+    let tokens = Lexer::new(src, aid).collect::<Result<Vec<_>>>()?;
+    parse(&tokens, kind)?;
+    let formatted = Formatter::default().format(src, &tokens);
+
+    // formatting must never change anything but whitespace
+    if unchanged(&tokens, &formatted)? {
+        Ok(formatted)
+    } else {
+        Err("formatting would change the meaning of the source".into())
+    }
+}
+
+fn unchanged(tokens: &[TokenSpan], formatted: &str) -> Result<bool> {
+    let reformatted = Lexer::new(formatted, arena::Index::INVALID).collect::<Result<Vec<_>>>()?;
+    Ok(significant(tokens).eq(significant(&reformatted)))
+}
+
+fn parse(tokens: &[TokenSpan<'static>], kind: Kind) -> Result<()> {
+    let filtered = || tokens.iter().filter(|t| !t.value.is_ignorable()).cloned();
+    match kind {
+        // `.tremor` files are either scripts or modules
+        Kind::Tremor => {
+            if let Err(script) = grammar::ScriptParser::new().parse(filtered()) {
+                let script = Error::from(script);
+                grammar::ModuleFileParser::new()
+                    .parse(filtered())
+                    .map_err(|module| furthest(script, module.into()))?;
+            }
+        }
+        Kind::Trickle => {
+            grammar::QueryParser::new().parse(filtered())?;
+        }
+        Kind::Troy => {
+            grammar::DeployParser::new().parse(filtered())?;
+        }
+    }
+    Ok(())
+}
+
+/// The error that made it further into the source, errors without a location
+/// are raised at the end of the input
+fn furthest(a: Error, b: Error) -> Error {
+    let offset = |e: &Error| e.context().1.map_or(usize::MAX, |s| s.start().absolute());
+    if offset(&b) > offset(&a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// The tokens that carry meaning, comments without their trailing whitespace
+fn significant<'input>(
+    tokens: &'input [TokenSpan<'input>],
+) -> impl Iterator<Item = Token<'input>> + 'input {
+    tokens.iter().filter_map(|t| match &t.value {
+        Token::Whitespace(_) | Token::NewLine => None,
+        Token::SingleLineComment(c) => Some(Token::SingleLineComment(c.trim_end())),
+        Token::DocComment(c) => Some(Token::DocComment(c.trim_end())),
+        Token::ModComment(c) => Some(Token::ModComment(c.trim_end())),
+        t => Some(t.clone()),
+    })
+}
+
+/// A token with the source text it was lexed from
+struct Item<'src> {
+    token: &'src Token<'src>,
+    text: &'src str,
+}
+
+/// Splits the tokens into lines, strings and heredocs become a single item
+fn lines<'src>(src: &'src str, tokens: &'src [TokenSpan<'src>]) -> Vec<Vec<Item<'src>>> {
+    /// What we are inside of while copying a string verbatim
+    #[derive(PartialEq)]
+    enum Quote {
+        String,
+        HereDoc,
+        Interpolation,
+        Brace,
+    }
+    let mut lines = vec![Vec::new()];
+    let mut quotes = Vec::new();
+    let mut start = 0;
+    for t in tokens {
+        let (from, to) = (t.span.start().absolute(), t.span.end().absolute());
+        let in_quote = !quotes.is_empty();
+        match &t.value {
+            Token::DQuote if quotes.last() == Some(&Quote::String) => {
+                quotes.pop();
+            }
+            Token::DQuote => quotes.push(Quote::String),
+            Token::HereDocStart => quotes.push(Quote::HereDoc),
+            Token::Interpol => quotes.push(Quote::Interpolation),
+            Token::LBrace | Token::LPatBrace if in_quote => quotes.push(Quote::Brace),
+            Token::HereDocEnd | Token::RBrace if in_quote => {
+                quotes.pop();
+            }
+            _ => (),
+        }
+        let text = match (in_quote, quotes.is_empty()) {
+            (false, true) => match t.value {
+                Token::NewLine => {
+                    lines.push(Vec::new());
+                    continue;
+                }
+                Token::Whitespace(_) => continue,
+                _ => src[from..to].trim_end(),
+            },
+            // a string or heredoc starts
+            (false, false) => {
+                start = from;
+                continue;
+            }
+            // the string or heredoc is complete, its closing quote stands in for it
+            (true, true) => &src[start..to],
+            (true, false) => continue,
+        };
+        if let Some(line) = lines.last_mut() {
+            line.push(Item {
+                token: &t.value,
+                text,
+            });
+        }
+    }
+    lines
+}
+
+/// A block that is closed by `end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Match,
+    For,
+    Fn,
+    Patch,
+    Merge,
+    Try,
+    Flow,
+    Pipeline,
+    Script,
+    Window,
+    Aggregate,
+    /// `create`, `deploy` and `define connector` or `operator` which end with
+    /// either `end` or `;`
+    Statement,
+    /// A `select` with its projection on the next line, ends with `;`
+    Select,
+}
+
+impl Block {
+    /// The position of a keyword that starts a new section of the block,
+    /// sections can only appear in this order
+    fn section(self, t: &Token) -> Option<usize> {
+        match (self, t) {
+            (Block::Match | Block::For | Block::Patch | Block::Merge, Token::Of)
+            | (Block::Fn, Token::With | Token::Of)
+            | (Block::Try, Token::Catch)
+            | (Block::Pipeline, Token::From)
+            | (Block::Flow | Block::Script | Block::Statement, Token::Args)
+            | (Block::Window, Token::With)
+            | (
+                Block::Select,
+                Token::From | Token::Where | Token::Group | Token::Into | Token::Having,
+            ) => Some(0),
+            (Block::For | Block::Pipeline, Token::Into)
+            | (Block::Flow, Token::Flow)
+            | (Block::Script | Block::Window, Token::State)
+            | (Block::Statement, Token::With) => Some(1),
+            (Block::Pipeline, Token::Args) | (Block::Script | Block::Window, Token::Script) => {
+                Some(2)
+            }
+            (Block::Pipeline, Token::Pipeline) => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Blocks with `case` clauses
+    fn has_cases(self) -> bool {
+        matches!(self, Block::Match | Block::For | Block::Fn)
+    }
+}
+
+/// Everything that indents the lines inside of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    /// `(` and `[` as well as their pattern forms
+    Bracket,
+    /// `{` and `%{`
+    Brace,
+    /// `<<` and `>>` of a binary literal
+    Bytes,
+    /// A block and the section it is in
+    Block(Block, usize),
+    /// A `case` of a block
+    Case,
+}
+
+/// What a token is for deciding on the spacing around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Value,
+    Open,
+    Close,
+    /// `.` and `::`
+    Join,
+    /// `/` between a stream, connector or pipeline and its port
+    Port,
+    /// unary operators
+    Prefix,
+    Comma,
+    Colon,
+    Semi,
+    Operator,
+    Keyword,
+    Comment,
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    frames: Vec<Frame>,
+    /// the last two tokens, as far as we need to look back on them
+    prev: [Option<Token<'static>>; 2],
+    /// class of the last token on the current line
+    class: Option<Class>,
+    /// we are in a `connect` statement
+    connect: bool,
+    /// a blank line was skipped
+    blank: bool,
+}
+
+impl Formatter {
+    fn format(mut self, src: &str, tokens: &[TokenSpan]) -> String {
+        let lines = lines(src, tokens);
+        for (i, line) in lines.iter().enumerate() {
+            let Some((first, rest)) = line.split_first() else {
+                self.blank = !self.out.is_empty();
+                continue;
+            };
+            if std::mem::take(&mut self.blank) {
+                self.out.push('\n');
+            }
+            let next = |j: usize| {
+                line.get(j)
+                    .or_else(|| lines.iter().skip(i + 1).flatten().next())
+                    .map(|item| item.token)
+            };
+            self.class = None;
+            let class = self.classify(first.token);
+            let indent = self.close(first.token).unwrap_or(self.frames.len());
+            for _ in 0..indent {
+                self.out.push_str(INDENT);
+            }
+            // the last token before a trailing comment
+            let eol = |j: usize| {
+                line[j + 1..]
+                    .iter()
+                    .all(|item| matches!(item.token, Token::SingleLineComment(_)))
+            };
+            self.push(first, class, next(1), eol(0));
+            for (j, item) in rest.iter().enumerate().map(|(j, item)| (j + 1, item)) {
+                let class = self.classify(item.token);
+                self.close(item.token);
+                self.push(item, class, next(j + 1), eol(j));
+            }
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn block(&self) -> Option<(usize, Block, usize)> {
+        self.frames
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, f)| match f {
+                Frame::Case => None,
+                Frame::Block(block, section) => Some(Some((i, *block, *section))),
+                _ => Some(None),
+            })
+            .flatten()
+    }
+
+    /// Pops the frames a token closes, returns the indentation of a section keyword
+    fn close(&mut self, t: &Token) -> Option<usize> {
+        match t {
+            Token::RParen | Token::RBracket | Token::RBrace => {
+                while let Some(f) = self.frames.pop() {
+                    if matches!(f, Frame::Bracket | Frame::Brace) {
+                        break;
+                    }
+                }
+            }
+            Token::RBitShiftSigned if self.frames.last() == Some(&Frame::Bytes) => {
+                self.frames.pop();
+            }
+            Token::End => {
+                while let Some(f) = self.frames.pop() {
+                    if matches!(f, Frame::Block(..)) {
+                        break;
+                    }
+                }
+            }
+            Token::Case | Token::Default if self.frames.last() == Some(&Frame::Case) => {
+                self.frames.pop();
+            }
+            Token::Semi
+                if matches!(
+                    self.frames.last(),
+                    Some(Frame::Block(Block::Statement | Block::Select, _))
+                ) =>
+            {
+                self.frames.pop();
+            }
+            // the name of a definition is never a section
+            _ if matches!(self.prev[1], Some(Token::Define | Token::Fun)) => (),
+            t => {
+                let (i, block, section) = self.block()?;
+                let s = block.section(t).filter(|s| *s >= section)?;
+                self.frames.truncate(i);
+                self.frames.push(Frame::Block(block, s));
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Pushes the frames a token opens
+    fn open(&mut self, t: &Token, class: Class, next: Option<&Token>, eol: bool) {
+        if self.prev[1] == Some(Token::Fun) {
+            // function names can be keywords, `fn merge(...)` in aggregates
+            return;
+        }
+        let block = match t {
+            Token::LParen | Token::LBracket | Token::LPatParen | Token::LPatBracket => {
+                return self.frames.push(Frame::Bracket);
+            }
+            Token::LBrace | Token::LPatBrace => return self.frames.push(Frame::Brace),
+            Token::LBitShift if class == Class::Open => return self.frames.push(Frame::Bytes),
+            Token::Case | Token::Default => {
+                if self.block().is_some_and(|(_, b, _)| b.has_cases()) {
+                    self.frames.push(Frame::Case);
+                }
+                return;
+            }
+            Token::Match => Block::Match,
+            Token::For => Block::For,
+            Token::Patch => Block::Patch,
+            Token::Merge => Block::Merge,
+            Token::Try => Block::Try,
+            Token::Fun if self.prev[1] != Some(Token::Intrinsic) => Block::Fn,
+            Token::Create | Token::Deploy => Block::Statement,
+            Token::Select if eol => Block::Select,
+            Token::Define => match next {
+                Some(Token::Flow) => Block::Flow,
+                Some(Token::Pipeline) => Block::Pipeline,
+                Some(Token::Script) => Block::Script,
+                Some(Token::Window) => Block::Window,
+                Some(Token::Aggregate) => Block::Aggregate,
+                _ => Block::Statement,
+            },
+            _ => return,
+        };
+        self.frames.push(Frame::Block(block, 0));
+    }
+
+    fn classify(&self, t: &Token) -> Class {
+        let after_value = matches!(self.class, Some(Class::Value | Class::Close));
+        match t {
+            Token::SingleLineComment(_) | Token::DocComment(_) | Token::ModComment(_) => {
+                Class::Comment
+            }
+            Token::LParen
+            | Token::LBracket
+            | Token::LBrace
+            | Token::LPatParen
+            | Token::LPatBracket
+            | Token::LPatBrace => Class::Open,
+            _ if self.prev[1] == Some(Token::Fun) => Class::Value,
+            Token::LBitShift if !after_value => Class::Open,
+            Token::RParen | Token::RBracket | Token::RBrace => Class::Close,
+            Token::RBitShiftSigned if self.frames.last() == Some(&Frame::Bytes) => Class::Close,
+            Token::Dot | Token::ColonColon => Class::Join,
+            Token::Div
+                if self.connect || matches!(self.prev[0], Some(Token::From | Token::Into)) =>
+            {
+                Class::Port
+            }
+            Token::Sub | Token::Add if !after_value => Class::Prefix,
+            Token::BitNot => Class::Prefix,
+            Token::Comma => Class::Comma,
+            Token::Colon => Class::Colon,
+            Token::Semi => Class::Semi,
+            Token::Ident(..)
+            | Token::Nil
+            | Token::BoolLiteral(_)
+            | Token::IntLiteral(_)
+            | Token::FloatLiteral(..)
+            | Token::TestLiteral(..)
+            | Token::DQuote
+            | Token::HereDocEnd
+            | Token::DontCare
+            | Token::Dollar
+            | Token::Event
+            | Token::State
+            | Token::Args
+            | Token::Group
+            | Token::Window => Class::Value,
+            t if t.is_keyword() || matches!(t, Token::Not | Token::ConfigDirective) => {
+                Class::Keyword
+            }
+            _ => Class::Operator,
+        }
+    }
+
+    /// Whether there is a space between the last token and `t`
+    fn space(&self, t: &Token, class: Class) -> bool {
+        let Some(prev) = self.class else {
+            return false;
+        };
+        if self.frames.last() == Some(&Frame::Bytes) {
+            return prev == Class::Comma;
+        }
+        match (prev, class) {
+            (_, Class::Comment) | (Class::Comma | Class::Semi, _) => true,
+            (Class::Open | Class::Join | Class::Prefix | Class::Port, _)
+            | (_, Class::Join | Class::Comma | Class::Semi | Class::Close | Class::Colon) => false,
+            (Class::Colon, _) => matches!(self.frames.last(), Some(Frame::Brace)),
+            (_, Class::Port) => matches!(self.prev[1], Some(Token::Connect | Token::To)),
+            (_, Class::Open) => match (&self.prev[1], t) {
+                // invocations and indexing
+                (
+                    Some(Token::Ident(..) | Token::Recur | Token::Set | Token::Each),
+                    Token::LParen,
+                )
+                | (Some(Token::Dollar), Token::LBracket) => false,
+                (_, Token::LBracket) => !matches!(prev, Class::Value | Class::Close),
+                _ => true,
+            },
+            (Class::Value, Class::Value) => !matches!(
+                (&self.prev[1], t),
+                (Some(Token::Dollar), Token::Ident(..)) | (_, Token::TestLiteral(..))
+            ),
+            _ => true,
+        }
+    }
+
+    fn push(&mut self, item: &Item, class: Class, next: Option<&Token>, eol: bool) {
+        if self.space(item.token, class) {
+            self.out.push(' ');
+        }
+        self.out.push_str(item.text);
+        self.open(item.token, class, next, eol);
+        if class != Class::Comment {
+            match item.token {
+                Token::Connect => self.connect = true,
+                Token::Semi => self.connect = false,
+                _ => (),
+            }
+            let unit = if self.prev[1] == Some(Token::Fun) {
+                Some(Token::Ident("".into(), false))
+            } else {
+                unit(item.token)
+            };
+            let [_, last] = &self.prev;
+            self.prev = [last.clone(), unit];
+        }
+        self.class = Some(class);
+    }
+}
+
+/// The tokens we look back on, without the data they carry
+fn unit(t: &Token) -> Option<Token<'static>> {
+    Some(match t {
+        Token::Ident(..) => Token::Ident("".into(), false),
+        Token::Dollar => Token::Dollar,
+        Token::Define => Token::Define,
+        Token::Fun => Token::Fun,
+        Token::Intrinsic => Token::Intrinsic,
+        Token::From => Token::From,
+        Token::Into => Token::Into,
+        Token::Recur => Token::Recur,
+        Token::Set => Token::Set,
+        Token::Each => Token::Each,
+        Token::Connect => Token::Connect,
+        Token::To => Token::To,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(src: &str, kind: Kind) -> String {
+        format(src, kind).expect("failed to format")
+    }
+
+    #[test]
+    fn scripts() {
+        assert_eq!(
+            "let a = [1, 2, {\"b\": -3}];\nlet $m = a[0] + event.x.y;\n\nstring::len(\"snot #{ a }\")\n",
+            fmt(
+                "  let a=[ 1,2,{ \"b\" :- 3 } ] ;\nlet $m=a [0]+event . x.y ;\n\n\n\nstring :: len ( \"snot #{ a }\" )",
+                Kind::Tremor
+            )
+        );
+        assert_eq!(
+            "match event of\n  case %{a == 1} =>\n    let x = 1;\n    x\n  case _ => drop\nend\n",
+            fmt(
+                "match event of\ncase %{ a == 1 } =>\nlet x = 1;\n      x\n   case _ => drop\nend",
+                Kind::Tremor
+            )
+        );
+        assert_eq!(
+            "<<event.a:8/signed, 1:4>>\n",
+            fmt("<< event.a : 8 / signed,1:4 >>", Kind::Tremor)
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let src = "### module docs\n\n## function docs   \nfn snot(a, ...) with # trailing\n# leading\na\n   end;\n";
+        assert_eq!(
+            "### module docs\n\n## function docs\nfn snot(a, ...) with # trailing\n  # leading\n  a\nend;\n",
+            fmt(src, Kind::Tremor)
+        );
+    }
+
+    #[test]
+    fn heredocs() {
+        let src = "let a = \"\"\"\n    keep   this\n  \"\"\";\n  a";
+        assert_eq!(
+            "let a = \"\"\"\n    keep   this\n  \"\"\";\na\n",
+            fmt(src, Kind::Tremor)
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let src = "define window w from tumbling\nwith\nsize = 2\nend;\nselect aggr::win::first(event) from in[w] into out/err;";
+        assert_eq!(
+            "define window w from tumbling\nwith\n  size = 2\nend;\nselect aggr::win::first(event) from in[w] into out/err;\n",
+            fmt(src, Kind::Trickle)
+        );
+        let src = "select\n{\"a\": event}\nfrom in\nwhere event.a\ninto out;";
+        assert_eq!(
+            "select\n  {\"a\": event}\nfrom in\nwhere event.a\ninto out;\n",
+            fmt(src, Kind::Trickle)
+        );
+    }
+
+    #[test]
+    fn deploys() {
+        let src = "define flow main\nflow\ndefine connector c from metronome\nwith\nconfig = {\"interval\": 1}\nend;\ncreate connector c;\nconnect /connector/c/out to /pipeline/p ;\nend;\ndeploy flow main;";
+        assert_eq!(
+            "define flow main\nflow\n  define connector c from metronome\n  with\n    config = {\"interval\": 1}\n  end;\n  create connector c;\n  connect /connector/c/out to /pipeline/p;\nend;\ndeploy flow main;\n",
+            fmt(src, Kind::Troy)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(format("let = 1", Kind::Tremor).is_err());
+        assert!(format("select from", Kind::Trickle).is_err());
+    }
+
+    #[test]
+    fn stdlib() -> Result<()> {
+        // formatting is idempotent and keeps the meaning of every file in the standard library
+        for entry in walkdir("lib")? {
+            let src = std::fs::read_to_string(&entry)?;
+            let formatted = format(&src, Kind::Tremor)?;
+            assert_eq!(formatted, format(&formatted, Kind::Tremor)?, "{entry}");
+        }
+        Ok(())
+    }
+
+    fn walkdir(dir: &str) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                files.extend(walkdir(&path.to_string_lossy())?);
+            } else if path.extension().is_some_and(|e| e == "tremor") {
+                files.push(path.to_string_lossy().to_string());
+            }
+        }
+        Ok(files)
+    }
+}
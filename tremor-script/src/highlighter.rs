@@ -126,6 +126,88 @@ impl From<&Warning> for Error {
     }
 }
 
+/// Syntactic class of a token, this decides how a token is highlighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// Keywords such as `match` or `define`
+    Keyword,
+    /// Operators and delimiters
+    Operator,
+    /// Non string literals such as numbers, booleans or `null`
+    Literal,
+    /// String literals
+    String,
+    /// Regular comments
+    Comment,
+    /// Documentation comments
+    Doc,
+    /// Identifiers
+    Ident,
+    /// Tokens the lexer could not make sense of
+    Bad,
+    /// Everything else, like whitespace
+    Other,
+}
+
+impl Class {
+    /// Classifies a token
+    #[must_use]
+    pub fn of(token: &Token) -> Self {
+        match token {
+            t if t.is_keyword() => Self::Keyword,
+            t if t.is_operator() || t.is_symbol() => Self::Operator,
+            t if t.is_literal() && !t.is_string_like() => Self::Literal,
+            Token::TestLiteral(_, _) | Token::StringLiteral(_) | Token::HereDocLiteral(_) => {
+                Self::String
+            }
+            Token::SingleLineComment(_) => Self::Comment,
+            Token::DocComment(_) | Token::ModComment(_) => Self::Doc,
+            Token::Ident(_, _) => Self::Ident,
+            Token::Bad(_) => Self::Bad,
+            _ => Self::Other,
+        }
+    }
+
+    fn color_spec(self) -> ColorSpec {
+        let mut c = ColorSpec::new();
+        match self {
+            Self::Keyword => {
+                c.set_bold(true)
+                    .set_intense(true)
+                    .set_fg(Some(Color::Green));
+            }
+            Self::Operator => {
+                c.set_bold(true)
+                    .set_intense(true)
+                    .set_fg(Some(Color::White));
+            }
+            Self::Literal => {
+                c.set_intense(true).set_fg(Some(Color::Red));
+            }
+            Self::String => {
+                c.set_intense(true).set_fg(Some(Color::Magenta));
+            }
+            Self::Comment => {
+                c.set_intense(true).set_fg(Some(Color::Blue));
+            }
+            Self::Doc => {
+                c.set_intense(true).set_fg(Some(Color::Cyan));
+            }
+            Self::Ident => {
+                c.set_intense(true).set_fg(Some(Color::Yellow));
+            }
+            Self::Bad => {
+                c.set_bold(true)
+                    .set_intense(true)
+                    .set_bg(Some(Color::Red))
+                    .set_fg(Some(Color::White));
+            }
+            Self::Other => (), // Just an empty spec
+        }
+        c
+    }
+}
+
 /// Highlighter trait for generalising over different output types
 pub trait Highlighter {
     /// Writer for the highligher to write to
@@ -415,41 +497,7 @@ pub trait Highlighter {
                 self.write_line_prefix(line_prefix, line, emit_linenos)?;
             }
             let x = t;
-            let mut c = ColorSpec::new();
-            if x.value.is_keyword() {
-                c.set_bold(true)
-                    .set_intense(true)
-                    .set_fg(Some(Color::Green));
-            }
-            if x.value.is_operator() || x.value.is_symbol() {
-                c.set_bold(true)
-                    .set_intense(true)
-                    .set_fg(Some(Color::White));
-            }
-            if x.value.is_literal() && !x.value.is_string_like() {
-                c.set_intense(true).set_fg(Some(Color::Red));
-            }
-            match &x.value {
-                Token::SingleLineComment(_) => {
-                    c.set_intense(true).set_fg(Some(Color::Blue));
-                }
-                Token::DocComment(_) => {
-                    c.set_intense(true).set_fg(Some(Color::Cyan));
-                }
-                Token::TestLiteral(_, _) | Token::StringLiteral(_) | Token::HereDocLiteral(_) => {
-                    c.set_intense(true).set_fg(Some(Color::Magenta));
-                }
-                Token::Bad(_) => {
-                    c.set_bold(true)
-                        .set_intense(true)
-                        .set_bg(Some(Color::Red))
-                        .set_fg(Some(Color::White));
-                }
-                Token::Ident(_, _) => {
-                    c.set_intense(true).set_fg(Some(Color::Yellow));
-                }
-                _other => (), // Just an empty spec
-            }
+            let mut c = Class::of(&x.value).color_spec();
             self.set_color(&mut c)?;
             match &x.value {
                 Token::HereDocStart => {
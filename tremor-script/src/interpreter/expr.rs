@@ -31,7 +31,7 @@ use crate::{
 use crate::{
     ast::{
         ClauseGroup, ClausePreCondition, Comprehension, DefaultCase, EmitExpr, EventPath, Expr,
-        IfElse, ImutExpr, Match, Path, Segment,
+        IfElse, ImutExpr, Match, Path, Segment, Try,
     },
     errors::error_oops_err,
 };
@@ -95,6 +95,52 @@ impl<'script> Expr<'script> {
         )))
     }
 
+    fn try_expr<'run, 'event>(
+        &'run self,
+        opts: ExecOpts,
+        env: &'run Env<'run, 'event>,
+        event: &'run mut Value<'event>,
+        state: &'run mut Value<'static>,
+        meta: &'run mut Value<'event>,
+        local: &'run mut LocalStack<'event>,
+        expr: &'run Try<Expr<'event>>,
+    ) -> Result<Cont<'run, 'event>> {
+        // changes made before the error are kept, only the value of the try is replaced
+        let error = match Expr::execute_effectors(
+            opts,
+            env,
+            event,
+            state,
+            meta,
+            local,
+            &expr.exprs,
+            &expr.last_expr,
+        ) {
+            // the result is owned so the borrow of the event ends here
+            Ok(Cont::Cont(v)) => return Ok(Cont::Cont(Cow::Owned(v.into_owned()))),
+            Ok(Cont::Emit(v, p)) => return Ok(Cont::Emit(v, p)),
+            Ok(Cont::Drop) => return Ok(Cont::Drop),
+            Ok(Cont::EmitEvent(p)) => return Ok(Cont::EmitEvent(p)),
+            Err(e) => e,
+        };
+        stry!(set_local_shadow(
+            self,
+            local,
+            expr.error_idx,
+            error.to_value()
+        ));
+        Expr::execute_effectors(
+            opts,
+            env,
+            event,
+            state,
+            meta,
+            local,
+            &expr.catch_exprs,
+            &expr.catch_last_expr,
+        )
+    }
+
     #[inline]
     #[allow(clippy::too_many_lines)]
     fn match_expr<'run, 'event>(
@@ -477,7 +523,7 @@ impl<'script> Expr<'script> {
     }
 
     #[inline]
-    fn assign<'run, 'event>(
+    pub(crate) fn assign<'run, 'event>(
         &'run self,
         opts: ExecOpts,
         env: &'run Env<'run, 'event>,
@@ -714,6 +760,7 @@ impl<'script> Expr<'script> {
             Expr::Comprehension(ref expr) => {
                 self.comprehension(opts, env, event, state, meta, local, expr)
             }
+            Expr::Try(ref expr) => self.try_expr(opts, env, event, state, meta, local, expr),
             Expr::Imut(expr) => {
                 // If we don't need the result of a immutable value then we
                 // don't need to evaluate it.
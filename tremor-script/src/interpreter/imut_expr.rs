@@ -16,7 +16,7 @@ use crate::{
     ast::{
         binary::extend_bytes_from_value, BinExpr, Comprehension, ExprPath, ImutExpr, Invoke,
         InvokeAggr, Literal, LocalPath, Match, Merge, Patch, Path, Recur, ReservedPath, Segment,
-        UnaryExpr,
+        Try, UnaryExpr,
     },
     errors::Kind as ErrorKind,
     errors::{
@@ -255,6 +255,7 @@ impl<'script> ImutExpr<'script> {
             ImutExpr::Comprehension(ref expr) => {
                 self.comprehension(opts, env, event, state, meta, local, expr)
             }
+            ImutExpr::Try(ref expr) => self.try_expr(opts, env, event, state, meta, local, expr),
             ImutExpr::ArrayAppend(ArrayAppend {
                 left,
                 right,
@@ -517,6 +518,29 @@ impl<'script> ImutExpr<'script> {
         effector.run(opts, env, event, state, meta, local)
     }
 
+    fn try_expr<'run, 'event>(
+        &'run self,
+        opts: ExecOpts,
+        env: &'run Env<'run, 'event>,
+        event: &'run Value<'event>,
+        state: &'run Value<'static>,
+        meta: &'run Value<'event>,
+        local: &'run LocalStack<'event>,
+        expr: &'run Try<'event, ImutExpr<'event>>,
+    ) -> Result<Cow<'run, Value<'event>>>
+    where
+        'script: 'event,
+    {
+        match expr.last_expr.run(opts, env, event, state, meta, local) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                stry!(set_local_shadow(self, local, expr.error_idx, e.to_value()));
+                expr.catch_last_expr
+                    .run(opts, env, event, state, meta, local)
+            }
+        }
+    }
+
     #[allow(clippy::too_many_lines)]
     fn match_expr<'run, 'event>(
         &'run self,
@@ -99,6 +99,8 @@ pub(crate) fn ident_to_token(ident: &str) -> Token {
         "copy" => Token::Copy,
         "merge" => Token::Merge,
         "for" => Token::For,
+        "try" => Token::Try,
+        "catch" => Token::Catch,
         "event" => Token::Event,
         "state" => Token::State,
         "present" => Token::Present,
@@ -124,6 +126,7 @@ pub(crate) fn ident_to_token(ident: &str) -> Token {
         "tumbling" => Token::Tumbling,
         "sliding" => Token::Sliding,
         "window" => Token::Window,
+        "aggregate" => Token::Aggregate,
         "stream" => Token::Stream,
         "operator" => Token::Operator,
         "script" => Token::Script,
@@ -234,6 +237,10 @@ pub enum Token<'input> {
     Emit,
     /// the `for` keyword
     For,
+    /// the `try` keyword
+    Try,
+    /// the `catch` keyword
+    Catch,
     /// the `event` keyword
     Event,
     /// the `state` keyword
@@ -370,6 +377,8 @@ pub enum Token<'input> {
     Sliding,
     /// The `window` keyword
     Window,
+    /// The `aggregate` keyword
+    Aggregate,
     /// The `stream` keyword
     Stream,
     /// The `operator` keyword
@@ -419,9 +428,11 @@ impl<'input> Token<'input> {
         matches!(
             *self,
             Token::Absent
+                | Token::Aggregate
                 | Token::Args
                 | Token::By
                 | Token::Case
+                | Token::Catch
                 | Token::Const
                 | Token::Copy
                 | Token::Create
@@ -459,6 +470,7 @@ impl<'input> Token<'input> {
                 | Token::Sliding
                 | Token::State
                 | Token::Stream
+                | Token::Try
                 | Token::Tumbling
                 | Token::Update
                 | Token::Upsert
@@ -653,6 +665,8 @@ impl<'input> fmt::Display for Token<'input> {
             Token::Copy => write!(f, "copy"),
             Token::Merge => write!(f, "merge"),
             Token::For => write!(f, "for"),
+            Token::Try => write!(f, "try"),
+            Token::Catch => write!(f, "catch"),
             Token::Event => write!(f, "event"),
             Token::State => write!(f, "state"),
             Token::Present => write!(f, "present"),
@@ -732,6 +746,7 @@ impl<'input> fmt::Display for Token<'input> {
             Token::Tumbling => write!(f, "tumbling"),
             Token::Sliding => write!(f, "sliding"),
             Token::Window => write!(f, "window"),
+            Token::Aggregate => write!(f, "aggregate"),
             Token::Stream => write!(f, "stream"),
             Token::Operator => write!(f, "operator"),
             Token::Script => write!(f, "script"),
@@ -45,6 +45,8 @@ pub mod docs;
 /// Errors
 pub mod errors;
 mod extractor;
+/// Source formatter for tremor, trickle and troy
+pub mod formatter;
 /// Grok implementation
 pub mod grok;
 /// Tremor Script highlighter
@@ -71,6 +73,8 @@ pub mod srs;
 mod std_lib;
 /// Utility functions
 pub mod utils;
+/// Bytecode compiler and virtual machine
+pub mod vm;
 pub use srs::{EventPayload, ValueAndMeta};
 
 pub use crate::ast::deploy::raw::run_script;
@@ -89,7 +93,7 @@ use ast::{Consts, InvokeAggrFn};
 pub use interpreter::{AggrType, FALSE, NULL, TRUE};
 use lazy_static::lazy_static;
 use std::sync::{
-    atomic::{AtomicU32, Ordering},
+    atomic::{AtomicBool, AtomicU32, Ordering},
     RwLock,
 };
 use tremor_common::stry;
@@ -97,6 +101,8 @@ use tremor_value::{Object, Value};
 
 /// Default recursion limit
 pub static RECURSION_LIMIT: AtomicU32 = AtomicU32::new(1024);
+/// Compile scripts and select statements to bytecode
+pub static COMPILE_SCRIPTS: AtomicBool = AtomicBool::new(false);
 /// No aggregates
 pub const NO_AGGRS: [InvokeAggrFn<'static>; 0] = [];
 
@@ -106,6 +112,12 @@ pub fn recursion_limit() -> u32 {
     RECURSION_LIMIT.load(Ordering::Relaxed)
 }
 
+/// if scripts and select statements should be compiled to bytecode
+#[inline]
+pub fn compile_scripts() -> bool {
+    COMPILE_SCRIPTS.load(Ordering::Relaxed)
+}
+
 lazy_static! {
     /// No Constants
     pub static ref NO_CONSTS: Consts<'static> = Consts::new();
@@ -236,6 +248,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_catch() {
+        eval!("try 1 + 1 catch e => 0 end", Value::from(2));
+        eval!(
+            r#"let a = try 1 + "snot" catch e => "fallback" end; a"#,
+            Value::from("fallback")
+        );
+        eval!(
+            r#"try 1 + "snot" catch e => e.kind end"#,
+            Value::from("InvalidBinary")
+        );
+        eval!(
+            r#"try 1 + "snot" catch e => [present e.message, e.location.start.column] end"#,
+            literal!([true, 5])
+        );
+        eval!(
+            "try try 1 / 0 catch e => e.snot end catch e => e.kind end",
+            Value::from("BadAccessInLocal")
+        );
+        eval_event!(
+            "try let event.a = 1; 1 / 0 catch e => let event.b = e.kind; null end",
+            literal!({"a": 1, "b": "DivisionByZero"})
+        );
+        // `try` and `catch` are keywords, as identifiers they need to be escaped
+        eval!("let `try` = 1; `try` + 1", Value::from(2));
+        eval!(r#"let a = {"catch": 1}; a.`catch`"#, Value::from(1));
+    }
+
     #[test]
     fn test_present() {
         eval!(r"let t = {}; present t", Value::from(true));
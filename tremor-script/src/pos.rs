@@ -219,7 +219,9 @@ impl Span {
         self.end = self.end.move_down_lines(lines);
         self
     }
-    pub(crate) fn aid(self) -> arena::Index {
+    /// arena index of the source the span points into
+    #[must_use]
+    pub fn aid(self) -> arena::Index {
         self.start.aid
     }
     /// start of the span
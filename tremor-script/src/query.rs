@@ -44,6 +44,12 @@ where
     /// server where we know that we really only parse the script to check for errors and
     /// warnings.
     /// That's also why it's behind a feature falg
+    ///
+    /// # Safety
+    /// Nothing may reference the source after it was freed
+    ///
+    /// # Errors
+    /// if the index was already freed
     #[cfg(feature = "arena-delete")]
     pub unsafe fn consume_and_free(self) -> Result<()> {
         let Query { aid, query, .. } = self;
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod custom_aggr;
 mod custom_fn;
+pub use self::custom_aggr::CustomAggr;
+pub(crate) use self::custom_aggr::CustomAggrFns;
 pub use self::custom_fn::CustomFn;
 pub(crate) use self::custom_fn::{RECUR_PTR, RECUR_REF};
 use crate::{
@@ -320,6 +323,11 @@ impl TremorFnWrapper {
     pub fn name(&self) -> &str {
         self.name.as_str()
     }
+    /// name of the module the function is in
+    #[must_use]
+    pub fn module(&self) -> &str {
+        self.module.as_str()
+    }
     /// Creates a new wrapper
     #[must_use]
     pub fn new(module: String, name: String, fun: Box<dyn TremorFn>) -> Self {
@@ -799,6 +807,11 @@ impl Registry {
     pub fn find_module(&self, module: &str) -> Option<&HashMap<String, TremorFnWrapper>> {
         self.functions.get(module)
     }
+
+    /// Iterates over all functions in the registry
+    pub fn functions(&self) -> impl Iterator<Item = &TremorFnWrapper> {
+        self.functions.values().flat_map(HashMap::values)
+    }
 }
 
 /// Wrapper around an aggregate function
@@ -1234,6 +1247,17 @@ mod tests {
 
         assert!(res.is_err());
     }
+    #[test]
+    pub fn functions() {
+        let registry = registry();
+        assert!(registry
+            .functions()
+            .any(|f| f.module() == "string" && f.name() == "len"));
+        assert!(registry
+            .functions()
+            .all(|f| registry.find(f.module(), f.name()).is_ok()));
+    }
+
     #[test]
     pub fn nested_module_path_fns() -> Result<()> {
         let mut registry = registry();
@@ -0,0 +1,127 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{mfa, CustomFn, FResult, FunctionError, TremorAggrFn};
+use crate::interpreter::Env;
+use crate::prelude::*;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// The functions making up a user defined aggregate, they are shared between
+/// all instances of the aggregate
+#[derive(Debug, PartialEq)]
+pub(crate) struct CustomAggrFns {
+    /// name of the aggregate
+    pub(crate) name: String,
+    pub(crate) init: CustomFn<'static>,
+    pub(crate) accumulate: CustomFn<'static>,
+    pub(crate) merge: CustomFn<'static>,
+    pub(crate) emit: CustomFn<'static>,
+}
+
+/// An aggregate function defined in tremor-script with `define aggregate`
+///
+/// The state of the aggregate is a value that is created by `init`, passed as
+/// the first argument to `accumulate`, `merge` and `emit` and replaced by the
+/// result of `accumulate` and `merge`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomAggr {
+    fns: Arc<CustomAggrFns>,
+    state: Value<'static>,
+    /// true if the last `init` failed
+    uninitialized: bool,
+}
+
+impl CustomAggr {
+    /// Creates the aggregate and runs `init` once to create its initial state
+    pub(crate) fn new(fns: CustomAggrFns) -> FResult<Self> {
+        let state = fns.init.invoke(&Env::default(), &[])?.into_static();
+        Ok(Self {
+            fns: Arc::new(fns),
+            state,
+            uninitialized: false,
+        })
+    }
+
+    /// `TremorAggrFn::init` can't return errors, so if it failed the next call
+    /// that can runs `init` again and returns its error
+    fn ensure_init(&mut self) -> FResult<()> {
+        if self.uninitialized {
+            self.state = self.fns.init.invoke(&Env::default(), &[])?.into_static();
+            self.uninitialized = false;
+        }
+        Ok(())
+    }
+}
+
+impl TremorAggrFn for CustomAggr {
+    fn accumulate(&mut self, args: &[&Value]) -> FResult<()> {
+        self.ensure_init()?;
+        let mut fn_args: Vec<&Value> = Vec::with_capacity(args.len() + 1);
+        fn_args.push(&self.state);
+        fn_args.extend_from_slice(args);
+        let state = self.fns.accumulate.invoke(&Env::default(), &fn_args)?;
+        self.state = state.into_static();
+        Ok(())
+    }
+
+    fn emit<'event>(&mut self) -> FResult<Value<'event>> {
+        self.ensure_init()?;
+        self.fns.emit.invoke(&Env::default(), &[&self.state])
+    }
+
+    fn init(&mut self) {
+        if let Ok(state) = self.fns.init.invoke(&Env::default(), &[]) {
+            self.state = state.into_static();
+            self.uninitialized = false;
+        } else {
+            self.state = Value::null();
+            self.uninitialized = true;
+        }
+    }
+
+    fn merge(&mut self, src: &dyn TremorAggrFn) -> FResult<()> {
+        let other = src
+            .downcast_ref::<Self>()
+            .ok_or_else(|| FunctionError::BadType {
+                mfa: mfa(&self.fns.name, "merge", 2),
+            })?;
+        self.ensure_init()?;
+        let other_state = if other.uninitialized {
+            self.fns.init.invoke(&Env::default(), &[])?.into_static()
+        } else {
+            other.state.clone()
+        };
+        let state = self
+            .fns
+            .merge
+            .invoke(&Env::default(), &[&self.state, &other_state])?;
+        self.state = state.into_static();
+        Ok(())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn TremorAggrFn> {
+        Box::new(self.clone())
+    }
+
+    fn arity(&self) -> RangeInclusive<usize> {
+        // the first argument of `accumulate` is the state of the aggregate
+        let argc = self.fns.accumulate.args.len().saturating_sub(1);
+        if self.fns.accumulate.open {
+            argc..=usize::MAX
+        } else {
+            argc..=argc
+        }
+    }
+}
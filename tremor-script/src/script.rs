@@ -75,6 +75,12 @@ impl Script {
     /// server where we know that we really only parse the script to check for errors and
     /// warnings.
     /// That's also why it's behind a feature falg
+    ///
+    /// # Safety
+    /// Nothing may reference the source after it was freed
+    ///
+    /// # Errors
+    /// if the index was already freed
     #[cfg(feature = "arena-delete")]
     pub unsafe fn consume_and_free(self) -> Result<()> {
         let Script { aid, script, .. } = self;
@@ -141,6 +147,14 @@ impl Script {
         })
     }
 
+    /// Compiles the script and all its named scripts to bytecode
+    pub fn compile(&mut self) {
+        self.script.compile();
+        for script in self.named.values_mut() {
+            script.compile();
+        }
+    }
+
     /// Returns the documentation for the script
     #[must_use]
     pub fn docs(&self) -> &Docs {
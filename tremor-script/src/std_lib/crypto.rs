@@ -0,0 +1,167 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::prelude::*;
+use crate::{tremor_const_fn, tremor_fn_};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// strings are hashed as their UTF-8 bytes
+fn bytes<'v>(value: &'v Value) -> Option<&'v [u8]> {
+    match value {
+        Value::String(s) => Some(s.as_bytes()),
+        Value::Bytes(b) => Some(b),
+        _ => None,
+    }
+}
+
+fn digest(algorithm: &str, data: &[u8]) -> Option<Vec<u8>> {
+    Some(match algorithm {
+        "md5" => Md5::digest(data).to_vec(),
+        "sha1" => Sha1::digest(data).to_vec(),
+        "sha256" => Sha256::digest(data).to_vec(),
+        "sha384" => Sha384::digest(data).to_vec(),
+        "sha512" => Sha512::digest(data).to_vec(),
+        "crc32" => crc32fast::hash(data).to_be_bytes().to_vec(),
+        "xxh64" => xxhash_rust::xxh64::xxh64(data, 0).to_be_bytes().to_vec(),
+        "xxh3" => xxhash_rust::xxh3::xxh3_64(data).to_be_bytes().to_vec(),
+        _ => return None,
+    })
+}
+
+fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key).ok()?;
+    mac.update(data);
+    Some(mac.finalize().into_bytes().to_vec())
+}
+
+fn hmac(algorithm: &str, key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    match algorithm {
+        "md5" => mac::<Hmac<Md5>>(key, data),
+        "sha1" => mac::<Hmac<Sha1>>(key, data),
+        "sha256" => mac::<Hmac<Sha256>>(key, data),
+        "sha384" => mac::<Hmac<Sha384>>(key, data),
+        "sha512" => mac::<Hmac<Sha512>>(key, data),
+        _ => None,
+    }
+}
+
+pub fn load(registry: &mut Registry) {
+    registry
+        .insert(tremor_const_fn! (crypto|hash(_context, _algorithm, _data) {
+            let (Some(algorithm), Some(data)) = (_algorithm.as_str(), bytes(_data)) else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            digest(algorithm, data)
+                .map(|d| Value::from(hex::encode(d)))
+                .ok_or_else(|| to_runtime_error(format!("unknown hash algorithm `{algorithm}`")))
+        }))
+        .insert(tremor_const_fn! (crypto|hash_bytes(_context, _algorithm, _data) {
+            let (Some(algorithm), Some(data)) = (_algorithm.as_str(), bytes(_data)) else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            digest(algorithm, data)
+                .map(|d| Value::Bytes(d.into()))
+                .ok_or_else(|| to_runtime_error(format!("unknown hash algorithm `{algorithm}`")))
+        }))
+        .insert(tremor_const_fn! (crypto|hmac(_context, _algorithm, _key, _data) {
+            let (Some(algorithm), Some(key), Some(data)) = (_algorithm.as_str(), bytes(_key), bytes(_data)) else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            hmac(algorithm, key, data)
+                .map(|d| Value::from(hex::encode(d)))
+                .ok_or_else(|| to_runtime_error(format!("unknown hmac algorithm `{algorithm}`")))
+        }))
+        .insert(tremor_const_fn! (crypto|hmac_bytes(_context, _algorithm, _key, _data) {
+            let (Some(algorithm), Some(key), Some(data)) = (_algorithm.as_str(), bytes(_key), bytes(_data)) else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            hmac(algorithm, key, data)
+                .map(|d| Value::Bytes(d.into()))
+                .ok_or_else(|| to_runtime_error(format!("unknown hmac algorithm `{algorithm}`")))
+        }));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::registry::fun;
+    use crate::Value;
+
+    #[test]
+    fn hash() {
+        let f = fun("crypto", "hash");
+        let snot = Value::from("snot");
+        assert_val!(
+            f(&[&Value::from("sha256"), &Value::from("")]),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_val!(
+            f(&[&Value::from("md5"), &Value::from("")]),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert_val!(
+            f(&[&Value::from("sha1"), &Value::from("abc")]),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_val!(
+            f(&[&Value::from("crc32"), &Value::from("123456789")]),
+            "cbf43926"
+        );
+        assert_val!(
+            f(&[&Value::from("xxh64"), &Value::from("")]),
+            "ef46db3751d8e999"
+        );
+        // strings and binaries with the same content hash the same
+        assert_eq!(
+            f(&[&Value::from("sha512"), &snot]),
+            f(&[
+                &Value::from("sha512"),
+                &Value::Bytes(b"snot".to_vec().into())
+            ])
+        );
+        assert!(f(&[&Value::from("snot"), &snot]).is_err());
+        assert!(f(&[&Value::from("sha256"), &Value::from(1)]).is_err());
+    }
+
+    #[test]
+    fn hash_bytes() {
+        let f = fun("crypto", "hash_bytes");
+        assert_val!(
+            f(&[&Value::from("crc32"), &Value::from("123456789")]),
+            Value::Bytes(vec![0xcb, 0xf4, 0x39, 0x26].into())
+        );
+    }
+
+    #[test]
+    fn hmac() {
+        let f = fun("crypto", "hmac");
+        let key = Value::from("key");
+        let data = Value::from("The quick brown fox jumps over the lazy dog");
+        assert_val!(
+            f(&[&Value::from("sha256"), &key, &data]),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert_val!(
+            f(&[&Value::from("md5"), &key, &data]),
+            "80070713463e7749b90c2dc24911e275"
+        );
+        assert!(f(&[&Value::from("crc32"), &key, &data]).is_err());
+
+        let f = fun("crypto", "hmac_bytes");
+        let r = f(&[&Value::from("sha512"), &key, &data]);
+        assert!(matches!(r, Ok(Value::Bytes(b)) if b.len() == 64));
+    }
+}
@@ -0,0 +1,342 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::prelude::*;
+use crate::tremor_fn;
+use maxminddb::{MaxMindDBError, Mmap, Reader};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// how often we check if a database file changed
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+struct Database {
+    reader: Arc<Reader<Mmap>>,
+    modified: Option<SystemTime>,
+    checked: Instant,
+}
+
+impl Database {
+    fn open(path: &str) -> Result<Self, String> {
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("unable to open `{path}`: {e}"))?;
+        let reader =
+            Reader::open_mmap(path).map_err(|e| format!("unable to open `{path}`: {e}"))?;
+        Ok(Self {
+            reader: Arc::new(reader),
+            modified: Some(modified),
+            checked: Instant::now(),
+        })
+    }
+
+    fn changed(&mut self, path: &str) -> bool {
+        if self.checked.elapsed() < RELOAD_INTERVAL {
+            return false;
+        }
+        self.checked = Instant::now();
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        modified.is_some() && modified != self.modified
+    }
+}
+
+lazy_static::lazy_static! {
+    // databases are memory mapped once per runtime and shared between all scripts
+    static ref DATABASES: Mutex<HashMap<String, Database>> = Mutex::new(HashMap::new());
+}
+
+fn reader(path: &str) -> Result<Arc<Reader<Mmap>>, String> {
+    let mut dbs = DATABASES
+        .lock()
+        .map_err(|_| "geoip database lock poisoned".to_string())?;
+    if let Some(db) = dbs.get_mut(path) {
+        if db.changed(path) {
+            // keep serving the old database if the new one can't be opened (yet)
+            if let Ok(new) = Database::open(path) {
+                *db = new;
+            }
+        }
+        Ok(db.reader.clone())
+    } else {
+        let db = Database::open(path)?;
+        let reader = db.reader.clone();
+        dbs.insert(path.to_string(), db);
+        Ok(reader)
+    }
+}
+
+/// Looks up `ip` in the database at `path`, returns `None` if the address is not in it
+fn lookup(path: &str, ip: &str) -> Result<Option<Value<'static>>, String> {
+    let ip = IpAddr::from_str(ip).map_err(|e| format!("invalid ip `{ip}`: {e}"))?;
+    let reader = reader(path)?;
+    reader
+        .lookup::<Value>(ip)
+        .map(|v| Some(v.into_static()))
+        .or_else(|e| match e {
+            MaxMindDBError::AddressNotFoundError(_) => Ok(None),
+            e => Err(e.to_string()),
+        })
+}
+
+/// The english name of an entity in a geoip record
+fn name<'v>(record: &Value<'v>, key: &str) -> Value<'v> {
+    record
+        .get(key)
+        .and_then(|e| e.get("names"))
+        .and_then(|names| names.get("en"))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn field<'v>(record: &Value<'v>, key: &str, field: &str) -> Value<'v> {
+    record
+        .get(key)
+        .and_then(|e| e.get(field))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn city(record: &Value<'static>) -> Value<'static> {
+    let subdivision = record
+        .get("subdivisions")
+        .and_then(|s| s.get_idx(0))
+        .and_then(|s| s.get("names"))
+        .and_then(|names| names.get("en"))
+        .cloned()
+        .unwrap_or_default();
+    literal!({
+        "continent": {
+            "code": field(record, "continent", "code"),
+            "name": name(record, "continent"),
+        },
+        "country": {
+            "iso_code": field(record, "country", "iso_code"),
+            "name": name(record, "country"),
+        },
+        "subdivision": subdivision,
+        "city": name(record, "city"),
+        "postal_code": field(record, "postal", "code"),
+        "location": {
+            "latitude": field(record, "location", "latitude"),
+            "longitude": field(record, "location", "longitude"),
+            "accuracy_radius": field(record, "location", "accuracy_radius"),
+            "time_zone": field(record, "location", "time_zone"),
+        }
+    })
+}
+
+fn asn(record: &Value<'static>) -> Value<'static> {
+    literal!({
+        "number": record.get("autonomous_system_number").cloned().unwrap_or_default(),
+        "organization": record.get("autonomous_system_organization").cloned().unwrap_or_default(),
+    })
+}
+
+pub fn load(registry: &mut Registry) {
+    registry
+        .insert(tremor_fn! (geoip|lookup(_context, _db: String, _ip: String) {
+            lookup(_db, _ip).map(Option::unwrap_or_default).map_err(to_runtime_error)
+        }))
+        .insert(tremor_fn! (geoip|city(_context, _db: String, _ip: String) {
+            lookup(_db, _ip).map(|r| r.as_ref().map(city).unwrap_or_default()).map_err(to_runtime_error)
+        }))
+        .insert(tremor_fn! (geoip|asn(_context, _db: String, _ip: String) {
+            lookup(_db, _ip).map(|r| r.as_ref().map(asn).unwrap_or_default()).map_err(to_runtime_error)
+        }));
+}
+
+#[cfg(test)]
+mod test {
+    // ALLOW: the test databases only contain small strings, maps and arrays
+    #![allow(clippy::cast_possible_truncation)]
+
+    use crate::registry::fun;
+    use crate::Value;
+    use std::io::Write;
+    use tremor_value::literal;
+
+    // A minimal writer for the MaxMind DB format, just enough to create test databases
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut res = if s.len() < 29 {
+            vec![0x40 | s.len() as u8]
+        } else {
+            vec![0x40 | 29, (s.len() - 29) as u8]
+        };
+        res.extend_from_slice(s.as_bytes());
+        res
+    }
+
+    fn uint16(n: u16) -> Vec<u8> {
+        let mut res = vec![0xa0 | 2];
+        res.extend_from_slice(&n.to_be_bytes());
+        res
+    }
+
+    fn uint32(n: u32) -> Vec<u8> {
+        let mut res = vec![0xc0 | 4];
+        res.extend_from_slice(&n.to_be_bytes());
+        res
+    }
+
+    fn uint64(n: u64) -> Vec<u8> {
+        let mut res = vec![8, 9 - 7];
+        res.extend_from_slice(&n.to_be_bytes());
+        res
+    }
+
+    fn double(f: f64) -> Vec<u8> {
+        let mut res = vec![0x60 | 8];
+        res.extend_from_slice(&f.to_be_bytes());
+        res
+    }
+
+    fn array(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut res = vec![items.len() as u8, 11 - 7];
+        items.iter().for_each(|i| res.extend_from_slice(i));
+        res
+    }
+
+    fn map(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut res = vec![0xe0 | entries.len() as u8];
+        for (k, v) in entries {
+            res.extend(string(k));
+            res.extend_from_slice(v);
+        }
+        res
+    }
+
+    /// an IPv4 database with a single record for `1.2.3.0/24`
+    fn database(record: &[u8]) -> std::io::Result<tempfile::NamedTempFile> {
+        let network = u32::from_be_bytes([1, 2, 3, 0]);
+        let node_count: u32 = 24;
+        let not_found = node_count;
+        let data = node_count + 16;
+        let mut tree = Vec::new();
+        for i in 0..node_count {
+            let next = if i + 1 < node_count { i + 1 } else { data };
+            let records = if (network >> (31 - i)) & 1 == 0 {
+                [next, not_found]
+            } else {
+                [not_found, next]
+            };
+            for r in records {
+                tree.extend_from_slice(&r.to_be_bytes()[1..]);
+            }
+        }
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(&tree)?;
+        file.write_all(&[0; 16])?;
+        file.write_all(record)?;
+        file.write_all(b"\xab\xcd\xefMaxMind.com")?;
+        file.write_all(&map(&[
+            ("binary_format_major_version", uint16(2)),
+            ("binary_format_minor_version", uint16(0)),
+            ("build_epoch", uint64(1_700_000_000)),
+            ("database_type", string("Tremor-Test")),
+            (
+                "description",
+                map(&[("en", string("tremor test database"))]),
+            ),
+            ("ip_version", uint16(4)),
+            ("languages", array(&[string("en")])),
+            ("node_count", uint32(node_count)),
+            ("record_size", uint16(24)),
+        ]))?;
+        file.flush()?;
+        Ok(file)
+    }
+
+    fn names(name: &str) -> Vec<u8> {
+        map(&[("names", map(&[("en", string(name))]))])
+    }
+
+    #[test]
+    fn city() -> std::io::Result<()> {
+        let db = database(&map(&[
+            ("city", names("Linköping")),
+            (
+                "country",
+                map(&[
+                    ("iso_code", string("SE")),
+                    ("names", map(&[("en", string("Sweden"))])),
+                ]),
+            ),
+            (
+                "location",
+                map(&[
+                    ("latitude", double(58.4167)),
+                    ("longitude", double(15.6167)),
+                    ("accuracy_radius", uint16(76)),
+                    ("time_zone", string("Europe/Stockholm")),
+                ]),
+            ),
+            ("subdivisions", array(&[names("Östergötland County")])),
+        ]))?;
+        let path = Value::from(db.path().to_string_lossy().to_string());
+        let f = fun("geoip", "city");
+        assert_val!(
+            f(&[&path, &Value::from("1.2.3.4")]),
+            literal!({
+                "continent": {"code": null, "name": null},
+                "country": {"iso_code": "SE", "name": "Sweden"},
+                "subdivision": "Östergötland County",
+                "city": "Linköping",
+                "postal_code": null,
+                "location": {
+                    "latitude": 58.4167,
+                    "longitude": 15.6167,
+                    "accuracy_radius": 76,
+                    "time_zone": "Europe/Stockholm"
+                }
+            })
+        );
+        assert_val!(f(&[&path, &Value::from("8.8.8.8")]), Value::const_null());
+        assert!(f(&[&path, &Value::from("snot")]).is_err());
+        assert!(f(&[
+            &Value::from("/does/not/exist.mmdb"),
+            &Value::from("1.2.3.4")
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn asn() -> std::io::Result<()> {
+        let db = database(&map(&[
+            ("autonomous_system_number", uint32(64_512)),
+            ("autonomous_system_organization", string("Tremor Networks")),
+        ]))?;
+        let path = Value::from(db.path().to_string_lossy().to_string());
+        let f = fun("geoip", "asn");
+        assert_val!(
+            f(&[&path, &Value::from("1.2.3.255")]),
+            literal!({"number": 64_512, "organization": "Tremor Networks"})
+        );
+        let f = fun("geoip", "lookup");
+        assert_val!(
+            f(&[&path, &Value::from("1.2.3.1")]),
+            literal!({
+                "autonomous_system_number": 64_512,
+                "autonomous_system_organization": "Tremor Networks"
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::prelude::*;
+use crate::{tremor_const_fn, tremor_fn_};
+use cidr_utils::{
+    cidr::IpInet,
+    combiner::{Ipv4CidrCombiner, Ipv6CidrCombiner},
+};
+use std::{
+    cell::RefCell,
+    net::{IpAddr, Ipv4Addr},
+    str::FromStr,
+};
+
+/// A set of CIDRs to test addresses against
+struct Ranges {
+    rules: Vec<String>,
+    v4: Ipv4CidrCombiner,
+    v6: Ipv6CidrCombiner,
+}
+
+impl Ranges {
+    fn new(rules: Vec<String>) -> Result<Self, String> {
+        let mut v4 = Ipv4CidrCombiner::new();
+        let mut v6 = Ipv6CidrCombiner::new();
+        for rule in &rules {
+            match IpInet::from_str(rule).map(|inet| inet.network()) {
+                Ok(cidr_utils::cidr::IpCidr::V4(cidr)) => v4.push(cidr),
+                Ok(cidr_utils::cidr::IpCidr::V6(cidr)) => v6.push(cidr),
+                Err(_) => return Err(format!("invalid CIDR `{rule}`")),
+            }
+        }
+        Ok(Self { rules, v4, v6 })
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => self.v4.contains(ip),
+            IpAddr::V6(ip) => self.v6.contains(ip),
+        }
+    }
+}
+
+thread_local! {
+    // CIDR lists are usually constant in a script, so we keep the last one around instead
+    // of parsing it for every event
+    static RANGES: RefCell<Option<Ranges>> = const { RefCell::new(None) };
+}
+
+fn in_cidr(ip: &IpAddr, rules: &[&str]) -> Result<bool, String> {
+    RANGES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let cached = cache.as_ref().is_some_and(|ranges| {
+            ranges
+                .rules
+                .iter()
+                .map(String::as_str)
+                .eq(rules.iter().copied())
+        });
+        if !cached {
+            *cache = Some(Ranges::new(
+                rules.iter().map(ToString::to_string).collect(),
+            )?);
+        }
+        Ok(cache.as_ref().is_some_and(|ranges| ranges.contains(ip)))
+    })
+}
+
+fn is_private(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private(),
+        // unique local addresses, fc00::/7
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xfe00) == 0xfc00,
+    }
+}
+
+fn version(ip: &IpAddr) -> u8 {
+    if ip.is_ipv4() {
+        4
+    } else {
+        6
+    }
+}
+
+pub fn load(registry: &mut Registry) {
+    registry
+        .insert(tremor_const_fn! (net|is_ip(_context, _input: String) {
+            Ok(Value::from(IpAddr::from_str(_input).is_ok()))
+        }))
+        .insert(tremor_const_fn! (net|is_ipv4(_context, _input: String) {
+            Ok(Value::from(IpAddr::from_str(_input).is_ok_and(|ip| ip.is_ipv4())))
+        }))
+        .insert(tremor_const_fn! (net|is_ipv6(_context, _input: String) {
+            Ok(Value::from(IpAddr::from_str(_input).is_ok_and(|ip| ip.is_ipv6())))
+        }))
+        .insert(tremor_const_fn! (net|parse_ip(_context, _input: String) {
+            let ip = IpAddr::from_str(_input).map_err(to_runtime_error)?;
+            Ok(literal!({
+                "address": ip.to_string(),
+                "version": version(&ip),
+            }))
+        }))
+        .insert(tremor_const_fn! (net|parse_cidr(_context, _input: String) {
+            let inet = IpInet::from_str(_input).map_err(to_runtime_error)?;
+            Ok(literal!({
+                "network": inet.first_address().to_string(),
+                "broadcast": inet.last_address().to_string(),
+                "netmask": inet.mask().to_string(),
+                "prefix_len": inet.network_length(),
+                "version": version(&inet.address()),
+            }))
+        }))
+        .insert(tremor_const_fn! (net|network(_context, _input: String) {
+            let inet = IpInet::from_str(_input).map_err(to_runtime_error)?;
+            Ok(Value::from(inet.first_address().to_string()))
+        }))
+        .insert(tremor_const_fn! (net|broadcast(_context, _input: String) {
+            let inet = IpInet::from_str(_input).map_err(to_runtime_error)?;
+            Ok(Value::from(inet.last_address().to_string()))
+        }))
+        .insert(tremor_const_fn! (net|in_cidr(_context, _ip, _cidrs) {
+            let Some(ip) = _ip.as_str() else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            let rules: Vec<&str> = if let Some(rule) = _cidrs.as_str() {
+                vec![rule]
+            } else if let Some(rules) = _cidrs.as_array() {
+                rules.iter().map(ValueAsScalar::as_str).collect::<Option<_>>().ok_or_else(|| FunctionError::BadType{mfa: this_mfa()})?
+            } else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            let ip = IpAddr::from_str(ip).map_err(to_runtime_error)?;
+            in_cidr(&ip, &rules).map(Value::from).map_err(to_runtime_error)
+        }))
+        .insert(tremor_const_fn! (net|is_private(_context, _input: String) {
+            let ip = IpAddr::from_str(_input).map_err(to_runtime_error)?;
+            Ok(Value::from(is_private(&ip)))
+        }))
+        .insert(tremor_const_fn! (net|is_loopback(_context, _input: String) {
+            let ip = IpAddr::from_str(_input).map_err(to_runtime_error)?;
+            Ok(Value::from(ip.is_loopback()))
+        }))
+        .insert(tremor_const_fn! (net|is_multicast(_context, _input: String) {
+            let ip = IpAddr::from_str(_input).map_err(to_runtime_error)?;
+            Ok(Value::from(ip.is_multicast()))
+        }))
+        .insert(tremor_const_fn! (net|to_integer(_context, _input: String) {
+            match IpAddr::from_str(_input).map_err(to_runtime_error)? {
+                IpAddr::V4(ip) => Ok(Value::from(u32::from(ip))),
+                IpAddr::V6(_) => Err(to_runtime_error("IPv6 addresses do not fit into an integer")),
+            }
+        }))
+        .insert(tremor_const_fn! (net|from_integer(_context, _input) {
+            let ip = _input.as_u32().ok_or_else(|| FunctionError::BadType{mfa: this_mfa()})?;
+            Ok(Value::from(Ipv4Addr::from(ip).to_string()))
+        }));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::registry::fun;
+    use crate::Value;
+    use tremor_value::literal;
+
+    #[test]
+    fn parse() {
+        let f = fun("net", "is_ip");
+        assert_val!(f(&[&Value::from("10.0.0.1")]), true);
+        assert_val!(f(&[&Value::from("::1")]), true);
+        assert_val!(f(&[&Value::from("snot")]), false);
+        let f = fun("net", "is_ipv4");
+        assert_val!(f(&[&Value::from("::1")]), false);
+        let f = fun("net", "is_ipv6");
+        assert_val!(f(&[&Value::from("::1")]), true);
+
+        let f = fun("net", "parse_ip");
+        assert_val!(
+            f(&[&Value::from("2001:DB8:0:0::1")]),
+            literal!({"address": "2001:db8::1", "version": 6})
+        );
+        assert!(f(&[&Value::from("256.0.0.1")]).is_err());
+    }
+
+    #[test]
+    fn cidr() {
+        let f = fun("net", "parse_cidr");
+        assert_val!(
+            f(&[&Value::from("192.168.1.77/24")]),
+            literal!({
+                "network": "192.168.1.0",
+                "broadcast": "192.168.1.255",
+                "netmask": "255.255.255.0",
+                "prefix_len": 24,
+                "version": 4
+            })
+        );
+        let f = fun("net", "network");
+        assert_val!(f(&[&Value::from("2001:db8::1/32")]), "2001:db8::");
+        let f = fun("net", "broadcast");
+        assert_val!(f(&[&Value::from("10.1.2.3/8")]), "10.255.255.255");
+        assert!(f(&[&Value::from("10.1.2.3/33")]).is_err());
+    }
+
+    #[test]
+    fn in_cidr() {
+        let f = fun("net", "in_cidr");
+        let cidrs = literal!(["10.0.0.0/8", "192.168.0.0/16", "fc00::/7"]);
+        assert_val!(f(&[&Value::from("10.1.2.3"), &cidrs]), true);
+        assert_val!(f(&[&Value::from("192.169.0.1"), &cidrs]), false);
+        assert_val!(f(&[&Value::from("fd00::1"), &cidrs]), true);
+        assert_val!(
+            f(&[&Value::from("192.169.0.1"), &Value::from("192.169.0.0/24")]),
+            true
+        );
+        assert!(f(&[&Value::from("10.1.2.3"), &literal!(["snot"])]).is_err());
+        assert!(f(&[&Value::from("10.1.2.3"), &literal!([1])]).is_err());
+    }
+
+    #[test]
+    fn classify() {
+        let private = fun("net", "is_private");
+        let loopback = fun("net", "is_loopback");
+        let multicast = fun("net", "is_multicast");
+        assert_val!(private(&[&Value::from("172.16.0.1")]), true);
+        assert_val!(private(&[&Value::from("8.8.8.8")]), false);
+        assert_val!(private(&[&Value::from("fd12::1")]), true);
+        assert_val!(loopback(&[&Value::from("127.0.0.1")]), true);
+        assert_val!(loopback(&[&Value::from("::1")]), true);
+        assert_val!(multicast(&[&Value::from("224.0.0.1")]), true);
+        assert_val!(multicast(&[&Value::from("ff02::1")]), true);
+        assert_val!(multicast(&[&Value::from("10.0.0.1")]), false);
+    }
+
+    #[test]
+    fn integers() {
+        let to = fun("net", "to_integer");
+        let from = fun("net", "from_integer");
+        assert_val!(to(&[&Value::from("10.0.0.1")]), 167_772_161);
+        assert!(to(&[&Value::from("::1")]).is_err());
+        assert_val!(from(&[&Value::from(167_772_161)]), "10.0.0.1");
+        assert!(from(&[&Value::from(-1)]).is_err());
+    }
+}
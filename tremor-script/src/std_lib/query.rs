@@ -0,0 +1,754 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime evaluated `JSONPath` (and jq style) queries over values
+//!
+//! Supported are:
+//! * `$` / `.` - the root
+//! * `.name`, `['name']`, `."name"` - object keys
+//! * `[1]`, `[-1]` - array indexes, negative indexes count from the end
+//! * `*`, `[*]`, `[]` - all children
+//! * `[start:end:step]` - array slices
+//! * `['a', 'b']`, `[0, 2]` - unions
+//! * `..name`, `..[0]`, `..*`, `..` - recursive descent
+//! * `[?(@.price < 10 && @.tags)]` - filters with `==`, `!=`, `<`, `<=`, `>`, `>=`, `&&`, `||`, `!`
+
+use crate::prelude::*;
+use crate::registry::{mfa, FResult, FunctionError, Mfa, TremorFn, TremorFnWrapper};
+use std::{cmp::Ordering, collections::HashMap, sync::Arc, sync::Mutex};
+
+/// maximum number of compiled queries cached per call site
+const CACHE_SIZE: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+    Slice(Option<i64>, Option<i64>, usize),
+    Union(Vec<Selector>),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    recursive: bool,
+    selector: Selector,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    /// a path relative to the current element `@`
+    Current(Vec<Selector>),
+    Literal(Value<'static>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Test(Operand),
+    Cmp(Operand, Op, Operand),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+type Query = Vec<Segment>;
+
+struct Parser<'src> {
+    src: &'src str,
+    pos: usize,
+}
+
+impl<'src> Parser<'src> {
+    fn new(src: &'src str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'src str {
+        self.src.get(self.pos..).unwrap_or_default()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        self.skip_ws();
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{s}`")))
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("{msg} at offset {} of `{}`", self.pos, self.src)
+    }
+
+    fn query(mut self) -> Result<Query, String> {
+        let mut query = Vec::new();
+        // a lone `.` is the root in jq
+        if self.src.trim() == "." {
+            return Ok(query);
+        }
+        self.skip_ws();
+        if !self.eat("$") && self.peek().is_some_and(is_ident) {
+            // a bare `a.b` is treated as `$.a.b`
+            query.push(Segment {
+                recursive: false,
+                selector: Selector::Name(self.ident()),
+            });
+        }
+        self.segments(&mut query, true)?;
+        self.skip_ws();
+        if self.pos < self.src.len() {
+            return Err(self.error("unexpected input"));
+        }
+        Ok(query)
+    }
+
+    fn segments(&mut self, query: &mut Query, allow_recursive: bool) -> Result<(), String> {
+        loop {
+            let recursive = allow_recursive && self.eat("..");
+            let selector = if recursive {
+                match self.peek() {
+                    Some('[') => {
+                        self.pos += 1;
+                        self.bracket()?
+                    }
+                    Some('*') => {
+                        self.pos += 1;
+                        Selector::Wildcard
+                    }
+                    Some(c) if is_ident(c) => Selector::Name(self.ident()),
+                    Some('"' | '\'') => Selector::Name(self.string()?),
+                    // jq's `..`, all descendants
+                    _ => Selector::Wildcard,
+                }
+            } else if self.eat(".") {
+                match self.peek() {
+                    Some('[') => {
+                        self.pos += 1;
+                        self.bracket()?
+                    }
+                    Some('*') if allow_recursive => {
+                        self.pos += 1;
+                        Selector::Wildcard
+                    }
+                    Some(c) if is_ident(c) => Selector::Name(self.ident()),
+                    Some('"' | '\'') => Selector::Name(self.string()?),
+                    _ => return Err(self.error("expected a key")),
+                }
+            } else if self.eat("[") {
+                self.bracket()?
+            } else {
+                return Ok(());
+            };
+            if !allow_recursive && !matches!(selector, Selector::Name(_) | Selector::Index(_)) {
+                return Err(self.error("only keys and indexes are allowed in filter paths"));
+            }
+            query.push(Segment {
+                recursive,
+                selector,
+            });
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let rest = self.rest();
+        let end = rest.find(|c| !is_ident(c)).unwrap_or(rest.len());
+        self.pos += end;
+        rest[..end].to_string()
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.peek().ok_or_else(|| self.error("expected a string"))?;
+        self.pos += 1;
+        let mut res = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, c)) = chars.next() {
+                        res.push(c);
+                    }
+                }
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(res);
+                }
+                c => res.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn int(&mut self) -> Option<i64> {
+        let rest = self.rest();
+        let end = rest
+            .char_indices()
+            .find(|(i, c)| !(c.is_ascii_digit() || (*i == 0 && *c == '-')))
+            .map_or(rest.len(), |(i, _)| i);
+        let n = rest[..end].parse().ok()?;
+        self.pos += end;
+        Some(n)
+    }
+
+    /// the content of `[...]`, the opening bracket is already consumed
+    fn bracket(&mut self) -> Result<Selector, String> {
+        self.skip_ws();
+        if self.eat("]") {
+            return Ok(Selector::Wildcard);
+        }
+        if self.eat("*") {
+            self.expect("]")?;
+            return Ok(Selector::Wildcard);
+        }
+        if self.eat("?") {
+            self.skip_ws();
+            let filter = self.filter()?;
+            self.expect("]")?;
+            return Ok(Selector::Filter(filter));
+        }
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            let item = match self.peek() {
+                Some('"' | '\'') => Selector::Name(self.string()?),
+                _ => self.index_or_slice()?,
+            };
+            items.push(item);
+            self.skip_ws();
+            if self.eat("]") {
+                break;
+            }
+            self.expect(",")?;
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Selector::Union(items)
+        })
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, String> {
+        let start = self.int();
+        self.skip_ws();
+        if !self.eat(":") {
+            return start
+                .map(Selector::Index)
+                .ok_or_else(|| self.error("expected an index, a slice or a string"));
+        }
+        self.skip_ws();
+        let end = self.int();
+        self.skip_ws();
+        let step = if self.eat(":") {
+            self.skip_ws();
+            match self.int() {
+                Some(step) if step > 0 => usize::try_from(step).unwrap_or(1),
+                None => 1,
+                Some(_) => return Err(self.error("the step of a slice must be positive")),
+            }
+        } else {
+            1
+        };
+        Ok(Selector::Slice(start, end, step))
+    }
+
+    fn filter(&mut self) -> Result<Filter, String> {
+        let mut lhs = self.and()?;
+        loop {
+            self.skip_ws();
+            if self.eat("||") {
+                lhs = Filter::Or(Box::new(lhs), Box::new(self.and()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            self.skip_ws();
+            if self.eat("&&") {
+                lhs = Filter::And(Box::new(lhs), Box::new(self.unary()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        self.skip_ws();
+        if self.eat("!") && !self.rest().starts_with('=') {
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let filter = self.filter()?;
+            self.expect(")")?;
+            return Ok(filter);
+        }
+        let lhs = self.operand()?;
+        self.skip_ws();
+        let op = if self.eat("==") {
+            Op::Eq
+        } else if self.eat("!=") {
+            Op::NotEq
+        } else if self.eat("<=") {
+            Op::Lte
+        } else if self.eat(">=") {
+            Op::Gte
+        } else if self.eat("<") {
+            Op::Lt
+        } else if self.eat(">") {
+            Op::Gt
+        } else {
+            return Ok(Filter::Test(lhs));
+        };
+        self.skip_ws();
+        Ok(Filter::Cmp(lhs, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        if self.eat("@") {
+            let mut path = Vec::new();
+            self.segments(&mut path, false)?;
+            return Ok(Operand::Current(
+                path.into_iter().map(|s| s.selector).collect(),
+            ));
+        }
+        let literal = match self.peek() {
+            Some('"' | '\'') => Value::from(self.string()?),
+            _ if self.eat("true") => Value::from(true),
+            _ if self.eat("false") => Value::from(false),
+            _ if self.eat("null") => Value::null(),
+            _ => {
+                let rest = self.rest();
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_digit() || "-+.eE".contains(c)))
+                    .unwrap_or(rest.len());
+                let n = &rest[..end];
+                let v = if let Ok(i) = n.parse::<i64>() {
+                    Value::from(i)
+                } else if let Ok(f) = n.parse::<f64>() {
+                    Value::from(f)
+                } else {
+                    return Err(self.error("expected `@` or a literal"));
+                };
+                self.pos += end;
+                v
+            }
+        };
+        Ok(Operand::Literal(literal))
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn compile(src: &str) -> Result<Query, String> {
+    Parser::new(src).query()
+}
+
+fn children<'v, 'event>(
+    value: &'v Value<'event>,
+) -> Box<dyn Iterator<Item = &'v Value<'event>> + 'v> {
+    if let Some(a) = value.as_array() {
+        Box::new(a.iter())
+    } else if let Some(o) = value.as_object() {
+        Box::new(o.values())
+    } else {
+        Box::new(std::iter::empty())
+    }
+}
+
+fn index(len: usize, i: i64) -> Option<usize> {
+    let len = i64::try_from(len).ok()?;
+    let i = if i < 0 { len + i } else { i };
+    if (0..len).contains(&i) {
+        usize::try_from(i).ok()
+    } else {
+        None
+    }
+}
+
+fn slice_bound(len: usize, i: Option<i64>, default: usize) -> usize {
+    i.map_or(default, |i| {
+        let signed_len = i64::try_from(len).unwrap_or(i64::MAX);
+        let i = if i < 0 {
+            (signed_len + i).max(0)
+        } else {
+            i.min(signed_len)
+        };
+        usize::try_from(i).unwrap_or_default()
+    })
+}
+
+fn select<'v, 'event>(
+    selector: &Selector,
+    value: &'v Value<'event>,
+    out: &mut Vec<&'v Value<'event>>,
+) {
+    match selector {
+        Selector::Name(name) => out.extend(value.get(name.as_str())),
+        Selector::Index(i) => {
+            if let Some(a) = value.as_array() {
+                out.extend(index(a.len(), *i).and_then(|i| a.get(i)));
+            }
+        }
+        Selector::Wildcard => out.extend(children(value)),
+        Selector::Slice(start, end, step) => {
+            if let Some(a) = value.as_array() {
+                let start = slice_bound(a.len(), *start, 0);
+                let end = slice_bound(a.len(), *end, a.len());
+                if start < end {
+                    out.extend(a[start..end].iter().step_by(*step));
+                }
+            }
+        }
+        Selector::Union(selectors) => {
+            for s in selectors {
+                select(s, value, out);
+            }
+        }
+        Selector::Filter(filter) => {
+            out.extend(children(value).filter(|c| matches(filter, c)));
+        }
+    }
+}
+
+fn eval<'v, 'event>(query: &[Segment], value: &'v Value<'event>, out: &mut Vec<&'v Value<'event>>) {
+    let Some((segment, rest)) = query.split_first() else {
+        out.push(value);
+        return;
+    };
+    let mut selected = Vec::new();
+    select(&segment.selector, value, &mut selected);
+    for v in selected {
+        eval(rest, v, out);
+    }
+    if segment.recursive {
+        for child in children(value) {
+            eval(query, child, out);
+        }
+    }
+}
+
+fn resolve<'v>(operand: &'v Operand, current: &'v Value) -> Option<&'v Value<'v>> {
+    match operand {
+        Operand::Literal(v) => Some(v),
+        Operand::Current(path) => {
+            let mut v = current;
+            for s in path {
+                v = match s {
+                    Selector::Name(name) => v.get(name.as_str())?,
+                    Selector::Index(i) => {
+                        let a = v.as_array()?;
+                        a.get(index(a.len(), *i)?)?
+                    }
+                    _ => return None,
+                };
+            }
+            Some(v)
+        }
+    }
+}
+
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    if let (Some(l), Some(r)) = (lhs.as_str(), rhs.as_str()) {
+        Some(l.cmp(r))
+    } else if let (Some(l), Some(r)) = (lhs.cast_f64(), rhs.cast_f64()) {
+        l.partial_cmp(&r)
+    } else if lhs == rhs {
+        Some(Ordering::Equal)
+    } else {
+        None
+    }
+}
+
+fn matches(filter: &Filter, current: &Value) -> bool {
+    match filter {
+        Filter::Test(operand) => {
+            resolve(operand, current).is_some_and(|v| v.as_bool().unwrap_or(!v.is_null()))
+        }
+        Filter::Cmp(lhs, op, rhs) => {
+            let (Some(lhs), Some(rhs)) = (resolve(lhs, current), resolve(rhs, current)) else {
+                return false;
+            };
+            let ord = compare(lhs, rhs);
+            match op {
+                Op::Eq => ord == Some(Ordering::Equal),
+                Op::NotEq => ord != Some(Ordering::Equal),
+                Op::Lt => ord == Some(Ordering::Less),
+                Op::Lte => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+                Op::Gt => ord == Some(Ordering::Greater),
+                Op::Gte => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+            }
+        }
+        Filter::And(lhs, rhs) => matches(lhs, current) && matches(rhs, current),
+        Filter::Or(lhs, rhs) => matches(lhs, current) || matches(rhs, current),
+        Filter::Not(f) => !matches(f, current),
+    }
+}
+
+/// The query functions, every call site gets its own instance and with it its own cache
+/// of compiled queries.
+#[derive(Debug, Default)]
+struct QueryFn {
+    first: bool,
+    cache: Mutex<HashMap<String, Arc<Query>>>,
+}
+
+impl QueryFn {
+    fn new(first: bool) -> Self {
+        Self {
+            first,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn mfa(&self) -> Mfa {
+        mfa("query", if self.first { "first" } else { "all" }, 2)
+    }
+
+    fn compiled(&self, src: &str) -> FResult<Arc<Query>> {
+        let to_error = |error| FunctionError::RuntimeError {
+            mfa: self.mfa(),
+            error,
+        };
+        let mut cache = self
+            .cache
+            .lock()
+            .map_err(|_| to_error("query cache lock poisoned".to_string()))?;
+        if let Some(query) = cache.get(src) {
+            return Ok(query.clone());
+        }
+        let query = Arc::new(compile(src).map_err(to_error)?);
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(src.to_string(), query.clone());
+        Ok(query)
+    }
+}
+
+impl TremorFn for QueryFn {
+    fn invoke<'event>(
+        &self,
+        _ctx: &EventContext,
+        args: &[&Value<'event>],
+    ) -> FResult<Value<'event>> {
+        let [value, query] = args else {
+            return Err(FunctionError::BadArity {
+                mfa: self.mfa(),
+                calling_a: args.len(),
+            });
+        };
+        let query = query
+            .as_str()
+            .ok_or_else(|| FunctionError::BadType { mfa: self.mfa() })?;
+        let query = self.compiled(query)?;
+        let mut out = Vec::new();
+        eval(&query, value, &mut out);
+        if self.first {
+            Ok(out.first().map(|v| (*v).clone()).unwrap_or_default())
+        } else {
+            Ok(Value::from(out.into_iter().cloned().collect::<Vec<_>>()))
+        }
+    }
+    fn boxed_clone(&self) -> Box<dyn TremorFn> {
+        Box::new(Self::new(self.first))
+    }
+    fn arity(&self) -> std::ops::RangeInclusive<usize> {
+        2..=2
+    }
+    fn is_const(&self) -> bool {
+        true
+    }
+}
+
+pub fn load(registry: &mut Registry) {
+    registry
+        .insert(TremorFnWrapper::new(
+            "query".to_string(),
+            "all".to_string(),
+            Box::new(QueryFn::new(false)),
+        ))
+        .insert(TremorFnWrapper::new(
+            "query".to_string(),
+            "first".to_string(),
+            Box::new(QueryFn::new(true)),
+        ));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compile, QueryFn, Selector};
+    use crate::registry::fun;
+    use crate::Value;
+    use simd_json::prelude::*;
+    use tremor_value::literal;
+
+    fn store() -> Value<'static> {
+        literal!({
+            "store": {
+                "book": [
+                    {"category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95},
+                    {"category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99},
+                    {"category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99},
+                    {"category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99}
+                ],
+                "bicycle": {"color": "red", "price": 19.95}
+            }
+        })
+    }
+
+    fn all(query: &str) -> Value<'static> {
+        let f = fun("query", "all");
+        f(&[&store(), &Value::from(query)])
+            .expect("query failed")
+            .into_static()
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            all("$.store.book[*].author"),
+            literal!([
+                "Nigel Rees",
+                "Evelyn Waugh",
+                "Herman Melville",
+                "J. R. R. Tolkien"
+            ])
+        );
+        // jq style
+        assert_eq!(all(".store.book[].author"), all("$.store.book[*].author"));
+        assert_eq!(all("store.bicycle.color"), literal!(["red"]));
+        assert_eq!(all("$['store']['bicycle']['color']"), literal!(["red"]));
+        assert_eq!(all(".store.\"bicycle\".color"), literal!(["red"]));
+        assert_eq!(
+            all("$.store.book[-1].title"),
+            literal!(["The Lord of the Rings"])
+        );
+        assert_eq!(all("$.store.book[0,2].price"), literal!([8.95, 8.99]));
+        assert_eq!(all("$.store.book[1:3].price"), literal!([12.99, 8.99]));
+        assert_eq!(all("$.store.book[::2].price"), literal!([8.95, 8.99]));
+        assert_eq!(
+            all("$.store.bicycle['color', 'price']"),
+            literal!(["red", 19.95])
+        );
+        assert_eq!(all("$.store.book[7]"), literal!([]));
+        assert_eq!(all("$.snot"), literal!([]));
+        assert_eq!(all("."), literal!([store()]));
+    }
+
+    #[test]
+    fn recursive() {
+        assert_eq!(all("$..price").as_array().map(Vec::len), Some(5));
+        assert_eq!(all("$..book[2].isbn"), literal!(["0-553-21311-3"]));
+        assert_eq!(all("$..isbn"), literal!(["0-553-21311-3", "0-395-19395-8"]));
+        assert_eq!(all("$.store.bicycle.."), literal!(["red", 19.95]));
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            all("$.store.book[?(@.price < 10)].title"),
+            literal!(["Sayings of the Century", "Moby Dick"])
+        );
+        assert_eq!(
+            all("$..book[?(@.isbn)].author"),
+            literal!(["Herman Melville", "J. R. R. Tolkien"])
+        );
+        assert_eq!(
+            all("$..book[?(@.category == 'fiction' && !(@.price >= 10))].title"),
+            literal!(["Moby Dick"])
+        );
+        assert_eq!(
+            all("$..book[?@.author == \"Nigel Rees\" || @.price > 20].price"),
+            literal!([8.95, 22.99])
+        );
+        assert_eq!(
+            all("$..book[?(@.price != 8.95)]").as_array().map(Vec::len),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn first() {
+        let f = fun("query", "first");
+        assert_eq!(
+            f(&[&store(), &Value::from("$..author")]),
+            Ok(Value::from("Nigel Rees"))
+        );
+        assert_eq!(
+            f(&[&store(), &Value::from("$.snot")]),
+            Ok(Value::const_null())
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for bad in [
+            "$.",
+            "$[",
+            "$['snot",
+            "$[?(@.a ==)]",
+            "$[::0]",
+            "$ snot",
+            "$..book[?(@..a)]",
+        ] {
+            assert!(compile(bad).is_err(), "{bad}");
+        }
+        let f = fun("query", "all");
+        assert!(f(&[&store(), &Value::from("$[")]).is_err());
+        assert!(f(&[&store(), &Value::from(1)]).is_err());
+    }
+
+    #[test]
+    fn cache() -> Result<(), String> {
+        let f = QueryFn::new(false);
+        let a = f.compiled("$.a").map_err(|e| format!("{e:?}"))?;
+        let b = f.compiled("$.a").map_err(|e| format!("{e:?}"))?;
+        assert!(std::sync::Arc::ptr_eq(&a, &b));
+        assert_eq!(a[0].selector, Selector::Name("a".to_string()));
+        Ok(())
+    }
+}
@@ -17,10 +17,13 @@ use crate::prelude::*;
 use crate::registry::{
     mfa, Aggr as AggrRegistry, FResult, FunctionError, TremorAggrFn, TremorAggrFnWrapper,
 };
+use halfbrown::HashMap;
 use hdrhistogram::Histogram;
 use sketches_ddsketch::{Config as DDSketchConfig, DDSketch};
 use std::cmp::max;
 use std::ops::RangeInclusive;
+use tremor_value::utils::sorted_serialize;
+use xxhash_rust::xxh3::xxh3_64;
 
 /// Round up.
 ///
@@ -715,6 +718,481 @@ impl TremorAggrFn for Hdr {
     }
 }
 
+/// A stable key for a value, records are serialized with sorted keys so equal
+/// values always end up with the same key
+fn value_key(v: &Value, name: &str, arity: usize) -> FResult<Vec<u8>> {
+    sorted_serialize(v).map_err(|e| FunctionError::RuntimeError {
+        mfa: mfa("stats", name, arity),
+        error: e.to_string(),
+    })
+}
+
+const HLL_DEFAULT_PRECISION: u8 = 12;
+const HLL_PRECISION: RangeInclusive<u8> = 4..=16;
+
+/// `HyperLogLog` distinct counter, the registers are only allocated once
+/// the first value is added
+#[derive(Clone, Debug)]
+struct ApproxDistinct {
+    precision: u8,
+    precision_set: bool,
+    registers: Vec<u8>,
+}
+
+impl Default for ApproxDistinct {
+    fn default() -> Self {
+        Self {
+            precision: HLL_DEFAULT_PRECISION,
+            precision_set: false,
+            registers: Vec::new(),
+        }
+    }
+}
+
+impl ApproxDistinct {
+    fn add(&mut self, hash: u64) {
+        if self.registers.is_empty() {
+            self.registers = vec![0; 1 << self.precision];
+        }
+        let p = u32::from(self.precision);
+        // ALLOW: the index has at most 16 bits
+        #[allow(clippy::cast_possible_truncation)]
+        let idx = (hash >> (64 - p)) as usize;
+        // ALLOW: the rank is at most 61
+        #[allow(clippy::cast_possible_truncation)]
+        let rank = ((hash << p) | (1 << (p - 1))).leading_zeros() as u8 + 1;
+        if let Some(r) = self.registers.get_mut(idx) {
+            *r = max(*r, rank);
+        }
+    }
+
+    fn estimate(&self) -> u64 {
+        if self.registers.is_empty() {
+            return 0;
+        }
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|r| 2_f64.powi(-i32::from(*r)))
+            .sum();
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        let raw = alpha * m * m / sum;
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            // linear counting for small cardinalities
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        // ALLOW: the estimate is always positive and far below u64::MAX
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let estimate = estimate.round() as u64;
+        estimate
+    }
+}
+
+impl TremorAggrFn for ApproxDistinct {
+    fn accumulate(&mut self, args: &[&Value]) -> FResult<()> {
+        if !self.precision_set {
+            if let Some(p) = args.get(1) {
+                self.precision =
+                    p.as_u8()
+                        .filter(|p| HLL_PRECISION.contains(p))
+                        .ok_or_else(|| FunctionError::RuntimeError {
+                            mfa: mfa("stats", "approx_distinct", 2),
+                            error: format!(
+                                "The precision needs to be an integer between {} and {}",
+                                HLL_PRECISION.start(),
+                                HLL_PRECISION.end()
+                            ),
+                        })?;
+            }
+            self.precision_set = true;
+        }
+        if let Some(v) = args.first() {
+            let key = value_key(v, "approx_distinct", args.len())?;
+            self.add(xxh3_64(&key));
+        }
+        Ok(())
+    }
+
+    fn emit<'event>(&mut self) -> FResult<Value<'event>> {
+        Ok(Value::from(self.estimate()))
+    }
+
+    fn init(&mut self) {
+        self.registers.clear();
+    }
+
+    fn merge(&mut self, src: &dyn TremorAggrFn) -> FResult<()> {
+        if let Some(other) = src.downcast_ref::<Self>() {
+            if other.registers.is_empty() {
+                return Ok(());
+            }
+            if self.registers.is_empty() {
+                self.precision = other.precision;
+                self.precision_set = other.precision_set;
+                self.registers.clone_from(&other.registers);
+            } else if self.registers.len() == other.registers.len() {
+                for (r, o) in self.registers.iter_mut().zip(&other.registers) {
+                    *r = max(*r, *o);
+                }
+            } else {
+                return Err(FunctionError::RuntimeError {
+                    mfa: mfa("stats", "approx_distinct", 2),
+                    error: "Can't merge counters with different precisions".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn TremorAggrFn> {
+        Box::new(self.clone())
+    }
+    fn arity(&self) -> RangeInclusive<usize> {
+        1..=2
+    }
+}
+
+const TOP_K_DEFAULT: usize = 10;
+
+/// A monitored value of the Space-Saving algorithm
+#[derive(Clone, Debug)]
+struct Counter {
+    value: Value<'static>,
+    count: u64,
+    error: u64,
+}
+
+/// Space-Saving top-k, keeps at most `k` counters
+#[derive(Clone, Debug)]
+struct TopK {
+    k: usize,
+    k_set: bool,
+    counters: HashMap<Vec<u8>, Counter>,
+}
+
+impl Default for TopK {
+    fn default() -> Self {
+        Self {
+            k: TOP_K_DEFAULT,
+            k_set: false,
+            counters: HashMap::new(),
+        }
+    }
+}
+
+impl TopK {
+    /// the counters ordered by their count, largest first
+    fn sorted(&self) -> Vec<(&Vec<u8>, &Counter)> {
+        let mut counters: Vec<_> = self.counters.iter().collect();
+        // order by key for equal counts so the result is stable
+        counters.sort_by(|(k1, c1), (k2, c2)| c2.count.cmp(&c1.count).then_with(|| k1.cmp(k2)));
+        counters
+    }
+}
+
+impl TremorAggrFn for TopK {
+    fn accumulate(&mut self, args: &[&Value]) -> FResult<()> {
+        if !self.k_set {
+            if let Some(k) = args.get(1) {
+                self.k =
+                    k.as_usize()
+                        .filter(|k| *k > 0)
+                        .ok_or_else(|| FunctionError::RuntimeError {
+                            mfa: mfa("stats", "top_k", 2),
+                            error: "k needs to be a positive integer".to_string(),
+                        })?;
+            }
+            self.k_set = true;
+        }
+        let Some(v) = args.first() else {
+            return Ok(());
+        };
+        let key = value_key(v, "top_k", args.len())?;
+        if let Some(counter) = self.counters.get_mut(&key) {
+            counter.count += 1;
+        } else if self.counters.len() < self.k {
+            self.counters.insert(
+                key,
+                Counter {
+                    value: v.clone_static(),
+                    count: 1,
+                    error: 0,
+                },
+            );
+        } else {
+            // replace the smallest counter, the new value might have been seen
+            // up to `min` times before
+            let smallest = self
+                .counters
+                .iter()
+                .min_by(|(k1, c1), (k2, c2)| c1.count.cmp(&c2.count).then_with(|| k2.cmp(k1)))
+                .map(|(k, c)| (k.clone(), c.count));
+            if let Some((smallest, min)) = smallest {
+                self.counters.remove(&smallest);
+                self.counters.insert(
+                    key,
+                    Counter {
+                        value: v.clone_static(),
+                        count: min + 1,
+                        error: min,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn emit<'event>(&mut self) -> FResult<Value<'event>> {
+        Ok(Value::from(
+            self.sorted()
+                .into_iter()
+                .map(|(_, c)| {
+                    literal!({
+                        "value": c.value.clone(),
+                        "count": c.count,
+                        "error": c.error,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn init(&mut self) {
+        self.counters.clear();
+    }
+
+    fn merge(&mut self, src: &dyn TremorAggrFn) -> FResult<()> {
+        if let Some(other) = src.downcast_ref::<Self>() {
+            if !self.k_set {
+                self.k = other.k;
+                self.k_set = other.k_set;
+            }
+            for (key, counter) in &other.counters {
+                if let Some(c) = self.counters.get_mut(key) {
+                    c.count += counter.count;
+                    c.error += counter.error;
+                } else {
+                    self.counters.insert(key.clone(), counter.clone());
+                }
+            }
+            // only keep the k largest counters
+            if self.counters.len() > self.k {
+                let keep: Vec<Vec<u8>> = self
+                    .sorted()
+                    .into_iter()
+                    .take(self.k)
+                    .map(|(k, _)| k.clone())
+                    .collect();
+                self.counters.retain(|k, _| keep.contains(k));
+            }
+        }
+        Ok(())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn TremorAggrFn> {
+        Box::new(self.clone())
+    }
+    fn arity(&self) -> RangeInclusive<usize> {
+        1..=2
+    }
+}
+
+/// Exponentially weighted moving average, the first value is used as the
+/// initial average
+#[derive(Clone, Debug, Default)]
+struct Ewma {
+    alpha: f64,
+    first: f64,
+    value: f64,
+    count: u64,
+}
+
+impl TremorAggrFn for Ewma {
+    fn accumulate(&mut self, args: &[&Value]) -> FResult<()> {
+        let (Some(v), Some(alpha)) = (args.first().cast_f64(), args.get(1).cast_f64()) else {
+            return Err(FunctionError::BadType {
+                mfa: mfa("stats", "ewma", 2),
+            });
+        };
+        if alpha <= 0.0 || alpha > 1.0 {
+            return Err(FunctionError::RuntimeError {
+                mfa: mfa("stats", "ewma", 2),
+                error: format!("alpha needs to be in the range (0, 1] but was {alpha}"),
+            });
+        }
+        self.alpha = alpha;
+        if self.count == 0 {
+            self.first = v;
+            self.value = v;
+        } else {
+            self.value = alpha * v + (1.0 - alpha) * self.value;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn emit<'event>(&mut self) -> FResult<Value<'event>> {
+        if self.count == 0 {
+            Ok(Value::null())
+        } else {
+            Ok(Value::from(self.value))
+        }
+    }
+
+    fn init(&mut self) {
+        self.count = 0;
+        self.first = 0.0;
+        self.value = 0.0;
+    }
+
+    fn merge(&mut self, src: &dyn TremorAggrFn) -> FResult<()> {
+        if let Some(other) = src.downcast_ref::<Self>() {
+            if other.count == 0 {
+                return Ok(());
+            }
+            if self.count == 0 {
+                *self = other.clone();
+                return Ok(());
+            }
+            // self is earlier than other, so our average takes the place of the first
+            // value that other started with and decays over all of others values
+            let decay = (1.0 - other.alpha).powf(other.count as f64);
+            self.value = other.value + decay * (self.value - other.first);
+            self.alpha = other.alpha;
+            self.count += other.count;
+        }
+        Ok(())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn TremorAggrFn> {
+        Box::new(self.clone())
+    }
+    fn arity(&self) -> RangeInclusive<usize> {
+        2..=2
+    }
+}
+
+/// Exact median, keeps all values of the window
+#[derive(Clone, Debug, Default)]
+struct Median(Vec<f64>);
+
+impl TremorAggrFn for Median {
+    fn accumulate(&mut self, args: &[&Value]) -> FResult<()> {
+        args.first().cast_f64().map_or_else(
+            || {
+                Err(FunctionError::BadType {
+                    mfa: mfa("stats", "median", 1),
+                })
+            },
+            |v| {
+                self.0.push(v);
+                Ok(())
+            },
+        )
+    }
+
+    fn emit<'event>(&mut self) -> FResult<Value<'event>> {
+        let n = self.0.len();
+        if n == 0 {
+            return Ok(Value::null());
+        }
+        self.0.sort_by(f64::total_cmp);
+        let mid = n / 2;
+        let median = if n % 2 == 0 {
+            (self.0[mid - 1] + self.0[mid]) / 2.0
+        } else {
+            self.0[mid]
+        };
+        Ok(Value::from(median))
+    }
+
+    fn init(&mut self) {
+        self.0.clear();
+    }
+
+    fn merge(&mut self, src: &dyn TremorAggrFn) -> FResult<()> {
+        if let Some(other) = src.downcast_ref::<Self>() {
+            self.0.extend_from_slice(&other.0);
+        }
+        Ok(())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn TremorAggrFn> {
+        Box::new(self.clone())
+    }
+    fn arity(&self) -> RangeInclusive<usize> {
+        1..=1
+    }
+}
+
+/// The most frequent value, ties go to the value seen first
+#[derive(Clone, Debug, Default)]
+struct Mode {
+    seen: u64,
+    // value, count and the position the value was first seen at
+    values: HashMap<Vec<u8>, (Value<'static>, u64, u64)>,
+}
+
+impl TremorAggrFn for Mode {
+    fn accumulate(&mut self, args: &[&Value]) -> FResult<()> {
+        if let Some(v) = args.first() {
+            let key = value_key(v, "mode", 1)?;
+            let seen = self.seen;
+            self.values
+                .entry(key)
+                .or_insert_with(|| (v.clone_static(), 0, seen))
+                .1 += 1;
+            self.seen += 1;
+        }
+        Ok(())
+    }
+
+    fn emit<'event>(&mut self) -> FResult<Value<'event>> {
+        Ok(self
+            .values
+            .values()
+            .max_by(|(_, c1, s1), (_, c2, s2)| c1.cmp(c2).then_with(|| s2.cmp(s1)))
+            .map(|(v, _, _)| v.clone())
+            .unwrap_or_default())
+    }
+
+    fn init(&mut self) {
+        self.seen = 0;
+        self.values.clear();
+    }
+
+    fn merge(&mut self, src: &dyn TremorAggrFn) -> FResult<()> {
+        if let Some(other) = src.downcast_ref::<Self>() {
+            // self is earlier than other so all of others values were seen after ours
+            for (key, (value, count, seen)) in &other.values {
+                let seen = self.seen + seen;
+                self.values
+                    .entry(key.clone())
+                    .or_insert_with(|| (value.clone(), 0, seen))
+                    .1 += count;
+            }
+            self.seen += other.seen;
+        }
+        Ok(())
+    }
+
+    fn boxed_clone(&self) -> Box<dyn TremorAggrFn> {
+        Box::new(self.clone())
+    }
+    fn arity(&self) -> RangeInclusive<usize> {
+        1..=1
+    }
+}
+
 pub fn load_aggr(registry: &mut AggrRegistry) {
     // Allow: this is ok because we must use the result of insert
     registry
@@ -762,6 +1240,31 @@ pub fn load_aggr(registry: &mut AggrRegistry) {
             "stats".to_string(),
             "dds".to_string(),
             Box::<Dds>::default(),
+        ))
+        .insert(TremorAggrFnWrapper::new(
+            "stats".to_string(),
+            "approx_distinct".to_string(),
+            Box::<ApproxDistinct>::default(),
+        ))
+        .insert(TremorAggrFnWrapper::new(
+            "stats".to_string(),
+            "top_k".to_string(),
+            Box::<TopK>::default(),
+        ))
+        .insert(TremorAggrFnWrapper::new(
+            "stats".to_string(),
+            "ewma".to_string(),
+            Box::<Ewma>::default(),
+        ))
+        .insert(TremorAggrFnWrapper::new(
+            "stats".to_string(),
+            "median".to_string(),
+            Box::<Median>::default(),
+        ))
+        .insert(TremorAggrFnWrapper::new(
+            "stats".to_string(),
+            "mode".to_string(),
+            Box::<Mode>::default(),
         ));
 }
 
@@ -1111,6 +1614,142 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn approx_distinct() -> Result<()> {
+        let mut a = ApproxDistinct::default();
+        a.init();
+        assert_eq!(a.emit()?, 0);
+        for i in 0..10_000 {
+            a.accumulate(&[&Value::from(i % 5_000)])?;
+        }
+        let estimate = a.emit()?.cast_f64().unwrap_or_default();
+        assert!((estimate - 5_000.0).abs() < 250.0, "{estimate}");
+
+        // half of the values overlap
+        let mut b = ApproxDistinct::default();
+        b.init();
+        for i in 2_500..7_500 {
+            b.accumulate(&[&Value::from(i)])?;
+        }
+        a.merge(&b)?;
+        let estimate = a.emit()?.cast_f64().unwrap_or_default();
+        assert!((estimate - 7_500.0).abs() < 375.0, "{estimate}");
+
+        // records are equal independent of their key order
+        let mut c = ApproxDistinct::default();
+        c.accumulate(&[&literal!({"a": 1, "b": 2}), &Value::from(8)])?;
+        c.accumulate(&[&literal!({"b": 2, "a": 1}), &Value::from(8)])?;
+        assert_eq!(c.emit()?, 1);
+        assert!(c.merge(&b).is_err());
+
+        let mut d = ApproxDistinct::default();
+        assert!(d.accumulate(&[&Value::from(1), &Value::from(20)]).is_err());
+        assert_eq!(d.arity(), 1..=2);
+        Ok(())
+    }
+
+    #[test]
+    fn top_k() -> Result<()> {
+        let k = Value::from(2);
+        let mut a = TopK::default();
+        a.init();
+        for v in ["a", "b", "a", "c", "a", "b", "d", "a"] {
+            a.accumulate(&[&Value::from(v), &k])?;
+        }
+        assert_eq!(
+            a.emit()?,
+            literal!([
+                {"value": "a", "count": 4, "error": 0},
+                {"value": "d", "count": 4, "error": 3}
+            ])
+        );
+
+        let mut b = TopK::default();
+        for v in ["d", "d", "e"] {
+            b.accumulate(&[&Value::from(v), &k])?;
+        }
+        a.merge(&b)?;
+        assert_eq!(
+            a.emit()?,
+            literal!([
+                {"value": "d", "count": 6, "error": 3},
+                {"value": "a", "count": 4, "error": 0}
+            ])
+        );
+
+        let mut c = TopK::default();
+        assert!(c.accumulate(&[&Value::from(1), &Value::from(0)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ewma() -> Result<()> {
+        let alpha = Value::from(0.5);
+        let mut a = Ewma::default();
+        a.init();
+        assert_eq!(a.emit()?, Value::null());
+        for v in [2, 4, 8] {
+            a.accumulate(&[&Value::from(v), &alpha])?;
+        }
+        assert_eq!(a.emit()?, 5.5);
+
+        // merging a later window is the same as accumulating its values
+        let mut b = Ewma::default();
+        for v in [4, 0] {
+            b.accumulate(&[&Value::from(v), &alpha])?;
+        }
+        a.merge(&b)?;
+        for v in [4, 0] {
+            b.accumulate(&[&Value::from(v), &alpha])?;
+        }
+        let mut c = Ewma::default();
+        for v in [2, 4, 8, 4, 0] {
+            c.accumulate(&[&Value::from(v), &alpha])?;
+        }
+        assert_eq!(a.emit()?, c.emit()?);
+
+        assert!(c.accumulate(&[&Value::from(1), &Value::from(1.5)]).is_err());
+        assert!(c.accumulate(&[&Value::from("snot"), &alpha]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn median() -> Result<()> {
+        let mut a = Median::default();
+        a.init();
+        assert_eq!(a.emit()?, Value::null());
+        for v in [5, 1, 3] {
+            a.accumulate(&[&Value::from(v)])?;
+        }
+        assert_eq!(a.emit()?, 3.0);
+        let mut b = Median::default();
+        b.accumulate(&[&Value::from(10)])?;
+        a.merge(&b)?;
+        assert_eq!(a.emit()?, 4.0);
+        assert!(a.accumulate(&[&Value::from("snot")]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mode() -> Result<()> {
+        let mut a = Mode::default();
+        a.init();
+        assert_eq!(a.emit()?, Value::null());
+        for v in ["b", "a", "a", "b"] {
+            a.accumulate(&[&Value::from(v)])?;
+        }
+        // ties go to the value seen first
+        assert_eq!(a.emit()?, "b");
+
+        let mut b = Mode::default();
+        for v in ["c", "a"] {
+            b.accumulate(&[&Value::from(v)])?;
+        }
+        a.merge(&b)?;
+        assert_eq!(a.emit()?, "a");
+        Ok(())
+    }
+
     use crate::errors::Error;
     use proptest::prelude::*;
 
@@ -0,0 +1,204 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::prelude::*;
+use crate::{tremor_const_fn, tremor_fn, tremor_fn_};
+use rand::Rng;
+use tremor_common::time::nanotime;
+use uuid::Uuid;
+
+/// Crockford's base32 alphabet used by ULIDs
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const ULID_LEN: usize = 26;
+
+/// A ULID is a 48 bit millisecond timestamp followed by 80 random bits
+fn ulid(timestamp_ms: u64, random: u128) -> String {
+    let value = (u128::from(timestamp_ms) << 80) | (random & ((1 << 80) - 1));
+    (0..ULID_LEN)
+        .map(|i| {
+            // ALLOW: the index is masked to 5 bits
+            #[allow(clippy::cast_possible_truncation)]
+            let idx = ((value >> (125 - 5 * i)) & 0x1f) as usize;
+            char::from(ULID_ALPHABET[idx])
+        })
+        .collect()
+}
+
+fn parse_ulid(s: &str) -> Option<u128> {
+    if s.len() != ULID_LEN {
+        return None;
+    }
+    let mut value: u128 = 0;
+    for (i, c) in s.bytes().enumerate() {
+        let idx = ULID_ALPHABET
+            .iter()
+            .position(|a| *a == c.to_ascii_uppercase())?;
+        // the first character only holds 3 bits
+        if i == 0 && idx > 7 {
+            return None;
+        }
+        value = (value << 5) | idx as u128;
+    }
+    Some(value)
+}
+
+fn namespace(ns: &str) -> Option<Uuid> {
+    match ns {
+        "dns" => Some(Uuid::NAMESPACE_DNS),
+        "url" => Some(Uuid::NAMESPACE_URL),
+        "oid" => Some(Uuid::NAMESPACE_OID),
+        "x500" => Some(Uuid::NAMESPACE_X500),
+        other => Uuid::parse_str(other).ok(),
+    }
+}
+
+pub fn load(registry: &mut Registry) {
+    registry
+        .insert(tremor_fn! (uuid|v4(_context) {
+            Ok(Value::from(Uuid::new_v4().hyphenated().to_string()))
+        }))
+        .insert(tremor_fn! (uuid|v7(_context) {
+            Ok(Value::from(Uuid::now_v7().hyphenated().to_string()))
+        }))
+        .insert(tremor_fn! (uuid|ulid(_context) {
+            Ok(Value::from(ulid(nanotime() / 1_000_000, rand::thread_rng().gen())))
+        }))
+        .insert(tremor_const_fn! (uuid|v5(_context, _namespace, _name) {
+            let Some(ns) = _namespace.as_str() else {
+                return Err(FunctionError::BadType{mfa: this_mfa()});
+            };
+            let name: &[u8] = match _name {
+                Value::String(s) => s.as_bytes(),
+                Value::Bytes(b) => b,
+                _ => return Err(FunctionError::BadType{mfa: this_mfa()}),
+            };
+            let ns = namespace(ns).ok_or_else(|| to_runtime_error(format!("invalid namespace `{ns}`")))?;
+            Ok(Value::from(Uuid::new_v5(&ns, name).hyphenated().to_string()))
+        }))
+        .insert(tremor_const_fn! (uuid|is_valid(_context, _input: String) {
+            Ok(Value::from(Uuid::parse_str(_input).is_ok()))
+        }))
+        .insert(tremor_const_fn! (uuid|parse(_context, _input: String) {
+            let uuid = Uuid::parse_str(_input).map_err(to_runtime_error)?;
+            let timestamp = uuid.get_timestamp().map(|ts| {
+                let (secs, nanos) = ts.to_unix();
+                secs * 1_000_000_000 + u64::from(nanos)
+            });
+            Ok(literal!({
+                "uuid": uuid.hyphenated().to_string(),
+                "version": uuid.get_version_num(),
+                "timestamp": timestamp,
+                "bytes": Value::Bytes(uuid.as_bytes().to_vec().into()),
+            }))
+        }))
+        .insert(tremor_const_fn! (uuid|is_valid_ulid(_context, _input: String) {
+            Ok(Value::from(parse_ulid(_input).is_some()))
+        }))
+        .insert(tremor_const_fn! (uuid|parse_ulid(_context, _input: String) {
+            let value = parse_ulid(_input).ok_or_else(|| to_runtime_error(format!("invalid ulid `{_input}`")))?;
+            // ALLOW: the timestamp is 48 bits
+            #[allow(clippy::cast_possible_truncation)]
+            let timestamp_ms = (value >> 80) as u64;
+            Ok(literal!({
+                "ulid": _input.to_ascii_uppercase(),
+                "timestamp": timestamp_ms * 1_000_000,
+                "bytes": Value::Bytes(value.to_be_bytes().to_vec().into()),
+            }))
+        }));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::registry::{fun, registry};
+    use crate::Value;
+    use simd_json::prelude::*;
+
+    #[test]
+    fn not_const() {
+        let r = registry();
+        for f in ["v4", "v7", "ulid"] {
+            assert!(!r.find("uuid", f).expect("function").is_const());
+        }
+        assert!(r.find("uuid", "v5").expect("function").is_const());
+    }
+
+    #[test]
+    fn v4_and_v7() {
+        let v4 = fun("uuid", "v4");
+        let v7 = fun("uuid", "v7");
+        let parse = fun("uuid", "parse");
+        let a = v4(&[]).expect("uuid");
+        assert_ne!(Ok(a.clone()), v4(&[]));
+        let p = parse(&[&a]).expect("parsed");
+        assert_eq!(p.get_u8("version"), Some(4));
+        assert!(p.get("timestamp").is_some_and(TypedScalarValue::is_null));
+
+        let a = v7(&[]).expect("uuid");
+        let b = v7(&[]).expect("uuid");
+        // v7 uuids are time ordered
+        assert!(a.as_str() < b.as_str());
+        let p = parse(&[&a]).expect("parsed");
+        assert_eq!(p.get_u8("version"), Some(7));
+        assert!(p.get_u64("timestamp").is_some_and(|ts| ts > 0));
+    }
+
+    #[test]
+    fn v5() {
+        let f = fun("uuid", "v5");
+        assert_val!(
+            f(&[&Value::from("dns"), &Value::from("python.org")]),
+            "886313e1-3b8a-5372-9b90-0c9aee199e5d"
+        );
+        assert_eq!(
+            f(&[&Value::from("dns"), &Value::from("tremor.rs")]),
+            f(&[
+                &Value::from("6ba7b810-9dad-11d1-80b4-00c04fd430c8"),
+                &Value::Bytes(b"tremor.rs".to_vec().into())
+            ])
+        );
+        assert!(f(&[&Value::from("snot"), &Value::from("badger")]).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let f = fun("uuid", "is_valid");
+        assert_val!(
+            f(&[&Value::from("67e55044-10b1-426f-9247-bb680e5fe0c8")]),
+            true
+        );
+        assert_val!(f(&[&Value::from("snot")]), false);
+        assert!(fun("uuid", "parse")(&[&Value::from("snot")]).is_err());
+    }
+
+    #[test]
+    fn ulid() {
+        assert_eq!(
+            super::ulid(1_469_918_176_385, 0),
+            "01ARYZ6S410000000000000000"
+        );
+        let f = fun("uuid", "ulid");
+        let parse = fun("uuid", "parse_ulid");
+        let valid = fun("uuid", "is_valid_ulid");
+        let a = f(&[]).expect("ulid");
+        assert_eq!(a.as_str().map(str::len), Some(26));
+        assert_val!(valid(&[&a]), true);
+        assert_val!(valid(&[&Value::from("81ARYZ6S410000000000000000")]), false);
+        assert_val!(valid(&[&Value::from("snot")]), false);
+
+        let p = parse(&[&Value::from("01aryz6s410000000000000000")]).expect("parsed");
+        assert_eq!(p.get_str("ulid"), Some("01ARYZ6S410000000000000000"));
+        assert_eq!(p.get_u64("timestamp"), Some(1_469_918_176_385_000_000));
+        assert!(parse(&[&Value::from("snot")]).is_err());
+    }
+}
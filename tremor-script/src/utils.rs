@@ -29,6 +29,16 @@ pub fn hostname() -> String {
         .unwrap_or_else(|_| "tremor_host.local".to_string())
 }
 
+/// Consistent jump hash of `key` into one of `slot_count` slots
+///
+/// This uses the same keys as `chash::jump` so scripts and operators agree on the slot of a key.
+#[must_use]
+pub fn jump_hash(key: &str, slot_count: u32) -> u32 {
+    // This is 'tremor\0\0'  and '\0\0tremor' as integers
+    let jh = jumphash::JumpHasher::new_with_keys(8_390_880_576_440_238_080, 128_034_676_764_530);
+    jh.slot(&key, slot_count)
+}
+
 fn is_xz_file(filename: &str) -> bool {
     filename
         .rsplit('.')
@@ -0,0 +1,437 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A stack based virtual machine for tremor-script expressions.
+//!
+//! Expressions are lowered into a flat list of instructions with local
+//! variables resolved to their slots and paths resolved without intermediate
+//! values. Everything the compiler doesn't lower is handed back to the
+//! interpreter, which stays the reference implementation: a compiled program
+//! produces the same values and errors as the expression it was compiled from.
+
+mod compiler;
+mod op;
+#[cfg(test)]
+mod test;
+
+use self::{
+    compiler::Compiler,
+    op::{Base, Op, Segment},
+};
+use crate::{
+    ast::{BooleanBinOpKind, Expr, ImutExpr, NodeMeta, Path},
+    ctx::EventContext,
+    errors::{error_invalid_unary, error_oops, Kind as ErrorKind, Result},
+    interpreter::{exec_binary, exec_unary, resolve, Cont, Env, LocalStack},
+    lexer::Span,
+    prelude::*,
+    script::Return,
+    static_bool, stry,
+};
+use smallvec::SmallVec;
+use std::borrow::Cow;
+
+type Stack<'run, 'event> = SmallVec<[Cow<'run, Value<'event>>; 8]>;
+
+/// A compiled immutable expression
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program<'script> {
+    ops: Vec<Op<'script>>,
+    max_stack: usize,
+    mid: Box<NodeMeta>,
+}
+
+impl<'script> Program<'script> {
+    /// Compiles an expression
+    #[must_use]
+    pub fn compile(expr: &ImutExpr<'script>) -> Self {
+        Compiler::compile(expr)
+    }
+
+    /// Number of instructions
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// If the program has no instructions
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Number of instructions that are handed back to the interpreter
+    #[must_use]
+    pub fn interpreted(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| matches!(op, Op::Walk(_)))
+            .count()
+    }
+
+    fn stack_underflow<T>(&self) -> Result<T> {
+        error_oops(
+            &*self.mid,
+            0xdead_0013,
+            "Stack underflow in compiled expression",
+        )
+    }
+
+    /// Runs the program, this evaluates to the same result as running the
+    /// expression it was compiled from
+    ///
+    /// # Errors
+    /// on any runtime error
+    #[allow(clippy::too_many_lines)]
+    pub fn run<'run, 'event>(
+        &'run self,
+        opts: ExecOpts,
+        env: &'run Env<'run, 'event>,
+        event: &'run Value<'event>,
+        state: &'run Value<'static>,
+        meta: &'run Value<'event>,
+        local: &'run LocalStack<'event>,
+    ) -> Result<Cow<'run, Value<'event>>>
+    where
+        'script: 'event,
+    {
+        let mut stack: Stack<'run, 'event> = SmallVec::with_capacity(self.max_stack);
+        let mut pc = 0;
+        while let Some(op) = self.ops.get(pc) {
+            pc += 1;
+            match op {
+                Op::Const(value) => stack.push(Cow::Borrowed(value)),
+                Op::Path {
+                    base,
+                    segments,
+                    path,
+                } => {
+                    let value = stry!(Self::path(
+                        opts, env, event, state, meta, local, *base, segments, path
+                    ));
+                    stack.push(value);
+                }
+                Op::Own => {
+                    let Some(value) = stack.pop() else {
+                        return self.stack_underflow();
+                    };
+                    stack.push(Cow::Owned(value.clone_static()));
+                }
+                Op::Binary { kind, mid } => {
+                    let (Some(rhs), Some(lhs)) = (stack.pop(), stack.pop()) else {
+                        return self.stack_underflow();
+                    };
+                    stack.push(stry!(exec_binary(&**mid, &**mid, *kind, &lhs, &rhs)));
+                }
+                Op::Unary { kind, mid, inner } => {
+                    let Some(value) = stack.pop() else {
+                        return self.stack_underflow();
+                    };
+                    match exec_unary(*kind, &value) {
+                        Some(r) => stack.push(r),
+                        None => return error_invalid_unary(&**mid, inner, *kind, &value),
+                    }
+                }
+                Op::BoolLhs {
+                    kind,
+                    outer,
+                    lhs,
+                    end,
+                } => {
+                    let Some(value) = stack.last_mut() else {
+                        return self.stack_underflow();
+                    };
+                    let lval = value.try_as_bool().map_err(|e| {
+                        ErrorKind::InvalidBinaryBoolean(*outer, *lhs, *kind, e.got, None)
+                    })?;
+                    match kind {
+                        BooleanBinOpKind::Or if lval => {
+                            *value = Cow::Borrowed(&TRUE);
+                            pc = *end;
+                        }
+                        BooleanBinOpKind::And if !lval => {
+                            *value = Cow::Borrowed(&FALSE);
+                            pc = *end;
+                        }
+                        BooleanBinOpKind::Xor => {
+                            *value = Cow::Borrowed(if lval { &TRUE } else { &FALSE });
+                        }
+                        BooleanBinOpKind::Or | BooleanBinOpKind::And => {
+                            stack.pop();
+                        }
+                    }
+                }
+                Op::BoolRhs { kind, outer, rhs } => {
+                    let Some(value) = stack.pop() else {
+                        return self.stack_underflow();
+                    };
+                    let rval = value.try_as_bool().map_err(|e| {
+                        ErrorKind::InvalidBinaryBoolean(
+                            *outer,
+                            *rhs,
+                            *kind,
+                            ValueType::Bool,
+                            Some(e.got),
+                        )
+                    })?;
+                    if *kind == BooleanBinOpKind::Xor {
+                        let Some(lval) = stack.pop().as_ref().and_then(|v| v.as_bool()) else {
+                            return self.stack_underflow();
+                        };
+                        stack.push(static_bool!(lval ^ rval));
+                    } else {
+                        stack.push(static_bool!(rval));
+                    }
+                }
+                Op::Record { base, keys } => {
+                    let Some(start) = stack.len().checked_sub(keys.len()) else {
+                        return self.stack_underflow();
+                    };
+                    let mut object: Object = base.clone();
+                    object.reserve(keys.len());
+                    for (key, value) in keys.iter().zip(stack.drain(start..)) {
+                        object.insert(key.clone(), value.into_owned());
+                    }
+                    stack.push(Cow::Owned(Value::from(object)));
+                }
+                Op::List(n) => {
+                    let Some(start) = stack.len().checked_sub(*n) else {
+                        return self.stack_underflow();
+                    };
+                    let list: Vec<Value> = stack.drain(start..).map(Cow::into_owned).collect();
+                    stack.push(Cow::Owned(Value::from(list)));
+                }
+                Op::Call { invoke, argc } => {
+                    let Some(start) = stack.len().checked_sub(*argc) else {
+                        return self.stack_underflow();
+                    };
+                    let value = {
+                        let args: SmallVec<[&Value<'event>; 4]> =
+                            stack[start..].iter().map(AsRef::as_ref).collect();
+                        invoke.invocable.invoke(env, &args).map_err(|e| {
+                            let r: Option<&Registry> = None;
+                            let outer: Span = invoke.extent().expand_lines(2);
+                            e.into_err(&outer, &**invoke, r)
+                        })?
+                    };
+                    stack.truncate(start);
+                    stack.push(Cow::Owned(value));
+                }
+                Op::Walk(expr) => {
+                    stack.push(stry!(expr.run(opts, env, event, state, meta, local)));
+                }
+            }
+        }
+        match stack.pop() {
+            Some(value) => Ok(value),
+            None => self.stack_underflow(),
+        }
+    }
+
+    /// Resolves a path, if that fails the interpreter resolves it again to
+    /// report the error
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn path<'run, 'event>(
+        opts: ExecOpts,
+        env: &'run Env<'run, 'event>,
+        event: &'run Value<'event>,
+        state: &'run Value<'static>,
+        meta: &'run Value<'event>,
+        local: &'run LocalStack<'event>,
+        base: Base,
+        segments: &'run [Segment<'script>],
+        path: &'run Path<'script>,
+    ) -> Result<Cow<'run, Value<'event>>>
+    where
+        'script: 'event,
+    {
+        let mut current: &'run Value<'event> = match base {
+            Base::Event => event,
+            Base::Meta => meta,
+            Base::State => state,
+            Base::Args => env.consts.args,
+            Base::Group => env.consts.group,
+            Base::Window => env.consts.window,
+            Base::Local(idx) => match local.values.get(idx) {
+                Some(Some(value)) => value,
+                _ => return resolve(path, opts, env, event, state, meta, local, path),
+            },
+        };
+        for segment in segments {
+            let next = match segment {
+                Segment::Key(key) => key.lookup(current),
+                Segment::Idx(idx) => current.as_array().and_then(|a| a.get(*idx)),
+            };
+            if let Some(next) = next {
+                current = next;
+            } else {
+                return resolve(path, opts, env, event, state, meta, local, path);
+            }
+        }
+        Ok(Cow::Borrowed(current))
+    }
+}
+
+/// A statement of a compiled script
+#[derive(Clone, Debug, PartialEq)]
+enum Stmt<'script> {
+    /// An immutable expression
+    Imut(Program<'script>),
+    /// An assignment with a compiled value
+    Assign {
+        /// The value that gets assigned
+        value: Program<'script>,
+        /// The assignment, it is used for the target
+        assign: Expr<'script>,
+    },
+    /// Any other expression, these are run by the interpreter
+    Walk(Expr<'script>),
+}
+
+impl<'script> Stmt<'script> {
+    fn compile(expr: &Expr<'script>) -> Self {
+        match expr {
+            Expr::Imut(imut) => Stmt::Imut(Program::compile(imut)),
+            Expr::Assign { expr: value, .. } => {
+                if let Expr::Imut(value) = value.as_ref() {
+                    Stmt::Assign {
+                        value: Program::compile(value),
+                        assign: expr.clone(),
+                    }
+                } else {
+                    Stmt::Walk(expr.clone())
+                }
+            }
+            _ => Stmt::Walk(expr.clone()),
+        }
+    }
+
+    fn run<'run, 'event>(
+        &'run self,
+        opts: ExecOpts,
+        env: &'run Env<'run, 'event>,
+        event: &'run mut Value<'event>,
+        state: &'run mut Value<'static>,
+        meta: &'run mut Value<'event>,
+        local: &'run mut LocalStack<'event>,
+    ) -> Result<Cont<'run, 'event>>
+    where
+        'script: 'event,
+    {
+        match self {
+            Stmt::Imut(program) => {
+                // Like the interpreter we skip immutable expressions if their result isn't used
+                if opts.result_needed {
+                    program
+                        .run(opts, env, event, state, meta, local)
+                        .map(Cont::Cont)
+                } else {
+                    Ok(Cont::Cont(Cow::Borrowed(&NULL)))
+                }
+            }
+            Stmt::Assign { value, assign } => {
+                let value = stry!(value.run(opts.with_result(), env, event, state, meta, local))
+                    .into_owned();
+                if let Expr::Assign { path, .. } = assign {
+                    assign
+                        .assign(opts, env, event, state, meta, local, path, value)
+                        .map(Cont::Cont)
+                } else {
+                    error_oops(assign, 0xdead_0014, "Compiled assignment without a target")
+                }
+            }
+            Stmt::Walk(expr) => expr.run(opts, env, event, state, meta, local),
+        }
+    }
+}
+
+/// A compiled script, immutable expressions and the values of assignments
+/// are compiled, all other statements are run by the interpreter
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptProgram<'script> {
+    stmts: Vec<Stmt<'script>>,
+}
+
+impl<'script> ScriptProgram<'script> {
+    /// Compiles the expressions of a script
+    #[must_use]
+    pub fn compile(exprs: &[Expr<'script>]) -> Self {
+        Self {
+            stmts: exprs.iter().map(Stmt::compile).collect(),
+        }
+    }
+
+    /// Runs the compiled script
+    ///
+    /// # Errors
+    /// on runtime errors
+    pub(crate) fn run<'event>(
+        &self,
+        context: &EventContext,
+        aggr: AggrType,
+        event: &mut Value<'event>,
+        state: &mut Value<'static>,
+        meta: &mut Value<'event>,
+        locals: usize,
+    ) -> Result<Return<'event>>
+    where
+        'script: 'event,
+    {
+        let mut local = LocalStack::with_size(locals);
+
+        let mut stmts = self.stmts.iter().peekable();
+        let opts = ExecOpts {
+            result_needed: true,
+            aggr,
+        };
+
+        let env = Env {
+            context,
+            ..Env::default()
+        };
+
+        while let Some(stmt) = stmts.next() {
+            if stmts.peek().is_none() {
+                return match stry!(stmt.run(
+                    opts.with_result(),
+                    &env,
+                    event,
+                    state,
+                    meta,
+                    &mut local
+                )) {
+                    Cont::Drop => Ok(Return::Drop),
+                    Cont::Emit(value, port) => Ok(Return::Emit { value, port }),
+                    Cont::EmitEvent(port) => Ok(Return::EmitEvent { port }),
+                    Cont::Cont(v) => Ok(Return::Emit {
+                        value: v.into_owned(),
+                        port: None,
+                    }),
+                };
+            }
+            match stry!(stmt.run(opts.without_result(), &env, event, state, meta, &mut local)) {
+                Cont::Drop => return Ok(Return::Drop),
+                Cont::Emit(value, port) => return Ok(Return::Emit { value, port }),
+                Cont::EmitEvent(port) => return Ok(Return::EmitEvent { port }),
+                Cont::Cont(_v) => (),
+            }
+        }
+
+        // We never reach here as scripts have at least one expression, if this ever
+        // happens we got a serious logic error and want to fail hard to alert us.
+        // ALLOW: see above
+        unreachable!()
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    op::{Base, Op, Segment},
+    Program,
+};
+use crate::{
+    ast::{
+        BaseExpr, BooleanBinOpKind, ImutExpr, Invoke, Literal, Path, ReservedPath,
+        Segment as PathSegment,
+    },
+    prelude::*,
+};
+
+/// Lowers an expression tree into a flat list of instructions
+pub(crate) struct Compiler<'script> {
+    ops: Vec<Op<'script>>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'script> Compiler<'script> {
+    pub(crate) fn compile(expr: &ImutExpr<'script>) -> Program<'script> {
+        let mut compiler = Self {
+            ops: Vec::new(),
+            depth: 0,
+            max_depth: 0,
+        };
+        compiler.expr(expr);
+        Program {
+            ops: compiler.ops,
+            max_stack: compiler.max_depth,
+            mid: Box::new(expr.meta().clone()),
+        }
+    }
+
+    /// Adds an instruction that consumes `pops` values and pushes one
+    fn emit(&mut self, op: Op<'script>, pops: usize) -> usize {
+        self.depth = self.depth + 1 - pops;
+        self.max_depth = self.max_depth.max(self.depth);
+        self.ops.push(op);
+        self.ops.len() - 1
+    }
+
+    fn walk(&mut self, expr: &ImutExpr<'script>) {
+        self.emit(Op::Walk(Box::new(expr.clone())), 0);
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn expr(&mut self, expr: &ImutExpr<'script>) {
+        match expr {
+            ImutExpr::Literal(Literal { value, .. }) => {
+                self.emit(Op::Const(value.clone()), 0);
+            }
+            ImutExpr::String(s) => {
+                if let Some(s) = s.as_str() {
+                    self.emit(Op::Const(Value::from(s.to_string())), 0);
+                } else {
+                    self.walk(expr);
+                }
+            }
+            ImutExpr::Local { idx, mid } => {
+                let path = Path::Local(crate::ast::LocalPath {
+                    idx: *idx,
+                    mid: mid.clone(),
+                    segments: Vec::new(),
+                });
+                self.emit(
+                    Op::Path {
+                        base: Base::Local(*idx),
+                        segments: Vec::new(),
+                        path: Box::new(path),
+                    },
+                    0,
+                );
+            }
+            ImutExpr::Path(path) => self.path(expr, path),
+            ImutExpr::Binary(b) => {
+                self.expr(&b.lhs);
+                self.expr(&b.rhs);
+                self.emit(
+                    Op::Binary {
+                        kind: b.kind,
+                        mid: b.mid.clone(),
+                    },
+                    2,
+                );
+            }
+            ImutExpr::BinaryBoolean(b) => {
+                self.expr(&b.lhs);
+                let lhs = self.ops.len();
+                self.ops.push(Op::BoolLhs {
+                    kind: b.kind,
+                    outer: b.extent(),
+                    lhs: b.lhs.extent(),
+                    end: 0,
+                });
+                // `and` and `or` drop the left hand side if the right hand side decides
+                let pops = if b.kind == BooleanBinOpKind::Xor {
+                    2
+                } else {
+                    self.depth -= 1;
+                    1
+                };
+                self.expr(&b.rhs);
+                self.emit(
+                    Op::BoolRhs {
+                        kind: b.kind,
+                        outer: b.extent(),
+                        rhs: b.rhs.extent(),
+                    },
+                    pops,
+                );
+                let end = self.ops.len();
+                if let Some(Op::BoolLhs { end: e, .. }) = self.ops.get_mut(lhs) {
+                    *e = end;
+                }
+            }
+            ImutExpr::Unary(u) => {
+                self.expr(&u.expr);
+                self.emit(
+                    Op::Unary {
+                        kind: u.kind,
+                        mid: u.mid.clone(),
+                        inner: u.expr.extent(),
+                    },
+                    1,
+                );
+            }
+            ImutExpr::Record(r) => {
+                let keys: Option<Vec<_>> = r
+                    .fields
+                    .iter()
+                    .map(|f| f.name.as_str().map(|k| k.to_string().into()))
+                    .collect();
+                if let Some(keys) = keys {
+                    for f in &r.fields {
+                        self.expr(&f.value);
+                    }
+                    let pops = keys.len();
+                    let base = r.base.clone();
+                    self.emit(Op::Record { base, keys }, pops);
+                } else {
+                    self.walk(expr);
+                }
+            }
+            ImutExpr::List(l) => {
+                for e in &l.exprs {
+                    self.expr(e);
+                }
+                self.emit(Op::List(l.exprs.len()), l.exprs.len());
+            }
+            ImutExpr::Invoke1(i)
+            | ImutExpr::Invoke2(i)
+            | ImutExpr::Invoke3(i)
+            | ImutExpr::Invoke(i) => self.invoke(i),
+            ImutExpr::Match(_)
+            | ImutExpr::Comprehension(_)
+            | ImutExpr::Try(_)
+            | ImutExpr::Patch(_)
+            | ImutExpr::Merge(_)
+            | ImutExpr::Present { .. }
+            | ImutExpr::InvokeAggr(_)
+            | ImutExpr::Recur(_)
+            | ImutExpr::Bytes(_)
+            | ImutExpr::ArrayAppend(_) => self.walk(expr),
+        }
+    }
+
+    fn path(&mut self, expr: &ImutExpr<'script>, path: &Path<'script>) {
+        let base = match path {
+            Path::Local(p) => Base::Local(p.idx),
+            Path::Event(_) => Base::Event,
+            Path::State(_) => Base::State,
+            Path::Meta(_) => Base::Meta,
+            Path::Reserved(ReservedPath::Args { .. }) => Base::Args,
+            Path::Reserved(ReservedPath::Group { .. }) => Base::Group,
+            Path::Reserved(ReservedPath::Window { .. }) => Base::Window,
+            Path::Expr(_) => return self.walk(expr),
+        };
+        let mut segments = Vec::with_capacity(path.segments().len());
+        for segment in path.segments() {
+            match segment {
+                PathSegment::Id { key, .. } => segments.push(Segment::Key(key.clone())),
+                PathSegment::Idx { idx, .. } => segments.push(Segment::Idx(*idx)),
+                PathSegment::Element { .. }
+                | PathSegment::Range { .. }
+                | PathSegment::RangeExpr { .. } => return self.walk(expr),
+            }
+        }
+        self.emit(
+            Op::Path {
+                base,
+                segments,
+                path: Box::new(path.clone()),
+            },
+            0,
+        );
+    }
+
+    fn invoke(&mut self, invoke: &Invoke<'script>) {
+        for arg in &invoke.args {
+            self.expr(arg);
+            if let ImutExpr::Path(Path::Reserved(ReservedPath::Args { .. })) = arg {
+                self.emit(Op::Own, 1);
+            }
+        }
+        let argc = invoke.args.len();
+        let invoke = Invoke {
+            mid: invoke.mid.clone(),
+            node_id: invoke.node_id.clone(),
+            invocable: invoke.invocable.clone(),
+            args: Vec::new(),
+        };
+        self.emit(
+            Op::Call {
+                invoke: Box::new(invoke),
+                argc,
+            },
+            argc,
+        );
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    ast::{BinOpKind, BooleanBinOpKind, ImutExpr, Invoke, NodeMeta, Path, UnaryOpKind},
+    lexer::Span,
+    prelude::*,
+};
+use beef::Cow;
+use tremor_value::KnownKey;
+
+/// The value a path starts at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Base {
+    /// `event`
+    Event,
+    /// `$`
+    Meta,
+    /// `state`
+    State,
+    /// A local variable, by its slot on the local stack
+    Local(usize),
+    /// `args`
+    Args,
+    /// `group`
+    Group,
+    /// `window`
+    Window,
+}
+
+/// A path segment that can be resolved without evaluating an expression
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Segment<'script> {
+    /// A record key with a pre-computed hash
+    Key(KnownKey<'script>),
+    /// An array index
+    Idx(usize),
+}
+
+/// A single instruction of a compiled expression, instructions take their
+/// arguments from the value stack and push their result onto it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Op<'script> {
+    /// Pushes a constant
+    Const(Value<'script>),
+    /// Pushes the value a path points to, all segments are resolved in one go
+    Path {
+        /// The value the path starts at
+        base: Base,
+        /// The segments of the path
+        segments: Vec<Segment<'script>>,
+        /// The original path, only used to report errors
+        path: Box<Path<'script>>,
+    },
+    /// Replaces the top of the stack with an owned copy, `args` can change
+    /// while a function runs so we can't pass a reference to it
+    Own,
+    /// Replaces the two top values with the result of a binary operation
+    Binary {
+        /// The operation
+        kind: BinOpKind,
+        /// The binary expression
+        mid: Box<NodeMeta>,
+    },
+    /// Replaces the top value with the result of a unary operation
+    Unary {
+        /// The operation
+        kind: UnaryOpKind,
+        /// The unary expression
+        mid: Box<NodeMeta>,
+        /// The operand
+        inner: Span,
+    },
+    /// Checks the left hand side of a boolean operation, if it already decides
+    /// the result it is left on the stack and execution continues at `end`
+    BoolLhs {
+        /// The operation
+        kind: BooleanBinOpKind,
+        /// The boolean expression
+        outer: Span,
+        /// The left hand side
+        lhs: Span,
+        /// The instruction after the right hand side
+        end: usize,
+    },
+    /// Checks the right hand side of a boolean operation and computes the result
+    BoolRhs {
+        /// The operation
+        kind: BooleanBinOpKind,
+        /// The boolean expression
+        outer: Span,
+        /// The right hand side
+        rhs: Span,
+    },
+    /// Creates a record from the static part and as many values as there are keys
+    Record {
+        /// The static part of the record
+        base: Object<'script>,
+        /// The keys of the fields
+        keys: Vec<Cow<'script, str>>,
+    },
+    /// Creates a list from the given number of values
+    List(usize),
+    /// Calls a function with the given number of values as arguments
+    Call {
+        /// The invocation, without its arguments
+        invoke: Box<Invoke<'script>>,
+        /// Number of arguments
+        argc: usize,
+    },
+    /// Evaluates an expression with the interpreter, this is used for all
+    /// expressions the compiler doesn't lower
+    Walk(Box<ImutExpr<'script>>),
+}
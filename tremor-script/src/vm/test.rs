@@ -0,0 +1,218 @@
+// Copyright 2024, The Tremor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Program;
+use crate::{
+    ast::Expr,
+    interpreter::{Env, LocalStack},
+    module::Manager,
+    prelude::*,
+    registry, Script, NULL,
+};
+
+/// Runs the script with the interpreter and compiled and checks that both
+/// produce the same result, event and metadata
+fn check(src: &str, event: &Value<'static>) {
+    Manager::add_path(&"./lib").expect("failed to add lib path");
+    let reg = registry::registry();
+    let interpreted = Script::parse(src, &reg).expect("parse failed").script;
+    let mut compiled = interpreted.clone();
+    compiled.compile();
+    assert!(compiled.is_compiled());
+
+    let ctx = EventContext::new(0, None);
+
+    let mut event1 = event.clone();
+    let mut meta1 = literal!({"m": 1});
+    let mut state1 = Value::null();
+    let r1 = interpreted.run(&ctx, AggrType::Emit, &mut event1, &mut state1, &mut meta1);
+
+    let mut event2 = event.clone();
+    let mut meta2 = literal!({"m": 1});
+    let mut state2 = Value::null();
+    let r2 = compiled.run(&ctx, AggrType::Emit, &mut event2, &mut state2, &mut meta2);
+
+    // errors never compare equal, so we compare their kinds
+    let r1 = r1.map_err(|e| format!("{:?}", e.0));
+    let r2 = r2.map_err(|e| format!("{:?}", e.0));
+    assert_eq!(r1, r2, "result of `{src}`");
+    assert_eq!(event1, event2, "event of `{src}`");
+    assert_eq!(meta1, meta2, "meta of `{src}`");
+    assert_eq!(state1, state2, "state of `{src}`");
+}
+
+fn event() -> Value<'static> {
+    literal!({
+        "a": 3,
+        "b": 4.5,
+        "s": "snot",
+        "t": true,
+        "f": false,
+        "l": [1, 2, {"x": "badger"}],
+        "o": {"x": {"y": 42}}
+    })
+}
+
+#[test]
+fn arithmetics() {
+    let e = event();
+    check("event.a + event.b * 2", &e);
+    check("event.a - 1", &e);
+    check("event.a / 2", &e);
+    check("event.a % 2", &e);
+    check("event.a << 2", &e);
+    check("event.s + \"badger\"", &e);
+    check("event.l + [4]", &e);
+    check("event.a == 3", &e);
+    check("event.s != event.a", &e);
+    check("-event.a", &e);
+    check("not event.t", &e);
+    check("event.a / 0", &e);
+    check("event.a + event.s", &e);
+    check("-event.s", &e);
+}
+
+#[test]
+fn paths() {
+    let e = event();
+    check("event.o.x.y", &e);
+    check("event.l[2].x", &e);
+    check("event.l[1:2]", &e);
+    check("event.o[\"x\"]", &e);
+    check("$m", &e);
+    check("event", &e);
+    check("event.missing", &e);
+    check("event.o.x.z", &e);
+    check("event.l[5]", &e);
+    check("event.a[0]", &e);
+    check("event.a.x", &e);
+    check("let x = event.o; x.x.y", &e);
+    check("let x = event.o; x.y", &e);
+}
+
+#[test]
+fn booleans() {
+    let e = event();
+    check("event.t or (event.a / 0 == 1)", &e);
+    check("event.f and (event.a / 0 == 1)", &e);
+    check("event.t and event.f", &e);
+    check("event.f or event.t", &e);
+    check("event.t xor event.f", &e);
+    check("event.t xor event.t", &e);
+    check("event.a or event.t", &e);
+    check("event.f or event.a", &e);
+    check("event.t and event.s", &e);
+    check("event.a xor event.t", &e);
+    check("event.t xor event.a", &e);
+    check("(event.a > 1 and event.b < 5) or event.s == \"badger\"", &e);
+}
+
+#[test]
+fn constructors() {
+    let e = event();
+    check(
+        "{\"a\": event.a, \"b\": [1, event.b, \"c\"], \"c\": {}}",
+        &e,
+    );
+    check("[event.a, [event.s, [event.t]]]", &e);
+    check("{\"#{event.s}\": 1}", &e);
+    check("\"#{event.s} #{event.a}\"", &e);
+    check("[]", &e);
+}
+
+#[test]
+fn statements() {
+    let e = event();
+    check("let x = event.a; let y = x + 1; y", &e);
+    check("let event.c = event.a + 1; event", &e);
+    check("let event.o.x.z = event.s; event", &e);
+    check("let $n = event.a; $", &e);
+    check("let state = event.a; state + 1", &e);
+    check("let event = {\"snot\": \"badger\"}; event", &e);
+    check("let event.a.b = 1; event", &e);
+    check("let x = 1; let x = x + event.a; x", &e);
+    check("emit event", &e);
+    check("emit event.a + 1 => \"err\"", &e);
+    check("drop", &e);
+    check("let event.x = 1;", &e);
+    check("event.a; event.missing; 1", &e);
+}
+
+#[test]
+fn functions() {
+    let e = event();
+    check("use std::string; string::len(event.s)", &e);
+    check("use std::math; math::max(event.a, event.b)", &e);
+    check("use std::string; string::len(event.a)", &e);
+    check(
+        "use std::string; string::format(\"{}-{}-{}\", event.a, event.s, event.t)",
+        &e,
+    );
+    check("fn add(a, b) with a + b end; add(event.a, 2)", &e);
+    check("fn f(a) with a.x end; f(event.o)", &e);
+    check(
+        "fn fib_(a, b, n) of case (a, b, n) when n > 0 => recur(b, a + b, n - 1) case _ => a end; fib_(0, 1, event.a)",
+        &e,
+    );
+}
+
+#[test]
+fn interpreted() {
+    let e = event();
+    check(
+        "match event.a of case 3 => \"three\" case _ => \"other\" end",
+        &e,
+    );
+    check(
+        "let x = match event of case %{ a == 3 } => event.a case _ => 0 end; x + 1",
+        &e,
+    );
+    check("for event.l of case (i, v) => i end", &e);
+    check("patch event of insert \"z\" => event.a end", &e);
+    check("merge event of {\"a\": 1} end", &e);
+    check("present event.o.x", &e);
+    check("try event.a + event.s catch e => e.kind end", &e);
+    check("<<event.a:8>>", &e);
+}
+
+#[test]
+fn program() -> crate::Result<()> {
+    let reg = registry::registry();
+    let script = Script::parse("event.a + event.o.x.y * 2", &reg)?;
+    let Some(Expr::Imut(expr)) = script.script.exprs.first() else {
+        return Err("expected an immutable expression".into());
+    };
+    let program = Program::compile(expr);
+    // two paths, a constant and two binary operations
+    assert_eq!(5, program.len());
+    assert_eq!(0, program.interpreted());
+
+    let env = Env::default();
+    let local = LocalStack::default();
+    let e = event();
+    let opts = ExecOpts {
+        result_needed: true,
+        aggr: AggrType::Emit,
+    };
+    let r = program.run(opts, &env, &e, &NULL, &NULL, &local)?;
+    assert_eq!(Value::from(87), r.into_owned());
+
+    let script = Script::parse("[match event of case _ => 1 end, event.a]", &reg)?;
+    let Some(Expr::Imut(expr)) = script.script.exprs.first() else {
+        return Err("expected an immutable expression".into());
+    };
+    let program = Program::compile(expr);
+    assert_eq!(1, program.interpreted());
+    Ok(())
+}